@@ -269,13 +269,16 @@ impl GltfLoader {
                         let tangents: Vec<[f32; 4]> = reader.read_tangents().map(|t| t.collect()).unwrap_or_else(|| vec![[1.0, 0.0, 0.0, 1.0]; positions.len()]);
                         let joint_indices: Vec<[u32; 4]> = reader.read_joints(0).map(|j| j.into_u16().map(|v| v.map(|x| x as u32)).collect()).unwrap_or_else(|| vec![[0; 4]; positions.len()]);
                         let joint_weights: Vec<[f32; 4]> = reader.read_weights(0).map(|w| w.into_f32().collect()).unwrap_or_else(|| vec![[0.0; 4]; positions.len()]);
+                        // 第二组骨骼影响 (每个顶点最多 8 根骨骼)
+                        let joint_indices_1: Vec<[u32; 4]> = reader.read_joints(1).map(|j| j.into_u16().map(|v| v.map(|x| x as u32)).collect()).unwrap_or_else(|| vec![[0; 4]; positions.len()]);
+                        let joint_weights_1: Vec<[f32; 4]> = reader.read_weights(1).map(|w| w.into_f32().collect()).unwrap_or_else(|| vec![[0.0; 4]; positions.len()]);
 
                         let mut mesh_vertices = Vec::new();
                         for i in 0..positions.len() {
                             mesh_vertices.push(Vertex::with_skinning(
                                 positions[i].into(), normals[i].into(), uvs[i].into(), tangents[i].into(),
                                 joint_indices[i], joint_weights[i]
-                            ));
+                            ).with_secondary_influences(joint_indices_1[i], joint_weights_1[i]));
                         }
 
                         let mesh_indices = reader.read_indices().map(|indices| indices.into_u32().collect()).unwrap_or_else(|| (0..positions.len() as u32).collect());
@@ -334,17 +337,17 @@ impl GltfLoader {
                         match output {
                             gltf::animation::util::ReadOutputs::Translations(iter) => {
                                 let mut kfs = Vec::new();
-                                for (t, v) in input.iter().zip(iter) { kfs.push(super::scene::Keyframe { time: *t, value: v.into() }); }
+                                for (t, v) in input.iter().zip(iter) { kfs.push(super::scene::Keyframe::new(*t, v.into())); }
                                 c.position_track = Some(super::scene::AnimationTrack::new(kfs));
                             }
                             gltf::animation::util::ReadOutputs::Rotations(iter) => {
                                 let mut kfs = Vec::new();
-                                for (t, v) in input.iter().zip(iter.into_f32()) { kfs.push(super::scene::Keyframe { time: *t, value: glam::Quat::from_array(v) }); }
+                                for (t, v) in input.iter().zip(iter.into_f32()) { kfs.push(super::scene::Keyframe::new(*t, glam::Quat::from_array(v))); }
                                 c.rotation_track = Some(super::scene::AnimationTrack::new(kfs));
                             }
                             gltf::animation::util::ReadOutputs::Scales(iter) => {
                                 let mut kfs = Vec::new();
-                                for (t, v) in input.iter().zip(iter) { kfs.push(super::scene::Keyframe { time: *t, value: v.into() }); }
+                                for (t, v) in input.iter().zip(iter) { kfs.push(super::scene::Keyframe::new(*t, v.into())); }
                                 c.scale_track = Some(super::scene::AnimationTrack::new(kfs));
                             }
                             _ => {}
@@ -365,21 +368,21 @@ impl GltfLoader {
                         gltf::animation::util::ReadOutputs::Translations(iter) => {
                             let mut keyframes = Vec::new();
                             for (t, v) in input.iter().zip(iter) {
-                                keyframes.push(super::scene::Keyframe { time: *t, value: v.into() });
+                                keyframes.push(super::scene::Keyframe::new(*t, v.into()));
                             }
                             anim_channel.position_track = Some(super::scene::AnimationTrack::new(keyframes));
                         }
                         gltf::animation::util::ReadOutputs::Rotations(iter) => {
                             let mut keyframes = Vec::new();
                             for (t, v) in input.iter().zip(iter.into_f32()) {
-                                keyframes.push(super::scene::Keyframe { time: *t, value: glam::Quat::from_array(v) });
+                                keyframes.push(super::scene::Keyframe::new(*t, glam::Quat::from_array(v)));
                             }
                             anim_channel.rotation_track = Some(super::scene::AnimationTrack::new(keyframes));
                         }
                         gltf::animation::util::ReadOutputs::Scales(iter) => {
                             let mut keyframes = Vec::new();
                             for (t, v) in input.iter().zip(iter) {
-                                keyframes.push(super::scene::Keyframe { time: *t, value: v.into() });
+                                keyframes.push(super::scene::Keyframe::new(*t, v.into()));
                             }
                             anim_channel.scale_track = Some(super::scene::AnimationTrack::new(keyframes));
                         }
@@ -0,0 +1,202 @@
+//! 从网格生成碰撞体形状
+//!
+//! 检查器中的 "从网格自动适配碰撞体" 操作使用这里的函数。返回的形状位于网格局部空间，
+//! 偏移量为形状中心相对网格原点的位置 (写入 [`Collider::offset`](crate::scene::Collider))。
+
+use crate::math::AABB;
+use crate::scene::{ColliderShape, MeshData, RigidBodyType};
+use glam::{Vec2, Vec3};
+
+/// 自动适配生成高度场时每个方向的采样点数
+pub const HEIGHT_FIELD_RESOLUTION: usize = 32;
+
+/// 自动适配的目标形状
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColliderFit {
+    Cuboid,
+    Ball,
+    Capsule,
+    Cylinder,
+    ConvexHull,
+    ConvexDecomposition,
+    TriMesh,
+    HeightField,
+}
+
+impl ColliderFit {
+    pub const ALL: [ColliderFit; 8] = [
+        ColliderFit::Cuboid,
+        ColliderFit::Ball,
+        ColliderFit::Capsule,
+        ColliderFit::Cylinder,
+        ColliderFit::ConvexHull,
+        ColliderFit::ConvexDecomposition,
+        ColliderFit::TriMesh,
+        ColliderFit::HeightField,
+    ];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColliderFit::Cuboid => "包围盒",
+            ColliderFit::Ball => "包围球",
+            ColliderFit::Capsule => "胶囊体",
+            ColliderFit::Cylinder => "圆柱体",
+            ColliderFit::ConvexHull => "凸包",
+            ColliderFit::ConvexDecomposition => "凸分解",
+            ColliderFit::TriMesh => "三角网格",
+            ColliderFit::HeightField => "高度场",
+        }
+    }
+
+    /// 精确网格形状的推荐选择：动态刚体不支持三角网格，使用凸分解
+    pub fn exact_for(body_type: RigidBodyType) -> Self {
+        match body_type {
+            RigidBodyType::Dynamic => ColliderFit::ConvexDecomposition,
+            _ => ColliderFit::TriMesh,
+        }
+    }
+}
+
+/// 按指定方式从网格生成碰撞体形状，返回 (形状, 中心偏移)；空网格返回 None
+pub fn fit_collider(mesh: &MeshData, fit: ColliderFit) -> Option<(ColliderShape, Vec3)> {
+    let points: Vec<Vec3> = mesh.vertices.iter().map(|v| v.position).collect();
+    if points.is_empty() {
+        return None;
+    }
+    let aabb = AABB::from_points(&points);
+    let center = (aabb.min + aabb.max) * 0.5;
+    let half = (aabb.max - aabb.min) * 0.5;
+
+    let fitted = match fit {
+        ColliderFit::Cuboid => (ColliderShape::Cuboid { half_extents: half }, center),
+        ColliderFit::Ball => {
+            let radius = points.iter().map(|p| p.distance(center)).fold(0.0, f32::max);
+            (ColliderShape::Ball { radius }, center)
+        }
+        ColliderFit::Capsule => {
+            let radius = half.x.max(half.z);
+            (ColliderShape::Capsule { half_height: (half.y - radius).max(0.0), radius }, center)
+        }
+        ColliderFit::Cylinder => (ColliderShape::Cylinder { half_height: half.y, radius: half.x.max(half.z) }, center),
+        ColliderFit::ConvexHull => (ColliderShape::ConvexHull { points }, Vec3::ZERO),
+        ColliderFit::ConvexDecomposition => {
+            (ColliderShape::ConvexDecomposition { vertices: points, indices: triangles(mesh)? }, Vec3::ZERO)
+        }
+        ColliderFit::TriMesh => (ColliderShape::TriMesh { vertices: points, indices: triangles(mesh)? }, Vec3::ZERO),
+        ColliderFit::HeightField => sample_height_field(mesh, &aabb, HEIGHT_FIELD_RESOLUTION)?,
+    };
+    Some(fitted)
+}
+
+/// 网格的三角形索引；没有完整三角形时返回 None
+fn triangles(mesh: &MeshData) -> Option<Vec<[u32; 3]>> {
+    let indices: Vec<[u32; 3]> = mesh.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+    (!indices.is_empty()).then_some(indices)
+}
+
+/// 将网格三角形投影到 XZ 平面的网格上，每个采样点取覆盖它的三角形的最高点
+///
+/// 没有被任何三角形覆盖的采样点使用网格的最低高度。
+fn sample_height_field(mesh: &MeshData, aabb: &AABB, resolution: usize) -> Option<(ColliderShape, Vec3)> {
+    let extent = aabb.max - aabb.min;
+    if extent.x <= f32::EPSILON || extent.z <= f32::EPSILON {
+        return None;
+    }
+    let (rows, cols) = (resolution.max(2), resolution.max(2));
+    let cell = Vec2::new(extent.x / (cols - 1) as f32, extent.z / (rows - 1) as f32);
+    let mut heights = vec![f32::NEG_INFINITY; rows * cols];
+
+    for tri in triangles(mesh)? {
+        let [a, b, c] = tri.map(|i| mesh.vertices.get(i as usize).map(|v| v.position));
+        let (Some(a), Some(b), Some(c)) = (a, b, c) else { continue };
+        let min = a.min(b).min(c);
+        let max = a.max(b).max(c);
+        let col_range = grid_range(min.x - aabb.min.x, max.x - aabb.min.x, cell.x, cols);
+        let row_range = grid_range(min.z - aabb.min.z, max.z - aabb.min.z, cell.y, rows);
+        for row in row_range {
+            for col in col_range.clone() {
+                let p = Vec2::new(aabb.min.x + col as f32 * cell.x, aabb.min.z + row as f32 * cell.y);
+                if let Some(height) = height_in_triangle(p, a, b, c) {
+                    let h = &mut heights[row * cols + col];
+                    *h = h.max(height);
+                }
+            }
+        }
+    }
+
+    for h in &mut heights {
+        if !h.is_finite() {
+            *h = aabb.min.y;
+        }
+    }
+    let size = Vec3::new(extent.x, 1.0, extent.z);
+    let center = (aabb.min + aabb.max) * 0.5;
+    Some((ColliderShape::HeightField { rows, cols, heights, size }, Vec3::new(center.x, 0.0, center.z)))
+}
+
+/// 区间 [from, to] 覆盖的采样点下标范围
+fn grid_range(from: f32, to: f32, cell: f32, count: usize) -> std::ops::RangeInclusive<usize> {
+    let start = (from / cell).ceil().max(0.0) as usize;
+    let end = ((to / cell).floor().max(0.0) as usize).min(count - 1);
+    start..=end
+}
+
+/// 点 p (XZ) 落在三角形投影内时，返回三角形在该点的高度 (重心插值)
+fn height_in_triangle(p: Vec2, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let (a2, b2, c2) = (Vec2::new(a.x, a.z), Vec2::new(b.x, b.z), Vec2::new(c.x, c.z));
+    let area = (b2 - a2).perp_dot(c2 - a2);
+    if area.abs() <= f32::EPSILON {
+        return None;
+    }
+    let u = (b2 - p).perp_dot(c2 - p) / area;
+    let v = (c2 - p).perp_dot(a2 - p) / area;
+    let w = 1.0 - u - v;
+    const TOLERANCE: f32 = -1.0e-4;
+    (u >= TOLERANCE && v >= TOLERANCE && w >= TOLERANCE).then_some(a.y * u + b.y * v + c.y * w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Vertex;
+
+    fn mesh(positions: &[Vec3], indices: Vec<u32>) -> MeshData {
+        MeshData {
+            name: "test".into(),
+            vertices: positions.iter().map(|&p| Vertex::new(p, Vec3::Y, Vec2::ZERO)).collect(),
+            indices,
+        }
+    }
+
+    #[test]
+    fn test_fit_cuboid_uses_bounds_center() {
+        let m = mesh(&[Vec3::new(1.0, 0.0, -1.0), Vec3::new(3.0, 2.0, 1.0), Vec3::new(2.0, 1.0, 0.0)], vec![0, 1, 2]);
+        let (shape, offset) = fit_collider(&m, ColliderFit::Cuboid).unwrap();
+        assert_eq!(shape, ColliderShape::Cuboid { half_extents: Vec3::new(1.0, 1.0, 1.0) });
+        assert_eq!(offset, Vec3::new(2.0, 1.0, 0.0));
+
+        let (shape, _) = fit_collider(&m, ColliderFit::TriMesh).unwrap();
+        assert!(matches!(shape, ColliderShape::TriMesh { ref indices, .. } if indices == &vec![[0, 1, 2]]));
+        assert!(fit_collider(&mesh(&[], vec![]), ColliderFit::Ball).is_none());
+    }
+
+    #[test]
+    fn test_height_field_samples_sloped_quad() {
+        // 沿 X 方向从 0 升高到 1 的斜面
+        let m = mesh(
+            &[Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 0.0), Vec3::new(2.0, 1.0, 2.0), Vec3::new(0.0, 0.0, 2.0)],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+        let aabb = AABB::from_points(&m.vertices.iter().map(|v| v.position).collect::<Vec<_>>());
+        let (shape, offset) = sample_height_field(&m, &aabb, 3).unwrap();
+        assert_eq!(offset, Vec3::new(1.0, 0.0, 1.0));
+        let ColliderShape::HeightField { rows, cols, heights, size } = shape else { panic!("expected height field") };
+        assert_eq!((rows, cols, size), (3, 3, Vec3::new(2.0, 1.0, 2.0)));
+        for row in 0..rows {
+            for (col, expected) in [0.0, 0.5, 1.0].into_iter().enumerate() {
+                assert!((heights[row * cols + col] - expected).abs() < 1e-5);
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! 动画曲线
+//!
+//! 此模块定义关键帧切线模式与三次 Hermite/Bezier 曲线求值。
+//! 运行时采样与编辑器曲线面板共用同一套求值函数，保证所见即所得。
+
+use crate::scene::{AnimationTrack, Keyframe};
+use glam::{Quat, Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+/// 关键帧切线模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum TangentMode {
+    /// 自动平滑 (Catmull-Rom)
+    Auto,
+    /// 自动平滑，但在极值处放平且不过冲
+    Clamped,
+    /// 线性 (切线指向相邻关键帧)
+    #[default]
+    Linear,
+    /// 常量 (保持当前值直到下一个关键帧)
+    Constant,
+    /// 自由 (使用手动编辑的切线)
+    Free,
+}
+
+impl TangentMode {
+    pub const ALL: [TangentMode; 5] = [
+        TangentMode::Auto,
+        TangentMode::Clamped,
+        TangentMode::Linear,
+        TangentMode::Constant,
+        TangentMode::Free,
+    ];
+
+    /// 旋转轨道支持的模式：旋转按球面线性插值，不使用切线
+    pub const ROTATION: [TangentMode; 2] = [TangentMode::Linear, TangentMode::Constant];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            TangentMode::Auto => "自动",
+            TangentMode::Clamped => "钳制",
+            TangentMode::Linear => "线性",
+            TangentMode::Constant => "常量",
+            TangentMode::Free => "自由",
+        }
+    }
+}
+
+/// 可按分量进行曲线插值的值类型
+pub trait CurveValue: Copy {
+    /// 分量数量
+    const COMPONENTS: usize;
+    /// 读取第 i 个分量
+    fn component(&self, i: usize) -> f32;
+    /// 写入第 i 个分量
+    fn set_component(&mut self, i: usize, value: f32);
+    /// 全零值
+    fn zero() -> Self;
+}
+
+impl CurveValue for f32 {
+    const COMPONENTS: usize = 1;
+    fn component(&self, _i: usize) -> f32 { *self }
+    fn set_component(&mut self, _i: usize, value: f32) { *self = value; }
+    fn zero() -> Self { 0.0 }
+}
+
+impl CurveValue for Vec2 {
+    const COMPONENTS: usize = 2;
+    fn component(&self, i: usize) -> f32 { self[i] }
+    fn set_component(&mut self, i: usize, value: f32) { self[i] = value; }
+    fn zero() -> Self { Vec2::ZERO }
+}
+
+impl CurveValue for Vec3 {
+    const COMPONENTS: usize = 3;
+    fn component(&self, i: usize) -> f32 { self[i] }
+    fn set_component(&mut self, i: usize, value: f32) { self[i] = value; }
+    fn zero() -> Self { Vec3::ZERO }
+}
+
+impl CurveValue for Vec4 {
+    const COMPONENTS: usize = 4;
+    fn component(&self, i: usize) -> f32 { self[i] }
+    fn set_component(&mut self, i: usize, value: f32) { self[i] = value; }
+    fn zero() -> Self { Vec4::ZERO }
+}
+
+/// 三次 Hermite 插值 (等价于控制点为 p0 + m0·dt/3、p1 - m1·dt/3 的 Bezier 段)
+pub fn hermite(p0: f32, m0: f32, p1: f32, m1: f32, dt: f32, s: f32) -> f32 {
+    let s2 = s * s;
+    let s3 = s2 * s;
+    let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+    let h10 = s3 - 2.0 * s2 + s;
+    let h01 = -2.0 * s3 + 3.0 * s2;
+    let h11 = s3 - s2;
+    h00 * p0 + h10 * dt * m0 + h01 * p1 + h11 * dt * m1
+}
+
+/// 计算关键帧 i 在某一分量上的 (入切线, 出切线) 斜率 (值/秒)
+fn component_tangents<T: CurveValue>(keys: &[Keyframe<T>], i: usize, c: usize) -> (f32, f32) {
+    let key = &keys[i];
+    let prev = (i > 0).then(|| &keys[i - 1]);
+    let next = keys.get(i + 1);
+
+    let secant = |a: &Keyframe<T>, b: &Keyframe<T>| {
+        let dt = b.time - a.time;
+        if dt.abs() < f32::EPSILON { 0.0 } else { (b.value.component(c) - a.value.component(c)) / dt }
+    };
+    let left = prev.map(|p| secant(p, key));
+    let right = next.map(|n| secant(key, n));
+
+    match key.tangent_mode {
+        TangentMode::Constant => (0.0, 0.0),
+        TangentMode::Linear => (left.unwrap_or(0.0), right.unwrap_or(0.0)),
+        TangentMode::Free => (
+            key.in_tangent.map_or(0.0, |t| t.component(c)),
+            key.out_tangent.map_or(0.0, |t| t.component(c)),
+        ),
+        TangentMode::Auto | TangentMode::Clamped => {
+            let (Some(p), Some(n)) = (prev, next) else { return (0.0, 0.0) };
+            let dt = n.time - p.time;
+            let mut m = if dt.abs() < f32::EPSILON { 0.0 } else { (n.value.component(c) - p.value.component(c)) / dt };
+            if key.tangent_mode == TangentMode::Clamped {
+                let (l, r) = (left.unwrap_or(0.0), right.unwrap_or(0.0));
+                if l * r <= 0.0 {
+                    // 局部极值处放平
+                    m = 0.0;
+                } else {
+                    // Fritsch-Carlson 单调性约束，避免过冲
+                    let limit = 3.0 * l.abs().min(r.abs());
+                    m = m.clamp(-limit, limit);
+                }
+            }
+            (m, m)
+        }
+    }
+}
+
+/// 计算关键帧 i 的 (入切线, 出切线) 斜率
+pub fn key_tangents<T: CurveValue>(keys: &[Keyframe<T>], i: usize) -> (T, T) {
+    let mut t_in = T::zero();
+    let mut t_out = T::zero();
+    for c in 0..T::COMPONENTS {
+        let (a, b) = component_tangents(keys, i, c);
+        t_in.set_component(c, a);
+        t_out.set_component(c, b);
+    }
+    (t_in, t_out)
+}
+
+/// 定位时间所在的关键帧段，返回左侧关键帧索引；超出范围时返回 None
+fn find_segment<T>(keys: &[Keyframe<T>], time: f32) -> Option<usize> {
+    if keys.len() < 2 || time <= keys[0].time || time >= keys[keys.len() - 1].time {
+        return None;
+    }
+    // keyframes 按时间有序，使用二分查找
+    let idx = keys.partition_point(|k| k.time <= time);
+    Some(idx - 1)
+}
+
+impl<T: CurveValue> AnimationTrack<T> {
+    /// 采样曲线在指定时间的值
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let keys = &self.keyframes;
+        let first = keys.first()?;
+        let last = keys.last()?;
+        if time <= first.time { return Some(first.value); }
+        if time >= last.time { return Some(last.value); }
+
+        let i = find_segment(keys, time)?;
+        let (k0, k1) = (&keys[i], &keys[i + 1]);
+        if k0.tangent_mode == TangentMode::Constant {
+            return Some(k0.value);
+        }
+        let dt = k1.time - k0.time;
+        if dt <= f32::EPSILON {
+            return Some(k1.value);
+        }
+        let s = (time - k0.time) / dt;
+        let mut result = T::zero();
+        for c in 0..T::COMPONENTS {
+            let (_, m0) = component_tangents(keys, i, c);
+            let (m1, _) = component_tangents(keys, i + 1, c);
+            result.set_component(c, hermite(k0.value.component(c), m0, k1.value.component(c), m1, dt, s));
+        }
+        Some(result)
+    }
+
+    /// 关键帧 i 的切线斜率 (入, 出)
+    pub fn tangents(&self, i: usize) -> (T, T) {
+        key_tangents(&self.keyframes, i)
+    }
+
+    /// 修改关键帧 i 某一分量的切线，并将其转换为自由模式
+    ///
+    /// 其余分量保留当前模式下计算出的切线，因此转换不会改变曲线形状。
+    pub fn set_component_tangent(&mut self, i: usize, component: usize, in_slope: Option<f32>, out_slope: Option<f32>) {
+        if i >= self.keyframes.len() {
+            return;
+        }
+        let (mut t_in, mut t_out) = self.tangents(i);
+        if let Some(m) = in_slope { t_in.set_component(component, m); }
+        if let Some(m) = out_slope { t_out.set_component(component, m); }
+        let key = &mut self.keyframes[i];
+        key.tangent_mode = TangentMode::Free;
+        key.in_tangent = Some(t_in);
+        key.out_tangent = Some(t_out);
+    }
+}
+
+impl<T: Copy> AnimationTrack<T> {
+    /// 阶跃采样：保持左侧关键帧的值，用于不可插值的类型 (如布尔值)
+    pub fn sample_step(&self, time: f32) -> Option<T> {
+        let idx = self.keyframes.partition_point(|k| k.time <= time);
+        self.keyframes.get(idx.saturating_sub(1)).map(|k| k.value)
+    }
+}
+
+impl<T> AnimationTrack<T> {
+    /// 设置指定关键帧的切线模式
+    pub fn set_tangent_mode(&mut self, indices: &[usize], mode: TangentMode) {
+        for &i in indices {
+            if let Some(key) = self.keyframes.get_mut(i) {
+                key.tangent_mode = mode;
+            }
+        }
+    }
+}
+
+impl AnimationTrack<Quat> {
+    /// 采样旋转：常量关键帧保持，其余模式使用球面线性插值
+    ///
+    /// 旋转关键帧只支持 [`TangentMode::ROTATION`] 中的模式，见
+    /// [`AnimationChannel::set_tangent_mode`](crate::scene::AnimationChannel::set_tangent_mode)。
+    pub fn sample_rotation(&self, time: f32) -> Option<Quat> {
+        let keys = &self.keyframes;
+        let first = keys.first()?;
+        let last = keys.last()?;
+        if time <= first.time { return Some(first.value); }
+        if time >= last.time { return Some(last.value); }
+
+        let i = find_segment(keys, time)?;
+        let (k0, k1) = (&keys[i], &keys[i + 1]);
+        if k0.tangent_mode == TangentMode::Constant {
+            return Some(k0.value);
+        }
+        let dt = k1.time - k0.time;
+        if dt <= f32::EPSILON {
+            return Some(k1.value);
+        }
+        Some(k0.value.slerp(k1.value, (time - k0.time) / dt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(points: &[(f32, f32)], mode: TangentMode) -> AnimationTrack<f32> {
+        AnimationTrack::new(
+            points
+                .iter()
+                .map(|&(t, v)| Keyframe { tangent_mode: mode, ..Keyframe::new(t, v) })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_linear_matches_lerp() {
+        let t = track(&[(0.0, 0.0), (1.0, 2.0), (3.0, 0.0)], TangentMode::Linear);
+        assert!((t.sample(0.5).unwrap() - 1.0).abs() < 1e-5);
+        assert!((t.sample(2.0).unwrap() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_constant_holds_value() {
+        let t = track(&[(0.0, 1.0), (1.0, 5.0)], TangentMode::Constant);
+        assert_eq!(t.sample(0.99), Some(1.0));
+        assert_eq!(t.sample(1.0), Some(5.0));
+    }
+
+    #[test]
+    fn test_clamped_does_not_overshoot() {
+        let t = track(&[(0.0, 0.0), (1.0, 1.0), (2.0, 1.0), (3.0, 0.0)], TangentMode::Clamped);
+        for i in 0..=30 {
+            let v = t.sample(i as f32 * 0.1).unwrap();
+            assert!((-1e-5..=1.0 + 1e-5).contains(&v), "t={} v={}", i as f32 * 0.1, v);
+        }
+    }
+
+    #[test]
+    fn test_free_tangents_used() {
+        let mut t = track(&[(0.0, 0.0), (1.0, 0.0)], TangentMode::Free);
+        t.keyframes[0].out_tangent = Some(3.0);
+        // Hermite 起点导数应等于出切线
+        let h = 1e-3;
+        let slope = (t.sample(h).unwrap() - t.sample(0.0).unwrap()) / h;
+        assert!((slope - 3.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_moved_keys_replace_coincident_keys() {
+        let mut t = track(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)], TangentMode::Linear);
+        // 将 0 号关键帧移到 1 秒，并把 2、3 号缩放到同一时间
+        t.retime_keyframes(&[0], |_| 1.0);
+        t.retime_keyframes(&[2, 3], |_| 2.5);
+        t.merge_coincident_keyframes(&[0, 2, 3]);
+        t.sort_keyframes();
+        let keys: Vec<(f32, f32)> = t.keyframes.iter().map(|k| (k.time, k.value)).collect();
+        assert_eq!(keys, vec![(1.0, 0.0), (2.5, 3.0)]);
+    }
+}
@@ -0,0 +1,531 @@
+//! 反向动力学 (IK) 系统
+//!
+//! 此模块提供双骨骼 IK、FABRIK 链和注视 (Look-At) 约束组件及其求解器。
+//! 约束在动画采样之后、蒙皮之前运行，临时改写关节的局部 `Transform`；
+//! 蒙皮完成后通过 [`IkPose::restore`] 恢复求解前的姿态。
+//!
+//! 约束组件直接引用目标实体，不随场景保存。
+
+use crate::scene::{GlobalTransform, Parent, Transform, Children};
+use bevy_ecs::prelude::*;
+use glam::{Mat4, Quat, Vec3};
+use std::collections::HashMap;
+
+/// 数值计算容差
+const EPSILON: f32 = 1e-5;
+
+/// 双骨骼 IK 约束 (挂载在末端关节上，如脚踝/手腕)
+///
+/// 末端关节的父节点视为中间关节 (膝/肘)，祖父节点视为根关节 (髋/肩)。
+#[derive(Component, Debug, Clone)]
+pub struct TwoBoneIk {
+    /// 目标实体 (末端关节要到达的位置)
+    pub target: Entity,
+    /// 极向量目标 (决定中间关节的弯曲方向)
+    pub pole_target: Option<Entity>,
+    /// 混合权重 (0 = 保持动画姿态, 1 = 完全 IK)
+    pub weight: f32,
+}
+
+impl TwoBoneIk {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            pole_target: None,
+            weight: 1.0,
+        }
+    }
+}
+
+/// FABRIK 链约束 (挂载在链末端关节上)
+#[derive(Component, Debug, Clone)]
+pub struct FabrikChain {
+    /// 目标实体
+    pub target: Entity,
+    /// 链中骨骼数量 (从末端向上数的父节点个数)
+    pub chain_length: usize,
+    /// 最大迭代次数
+    pub iterations: usize,
+    /// 收敛容差 (世界单位)
+    pub tolerance: f32,
+    /// 每个关节相对父骨骼方向的最大弯曲角 (弧度)，按从根到末端排列，缺省表示不限制
+    pub joint_limits: Vec<f32>,
+    /// 混合权重
+    pub weight: f32,
+}
+
+impl FabrikChain {
+    pub fn new(target: Entity, chain_length: usize) -> Self {
+        Self {
+            target,
+            chain_length,
+            iterations: 10,
+            tolerance: 0.001,
+            joint_limits: Vec::new(),
+            weight: 1.0,
+        }
+    }
+}
+
+/// 注视/瞄准约束 (挂载在需要转向目标的关节上，如头部)
+#[derive(Component, Debug, Clone)]
+pub struct LookAtConstraint {
+    /// 目标实体
+    pub target: Entity,
+    /// 关节局部空间中的瞄准轴
+    pub aim_axis: Vec3,
+    /// 相对动画姿态允许的最大转角 (弧度)
+    pub max_angle: Option<f32>,
+    /// 混合权重
+    pub weight: f32,
+}
+
+impl LookAtConstraint {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            aim_axis: Vec3::NEG_Z,
+            max_angle: None,
+            weight: 1.0,
+        }
+    }
+}
+
+/// 求解双骨骼 IK，返回根关节和中间关节的新世界旋转
+///
+/// `root`/`mid`/`end` 为三个关节的世界位置，`root_rot`/`mid_rot` 为根与中间关节当前的世界旋转。
+pub fn solve_two_bone(
+    root: Vec3,
+    mid: Vec3,
+    end: Vec3,
+    root_rot: Quat,
+    mid_rot: Quat,
+    target: Vec3,
+    pole: Option<Vec3>,
+) -> (Quat, Quat) {
+    let len_ab = (mid - root).length();
+    let len_cb = (end - mid).length();
+    if len_ab < EPSILON || len_cb < EPSILON {
+        return (root_rot, mid_rot);
+    }
+    let len_at = (target - root)
+        .length()
+        .clamp(EPSILON, len_ab + len_cb - EPSILON);
+
+    let ac = (end - root).normalize_or_zero();
+    let ab = (mid - root).normalize_or_zero();
+    let ba = (root - mid).normalize_or_zero();
+    let bc = (end - mid).normalize_or_zero();
+    let at = (target - root).normalize_or_zero();
+
+    // 当前夹角
+    let ac_ab_0 = ac.dot(ab).clamp(-1.0, 1.0).acos();
+    let ba_bc_0 = ba.dot(bc).clamp(-1.0, 1.0).acos();
+    let ac_at_0 = ac.dot(at).clamp(-1.0, 1.0).acos();
+
+    // 余弦定理求目标夹角
+    let ac_ab_1 = ((len_cb * len_cb - len_ab * len_ab - len_at * len_at) / (-2.0 * len_ab * len_at))
+        .clamp(-1.0, 1.0)
+        .acos();
+    let ba_bc_1 = ((len_at * len_at - len_ab * len_ab - len_cb * len_cb) / (-2.0 * len_ab * len_cb))
+        .clamp(-1.0, 1.0)
+        .acos();
+
+    // 弯曲平面：有极向量时由极向量决定，否则沿用当前中间关节方向
+    let bend_hint = pole.map(|p| p - root).unwrap_or(mid - root);
+    let mut axis0 = ac.cross(bend_hint).normalize_or_zero();
+    if axis0 == Vec3::ZERO {
+        axis0 = ac.any_orthonormal_vector();
+    }
+    let axis1 = ac.cross(at).normalize_or_zero();
+
+    let r0 = Quat::from_axis_angle(axis0, ac_ab_1 - ac_ab_0);
+    let r1 = Quat::from_axis_angle(axis0, ba_bc_1 - ba_bc_0);
+    let r2 = if axis1 == Vec3::ZERO {
+        Quat::IDENTITY
+    } else {
+        Quat::from_axis_angle(axis1, ac_at_0)
+    };
+
+    let new_root_rot = (r2 * r0 * root_rot).normalize();
+    let new_mid_rot = (r2 * r0 * r1 * mid_rot).normalize();
+    (new_root_rot, new_mid_rot)
+}
+
+/// 使用 FABRIK 求解关节链，原地修改关节世界位置
+///
+/// `positions` 从根到末端排列，`joint_limits[i]` 约束第 i 个关节处的弯曲角。
+/// 返回求解后末端到目标的距离。
+pub fn solve_fabrik(
+    positions: &mut [Vec3],
+    target: Vec3,
+    iterations: usize,
+    tolerance: f32,
+    joint_limits: &[f32],
+) -> f32 {
+    let n = positions.len();
+    if n < 2 {
+        return positions.first().map_or(0.0, |p| p.distance(target));
+    }
+
+    let lengths: Vec<f32> = (0..n - 1)
+        .map(|i| positions[i + 1].distance(positions[i]))
+        .collect();
+    let total_length: f32 = lengths.iter().sum();
+    let root = positions[0];
+
+    // 目标不可达：沿目标方向拉直
+    if root.distance(target) >= total_length {
+        let dir = (target - root).normalize_or_zero();
+        for i in 1..n {
+            positions[i] = positions[i - 1] + dir * lengths[i - 1];
+        }
+        apply_joint_limits(positions, &lengths, joint_limits);
+        return positions[n - 1].distance(target);
+    }
+
+    for _ in 0..iterations {
+        if positions[n - 1].distance(target) <= tolerance {
+            break;
+        }
+
+        // 反向：末端贴合目标
+        positions[n - 1] = target;
+        for i in (0..n - 1).rev() {
+            let dir = (positions[i] - positions[i + 1]).normalize_or_zero();
+            positions[i] = positions[i + 1] + dir * lengths[i];
+        }
+
+        // 正向：根节点回到原位
+        positions[0] = root;
+        for i in 0..n - 1 {
+            let dir = (positions[i + 1] - positions[i]).normalize_or_zero();
+            positions[i + 1] = positions[i] + dir * lengths[i];
+        }
+        apply_joint_limits(positions, &lengths, joint_limits);
+    }
+
+    positions[n - 1].distance(target)
+}
+
+/// 将每段骨骼方向限制在父骨骼方向的锥形范围内
+fn apply_joint_limits(positions: &mut [Vec3], lengths: &[f32], joint_limits: &[f32]) {
+    for i in 1..positions.len() - 1 {
+        let Some(&max_angle) = joint_limits.get(i) else { continue };
+        let parent_dir = (positions[i] - positions[i - 1]).normalize_or_zero();
+        let dir = (positions[i + 1] - positions[i]).normalize_or_zero();
+        if parent_dir == Vec3::ZERO || dir == Vec3::ZERO {
+            continue;
+        }
+        let angle = parent_dir.dot(dir).clamp(-1.0, 1.0).acos();
+        if angle > max_angle {
+            let limited = Quat::from_rotation_arc(parent_dir, dir)
+                .slerp(Quat::IDENTITY, 1.0 - max_angle / angle)
+                * parent_dir;
+            let offset = limited.normalize() * lengths[i];
+            let shift = positions[i] + offset - positions[i + 1];
+            for p in positions.iter_mut().skip(i + 1) {
+                *p += shift;
+            }
+        }
+    }
+}
+
+/// 计算使关节瞄准轴指向目标的新世界旋转
+pub fn solve_look_at(position: Vec3, rotation: Quat, aim_axis: Vec3, target: Vec3, max_angle: Option<f32>) -> Quat {
+    let current = (rotation * aim_axis).normalize_or_zero();
+    let desired = (target - position).normalize_or_zero();
+    if current == Vec3::ZERO || desired == Vec3::ZERO {
+        return rotation;
+    }
+    let mut delta = Quat::from_rotation_arc(current, desired);
+    if let Some(max_angle) = max_angle {
+        let angle = current.dot(desired).clamp(-1.0, 1.0).acos();
+        if angle > max_angle && angle > EPSILON {
+            delta = Quat::IDENTITY.slerp(delta, max_angle / angle);
+        }
+    }
+    (delta * rotation).normalize()
+}
+
+/// IK 求解前各关节的局部旋转
+///
+/// IK 结果只用于本帧的蒙皮与渲染。若不恢复，没有动画重置姿态时权重会逐帧累积，
+/// 求解结果也会被保存进场景。
+#[derive(Debug, Default)]
+pub struct IkPose {
+    rotations: HashMap<Entity, Quat>,
+}
+
+impl IkPose {
+    /// 记录关节第一次被 IK 改写前的旋转
+    fn record(&mut self, entity: Entity, rotation: Quat) {
+        self.rotations.entry(entity).or_insert(rotation);
+    }
+
+    /// 恢复求解前的局部旋转 (`GlobalTransform` 在下一次层级更新时重新计算)
+    pub fn restore(self, world: &mut World) {
+        for (entity, rotation) in self.rotations {
+            if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+                transform.bypass_change_detection().rotation = rotation;
+            }
+        }
+    }
+}
+
+/// 执行场景中所有 IK 约束 (顺序：双骨骼 → FABRIK → 注视)
+///
+/// 调用前需保证 `GlobalTransform` 已与当前动画姿态同步。
+/// 返回求解前的姿态，使用完求解结果后需调用 [`IkPose::restore`]。
+#[must_use]
+pub fn solve_ik_constraints(world: &mut World) -> IkPose {
+    let mut pose = IkPose::default();
+    let two_bone: Vec<(Entity, TwoBoneIk)> = world
+        .query::<(Entity, &TwoBoneIk)>()
+        .iter(world)
+        .map(|(e, c)| (e, c.clone()))
+        .collect();
+    for (end, ik) in two_bone {
+        apply_two_bone(world, end, &ik, &mut pose);
+    }
+
+    let chains: Vec<(Entity, FabrikChain)> = world
+        .query::<(Entity, &FabrikChain)>()
+        .iter(world)
+        .map(|(e, c)| (e, c.clone()))
+        .collect();
+    for (end, chain) in chains {
+        apply_fabrik(world, end, &chain, &mut pose);
+    }
+
+    let look_ats: Vec<(Entity, LookAtConstraint)> = world
+        .query::<(Entity, &LookAtConstraint)>()
+        .iter(world)
+        .map(|(e, c)| (e, c.clone()))
+        .collect();
+    for (entity, constraint) in look_ats {
+        apply_look_at(world, entity, &constraint, &mut pose);
+    }
+    pose
+}
+
+fn apply_two_bone(world: &mut World, end: Entity, ik: &TwoBoneIk, pose: &mut IkPose) {
+    if ik.weight <= 0.0 {
+        return;
+    }
+    let Some(mid) = world.get::<Parent>(end).map(|p| p.0) else { return };
+    let Some(root) = world.get::<Parent>(mid).map(|p| p.0) else { return };
+    let Some(target) = world_position(world, ik.target) else { return };
+    let pole = ik.pole_target.and_then(|p| world_position(world, p));
+
+    let (Some(root_m), Some(mid_m), Some(end_m)) = (global_matrix(world, root), global_matrix(world, mid), global_matrix(world, end)) else { return };
+    let (_, root_rot, root_pos) = root_m.to_scale_rotation_translation();
+    let (_, mid_rot, mid_pos) = mid_m.to_scale_rotation_translation();
+    let end_pos = end_m.w_axis.truncate();
+
+    let (new_root_rot, new_mid_rot) = solve_two_bone(root_pos, mid_pos, end_pos, root_rot, mid_rot, target, pole);
+
+    let root_parent_rot = parent_world_rotation(world, root);
+    set_world_rotation(world, pose, root, root_parent_rot, new_root_rot, ik.weight);
+    let root_rot_after = root_parent_rot * local_rotation(world, root);
+    set_world_rotation(world, pose, mid, root_rot_after, new_mid_rot, ik.weight);
+    propagate_global_transform(world, root);
+}
+
+fn apply_fabrik(world: &mut World, end: Entity, chain: &FabrikChain, pose: &mut IkPose) {
+    if chain.weight <= 0.0 || chain.chain_length == 0 {
+        return;
+    }
+    let Some(target) = world_position(world, chain.target) else { return };
+
+    // 从末端向上收集关节，再反转为根 → 末端
+    let mut joints = vec![end];
+    let mut current = end;
+    for _ in 0..chain.chain_length {
+        let Some(parent) = world.get::<Parent>(current).map(|p| p.0) else { return };
+        joints.push(parent);
+        current = parent;
+    }
+    joints.reverse();
+
+    let mut matrices = Vec::with_capacity(joints.len());
+    for &joint in &joints {
+        let Some(m) = global_matrix(world, joint) else { return };
+        matrices.push(m);
+    }
+    let original: Vec<Vec3> = matrices.iter().map(|m| m.w_axis.truncate()).collect();
+    let mut solved = original.clone();
+    solve_fabrik(&mut solved, target, chain.iterations, chain.tolerance, &chain.joint_limits);
+
+    let mut parent_rot = parent_world_rotation(world, joints[0]);
+    for i in 0..joints.len() - 1 {
+        let (_, rot, _) = matrices[i].to_scale_rotation_translation();
+        let old_dir = (original[i + 1] - original[i]).normalize_or_zero();
+        let new_dir = (solved[i + 1] - solved[i]).normalize_or_zero();
+        let new_rot = if old_dir == Vec3::ZERO || new_dir == Vec3::ZERO {
+            rot
+        } else {
+            (Quat::from_rotation_arc(old_dir, new_dir) * rot).normalize()
+        };
+        set_world_rotation(world, pose, joints[i], parent_rot, new_rot, chain.weight);
+        parent_rot *= local_rotation(world, joints[i]);
+    }
+    propagate_global_transform(world, joints[0]);
+}
+
+fn apply_look_at(world: &mut World, entity: Entity, constraint: &LookAtConstraint, pose: &mut IkPose) {
+    if constraint.weight <= 0.0 {
+        return;
+    }
+    let Some(target) = world_position(world, constraint.target) else { return };
+    let Some(m) = global_matrix(world, entity) else { return };
+    let (_, rot, pos) = m.to_scale_rotation_translation();
+    let new_rot = solve_look_at(pos, rot, constraint.aim_axis, target, constraint.max_angle);
+    let parent_rot = parent_world_rotation(world, entity);
+    set_world_rotation(world, pose, entity, parent_rot, new_rot, constraint.weight);
+    propagate_global_transform(world, entity);
+}
+
+fn global_matrix(world: &World, entity: Entity) -> Option<Mat4> {
+    world.get::<GlobalTransform>(entity).map(|gt| gt.0)
+}
+
+fn world_position(world: &World, entity: Entity) -> Option<Vec3> {
+    global_matrix(world, entity).map(|m| m.w_axis.truncate())
+}
+
+fn local_rotation(world: &World, entity: Entity) -> Quat {
+    world.get::<Transform>(entity).map_or(Quat::IDENTITY, |t| t.rotation)
+}
+
+fn parent_world_rotation(world: &World, entity: Entity) -> Quat {
+    world
+        .get::<Parent>(entity)
+        .and_then(|p| global_matrix(world, p.0))
+        .map_or(Quat::IDENTITY, |m| m.to_scale_rotation_translation().1)
+}
+
+/// 将世界旋转转换为局部旋转，并按权重与原局部旋转混合后写回
+///
+/// 写入不触发变更检测：求解结果会在蒙皮后恢复，不是对场景的编辑。
+fn set_world_rotation(world: &mut World, pose: &mut IkPose, entity: Entity, parent_rot: Quat, world_rot: Quat, weight: f32) {
+    if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+        let transform = transform.bypass_change_detection();
+        pose.record(entity, transform.rotation);
+        let local = (parent_rot.inverse() * world_rot).normalize();
+        transform.rotation = transform.rotation.slerp(local, weight.clamp(0.0, 1.0)).normalize();
+    }
+}
+
+/// 重新计算实体及其子树的 `GlobalTransform`
+pub fn propagate_global_transform(world: &mut World, entity: Entity) {
+    let parent_global = world
+        .get::<Parent>(entity)
+        .and_then(|p| global_matrix(world, p.0))
+        .unwrap_or(Mat4::IDENTITY);
+    propagate_recursive(world, entity, parent_global);
+}
+
+fn propagate_recursive(world: &mut World, entity: Entity, parent_global: Mat4) {
+    let local = world.get::<Transform>(entity).map_or(Mat4::IDENTITY, |t| t.compute_matrix());
+    let global = parent_global * local;
+    if let Some(mut gt) = world.get_mut::<GlobalTransform>(entity) {
+        // 只在变化时写入，保持 GlobalTransform 变更检测对增量物理同步有效
+        if gt.0 != global {
+            gt.0 = global;
+        }
+    }
+    let children = world.get::<Children>(entity).map(|c| c.0.clone());
+    for child in children.into_iter().flatten() {
+        propagate_recursive(world, child, global);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构建一条沿 +Y 方向、每段长 1 的关节链
+    fn spawn_chain(world: &mut World, count: usize) -> Vec<Entity> {
+        let mut joints = Vec::new();
+        for i in 0..count {
+            let offset = if i == 0 { Vec3::ZERO } else { Vec3::Y };
+            let mut entity = world.spawn((Transform::from_translation(offset), GlobalTransform::default()));
+            if let Some(&parent) = joints.last() {
+                entity.insert(Parent(parent));
+            }
+            let id = entity.id();
+            if let Some(&parent) = joints.last() {
+                world.entity_mut(parent).insert(Children(vec![id]));
+            }
+            joints.push(id);
+        }
+        propagate_global_transform(world, joints[0]);
+        joints
+    }
+
+    #[test]
+    fn test_two_bone_reaches_target() {
+        let mut world = World::new();
+        let joints = spawn_chain(&mut world, 3);
+        let target = world.spawn((Transform::default(), GlobalTransform(Mat4::from_translation(Vec3::new(1.0, 1.0, 0.0))))).id();
+        world.entity_mut(joints[2]).insert(TwoBoneIk::new(target));
+
+        let _ = solve_ik_constraints(&mut world);
+
+        let end = world_position(&world, joints[2]).unwrap();
+        assert!(end.distance(Vec3::new(1.0, 1.0, 0.0)) < 1e-3, "末端位置 {:?}", end);
+    }
+
+    #[test]
+    fn test_partial_weight_does_not_accumulate() {
+        let mut world = World::new();
+        let joints = spawn_chain(&mut world, 3);
+        let target = world.spawn((Transform::default(), GlobalTransform(Mat4::from_translation(Vec3::new(1.0, 1.0, 0.0))))).id();
+        world.entity_mut(joints[2]).insert(TwoBoneIk { weight: 0.5, ..TwoBoneIk::new(target) });
+        let authored: Vec<Quat> = joints.iter().map(|&j| local_rotation(&world, j)).collect();
+
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            let pose = solve_ik_constraints(&mut world);
+            results.push(world_position(&world, joints[2]).unwrap());
+            pose.restore(&mut world);
+            propagate_global_transform(&mut world, joints[0]);
+        }
+
+        assert!(results[0].distance(results[1]) < 1e-5, "{:?} != {:?}", results[0], results[1]);
+        assert!(results[0].distance(Vec3::new(1.0, 1.0, 0.0)) > 0.1, "半权重不应完全到达目标");
+        let restored: Vec<Quat> = joints.iter().map(|&j| local_rotation(&world, j)).collect();
+        assert_eq!(authored, restored);
+    }
+
+    #[test]
+    fn test_fabrik_unreachable_stretches() {
+        let mut positions = vec![Vec3::ZERO, Vec3::Y, Vec3::Y * 2.0];
+        let remaining = solve_fabrik(&mut positions, Vec3::new(5.0, 0.0, 0.0), 10, 0.001, &[]);
+        assert!((remaining - 3.0).abs() < 1e-4);
+        assert!(positions[2].distance(Vec3::new(2.0, 0.0, 0.0)) < 1e-4);
+    }
+
+    #[test]
+    fn test_fabrik_chain_reaches_target() {
+        let mut world = World::new();
+        let joints = spawn_chain(&mut world, 4);
+        let goal = Vec3::new(1.5, 1.5, 0.5);
+        let target = world.spawn((Transform::default(), GlobalTransform(Mat4::from_translation(goal)))).id();
+        world.entity_mut(joints[3]).insert(FabrikChain::new(target, 3));
+
+        let _ = solve_ik_constraints(&mut world);
+
+        let end = world_position(&world, joints[3]).unwrap();
+        assert!(end.distance(goal) < 0.01, "末端位置 {:?}", end);
+    }
+
+    #[test]
+    fn test_look_at_respects_max_angle() {
+        let rot = solve_look_at(Vec3::ZERO, Quat::IDENTITY, Vec3::NEG_Z, Vec3::X, Some(std::f32::consts::FRAC_PI_4));
+        let aim = rot * Vec3::NEG_Z;
+        let angle = aim.angle_between(Vec3::NEG_Z);
+        assert!((angle - std::f32::consts::FRAC_PI_4).abs() < 1e-3);
+    }
+}
@@ -102,6 +102,30 @@ pub mod math {
 /// 资源系统
 pub mod assets;
 
+/// 反向动力学 (IK) 系统
+pub mod ik;
+
+/// 动画曲线
+pub mod curve;
+
+/// 组件属性访问与属性动画
+pub mod property;
+
+/// 动画重定向
+pub mod retarget;
+
+/// 动画状态机求值
+pub mod state_machine;
+
+/// 蒙皮关节调色板与校验
+pub mod skinning;
+
+/// 从网格生成碰撞体形状
+pub mod collider;
+
+/// 布娃娃生成与姿态写回
+pub mod ragdoll;
+
 /// 场景系统
 pub mod scene {
     use super::*;
@@ -232,6 +256,12 @@ pub mod scene {
         pub tangent: Vec4, // 切线 (含副切线符号)
         pub joint_indices: [u32; 4], // 骨骼索引
         pub joint_weights: [f32; 4], // 骨骼权重
+        /// 第二组骨骼索引 (超过 4 个影响骨骼时使用)
+        #[serde(default)]
+        pub joint_indices_1: [u32; 4],
+        /// 第二组骨骼权重
+        #[serde(default)]
+        pub joint_weights_1: [f32; 4],
     }
 
     impl Vertex {
@@ -244,6 +274,8 @@ pub mod scene {
                 tangent: Vec4::new(1.0, 0.0, 0.0, 1.0), // 默认切线
                 joint_indices: [0; 4],
                 joint_weights: [0.0; 4],
+                joint_indices_1: [0; 4],
+                joint_weights_1: [0.0; 4],
             }
         }
 
@@ -256,6 +288,8 @@ pub mod scene {
                 tangent,
                 joint_indices: [0; 4],
                 joint_weights: [0.0; 4],
+                joint_indices_1: [0; 4],
+                joint_weights_1: [0.0; 4],
             }
         }
 
@@ -275,8 +309,27 @@ pub mod scene {
                 tangent,
                 joint_indices,
                 joint_weights,
+                joint_indices_1: [0; 4],
+                joint_weights_1: [0.0; 4],
             }
         }
+
+        /// 设置第二组骨骼影响 (对应 glTF 的 JOINTS_1 / WEIGHTS_1)
+        pub fn with_secondary_influences(mut self, joint_indices: [u32; 4], joint_weights: [f32; 4]) -> Self {
+            self.joint_indices_1 = joint_indices;
+            self.joint_weights_1 = joint_weights;
+            self
+        }
+
+        /// 所有非零权重的骨骼影响 (索引, 权重)
+        pub fn influences(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+            self.joint_indices
+                .iter()
+                .chain(&self.joint_indices_1)
+                .zip(self.joint_weights.iter().chain(&self.joint_weights_1))
+                .filter(|(_, &w)| w > 0.0)
+                .map(|(&i, &w)| (i, w))
+        }
     }
 
     /// 材质数据资源
@@ -333,12 +386,119 @@ pub mod scene {
     /// 脚本组件
     #[derive(Component, Debug, Clone, Serialize, Deserialize, Default)]
     pub struct Script {
-        /// 脚本代码
+        /// 脚本代码 (未设置 `path` 时使用)
         pub code: String,
+        /// 脚本文件路径，相对于项目脚本目录 (如 `enemy/patrol.rhai`)
+        #[serde(default)]
+        pub path: Option<String>,
         /// 是否激活
         pub active: bool,
-        /// 最后一次运行错误
-        pub last_error: Option<String>,
+        /// 最后一次错误 (运行出错后脚本停用，需要在检查器中重新启用)
+        #[serde(default, deserialize_with = "deserialize_script_error")]
+        pub last_error: Option<ScriptError>,
+        /// 脚本通过 `param` 声明的可调参数 (值由检查器修改)
+        #[serde(default)]
+        pub parameters: Vec<ScriptParameter>,
+    }
+
+    impl Script {
+        /// 引用脚本文件的脚本组件
+        pub fn from_file(path: impl Into<String>) -> Self {
+            Self { path: Some(path.into()), active: true, ..Default::default() }
+        }
+
+        /// 是否有可执行的代码 (文件或内联代码)
+        pub fn has_source(&self) -> bool {
+            self.path.as_ref().map_or(!self.code.is_empty(), |path| !path.is_empty())
+        }
+    }
+
+    /// 脚本错误的类别
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum ScriptErrorKind {
+        /// 语法错误或无法读取的脚本文件
+        Compile,
+        /// 运行时错误
+        Runtime,
+        /// 超出操作次数、执行时间、内存或调用深度限制
+        Limit,
+    }
+
+    /// 带位置的脚本错误
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct ScriptError {
+        pub kind: ScriptErrorKind,
+        /// 出错的生命周期函数或回调 (如 `update`)，顶层语句为 None
+        pub hook: Option<String>,
+        pub message: String,
+        /// 出错的行号 (从 1 开始)
+        pub line: Option<usize>,
+        /// 出错的列号 (从 1 开始)
+        pub column: Option<usize>,
+    }
+
+    impl std::fmt::Display for ScriptError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(match self.kind {
+                ScriptErrorKind::Compile => "编译错误",
+                ScriptErrorKind::Runtime => "运行错误",
+                ScriptErrorKind::Limit => "超出限制",
+            })?;
+            let location: Vec<String> = self.hook.iter().cloned()
+                .chain(self.line.map(|line| match self.column {
+                    Some(column) => format!("行 {} 列 {}", line, column),
+                    None => format!("行 {}", line),
+                }))
+                .collect();
+            if !location.is_empty() {
+                write!(f, " ({})", location.join(", "))?;
+            }
+            write!(f, ": {}", self.message)
+        }
+    }
+
+    /// 兼容旧场景文件中以字符串保存的错误
+    fn deserialize_script_error<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<ScriptError>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum StoredError {
+            Structured(ScriptError),
+            Legacy(String),
+        }
+        Ok(Option::<StoredError>::deserialize(deserializer)?.map(|error| match error {
+            StoredError::Structured(error) => error,
+            StoredError::Legacy(message) => ScriptError {
+                kind: ScriptErrorKind::Runtime,
+                hook: None,
+                message,
+                line: None,
+                column: None,
+            },
+        }))
+    }
+
+    /// 脚本的可调参数
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct ScriptParameter {
+        pub name: String,
+        pub value: ScriptValue,
+    }
+
+    /// 脚本参数值
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub enum ScriptValue {
+        Float(f32),
+        Int(i64),
+        Bool(bool),
+        String(String),
+        Vec3(Vec3),
+    }
+
+    impl ScriptValue {
+        /// 两个值类型相同 (类型改变时参数恢复为脚本中的默认值)
+        pub fn same_type(&self, other: &ScriptValue) -> bool {
+            std::mem::discriminant(self) == std::mem::discriminant(other)
+        }
     }
 
     /// PBR 材质组件
@@ -429,6 +589,28 @@ pub mod scene {
         }
     }
 
+    /// 蒙皮方式
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+    pub enum SkinningMode {
+        /// 线性混合蒙皮 (LBS)
+        #[default]
+        LinearBlend,
+        /// 对偶四元数蒙皮 (DQS)，避免扭转时的 "糖纸" 塌陷
+        DualQuaternion,
+    }
+
+    impl SkinningMode {
+        pub const ALL: [SkinningMode; 2] = [SkinningMode::LinearBlend, SkinningMode::DualQuaternion];
+
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                SkinningMode::LinearBlend => "线性混合",
+                SkinningMode::DualQuaternion => "对偶四元数",
+            }
+        }
+    }
+
     /// 蒙皮组件
     #[derive(Component, Debug, Clone, Serialize, Deserialize)]
     pub struct Skin {
@@ -437,6 +619,26 @@ pub mod scene {
         pub inverse_bind_matrices: Vec<Mat4>,
         /// 骨骼节点引用
         pub joints: Vec<Entity>,
+        /// 渲染端关节调色板的标识，引用同一蒙皮的网格共享该调色板
+        #[serde(skip, default = "uuid::Uuid::new_v4")]
+        pub palette_id: uuid::Uuid,
+        /// 蒙皮方式
+        #[serde(default)]
+        pub mode: SkinningMode,
+    }
+
+    impl Skin {
+        pub fn new(name: String, inverse_bind_matrices: Vec<Mat4>, joints: Vec<Entity>) -> Self {
+            Self { name, inverse_bind_matrices, joints, palette_id: uuid::Uuid::new_v4(), mode: SkinningMode::LinearBlend }
+        }
+    }
+
+    /// 蒙皮网格组件：引用持有 [`Skin`] 的实体
+    ///
+    /// 多个网格可引用同一个蒙皮；未添加该组件但自身带有 Skin 的网格使用自身的蒙皮。
+    #[derive(Component, Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct SkinnedMesh {
+        pub skin: Entity,
     }
 
     /// 骨骼关节组件
@@ -459,20 +661,63 @@ pub mod scene {
         KinematicPositionBased,
     }
 
+    /// 刚体轴向锁定 (世界空间 X/Y/Z)
+    #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct AxisLocks {
+        pub translation: [bool; 3],
+        pub rotation: [bool; 3],
+    }
+
     /// 刚体组件
-    #[derive(Component, Debug, Clone, Serialize, Deserialize)]
+    ///
+    /// 速度字段在场景中保存为初始速度，模拟运行时每帧回读刚体的当前速度。
+    #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
     pub struct RigidBody {
         pub body_type: RigidBodyType,
+        /// 质量覆盖 (None 时由碰撞体密度计算)
+        pub mass: Option<f32>,
+        /// 质心覆盖 (局部空间，None 时由碰撞体形状计算)
+        pub center_of_mass: Option<Vec3>,
+        pub linear_damping: f32,
+        pub angular_damping: f32,
+        pub gravity_scale: f32,
+        pub linear_velocity: Vec3,
+        pub angular_velocity: Vec3,
+        pub locked_axes: AxisLocks,
+        /// 静止时是否允许进入睡眠
+        pub can_sleep: bool,
+        /// 是否处于睡眠状态 (保存为初始状态，模拟时回读)
+        pub sleeping: bool,
+        /// 连续碰撞检测，防止高速物体穿透
+        pub ccd_enabled: bool,
         #[serde(skip)]
         pub handle_index: Option<u32>,
         #[serde(skip)]
         pub handle_generation: Option<u32>,
     }
 
+    impl Default for RigidBody {
+        fn default() -> Self {
+            Self::new(RigidBodyType::Dynamic)
+        }
+    }
+
     impl RigidBody {
         pub fn new(body_type: RigidBodyType) -> Self {
             Self {
                 body_type,
+                mass: None,
+                center_of_mass: None,
+                linear_damping: 0.0,
+                angular_damping: 0.0,
+                gravity_scale: 1.0,
+                linear_velocity: Vec3::ZERO,
+                angular_velocity: Vec3::ZERO,
+                locked_axes: AxisLocks::default(),
+                can_sleep: true,
+                sleeping: false,
+                ccd_enabled: false,
                 handle_index: None,
                 handle_generation: None,
             }
@@ -480,7 +725,10 @@ pub mod scene {
     }
 
     /// 碰撞体形状
-    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    ///
+    /// 网格类形状直接保存几何数据 (网格局部空间)，以便随场景序列化；
+    /// 缩放由实体的全局变换在同步到物理世界时应用。
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
     pub enum ColliderShape {
         /// 球体 (半径)
         Ball { radius: f32 },
@@ -488,42 +736,462 @@ pub mod scene {
         Cuboid { half_extents: Vec3 },
         /// 胶囊体 (半高，半径)
         Capsule { half_height: f32, radius: f32 },
+        /// 圆柱体 (沿 Y 轴)
+        Cylinder { half_height: f32, radius: f32 },
+        /// 圆锥体 (沿 Y 轴，尖端朝上)
+        Cone { half_height: f32, radius: f32 },
+        /// 凸包 (由点集计算)
+        ConvexHull { points: Vec<Vec3> },
+        /// 凸分解 (适用于动态刚体的凹网格)
+        ConvexDecomposition { vertices: Vec<Vec3>, indices: Vec<[u32; 3]> },
+        /// 三角网格 (仅适用于静态/运动学刚体)
+        TriMesh { vertices: Vec<Vec3>, indices: Vec<[u32; 3]> },
+        /// 高度场：`heights` 按行存放 (行沿 Z 轴，列沿 X 轴)，`size` 为 XZ 范围与高度缩放
+        HeightField { rows: usize, cols: usize, heights: Vec<f32>, size: Vec3 },
+        /// 复合形状：由不带刚体的子实体上的碰撞体组合而成
+        Compound,
+    }
+
+    impl ColliderShape {
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                ColliderShape::Ball { .. } => "球体",
+                ColliderShape::Cuboid { .. } => "盒体",
+                ColliderShape::Capsule { .. } => "胶囊体",
+                ColliderShape::Cylinder { .. } => "圆柱体",
+                ColliderShape::Cone { .. } => "圆锥体",
+                ColliderShape::ConvexHull { .. } => "凸包",
+                ColliderShape::ConvexDecomposition { .. } => "凸分解",
+                ColliderShape::TriMesh { .. } => "三角网格",
+                ColliderShape::HeightField { .. } => "高度场",
+                ColliderShape::Compound => "复合 (子实体)",
+            }
+        }
+
+        /// 创建指定分辨率的平坦高度场
+        pub fn flat_height_field(rows: usize, cols: usize, size: Vec3) -> Self {
+            let (rows, cols) = (rows.max(2), cols.max(2));
+            ColliderShape::HeightField { rows, cols, heights: vec![0.0; rows * cols], size }
+        }
+    }
+
+    /// 碰撞层：所属层与可交互层的位掩码，每一位对应 [`PhysicsLayers`] 中的一个命名层
+    ///
+    /// 两个碰撞体仅当各自的所属层与对方的过滤层有交集时才会交互。
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct CollisionLayers {
+        pub memberships: u32,
+        pub filter: u32,
+    }
+
+    impl Default for CollisionLayers {
+        /// 属于第 0 层 (默认层)，与所有层交互
+        fn default() -> Self {
+            Self { memberships: 1, filter: u32::MAX }
+        }
+    }
+
+    impl CollisionLayers {
+        /// 属于所有层并与所有层交互 (用作查询过滤器时不过滤任何碰撞体)
+        pub const ALL: CollisionLayers = CollisionLayers { memberships: u32::MAX, filter: u32::MAX };
+
+        pub fn new(memberships: u32, filter: u32) -> Self {
+            Self { memberships, filter }
+        }
+
+        /// 是否与另一组碰撞层交互
+        pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+            self.memberships & other.filter != 0 && other.memberships & self.filter != 0
+        }
+    }
+
+    /// 项目的命名物理层配置 (最多 32 层，未命名的层不在界面中显示)
+    #[derive(Resource, Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct PhysicsLayers {
+        pub names: Vec<String>,
+    }
+
+    impl Default for PhysicsLayers {
+        fn default() -> Self {
+            let mut names = vec![String::new(); Self::MAX_LAYERS];
+            names[0] = "默认".to_string();
+            Self { names }
+        }
+    }
+
+    impl PhysicsLayers {
+        pub const MAX_LAYERS: usize = 32;
+
+        /// 已命名的层 (下标, 名称)
+        pub fn named(&self) -> impl Iterator<Item = (usize, &str)> {
+            self.names.iter().enumerate().take(Self::MAX_LAYERS).filter(|(_, n)| !n.is_empty()).map(|(i, n)| (i, n.as_str()))
+        }
+
+        /// 按名称获取层的位掩码
+        pub fn mask(&self, name: &str) -> Option<u32> {
+            self.named().find(|(_, n)| *n == name).map(|(i, _)| 1 << i)
+        }
+
+        /// 掩码中各层名称的简短描述
+        pub fn describe(&self, mask: u32) -> String {
+            if mask == u32::MAX {
+                return "全部".to_string();
+            }
+            let names: Vec<&str> = self.named().filter(|(i, _)| mask & (1 << i) != 0).map(|(_, n)| n).collect();
+            if names.is_empty() { "无".to_string() } else { names.join(", ") }
+        }
     }
 
     /// 碰撞体组件
-    #[derive(Component, Debug, Clone, Serialize, Deserialize)]
+    #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct Collider {
         pub shape: ColliderShape,
         pub friction: f32,
         pub restitution: f32,
+        /// 形状中心相对实体原点的偏移 (局部空间)
+        #[serde(default)]
+        pub offset: Vec3,
+        /// 密度 (刚体未设置质量覆盖时用于计算质量)
+        #[serde(default = "default_density")]
+        pub density: f32,
+        /// 接触力事件阈值，为 None 时不产生接触力事件
+        #[serde(default)]
+        pub contact_force_threshold: Option<f32>,
+        /// 传感器 (触发区域)：只检测重叠并产生事件，不产生碰撞响应
+        #[serde(default)]
+        pub is_sensor: bool,
+        /// 碰撞层
+        #[serde(default)]
+        pub layers: CollisionLayers,
         #[serde(skip)]
         pub handle_index: Option<u32>,
         #[serde(skip)]
         pub handle_generation: Option<u32>,
     }
 
+    fn default_density() -> f32 {
+        1.0
+    }
+
     impl Collider {
-        pub fn ball(radius: f32) -> Self {
+        pub fn new(shape: ColliderShape) -> Self {
             Self {
-                shape: ColliderShape::Ball { radius },
+                shape,
                 friction: 0.5,
                 restitution: 0.0,
+                offset: Vec3::ZERO,
+                density: default_density(),
+                contact_force_threshold: None,
+                is_sensor: false,
+                layers: CollisionLayers::default(),
                 handle_index: None,
                 handle_generation: None,
             }
         }
 
+        pub fn ball(radius: f32) -> Self {
+            Self::new(ColliderShape::Ball { radius })
+        }
+
         pub fn cuboid(hx: f32, hy: f32, hz: f32) -> Self {
+            Self::new(ColliderShape::Cuboid { half_extents: Vec3::new(hx, hy, hz) })
+        }
+    }
+
+    /// 物理关节类型
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+    pub enum PhysicsJointKind {
+        /// 固定关节 (锁定全部自由度)
+        Fixed,
+        /// 旋转关节 (绕轴旋转，限位单位为弧度)
+        Revolute,
+        /// 滑动关节 (沿轴平移，限位单位为米)
+        Prismatic,
+        /// 球形关节 (仅锁定平移)
+        Spherical,
+        /// 绳索关节 (锚点间距离不超过最大长度)
+        Rope { max_length: f32 },
+        /// 弹簧关节 (锚点间距离趋向静止长度)
+        Spring { rest_length: f32, stiffness: f32, damping: f32 },
+    }
+
+    impl PhysicsJointKind {
+        pub const ALL: [PhysicsJointKind; 6] = [
+            PhysicsJointKind::Fixed,
+            PhysicsJointKind::Revolute,
+            PhysicsJointKind::Prismatic,
+            PhysicsJointKind::Spherical,
+            PhysicsJointKind::Rope { max_length: 1.0 },
+            PhysicsJointKind::Spring { rest_length: 1.0, stiffness: 50.0, damping: 1.0 },
+        ];
+
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                PhysicsJointKind::Fixed => "固定",
+                PhysicsJointKind::Revolute => "旋转",
+                PhysicsJointKind::Prismatic => "滑动",
+                PhysicsJointKind::Spherical => "球形",
+                PhysicsJointKind::Rope { .. } => "绳索",
+                PhysicsJointKind::Spring { .. } => "弹簧",
+            }
+        }
+
+        /// 是否使用轴、限位与马达 (仅旋转与滑动关节)
+        pub fn has_axis(&self) -> bool {
+            matches!(self, PhysicsJointKind::Revolute | PhysicsJointKind::Prismatic)
+        }
+    }
+
+    /// 关节马达：以刚度/阻尼驱动到目标位置，或以目标速度驱动
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+    pub struct JointMotor {
+        pub target_position: f32,
+        pub target_velocity: f32,
+        pub stiffness: f32,
+        pub damping: f32,
+        pub max_force: f32,
+    }
+
+    impl Default for JointMotor {
+        fn default() -> Self {
+            Self { target_position: 0.0, target_velocity: 0.0, stiffness: 0.0, damping: 1.0, max_force: f32::MAX }
+        }
+    }
+
+    /// 物理关节组件：将本实体的刚体连接到 `connected` 实体的刚体
+    ///
+    /// 锚点分别位于两个刚体的局部空间中，`anchor1` 属于被连接的刚体，`anchor2` 属于本实体。
+    #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct PhysicsJoint {
+        pub kind: PhysicsJointKind,
+        /// 被连接的刚体实体 (场景文件中以 UUID 保存)
+        #[serde(skip, default = "placeholder_entity")]
+        pub connected: Entity,
+        pub anchor1: Vec3,
+        pub anchor2: Vec3,
+        /// 旋转/滑动轴 (两个刚体的局部空间)
+        pub axis: Vec3,
+        /// 旋转/滑动限位 [最小, 最大]
+        pub limits: Option<[f32; 2]>,
+        pub motor: Option<JointMotor>,
+        /// 被连接的两个刚体之间是否仍产生碰撞
+        pub contacts_enabled: bool,
+        #[serde(skip)]
+        pub handle_index: Option<u32>,
+        #[serde(skip)]
+        pub handle_generation: Option<u32>,
+    }
+
+    fn placeholder_entity() -> Entity {
+        Entity::PLACEHOLDER
+    }
+
+    impl PhysicsJoint {
+        pub fn new(kind: PhysicsJointKind, connected: Entity) -> Self {
             Self {
-                shape: ColliderShape::Cuboid { half_extents: Vec3::new(hx, hy, hz) },
-                friction: 0.5,
-                restitution: 0.0,
+                kind,
+                connected,
+                anchor1: Vec3::ZERO,
+                anchor2: Vec3::ZERO,
+                axis: Vec3::Y,
+                limits: None,
+                motor: None,
+                contacts_enabled: false,
+                handle_index: None,
+                handle_generation: None,
+            }
+        }
+    }
+
+    /// 运动学角色控制器组件
+    ///
+    /// 物理管理器为其创建运动学刚体和胶囊体碰撞体 (实体上不应再有 RigidBody)。
+    /// 每个物理步使用 `desired_movement` 移动角色，随后将其清零，因此脚本或输入需要每帧设置。
+    #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct CharacterController {
+        /// 胶囊体圆柱部分的半高
+        pub half_height: f32,
+        pub radius: f32,
+        /// 角色与障碍物之间保持的间隙
+        pub offset: f32,
+        /// 可自动登上的台阶最大高度，None 时不自动上台阶
+        pub max_step_height: Option<f32>,
+        /// 台阶顶部所需的最小宽度
+        pub min_step_width: f32,
+        /// 可攀爬的最大坡度 (度)
+        pub max_slope_climb_angle: f32,
+        /// 超过该坡度时角色沿斜坡下滑 (度)
+        pub min_slope_slide_angle: f32,
+        /// 贴地距离：下坡或走下台阶时在该距离内保持贴地，None 时不贴地
+        pub snap_to_ground: Option<f32>,
+        /// 碰到障碍物时是否沿表面滑动
+        pub slide: bool,
+        /// 离地时是否受重力下落
+        pub apply_gravity: bool,
+        /// 推动动态刚体时使用的角色质量，None 时不推动
+        pub push_mass: Option<f32>,
+        /// 移动时检测的碰撞层
+        pub layers: CollisionLayers,
+        /// 设置后模拟运行时由方向键驱动，值为移动速度
+        pub input_speed: Option<f32>,
+        /// 本帧期望的移动速度 (世界空间，米/秒)
+        #[serde(skip)]
+        pub desired_movement: Vec3,
+        /// 竖直方向速度 (重力与跳跃)
+        #[serde(skip)]
+        pub vertical_speed: f32,
+        /// 上一步结束时是否着地
+        #[serde(skip)]
+        pub grounded: bool,
+        /// 上一步移动中碰到的实体
+        #[serde(skip)]
+        pub collisions: Vec<Entity>,
+        #[serde(skip)]
+        pub handle_index: Option<u32>,
+        #[serde(skip)]
+        pub handle_generation: Option<u32>,
+    }
+
+    impl Default for CharacterController {
+        fn default() -> Self {
+            Self {
+                half_height: 0.5,
+                radius: 0.3,
+                offset: 0.01,
+                max_step_height: Some(0.3),
+                min_step_width: 0.2,
+                max_slope_climb_angle: 45.0,
+                min_slope_slide_angle: 30.0,
+                snap_to_ground: Some(0.2),
+                slide: true,
+                apply_gravity: true,
+                push_mass: None,
+                layers: CollisionLayers::default(),
+                input_speed: None,
+                desired_movement: Vec3::ZERO,
+                vertical_speed: 0.0,
+                grounded: false,
+                collisions: Vec::new(),
                 handle_index: None,
                 handle_generation: None,
             }
         }
     }
 
+    /// 力场作用范围 (以实体的世界位姿为中心)
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+    pub enum ForceFieldVolume {
+        Sphere { radius: f32 },
+        Box { half_extents: Vec3 },
+    }
+
+    impl ForceFieldVolume {
+        /// 从中心到边界的最大距离 (用于衰减)
+        pub fn extent(&self) -> f32 {
+            match self {
+                ForceFieldVolume::Sphere { radius } => *radius,
+                ForceFieldVolume::Box { half_extents } => half_extents.length(),
+            }
+        }
+    }
+
+    /// 力场强度随距中心距离的衰减
+    #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+    pub enum ForceFieldFalloff {
+        /// 范围内强度不变
+        #[default]
+        None,
+        /// 从中心到边界线性减弱到 0
+        Linear,
+        /// 与 (1 + 距离²) 成反比
+        InverseSquare,
+    }
+
+    impl ForceFieldFalloff {
+        pub const ALL: [ForceFieldFalloff; 3] = [ForceFieldFalloff::None, ForceFieldFalloff::Linear, ForceFieldFalloff::InverseSquare];
+
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                ForceFieldFalloff::None => "无",
+                ForceFieldFalloff::Linear => "线性",
+                ForceFieldFalloff::InverseSquare => "平方反比",
+            }
+        }
+
+        /// 距中心 `distance` 处的强度系数，`extent` 为作用范围的边界距离
+        pub fn factor(&self, distance: f32, extent: f32) -> f32 {
+            match self {
+                ForceFieldFalloff::None => 1.0,
+                ForceFieldFalloff::Linear if extent > 0.0 => (1.0 - distance / extent).clamp(0.0, 1.0),
+                ForceFieldFalloff::Linear => 0.0,
+                ForceFieldFalloff::InverseSquare => 1.0 / (1.0 + distance * distance),
+            }
+        }
+    }
+
+    /// 力场类型 (方向与轴位于实体的局部空间)
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+    pub enum ForceFieldKind {
+        /// 定向风：沿 `direction` 施加力 (牛)
+        Wind { direction: Vec3, strength: f32 },
+        /// 爆炸：启用后的下一个物理步沿径向施加一次冲量 (牛·秒)，随后自动禁用
+        Explosion { strength: f32 },
+        /// 涡旋：绕 `axis` 切向施加力，`pull` 为指向轴线的力 (负值向外)
+        Vortex { axis: Vec3, strength: f32, pull: f32 },
+        /// 阻力区域：与线速度/角速度成正比的阻力
+        Drag { linear: f32, angular: f32 },
+    }
+
+    impl ForceFieldKind {
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                ForceFieldKind::Wind { .. } => "风",
+                ForceFieldKind::Explosion { .. } => "爆炸",
+                ForceFieldKind::Vortex { .. } => "涡旋",
+                ForceFieldKind::Drag { .. } => "阻力",
+            }
+        }
+
+        /// 各类型的默认参数
+        pub fn presets() -> [ForceFieldKind; 4] {
+            [
+                ForceFieldKind::Wind { direction: Vec3::X, strength: 10.0 },
+                ForceFieldKind::Explosion { strength: 20.0 },
+                ForceFieldKind::Vortex { axis: Vec3::Y, strength: 10.0, pull: 2.0 },
+                ForceFieldKind::Drag { linear: 1.0, angular: 0.5 },
+            ]
+        }
+    }
+
+    /// 力场组件：每个物理步对范围内的动态刚体施加力
+    #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct ForceField {
+        pub kind: ForceFieldKind,
+        pub volume: ForceFieldVolume,
+        pub falloff: ForceFieldFalloff,
+        /// 只影响与这些层交互的碰撞体
+        pub layers: CollisionLayers,
+        pub enabled: bool,
+    }
+
+    impl Default for ForceField {
+        fn default() -> Self {
+            Self {
+                kind: ForceFieldKind::presets()[0],
+                volume: ForceFieldVolume::Sphere { radius: 5.0 },
+                falloff: ForceFieldFalloff::None,
+                layers: CollisionLayers::ALL,
+                enabled: true,
+            }
+        }
+    }
+
     impl Camera {
         /// 创建透视相机
         pub fn perspective(fov_y: f32, aspect_ratio: f32, near: f32, far: f32) -> Self {
@@ -587,6 +1255,19 @@ pub mod scene {
     pub struct Keyframe<T> {
         pub time: f32,
         pub value: T,
+        /// 切线模式
+        #[serde(default)]
+        pub tangent_mode: crate::curve::TangentMode,
+        /// 入切线斜率 (值/秒)，仅在自由模式下使用
+        pub in_tangent: Option<T>,
+        /// 出切线斜率 (值/秒)，仅在自由模式下使用
+        pub out_tangent: Option<T>,
+    }
+
+    impl<T> Keyframe<T> {
+        pub fn new(time: f32, value: T) -> Self {
+            Self { time, value, tangent_mode: Default::default(), in_tangent: None, out_tangent: None }
+        }
     }
 
     /// 动画轨道
@@ -596,38 +1277,15 @@ pub mod scene {
     }
 
     impl AnimationTrack<glam::Vec3> {
+        /// 按关键帧切线模式采样 (见 [`crate::curve`])
         pub fn sample_vec3(&self, time: f32) -> Option<glam::Vec3> {
-            if self.keyframes.is_empty() { return None; }
-            if time <= self.keyframes[0].time { return Some(self.keyframes[0].value); }
-            if time >= self.keyframes.last().unwrap().time { return Some(self.keyframes.last().unwrap().value); }
-
-            for i in 0..self.keyframes.len() - 1 {
-                let k1 = &self.keyframes[i];
-                let k2 = &self.keyframes[i+1];
-                if time >= k1.time && time < k2.time {
-                    let factor = (time - k1.time) / (k2.time - k1.time);
-                    return Some(k1.value.lerp(k2.value, factor));
-                }
-            }
-            None
+            self.sample(time)
         }
     }
 
     impl AnimationTrack<glam::Quat> {
         pub fn sample_quat(&self, time: f32) -> Option<glam::Quat> {
-            if self.keyframes.is_empty() { return None; }
-            if time <= self.keyframes[0].time { return Some(self.keyframes[0].value); }
-            if time >= self.keyframes.last().unwrap().time { return Some(self.keyframes.last().unwrap().value); }
-
-            for i in 0..self.keyframes.len() - 1 {
-                let k1 = &self.keyframes[i];
-                let k2 = &self.keyframes[i+1];
-                if time >= k1.time && time < k2.time {
-                    let factor = (time - k1.time) / (k2.time - k1.time);
-                    return Some(k1.value.slerp(k2.value, factor));
-                }
-            }
-            None
+            self.sample_rotation(time)
         }
     }
 
@@ -635,6 +1293,84 @@ pub mod scene {
         pub fn new(keyframes: Vec<Keyframe<T>>) -> Self {
             Self { keyframes }
         }
+
+        /// 在指定时间插入关键帧，若该时间已有关键帧则覆盖其值
+        pub fn insert_keyframe(&mut self, time: f32, value: T) {
+            if let Some(kf) = self.keyframes.iter_mut().find(|kf| (kf.time - time).abs() < KEY_TIME_EPSILON) {
+                kf.value = value;
+            } else {
+                self.keyframes.push(Keyframe::new(time, value));
+                self.sort_keyframes();
+            }
+        }
+
+        /// 按时间排序关键帧
+        pub fn sort_keyframes(&mut self) {
+            self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+
+        /// 所有关键帧的时间
+        pub fn key_times(&self) -> Vec<f32> {
+            self.keyframes.iter().map(|kf| kf.time).collect()
+        }
+
+        /// 按映射函数修改指定关键帧的时间 (不会重新排序)
+        pub fn retime_keyframes(&mut self, indices: &[usize], f: impl Fn(f32) -> f32) {
+            for &i in indices {
+                if let Some(kf) = self.keyframes.get_mut(i) {
+                    kf.time = f(kf.time).max(0.0);
+                }
+            }
+        }
+
+        /// 移除与指定关键帧时间重合的其他关键帧 (不会重新排序)
+        ///
+        /// 用于移动或缩放关键帧之后，保证同一时间只有一个关键帧；
+        /// 指定的关键帧之间互相重合时保留索引最大的一个。
+        pub fn merge_coincident_keyframes(&mut self, keep: &[usize]) {
+            let kept: Vec<(usize, f32)> = keep.iter().filter_map(|&i| Some((i, self.keyframes.get(i)?.time))).collect();
+            let mut i = 0;
+            self.keyframes.retain(|kf| {
+                let index = i;
+                i += 1;
+                let is_kept = keep.contains(&index);
+                !kept.iter().any(|&(j, time)| j != index && (!is_kept || j > index) && (time - kf.time).abs() < KEY_TIME_EPSILON)
+            });
+        }
+
+        /// 删除指定索引的关键帧
+        pub fn remove_keyframes(&mut self, indices: &[usize]) {
+            let mut i = 0;
+            self.keyframes.retain(|_| {
+                let keep = !indices.contains(&i);
+                i += 1;
+                keep
+            });
+        }
+    }
+
+    /// 关键帧时间比较容差 (秒)
+    pub const KEY_TIME_EPSILON: f32 = 1e-4;
+
+    /// 变换轨道类型
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum TransformTrack {
+        Position,
+        Rotation,
+        Scale,
+    }
+
+    impl TransformTrack {
+        pub const ALL: [TransformTrack; 3] = [TransformTrack::Position, TransformTrack::Rotation, TransformTrack::Scale];
+
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                TransformTrack::Position => "位置",
+                TransformTrack::Rotation => "旋转",
+                TransformTrack::Scale => "缩放",
+            }
+        }
     }
 
     /// 动画通道 (针对特定节点的轨道)
@@ -646,12 +1382,118 @@ pub mod scene {
         pub scale_track: Option<AnimationTrack<Vec3>>,
     }
 
+    impl AnimationChannel {
+        pub fn new(target_name: String) -> Self {
+            Self {
+                target_name,
+                position_track: None,
+                rotation_track: None,
+                scale_track: None,
+            }
+        }
+
+        /// 是否包含指定类型的轨道
+        pub fn has_track(&self, track: TransformTrack) -> bool {
+            match track {
+                TransformTrack::Position => self.position_track.is_some(),
+                TransformTrack::Rotation => self.rotation_track.is_some(),
+                TransformTrack::Scale => self.scale_track.is_some(),
+            }
+        }
+
+        /// 指定轨道的关键帧时间
+        pub fn key_times(&self, track: TransformTrack) -> Vec<f32> {
+            match track {
+                TransformTrack::Position => self.position_track.as_ref().map(|t| t.key_times()),
+                TransformTrack::Rotation => self.rotation_track.as_ref().map(|t| t.key_times()),
+                TransformTrack::Scale => self.scale_track.as_ref().map(|t| t.key_times()),
+            }
+            .unwrap_or_default()
+        }
+
+        /// 以变换在指定时间记录关键帧
+        pub fn set_transform_key(&mut self, track: TransformTrack, time: f32, transform: &Transform) {
+            match track {
+                TransformTrack::Position => self.position_track.get_or_insert_with(|| AnimationTrack::new(Vec::new())).insert_keyframe(time, transform.position),
+                TransformTrack::Rotation => self.rotation_track.get_or_insert_with(|| AnimationTrack::new(Vec::new())).insert_keyframe(time, transform.rotation),
+                TransformTrack::Scale => self.scale_track.get_or_insert_with(|| AnimationTrack::new(Vec::new())).insert_keyframe(time, transform.scale),
+            }
+        }
+
+        /// 修改指定关键帧的时间 (索引保持不变，完成后需调用 `sort_keys`)
+        pub fn retime_keys(&mut self, track: TransformTrack, indices: &[usize], f: impl Fn(f32) -> f32) {
+            match track {
+                TransformTrack::Position => if let Some(t) = &mut self.position_track { t.retime_keyframes(indices, f); },
+                TransformTrack::Rotation => if let Some(t) = &mut self.rotation_track { t.retime_keyframes(indices, f); },
+                TransformTrack::Scale => if let Some(t) = &mut self.scale_track { t.retime_keyframes(indices, f); },
+            }
+        }
+
+        /// 移除与指定关键帧时间重合的其他关键帧，见 [`AnimationTrack::merge_coincident_keyframes`]
+        pub fn merge_coincident_keys(&mut self, track: TransformTrack, keep: &[usize]) {
+            match track {
+                TransformTrack::Position => if let Some(t) = &mut self.position_track { t.merge_coincident_keyframes(keep); },
+                TransformTrack::Rotation => if let Some(t) = &mut self.rotation_track { t.merge_coincident_keyframes(keep); },
+                TransformTrack::Scale => if let Some(t) = &mut self.scale_track { t.merge_coincident_keyframes(keep); },
+            }
+        }
+
+        /// 重新按时间排序所有轨道的关键帧
+        pub fn sort_keys(&mut self) {
+            if let Some(t) = &mut self.position_track { t.sort_keyframes(); }
+            if let Some(t) = &mut self.rotation_track { t.sort_keyframes(); }
+            if let Some(t) = &mut self.scale_track { t.sort_keyframes(); }
+        }
+
+        /// 指定的向量轨道 (位置或缩放)
+        pub fn vec3_track(&self, track: TransformTrack) -> Option<&AnimationTrack<Vec3>> {
+            match track {
+                TransformTrack::Position => self.position_track.as_ref(),
+                TransformTrack::Scale => self.scale_track.as_ref(),
+                TransformTrack::Rotation => None,
+            }
+        }
+
+        /// 指定的向量轨道 (可变)
+        pub fn vec3_track_mut(&mut self, track: TransformTrack) -> Option<&mut AnimationTrack<Vec3>> {
+            match track {
+                TransformTrack::Position => self.position_track.as_mut(),
+                TransformTrack::Scale => self.scale_track.as_mut(),
+                TransformTrack::Rotation => None,
+            }
+        }
+
+        /// 设置指定关键帧的切线模式
+        pub fn set_tangent_mode(&mut self, track: TransformTrack, indices: &[usize], mode: crate::curve::TangentMode) {
+            match track {
+                TransformTrack::Position => if let Some(t) = &mut self.position_track { t.set_tangent_mode(indices, mode); },
+                // 旋转只有线性与常量两种插值，其余模式会被忽略
+                TransformTrack::Rotation => if let Some(t) = self.rotation_track.as_mut().filter(|_| crate::curve::TangentMode::ROTATION.contains(&mode)) {
+                    t.set_tangent_mode(indices, mode);
+                },
+                TransformTrack::Scale => if let Some(t) = &mut self.scale_track { t.set_tangent_mode(indices, mode); },
+            }
+        }
+
+        /// 删除指定关键帧
+        pub fn remove_keys(&mut self, track: TransformTrack, indices: &[usize]) {
+            match track {
+                TransformTrack::Position => if let Some(t) = &mut self.position_track { t.remove_keyframes(indices); },
+                TransformTrack::Rotation => if let Some(t) = &mut self.rotation_track { t.remove_keyframes(indices); },
+                TransformTrack::Scale => if let Some(t) = &mut self.scale_track { t.remove_keyframes(indices); },
+            }
+        }
+    }
+
     /// 动画剪辑资源 (含多个通道)
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct AnimationClip {
         pub name: String,
         pub duration: f32,
         pub channels: Vec<AnimationChannel>,
+        /// 非变换属性的动画通道
+        #[serde(default)]
+        pub property_channels: Vec<crate::property::PropertyChannel>,
     }
 
     impl AnimationClip {
@@ -660,6 +1502,47 @@ pub mod scene {
                 name,
                 duration: 0.0,
                 channels: Vec::new(),
+                property_channels: Vec::new(),
+            }
+        }
+
+        /// 获取 (或创建) 指定目标的通道
+        pub fn channel_mut(&mut self, target_name: &str) -> &mut AnimationChannel {
+            let idx = match self.channels.iter().position(|c| c.target_name == target_name) {
+                Some(idx) => idx,
+                None => {
+                    self.channels.push(AnimationChannel::new(target_name.to_string()));
+                    self.channels.len() - 1
+                }
+            };
+            &mut self.channels[idx]
+        }
+
+        /// 在指定时间为目标记录变换关键帧并更新时长
+        pub fn set_transform_key(&mut self, target_name: &str, time: f32, transform: &Transform, tracks: &[TransformTrack]) {
+            let channel = self.channel_mut(target_name);
+            for &track in tracks {
+                channel.set_transform_key(track, time, transform);
+            }
+            self.update_duration();
+        }
+
+        /// 在指定时间为目标属性记录关键帧并更新时长
+        pub fn set_property_key(&mut self, target_name: &str, component: &str, field: &str, time: f32, value: crate::property::PropertyValue) {
+            let idx = match self.property_channels.iter().position(|c| c.targets(target_name, component, field)) {
+                Some(idx) => idx,
+                None => {
+                    self.property_channels.push(crate::property::PropertyChannel::new(
+                        target_name.to_string(),
+                        component.to_string(),
+                        field.to_string(),
+                        value.kind(),
+                    ));
+                    self.property_channels.len() - 1
+                }
+            };
+            if self.property_channels[idx].track.insert_keyframe(time, value) {
+                self.update_duration();
             }
         }
 
@@ -677,6 +1560,9 @@ pub mod scene {
                     for kf in &track.keyframes { max_time = max_time.max(kf.time); }
                 }
             }
+            for channel in &self.property_channels {
+                for t in channel.track.key_times() { max_time = max_time.max(t); }
+            }
             self.duration = max_time;
         }
     }
@@ -736,20 +1622,68 @@ pub mod scene {
     }
 
     /// 动画参数类型
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub enum AnimParamValue {
         Float(f32),
         Bool(bool),
         Trigger(bool), // 触发器：一旦满足就设为 false
+        Int(i32),
+        /// 枚举：当前选项索引与所有选项名称
+        Enum { value: usize, options: Vec<String> },
     }
 
     /// 动画转换条件
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub enum AnimCondition {
         Greater(String, f32),
         Less(String, f32),
         Bool(String, bool),
         Trigger(String),
+        IntEquals(String, i32),
+        IntNotEquals(String, i32),
+        IntGreater(String, i32),
+        IntLess(String, i32),
+        /// 枚举参数等于指定选项
+        EnumIs(String, String),
+        /// 枚举参数不等于指定选项
+        EnumIsNot(String, String),
+    }
+
+    /// 转换进行中可被哪些转换打断
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+    pub enum InterruptionSource {
+        /// 不可打断
+        #[default]
+        None,
+        /// 源状态的转换
+        Source,
+        /// 目标状态的转换
+        Destination,
+        /// 先检查源状态，再检查目标状态
+        SourceThenDestination,
+        /// 先检查目标状态，再检查源状态
+        DestinationThenSource,
+    }
+
+    impl InterruptionSource {
+        pub const ALL: [InterruptionSource; 5] = [
+            InterruptionSource::None,
+            InterruptionSource::Source,
+            InterruptionSource::Destination,
+            InterruptionSource::SourceThenDestination,
+            InterruptionSource::DestinationThenSource,
+        ];
+
+        /// 显示名称
+        pub fn label(&self) -> &'static str {
+            match self {
+                InterruptionSource::None => "无",
+                InterruptionSource::Source => "源状态",
+                InterruptionSource::Destination => "目标状态",
+                InterruptionSource::SourceThenDestination => "源状态→目标状态",
+                InterruptionSource::DestinationThenSource => "目标状态→源状态",
+            }
+        }
     }
 
     /// 动画转换
@@ -758,6 +1692,32 @@ pub mod scene {
         pub target_state: String,
         pub conditions: Vec<AnimCondition>,
         pub duration: f32,
+        /// 是否需要等待退出时间
+        #[serde(default)]
+        pub has_exit_time: bool,
+        /// 退出时间 (归一化，1.0 表示剪辑播放完一遍；小于 1 时每次循环都会生效)
+        #[serde(default)]
+        pub exit_time: f32,
+        /// 优先级，数值大的先检查；相同时按列表顺序
+        #[serde(default)]
+        pub priority: i32,
+        /// 转换进行中允许的打断来源
+        #[serde(default)]
+        pub interruption_source: InterruptionSource,
+    }
+
+    impl AnimationTransition {
+        pub fn new(target_state: String, duration: f32) -> Self {
+            Self {
+                target_state,
+                conditions: Vec::new(),
+                duration,
+                has_exit_time: false,
+                exit_time: 1.0,
+                priority: 0,
+                interruption_source: InterruptionSource::None,
+            }
+        }
     }
 
     /// 动画状态
@@ -766,6 +1726,36 @@ pub mod scene {
         pub name: String,
         pub clip_index: usize,
         pub transitions: Vec<AnimationTransition>,
+        /// 在状态机编辑器中的节点位置
+        #[serde(default)]
+        pub position: Vec2,
+        /// 子状态机：存在时该状态播放子状态机当前状态的剪辑
+        #[serde(default)]
+        pub sub_machine: Option<Box<AnimationStateMachine>>,
+    }
+
+    impl AnimationState {
+        pub fn new(name: String, clip_index: usize) -> Self {
+            Self {
+                name,
+                clip_index,
+                transitions: Vec::new(),
+                position: Vec2::ZERO,
+                sub_machine: None,
+            }
+        }
+    }
+
+    /// 正在进行的状态转换
+    #[derive(Debug, Clone)]
+    pub struct ActiveTransition {
+        pub from: String,
+        pub to: String,
+        pub elapsed: f32,
+        pub duration: f32,
+        pub interruption_source: InterruptionSource,
+        /// 源状态已播放的时间 (用于源状态转换的退出时间)
+        pub source_time: f32,
     }
 
     /// 动画状态机组件
@@ -774,6 +1764,27 @@ pub mod scene {
         pub states: HashMap<String, AnimationState>,
         pub parameters: HashMap<String, AnimParamValue>,
         pub current_state: String,
+        /// 可从任意状态触发的转换
+        #[serde(default)]
+        pub any_state_transitions: Vec<AnimationTransition>,
+        /// 作为子状态机被进入时的初始状态 (为空时保持当前状态)
+        #[serde(default)]
+        pub entry_state: String,
+        /// "任意状态" 节点在编辑器中的位置
+        #[serde(default)]
+        pub any_state_position: Vec2,
+        /// 最近一次转换前的状态 (用于编辑器高亮正在进行的转换)
+        #[serde(skip)]
+        pub previous_state: Option<String>,
+        /// 进入当前状态后经过的时间
+        #[serde(skip)]
+        pub state_time: f32,
+        /// 正在进行的转换
+        #[serde(skip)]
+        pub active_transition: Option<ActiveTransition>,
+        /// 是否已进入初始状态
+        #[serde(skip)]
+        pub initialized: bool,
     }
 
     impl AnimationStateMachine {
@@ -782,7 +1793,60 @@ pub mod scene {
                 states: HashMap::new(),
                 parameters: HashMap::new(),
                 current_state: initial_state,
+                any_state_transitions: Vec::new(),
+                entry_state: String::new(),
+                any_state_position: Vec2::new(-160.0, 0.0),
+                previous_state: None,
+                state_time: 0.0,
+                active_transition: None,
+                initialized: false,
+            }
+        }
+
+        /// 生成不与现有状态重名的名称
+        pub fn unique_state_name(&self, base: &str) -> String {
+            if !self.states.contains_key(base) {
+                return base.to_string();
+            }
+            (1..).map(|i| format!("{} {}", base, i)).find(|n| !self.states.contains_key(n)).unwrap()
+        }
+
+        /// 重命名状态，并更新所有指向它的转换
+        pub fn rename_state(&mut self, old: &str, new: &str) -> bool {
+            if old == new || self.states.contains_key(new) {
+                return false;
+            }
+            let Some(mut state) = self.states.remove(old) else { return false };
+            state.name = new.to_string();
+            self.states.insert(new.to_string(), state);
+            for state in self.states.values_mut() {
+                for transition in &mut state.transitions {
+                    if transition.target_state == old {
+                        transition.target_state = new.to_string();
+                    }
+                }
+            }
+            for transition in &mut self.any_state_transitions {
+                if transition.target_state == old {
+                    transition.target_state = new.to_string();
+                }
+            }
+            if self.current_state == old {
+                self.current_state = new.to_string();
+            }
+            if self.entry_state == old {
+                self.entry_state = new.to_string();
             }
+            true
+        }
+
+        /// 删除状态及所有指向它的转换
+        pub fn remove_state(&mut self, name: &str) {
+            self.states.remove(name);
+            for state in self.states.values_mut() {
+                state.transitions.retain(|t| t.target_state != name);
+            }
+            self.any_state_transitions.retain(|t| t.target_state != name);
         }
 
         pub fn set_float(&mut self, name: &str, value: f32) {
@@ -796,6 +1860,21 @@ pub mod scene {
         pub fn trigger(&mut self, name: &str) {
             self.parameters.insert(name.to_string(), AnimParamValue::Trigger(true));
         }
+
+        pub fn set_int(&mut self, name: &str, value: i32) {
+            self.parameters.insert(name.to_string(), AnimParamValue::Int(value));
+        }
+
+        /// 设置枚举参数的当前选项，选项不存在时返回 false
+        pub fn set_enum(&mut self, name: &str, option: &str) -> bool {
+            if let Some(AnimParamValue::Enum { value, options }) = self.parameters.get_mut(name) {
+                if let Some(i) = options.iter().position(|o| o == option) {
+                    *value = i;
+                    return true;
+                }
+            }
+            false
+        }
     }
 }
 
@@ -817,6 +1896,41 @@ impl Default for Time {
     }
 }
 
+impl Time {
+    /// 推进一帧 (每帧只调用一次)
+    pub fn advance(&mut self, delta: f32) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+}
+
+/// 可复现的伪随机数生成器 (SplitMix64)
+///
+/// 物理模拟录制时以录制中的种子初始化，回放时得到相同的随机序列。
+#[derive(Resource, Debug, Clone)]
+pub struct SimulationRng {
+    state: u64,
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) 区间内的随机数
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
 /// 输入系统
 #[derive(Resource, Debug, Default)]
 pub struct InputState {
@@ -896,6 +2010,52 @@ pub mod events {
         pub change_type: SceneChangeType,
     }
 
+    /// 物理碰撞事件：两个碰撞体开始/停止接触 (实体由碰撞体映射而来，不含传感器)
+    #[derive(Event, Debug, Clone, Copy, PartialEq)]
+    pub enum CollisionEvent {
+        Started { entity1: Entity, entity2: Entity },
+        /// `removed` 表示因碰撞体被删除而结束
+        Stopped { entity1: Entity, entity2: Entity, removed: bool },
+    }
+
+    impl CollisionEvent {
+        /// 事件涉及的两个实体
+        pub fn entities(&self) -> (Entity, Entity) {
+            match *self {
+                CollisionEvent::Started { entity1, entity2 } | CollisionEvent::Stopped { entity1, entity2, .. } => (entity1, entity2),
+            }
+        }
+
+        pub fn is_started(&self) -> bool {
+            matches!(self, CollisionEvent::Started { .. })
+        }
+    }
+
+    /// 传感器相交事件：碰撞体进入/离开传感器 (触发区域)
+    #[derive(Event, Debug, Clone, Copy, PartialEq)]
+    pub struct IntersectionEvent {
+        /// 传感器所在实体
+        pub sensor: Entity,
+        /// 进入/离开传感器的实体
+        pub other: Entity,
+        /// true 为进入，false 为离开
+        pub started: bool,
+    }
+
+    /// 接触力事件：接触力总大小超过碰撞体设置的阈值时产生
+    #[derive(Event, Debug, Clone, Copy, PartialEq)]
+    pub struct ContactForceEvent {
+        pub entity1: Entity,
+        pub entity2: Entity,
+        /// 所有接触力之和
+        pub total_force: Vec3,
+        /// 各接触力大小之和 (不等于 total_force 的长度)
+        pub total_force_magnitude: f32,
+        /// 最大接触力的方向 (世界空间单位向量)
+        pub max_force_direction: Vec3,
+        pub max_force_magnitude: f32,
+    }
+
     /// 场景变更类型
     #[derive(Debug, Clone)]
     pub enum SceneChangeType {
@@ -973,3 +2133,18 @@ pub fn test_ecs_and_assets() {
     
     println!("=== ECS和资源管理功能测试完成 ===");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_advances_once_per_frame() {
+        let mut time = Time::default();
+        time.advance(1.0 / 60.0);
+        assert_eq!(time.elapsed, time.delta);
+        time.advance(0.5);
+        assert_eq!(time.delta, 0.5);
+        assert_eq!(time.elapsed, 1.0 / 60.0 + 0.5);
+    }
+}
@@ -0,0 +1,453 @@
+//! 组件属性访问与属性动画
+//!
+//! 通过 "组件名 + 字段路径" 读写实体上的组件字段，使动画剪辑可以驱动
+//! Transform 以外的属性 (如灯光强度、材质颜色、相机视场角)。
+
+use crate::curve::TangentMode;
+use crate::scene::{AnimationTrack, Camera, DirectionalLight, PBRMaterial, Perspective, PointLight, Projection, Script, ScriptValue, SpotLight};
+use bevy_ecs::prelude::*;
+use glam::{Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+/// 属性值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PropertyValue {
+    Float(f32),
+    Vec3(Vec3),
+    Color(Vec4),
+    Bool(bool),
+}
+
+impl PropertyValue {
+    /// 值的类型
+    pub fn kind(&self) -> PropertyKind {
+        match self {
+            PropertyValue::Float(_) => PropertyKind::Float,
+            PropertyValue::Vec3(_) => PropertyKind::Vec3,
+            PropertyValue::Color(_) => PropertyKind::Color,
+            PropertyValue::Bool(_) => PropertyKind::Bool,
+        }
+    }
+
+    /// 按权重混合两个同类型的值 (布尔值在权重过半时切换)
+    pub fn blend(&self, other: &PropertyValue, alpha: f32) -> PropertyValue {
+        match (self, other) {
+            (PropertyValue::Float(a), PropertyValue::Float(b)) => PropertyValue::Float(a + (b - a) * alpha),
+            (PropertyValue::Vec3(a), PropertyValue::Vec3(b)) => PropertyValue::Vec3(a.lerp(*b, alpha)),
+            (PropertyValue::Color(a), PropertyValue::Color(b)) => PropertyValue::Color(a.lerp(*b, alpha)),
+            _ => if alpha >= 0.5 { *other } else { *self },
+        }
+    }
+}
+
+/// 属性类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PropertyKind {
+    Float,
+    Vec3,
+    Color,
+    Bool,
+}
+
+/// 属性动画轨道
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PropertyTrack {
+    Float(AnimationTrack<f32>),
+    Vec3(AnimationTrack<Vec3>),
+    Color(AnimationTrack<Vec4>),
+    /// 布尔轨道总是阶跃采样
+    Bool(AnimationTrack<bool>),
+}
+
+impl PropertyTrack {
+    /// 创建指定类型的空轨道
+    pub fn new(kind: PropertyKind) -> Self {
+        match kind {
+            PropertyKind::Float => PropertyTrack::Float(AnimationTrack::new(Vec::new())),
+            PropertyKind::Vec3 => PropertyTrack::Vec3(AnimationTrack::new(Vec::new())),
+            PropertyKind::Color => PropertyTrack::Color(AnimationTrack::new(Vec::new())),
+            PropertyKind::Bool => PropertyTrack::Bool(AnimationTrack::new(Vec::new())),
+        }
+    }
+
+    pub fn kind(&self) -> PropertyKind {
+        match self {
+            PropertyTrack::Float(_) => PropertyKind::Float,
+            PropertyTrack::Vec3(_) => PropertyKind::Vec3,
+            PropertyTrack::Color(_) => PropertyKind::Color,
+            PropertyTrack::Bool(_) => PropertyKind::Bool,
+        }
+    }
+
+    /// 采样轨道
+    pub fn sample(&self, time: f32) -> Option<PropertyValue> {
+        match self {
+            PropertyTrack::Float(t) => t.sample(time).map(PropertyValue::Float),
+            PropertyTrack::Vec3(t) => t.sample(time).map(PropertyValue::Vec3),
+            PropertyTrack::Color(t) => t.sample(time).map(PropertyValue::Color),
+            PropertyTrack::Bool(t) => t.sample_step(time).map(PropertyValue::Bool),
+        }
+    }
+
+    /// 插入关键帧，值类型与轨道不符时返回 false
+    pub fn insert_keyframe(&mut self, time: f32, value: PropertyValue) -> bool {
+        match (self, value) {
+            (PropertyTrack::Float(t), PropertyValue::Float(v)) => t.insert_keyframe(time, v),
+            (PropertyTrack::Vec3(t), PropertyValue::Vec3(v)) => t.insert_keyframe(time, v),
+            (PropertyTrack::Color(t), PropertyValue::Color(v)) => t.insert_keyframe(time, v),
+            (PropertyTrack::Bool(t), PropertyValue::Bool(v)) => t.insert_keyframe(time, v),
+            _ => return false,
+        }
+        true
+    }
+
+    /// 所有关键帧的时间
+    pub fn key_times(&self) -> Vec<f32> {
+        match self {
+            PropertyTrack::Float(t) => t.key_times(),
+            PropertyTrack::Vec3(t) => t.key_times(),
+            PropertyTrack::Color(t) => t.key_times(),
+            PropertyTrack::Bool(t) => t.key_times(),
+        }
+    }
+
+    /// 按映射函数修改指定关键帧的时间 (不会重新排序)
+    pub fn retime_keys(&mut self, indices: &[usize], f: impl Fn(f32) -> f32) {
+        match self {
+            PropertyTrack::Float(t) => t.retime_keyframes(indices, f),
+            PropertyTrack::Vec3(t) => t.retime_keyframes(indices, f),
+            PropertyTrack::Color(t) => t.retime_keyframes(indices, f),
+            PropertyTrack::Bool(t) => t.retime_keyframes(indices, f),
+        }
+    }
+
+    /// 移除与指定关键帧时间重合的其他关键帧 (不会重新排序)
+    pub fn merge_coincident_keys(&mut self, keep: &[usize]) {
+        match self {
+            PropertyTrack::Float(t) => t.merge_coincident_keyframes(keep),
+            PropertyTrack::Vec3(t) => t.merge_coincident_keyframes(keep),
+            PropertyTrack::Color(t) => t.merge_coincident_keyframes(keep),
+            PropertyTrack::Bool(t) => t.merge_coincident_keyframes(keep),
+        }
+    }
+
+    /// 重新按时间排序关键帧
+    pub fn sort_keys(&mut self) {
+        match self {
+            PropertyTrack::Float(t) => t.sort_keyframes(),
+            PropertyTrack::Vec3(t) => t.sort_keyframes(),
+            PropertyTrack::Color(t) => t.sort_keyframes(),
+            PropertyTrack::Bool(t) => t.sort_keyframes(),
+        }
+    }
+
+    /// 设置指定关键帧的切线模式 (布尔轨道总是阶跃，忽略)
+    pub fn set_tangent_mode(&mut self, indices: &[usize], mode: TangentMode) {
+        match self {
+            PropertyTrack::Float(t) => t.set_tangent_mode(indices, mode),
+            PropertyTrack::Vec3(t) => t.set_tangent_mode(indices, mode),
+            PropertyTrack::Color(t) => t.set_tangent_mode(indices, mode),
+            PropertyTrack::Bool(_) => {}
+        }
+    }
+
+    /// 删除指定关键帧
+    pub fn remove_keys(&mut self, indices: &[usize]) {
+        match self {
+            PropertyTrack::Float(t) => t.remove_keyframes(indices),
+            PropertyTrack::Vec3(t) => t.remove_keyframes(indices),
+            PropertyTrack::Color(t) => t.remove_keyframes(indices),
+            PropertyTrack::Bool(t) => t.remove_keyframes(indices),
+        }
+    }
+}
+
+/// 属性动画通道：目标实体名称 + 组件名 + 字段路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyChannel {
+    pub target_name: String,
+    pub component: String,
+    pub field: String,
+    pub track: PropertyTrack,
+}
+
+impl PropertyChannel {
+    pub fn new(target_name: String, component: String, field: String, kind: PropertyKind) -> Self {
+        Self { target_name, component, field, track: PropertyTrack::new(kind) }
+    }
+
+    /// 是否指向同一属性
+    pub fn targets(&self, target_name: &str, component: &str, field: &str) -> bool {
+        self.target_name == target_name && self.component == component && self.field == field
+    }
+
+    /// 显示标签，例如 `Lamp / PointLight.intensity`
+    pub fn label(&self) -> String {
+        format!("{} / {}.{}", self.target_name, self.component, self.field)
+    }
+}
+
+/// 组件的一个可动画字段
+///
+/// 列出、读取与写入属性共用同一张字段表，保证列出的属性都可以被读写。
+/// 只收录渲染或运行时实际使用的字段：例如平行光方向由 Transform 旋转决定，
+/// 灯光的 `cast_shadows` 尚未被渲染器读取，动画这些字段不会有任何效果。
+struct Field<C> {
+    path: &'static str,
+    kind: PropertyKind,
+    get: fn(&C) -> PropertyValue,
+    set: fn(&mut C, PropertyValue) -> bool,
+}
+
+/// 构造字段表项：`field!("路径", 类型, 成员)`，或通过访问函数定位嵌套结构
+/// `field!("路径", 类型, 读取函数, 写入函数, 成员)`
+macro_rules! field {
+    ($path:literal, $kind:ident, $($member:ident).+) => {
+        field!($path, $kind, std::convert::identity, std::convert::identity, $($member).+)
+    };
+    ($path:literal, $kind:ident, $get:path, $get_mut:path, $($member:ident).+) => {
+        Field {
+            path: $path,
+            kind: PropertyKind::$kind,
+            get: |c| PropertyValue::$kind($get(c).$($member).+),
+            set: |c, value| match value {
+                PropertyValue::$kind(v) => {
+                    $get_mut(c).$($member).+ = v;
+                    true
+                }
+                _ => false,
+            },
+        }
+    };
+}
+
+/// 拥有固定字段表的组件
+trait AnimatableComponent: Component + Sized {
+    const NAME: &'static str;
+    const FIELDS: &'static [Field<Self>];
+}
+
+impl AnimatableComponent for PointLight {
+    const NAME: &'static str = "PointLight";
+    const FIELDS: &'static [Field<Self>] = &[
+        field!("color", Vec3, color),
+        field!("intensity", Float, intensity),
+        field!("range", Float, range),
+    ];
+}
+
+impl AnimatableComponent for DirectionalLight {
+    const NAME: &'static str = "DirectionalLight";
+    const FIELDS: &'static [Field<Self>] = &[
+        field!("color", Vec3, color),
+        field!("intensity", Float, intensity),
+    ];
+}
+
+impl AnimatableComponent for SpotLight {
+    const NAME: &'static str = "SpotLight";
+    const FIELDS: &'static [Field<Self>] = &[
+        field!("color", Vec3, color),
+        field!("intensity", Float, intensity),
+        field!("range", Float, range),
+        field!("inner_angle", Float, inner_angle),
+        field!("outer_angle", Float, outer_angle),
+    ];
+}
+
+impl AnimatableComponent for PBRMaterial {
+    const NAME: &'static str = "PBRMaterial";
+    const FIELDS: &'static [Field<Self>] = &[
+        field!("base_color", Color, base_color),
+        field!("metallic", Float, metallic),
+        field!("roughness", Float, roughness),
+        field!("emissive", Vec3, emissive),
+    ];
+}
+
+fn perspective(camera: &Camera) -> &Perspective {
+    let Projection::Perspective(p) = &camera.projection;
+    p
+}
+
+fn perspective_mut(camera: &mut Camera) -> &mut Perspective {
+    let Projection::Perspective(p) = &mut camera.projection;
+    p
+}
+
+impl AnimatableComponent for Camera {
+    const NAME: &'static str = "Camera";
+    const FIELDS: &'static [Field<Self>] = &[
+        field!("projection.fov_y", Float, perspective, perspective_mut, fov_y),
+        field!("projection.near", Float, perspective, perspective_mut, near),
+        field!("projection.far", Float, perspective, perspective_mut, far),
+    ];
+}
+
+fn push_fields<C: AnimatableComponent>(world: &World, entity: Entity, props: &mut Vec<(&'static str, String, PropertyKind)>) {
+    if world.get::<C>(entity).is_some() {
+        props.extend(C::FIELDS.iter().map(|f| (C::NAME, f.path.to_string(), f.kind)));
+    }
+}
+
+fn get_field<C: AnimatableComponent>(world: &World, entity: Entity, field: &str) -> Option<PropertyValue> {
+    let component = world.get::<C>(entity)?;
+    C::FIELDS.iter().find(|f| f.path == field).map(|f| (f.get)(component))
+}
+
+fn set_field<C: AnimatableComponent>(world: &mut World, entity: Entity, field: &str, value: PropertyValue) -> bool {
+    let Some(f) = C::FIELDS.iter().find(|f| f.path == field && f.kind == value.kind()) else { return false };
+    let Some(mut component) = world.get_mut::<C>(entity) else { return false };
+    (f.set)(&mut component, value)
+}
+
+/// 列出实体上所有可动画的属性: (组件名, 字段路径, 类型)
+pub fn animatable_properties(world: &World, entity: Entity) -> Vec<(&'static str, String, PropertyKind)> {
+    let mut props = Vec::new();
+    push_fields::<PointLight>(world, entity, &mut props);
+    push_fields::<DirectionalLight>(world, entity, &mut props);
+    push_fields::<SpotLight>(world, entity, &mut props);
+    push_fields::<PBRMaterial>(world, entity, &mut props);
+    push_fields::<Camera>(world, entity, &mut props);
+    if let Some(script) = world.get::<Script>(entity) {
+        props.push(("Script", "active".to_string(), PropertyKind::Bool));
+        for parameter in &script.parameters {
+            if let Some(value) = parameter_value(&parameter.value) {
+                props.push(("Script", format!("{}{}", PARAMETER_PREFIX, parameter.name), value.kind()));
+            }
+        }
+    }
+    props
+}
+
+/// 脚本参数的字段路径前缀，例如 `parameters.speed`
+const PARAMETER_PREFIX: &str = "parameters.";
+
+/// 脚本参数值对应的属性值 (整数按浮点动画，字符串不可动画)
+fn parameter_value(value: &ScriptValue) -> Option<PropertyValue> {
+    match value {
+        ScriptValue::Float(v) => Some(PropertyValue::Float(*v)),
+        ScriptValue::Int(v) => Some(PropertyValue::Float(*v as f32)),
+        ScriptValue::Bool(v) => Some(PropertyValue::Bool(*v)),
+        ScriptValue::Vec3(v) => Some(PropertyValue::Vec3(*v)),
+        ScriptValue::String(_) => None,
+    }
+}
+
+/// 将属性值写入同类型的脚本参数，类型不符时返回 false
+fn set_parameter_value(target: &mut ScriptValue, value: PropertyValue) -> bool {
+    match (target, value) {
+        (ScriptValue::Float(t), PropertyValue::Float(v)) => *t = v,
+        (ScriptValue::Int(t), PropertyValue::Float(v)) => *t = v.round() as i64,
+        (ScriptValue::Bool(t), PropertyValue::Bool(v)) => *t = v,
+        (ScriptValue::Vec3(t), PropertyValue::Vec3(v)) => *t = v,
+        _ => return false,
+    }
+    true
+}
+
+/// 读取组件字段
+pub fn get_property(world: &World, entity: Entity, component: &str, field: &str) -> Option<PropertyValue> {
+    match component {
+        PointLight::NAME => get_field::<PointLight>(world, entity, field),
+        DirectionalLight::NAME => get_field::<DirectionalLight>(world, entity, field),
+        SpotLight::NAME => get_field::<SpotLight>(world, entity, field),
+        PBRMaterial::NAME => get_field::<PBRMaterial>(world, entity, field),
+        Camera::NAME => get_field::<Camera>(world, entity, field),
+        "Script" => {
+            let c = world.get::<Script>(entity)?;
+            match field {
+                "active" => Some(PropertyValue::Bool(c.active)),
+                _ => {
+                    let name = field.strip_prefix(PARAMETER_PREFIX)?;
+                    parameter_value(&c.parameters.iter().find(|p| p.name == name)?.value)
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// 写入组件字段，组件或字段不存在、类型不符时返回 false
+pub fn set_property(world: &mut World, entity: Entity, component: &str, field: &str, value: PropertyValue) -> bool {
+    match component {
+        PointLight::NAME => set_field::<PointLight>(world, entity, field, value),
+        DirectionalLight::NAME => set_field::<DirectionalLight>(world, entity, field, value),
+        SpotLight::NAME => set_field::<SpotLight>(world, entity, field, value),
+        PBRMaterial::NAME => set_field::<PBRMaterial>(world, entity, field, value),
+        Camera::NAME => set_field::<Camera>(world, entity, field, value),
+        "Script" => {
+            let Some(mut c) = world.get_mut::<Script>(entity) else { return false };
+            match (field, value) {
+                ("active", PropertyValue::Bool(v)) => {
+                    c.active = v;
+                    true
+                }
+                _ => {
+                    let Some(name) = field.strip_prefix(PARAMETER_PREFIX) else { return false };
+                    let Some(parameter) = c.parameters.iter_mut().find(|p| p.name == name) else { return false };
+                    set_parameter_value(&mut parameter.value, value)
+                }
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Keyframe, ScriptParameter};
+
+    #[test]
+    fn test_property_roundtrip() {
+        let mut world = World::new();
+        let e = world.spawn(PointLight::default()).id();
+        assert!(set_property(&mut world, e, "PointLight", "intensity", PropertyValue::Float(3.0)));
+        assert_eq!(get_property(&world, e, "PointLight", "intensity"), Some(PropertyValue::Float(3.0)));
+        // 类型不符
+        assert!(!set_property(&mut world, e, "PointLight", "intensity", PropertyValue::Bool(true)));
+        assert!(!set_property(&mut world, e, "PBRMaterial", "metallic", PropertyValue::Float(1.0)));
+    }
+
+    #[test]
+    fn test_listed_properties_are_readable_and_writable() {
+        let mut world = World::new();
+        let e = world.spawn((DirectionalLight::default(), SpotLight::default(), PBRMaterial::default(), Camera::perspective(1.0, 1.5, 0.1, 100.0))).id();
+        let props = animatable_properties(&world, e);
+        assert!(!props.iter().any(|(c, f, _)| *c == "DirectionalLight" && f == "direction"));
+        for (component, field, kind) in props {
+            let value = get_property(&world, e, component, &field).unwrap_or_else(|| panic!("{}.{}", component, field));
+            assert_eq!(value.kind(), kind);
+            assert!(set_property(&mut world, e, component, &field, value), "{}.{}", component, field);
+        }
+    }
+
+    #[test]
+    fn test_script_parameter_properties() {
+        let script = Script {
+            parameters: vec![
+                ScriptParameter { name: "speed".to_string(), value: ScriptValue::Int(2) },
+                ScriptParameter { name: "label".to_string(), value: ScriptValue::String(String::new()) },
+            ],
+            ..Default::default()
+        };
+        let mut world = World::new();
+        let e = world.spawn(script).id();
+        let fields: Vec<_> = animatable_properties(&world, e).into_iter().map(|(_, f, k)| (f, k)).collect();
+        assert!(fields.contains(&("parameters.speed".to_string(), PropertyKind::Float)));
+        assert!(!fields.iter().any(|(f, _)| f == "parameters.label"));
+
+        assert!(set_property(&mut world, e, "Script", "parameters.speed", PropertyValue::Float(3.6)));
+        assert_eq!(world.get::<Script>(e).unwrap().parameters[0].value, ScriptValue::Int(4));
+        assert_eq!(get_property(&world, e, "Script", "parameters.speed"), Some(PropertyValue::Float(4.0)));
+        assert!(!set_property(&mut world, e, "Script", "parameters.missing", PropertyValue::Float(1.0)));
+    }
+
+    #[test]
+    fn test_bool_track_steps() {
+        let track = PropertyTrack::Bool(AnimationTrack::new(vec![Keyframe::new(0.0, false), Keyframe::new(1.0, true)]));
+        assert_eq!(track.sample(0.9), Some(PropertyValue::Bool(false)));
+        assert_eq!(track.sample(1.0), Some(PropertyValue::Bool(true)));
+    }
+}
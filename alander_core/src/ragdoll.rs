@@ -0,0 +1,439 @@
+//! 布娃娃 (Ragdoll)
+//!
+//! [`build_ragdoll`] 根据 [`Skin`] 的骨骼为每根主要骨骼生成胶囊体和关节限位，
+//! 结果以 [`RagdollBone`] 组件挂载在关节实体上，[`Ragdoll`] 组件挂载在蒙皮实体上控制模式。
+//! 动画模式下刚体跟随关节的动画姿态 (运动学)，模拟模式下由物理驱动，
+//! 并在 `blend_duration` 内从最后的动画姿态过渡到模拟结果，再通过 [`write_bone_poses`] 写回关节。
+
+use crate::scene::{CollisionLayers, GlobalTransform, Name, Parent, Skin, Transform};
+use crate::skinning::{validate_skin, SkinningError};
+use bevy_ecs::prelude::*;
+use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 布娃娃模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RagdollMode {
+    /// 刚体跟随动画 (运动学)
+    #[default]
+    Animated,
+    /// 由物理模拟驱动关节
+    Simulated,
+}
+
+impl RagdollMode {
+    pub const ALL: [RagdollMode; 2] = [RagdollMode::Animated, RagdollMode::Simulated];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            RagdollMode::Animated => "动画",
+            RagdollMode::Simulated => "模拟",
+        }
+    }
+}
+
+/// 布娃娃组件 (挂载在蒙皮实体上)
+///
+/// 骨骼实体由蒙皮资源在加载时生成，因此布娃娃不随场景保存，需要在加载后重新生成。
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Ragdoll {
+    pub mode: RagdollMode,
+    /// 切换到模拟模式后从动画姿态过渡到模拟结果的时长 (秒)
+    pub blend_duration: f32,
+    /// 骨骼碰撞体的碰撞层
+    pub layers: CollisionLayers,
+    /// 进入模拟模式后经过的时间
+    pub blend_elapsed: f32,
+}
+
+impl Default for Ragdoll {
+    fn default() -> Self {
+        Self { mode: RagdollMode::Animated, blend_duration: 0.2, layers: CollisionLayers::default(), blend_elapsed: 0.0 }
+    }
+}
+
+impl Ragdoll {
+    /// 切换模式；进入模拟模式时重新开始姿态过渡
+    pub fn set_mode(&mut self, mode: RagdollMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.blend_elapsed = 0.0;
+        }
+    }
+
+    /// 当前模拟结果所占的权重 (0 = 动画姿态, 1 = 完全模拟)
+    pub fn blend_weight(&self) -> f32 {
+        if self.blend_duration <= 0.0 {
+            1.0
+        } else {
+            (self.blend_elapsed / self.blend_duration).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// 骨骼与父骨骼之间的关节
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RagdollJointKind {
+    /// 球形关节：绕关节轴的扭转与偏离关节轴的摆动分别限位 (度)
+    Spherical { swing_limit: f32, twist_limit: f32 },
+    /// 铰链关节：只能绕关节轴旋转，限位 [最小, 最大] (度)
+    Revolute { limits: [f32; 2] },
+}
+
+impl RagdollJointKind {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            RagdollJointKind::Spherical { .. } => "球形",
+            RagdollJointKind::Revolute { .. } => "铰链",
+        }
+    }
+}
+
+/// 布娃娃骨骼组件 (挂载在关节实体上)
+///
+/// 刚体原点与关节的世界位置和旋转一致 (不含缩放)，胶囊体的中心和朝向位于该空间中。
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct RagdollBone {
+    /// 持有 [`Ragdoll`] 的蒙皮实体
+    pub ragdoll: Entity,
+    /// 父骨骼所在的关节实体，为 None 时是根骨骼
+    pub parent: Option<Entity>,
+    /// 胶囊体中心
+    pub center: Vec3,
+    /// 胶囊体朝向 (将 Y 轴旋转到骨骼方向)
+    pub rotation: Quat,
+    /// 胶囊体圆柱部分的半高
+    pub half_height: f32,
+    pub radius: f32,
+    pub density: f32,
+    /// 关节轴：球形关节的扭转轴 (沿骨骼方向)，铰链关节的旋转轴
+    pub joint_axis: Vec3,
+    pub joint: RagdollJointKind,
+    /// 进入模拟模式时关节的世界位姿 (过渡起点)
+    pub blend_from: Option<(Vec3, Quat)>,
+    pub handle_index: Option<u32>,
+    pub handle_generation: Option<u32>,
+    pub joint_handle_index: Option<u32>,
+    pub joint_handle_generation: Option<u32>,
+}
+
+/// 生成布娃娃的参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RagdollSettings {
+    /// 短于该长度的骨骼 (如手指) 不生成刚体，由父骨骼带动
+    pub min_bone_length: f32,
+    /// 胶囊体半径与骨骼长度之比
+    pub radius_ratio: f32,
+    pub density: f32,
+    /// 球形关节的摆动/扭转限位 (度)
+    pub swing_limit: f32,
+    pub twist_limit: f32,
+    /// 铰链关节限位 (度)
+    pub hinge_limits: [f32; 2],
+    /// 名称包含这些关键字 (不区分大小写) 的关节使用铰链关节
+    pub hinge_keywords: Vec<String>,
+}
+
+impl Default for RagdollSettings {
+    fn default() -> Self {
+        Self {
+            min_bone_length: 0.05,
+            radius_ratio: 0.2,
+            density: 1.0,
+            swing_limit: 45.0,
+            twist_limit: 30.0,
+            hinge_limits: [0.0, 140.0],
+            hinge_keywords: ["knee", "elbow", "calf", "shin", "forearm", "lowerarm", "lower_arm", "lowerleg", "lower_leg"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 布娃娃生成错误
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RagdollError {
+    #[error(transparent)]
+    Skinning(#[from] SkinningError),
+    #[error("蒙皮 \"{0}\" 没有可生成刚体的骨骼")]
+    NoBones(String),
+}
+
+/// 为蒙皮的主要骨骼生成布娃娃骨骼 (不修改场景)
+///
+/// 有子关节的关节生成一根指向子关节 (多个子关节时取平均位置) 的骨骼，
+/// 短于 `min_bone_length` 的骨骼被跳过；没有父关节的根关节总是生成骨骼，使各部分能连接到一起。
+pub fn build_ragdoll(world: &World, skin_entity: Entity, settings: &RagdollSettings) -> Result<Vec<(Entity, RagdollBone)>, RagdollError> {
+    let skin = world.get::<Skin>(skin_entity).ok_or(SkinningError::MissingSkin(skin_entity))?;
+    validate_skin(skin)?;
+
+    let mut poses = HashMap::new();
+    for (index, &joint) in skin.joints.iter().enumerate() {
+        let global = world.get::<GlobalTransform>(joint).ok_or_else(|| SkinningError::MissingJoint { skin: skin.name.clone(), index })?;
+        let (_, rotation, position) = global.0.to_scale_rotation_translation();
+        poses.insert(joint, (position, rotation));
+    }
+
+    // 每个关节最近的关节祖先 (中间可能隔着非关节节点)
+    let joint_set: HashSet<Entity> = skin.joints.iter().copied().collect();
+    let joint_parent = |joint: Entity| {
+        let mut current = world.get::<Parent>(joint).map(|p| p.0);
+        while let Some(entity) = current {
+            if joint_set.contains(&entity) {
+                return Some(entity);
+            }
+            current = world.get::<Parent>(entity).map(|p| p.0);
+        }
+        None
+    };
+    let parents: HashMap<Entity, Option<Entity>> = skin.joints.iter().map(|&j| (j, joint_parent(j))).collect();
+    let mut children: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for &joint in &skin.joints {
+        if let Some(parent) = parents[&joint] {
+            children.entry(parent).or_default().push(joint);
+        }
+    }
+
+    let mut bones: Vec<(Entity, RagdollBone)> = Vec::new();
+    for &joint in &skin.joints {
+        let (position, rotation) = poses[&joint];
+        let child_positions: Vec<Vec3> = children.get(&joint).into_iter().flatten().map(|c| poses[c].0).collect();
+        let is_root = parents[&joint].is_none();
+        if child_positions.is_empty() && !is_root {
+            continue;
+        }
+        let end = if child_positions.is_empty() {
+            position
+        } else {
+            child_positions.iter().sum::<Vec3>() / child_positions.len() as f32
+        };
+        let length = end.distance(position);
+        let reach = child_positions.iter().map(|p| p.distance(position)).fold(length, f32::max);
+        if length < settings.min_bone_length && !is_root {
+            continue;
+        }
+
+        let inverse = rotation.inverse();
+        let direction = (inverse * (end - position)).try_normalize().unwrap_or(Vec3::Y);
+        let radius = (reach * settings.radius_ratio).max(0.01);
+        let is_hinge = world.get::<Name>(joint).is_some_and(|name| {
+            let name = name.0.to_lowercase();
+            settings.hinge_keywords.iter().any(|k| !k.is_empty() && name.contains(&k.to_lowercase()))
+        });
+        let (joint_kind, joint_axis) = if is_hinge {
+            // 铰链轴取关节局部 X 轴中与骨骼垂直的分量
+            let axis = Vec3::X - direction * Vec3::X.dot(direction);
+            let axis = axis.try_normalize().unwrap_or_else(|| direction.any_orthonormal_vector());
+            (RagdollJointKind::Revolute { limits: settings.hinge_limits }, axis)
+        } else {
+            (RagdollJointKind::Spherical { swing_limit: settings.swing_limit, twist_limit: settings.twist_limit }, direction)
+        };
+
+        bones.push((joint, RagdollBone {
+            ragdoll: skin_entity,
+            parent: None,
+            center: inverse * (end - position) * 0.5,
+            rotation: Quat::from_rotation_arc(Vec3::Y, direction),
+            half_height: (length * 0.5 - radius).max(0.0),
+            radius,
+            density: settings.density,
+            joint_axis,
+            joint: joint_kind,
+            blend_from: None,
+            handle_index: None,
+            handle_generation: None,
+            joint_handle_index: None,
+            joint_handle_generation: None,
+        }));
+    }
+    if bones.is_empty() {
+        return Err(RagdollError::NoBones(skin.name.clone()));
+    }
+
+    // 父骨骼为最近的生成了骨骼的关节祖先
+    let bone_joints: HashSet<Entity> = bones.iter().map(|(j, _)| *j).collect();
+    for (joint, bone) in &mut bones {
+        let mut current = parents[joint];
+        while let Some(parent) = current {
+            if bone_joints.contains(&parent) {
+                bone.parent = Some(parent);
+                break;
+            }
+            current = parents[&parent];
+        }
+    }
+    Ok(bones)
+}
+
+/// 为蒙皮生成布娃娃并挂载到场景中，返回骨骼数量
+///
+/// 已有布娃娃时替换其骨骼，保留模式、过渡时长和碰撞层。
+pub fn insert_ragdoll(world: &mut World, skin_entity: Entity, settings: &RagdollSettings) -> Result<usize, RagdollError> {
+    let bones = build_ragdoll(world, skin_entity, settings)?;
+    let ragdoll = world.get::<Ragdoll>(skin_entity).cloned().unwrap_or_default();
+    remove_ragdoll(world, skin_entity);
+    let count = bones.len();
+    for (joint, bone) in bones {
+        world.entity_mut(joint).insert(bone);
+    }
+    world.entity_mut(skin_entity).insert(ragdoll);
+    Ok(count)
+}
+
+/// 删除蒙皮的布娃娃组件 (物理管理器随后释放对应的刚体)
+pub fn remove_ragdoll(world: &mut World, skin_entity: Entity) {
+    let bones: Vec<Entity> = world
+        .query::<(Entity, &RagdollBone)>()
+        .iter(world)
+        .filter(|(_, bone)| bone.ragdoll == skin_entity)
+        .map(|(e, _)| e)
+        .collect();
+    for joint in bones {
+        world.entity_mut(joint).remove::<RagdollBone>();
+    }
+    if let Some(mut entity) = world.get_entity_mut(skin_entity) {
+        entity.remove::<Ragdoll>();
+    }
+}
+
+/// 关节是否由处于模拟模式的布娃娃驱动 (动画不应再写入其变换)
+pub fn is_simulated_joint(world: &World, joint: Entity) -> bool {
+    world
+        .get::<RagdollBone>(joint)
+        .and_then(|bone| world.get::<Ragdoll>(bone.ragdoll))
+        .is_some_and(|ragdoll| ragdoll.mode == RagdollMode::Simulated)
+}
+
+/// 将关节的世界位姿 (位置, 旋转) 写回其局部 `Transform`，并更新关节子树的 `GlobalTransform`
+///
+/// 父关节在同一批次中时使用其新的位姿，因此 `poses` 可以按任意顺序排列。关节自身的缩放保持不变。
+pub fn write_bone_poses(world: &mut World, poses: &[(Entity, Vec3, Quat)]) {
+    let targets: HashMap<Entity, (Vec3, Quat)> = poses.iter().map(|&(e, p, r)| (e, (p, r))).collect();
+    let mut globals: HashMap<Entity, Mat4> = HashMap::new();
+    for &(joint, _, _) in poses {
+        global_with_targets(world, joint, &targets, &mut globals);
+    }
+
+    for &(joint, _, _) in poses {
+        let parent_global = world
+            .get::<Parent>(joint)
+            .map_or(Mat4::IDENTITY, |p| global_with_targets(world, p.0, &targets, &mut globals));
+        let local = parent_global.inverse() * globals[&joint];
+        let (_, rotation, position) = local.to_scale_rotation_translation();
+        if let Some(mut transform) = world.get_mut::<Transform>(joint) {
+            transform.position = position;
+            transform.rotation = rotation.normalize();
+        }
+    }
+
+    let roots: Vec<Entity> = poses
+        .iter()
+        .map(|&(joint, _, _)| joint)
+        .filter(|&joint| !has_ancestor_in(world, joint, &targets))
+        .collect();
+    for root in roots {
+        crate::ik::propagate_global_transform(world, root);
+    }
+}
+
+/// 计算实体的世界矩阵：目标位姿优先 (保留原缩放)，否则由父节点的世界矩阵与局部变换组合
+fn global_with_targets(world: &World, entity: Entity, targets: &HashMap<Entity, (Vec3, Quat)>, globals: &mut HashMap<Entity, Mat4>) -> Mat4 {
+    if let Some(&global) = globals.get(&entity) {
+        return global;
+    }
+    let scale = world.get::<GlobalTransform>(entity).map_or(Vec3::ONE, |gt| gt.0.to_scale_rotation_translation().0);
+    let global = match targets.get(&entity) {
+        Some(&(position, rotation)) => Mat4::from_scale_rotation_translation(scale, rotation, position),
+        None if has_ancestor_in(world, entity, targets) => {
+            let parent = world.get::<Parent>(entity).map(|p| p.0);
+            let parent_global = parent.map_or(Mat4::IDENTITY, |p| global_with_targets(world, p, targets, globals));
+            parent_global * world.get::<Transform>(entity).map_or(Mat4::IDENTITY, |t| t.compute_matrix())
+        }
+        None => world.get::<GlobalTransform>(entity).map_or(Mat4::IDENTITY, |gt| gt.0),
+    };
+    globals.insert(entity, global);
+    global
+}
+
+fn has_ancestor_in(world: &World, entity: Entity, targets: &HashMap<Entity, (Vec3, Quat)>) -> bool {
+    let mut current = world.get::<Parent>(entity).map(|p| p.0);
+    while let Some(parent) = current {
+        if targets.contains_key(&parent) {
+            return true;
+        }
+        current = world.get::<Parent>(parent).map(|p| p.0);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Children, Joint};
+
+    /// 髋 → 膝 → 踝 → 脚尖 的腿部骨骼链，沿 -Y 方向每节 0.5 米 (脚尖只有 0.02 米)
+    fn leg(world: &mut World) -> (Entity, Vec<Entity>) {
+        let names = ["hip", "knee", "ankle", "toe"];
+        let offsets = [Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -0.5, 0.0), Vec3::new(0.0, -0.5, 0.0), Vec3::new(0.0, -0.02, 0.0)];
+        let mut joints: Vec<Entity> = Vec::new();
+        let mut global = Mat4::IDENTITY;
+        for (i, (name, offset)) in names.iter().zip(offsets).enumerate() {
+            global *= Mat4::from_translation(offset);
+            let mut entity = world.spawn((Name(name.to_string()), Transform::from_translation(offset), GlobalTransform(global), Joint { index: i }));
+            if let Some(&parent) = joints.last() {
+                entity.insert(Parent(parent));
+            }
+            let id = entity.id();
+            if let Some(&parent) = joints.last() {
+                world.entity_mut(parent).insert(Children(vec![id]));
+            }
+            joints.push(id);
+        }
+        let skin = world.spawn(Skin::new("leg".into(), vec![Mat4::IDENTITY; 4], joints.clone())).id();
+        (skin, joints)
+    }
+
+    #[test]
+    fn test_build_ragdoll_creates_capsules_and_hinges() {
+        let mut world = World::new();
+        let (skin, joints) = leg(&mut world);
+        let bones = build_ragdoll(&world, skin, &RagdollSettings::default()).unwrap();
+
+        // 踝关节到脚尖太短，脚尖没有子关节：只有髋和膝生成骨骼
+        let bone_joints: Vec<Entity> = bones.iter().map(|(j, _)| *j).collect();
+        assert_eq!(bone_joints, vec![joints[0], joints[1]]);
+        let (_, hip) = &bones[0];
+        let (_, knee) = &bones[1];
+        assert_eq!((hip.parent, knee.parent), (None, Some(joints[0])));
+        assert!(hip.center.abs_diff_eq(Vec3::new(0.0, -0.25, 0.0), 1e-5));
+        assert!((hip.radius - 0.1).abs() < 1e-5 && (hip.half_height - 0.15).abs() < 1e-5);
+        assert!((hip.rotation * Vec3::Y).abs_diff_eq(Vec3::NEG_Y, 1e-5));
+        assert!(matches!(hip.joint, RagdollJointKind::Spherical { .. }));
+        assert!(matches!(knee.joint, RagdollJointKind::Revolute { .. }));
+        assert!(knee.joint_axis.abs_diff_eq(Vec3::X, 1e-5));
+
+        world.despawn(joints[2]);
+        assert!(matches!(build_ragdoll(&world, skin, &RagdollSettings::default()), Err(RagdollError::Skinning(SkinningError::MissingJoint { index: 2, .. }))));
+    }
+
+    #[test]
+    fn test_write_bone_poses_updates_local_transforms() {
+        let mut world = World::new();
+        let (_, joints) = leg(&mut world);
+        // 髋关节绕 Z 轴旋转 90 度，膝关节保持与髋关节相同的世界旋转
+        let rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let hip = Vec3::new(0.0, 1.0, 0.0);
+        let knee = hip + rotation * Vec3::new(0.0, -0.5, 0.0);
+        write_bone_poses(&mut world, &[(joints[1], knee, rotation), (joints[0], hip, rotation)]);
+
+        let knee_local = world.get::<Transform>(joints[1]).unwrap();
+        assert!(knee_local.position.abs_diff_eq(Vec3::new(0.0, -0.5, 0.0), 1e-5));
+        assert!(knee_local.rotation.abs_diff_eq(Quat::IDENTITY, 1e-5));
+        let ankle = world.get::<GlobalTransform>(joints[2]).unwrap().0.w_axis.truncate();
+        assert!(ankle.abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-5), "ankle at {:?}", ankle);
+    }
+}
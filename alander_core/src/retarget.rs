@@ -0,0 +1,258 @@
+//! 动画重定向
+//!
+//! 将为一套骨骼制作的 [`AnimationClip`] 转换到另一套骨骼上：
+//! 通过骨骼名称映射表匹配通道，基于双方绑定姿势补偿骨骼朝向差异，
+//! 并按骨架高度比例缩放根骨骼位移，最终烘焙为目标骨骼的新剪辑。
+
+use crate::curve::TangentMode;
+use crate::scene::{AnimationChannel, AnimationClip, AnimationTrack, Children, Keyframe, Name, Parent, Skin, Transform};
+use bevy_ecs::prelude::*;
+use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单条骨骼映射 (源骨骼名 -> 目标骨骼名)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoneMapping {
+    pub source: String,
+    pub target: String,
+}
+
+/// 骨骼名称映射资源
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoneMap {
+    pub name: String,
+    pub mappings: Vec<BoneMapping>,
+}
+
+impl BoneMap {
+    pub fn new(name: String) -> Self {
+        Self { name, mappings: Vec::new() }
+    }
+
+    /// 查找源骨骼对应的目标骨骼
+    pub fn target_for(&self, source: &str) -> Option<&str> {
+        self.mappings.iter().find(|m| m.source == source).map(|m| m.target.as_str())
+    }
+
+    /// 按规范化后的名称自动匹配两套骨骼
+    pub fn auto_map(name: String, source_bones: &[String], target_bones: &[String]) -> Self {
+        let targets: HashMap<String, &String> = target_bones.iter().map(|t| (normalize_bone_name(t), t)).collect();
+        let mappings = source_bones
+            .iter()
+            .filter_map(|s| {
+                targets.get(&normalize_bone_name(s)).map(|t| BoneMapping { source: s.clone(), target: (*t).clone() })
+            })
+            .collect();
+        Self { name, mappings }
+    }
+}
+
+/// 规范化骨骼名称：去掉命名空间前缀 (如 `mixamorig:`)、分隔符并转为小写
+pub fn normalize_bone_name(name: &str) -> String {
+    let base = name.rsplit([':', '|']).next().unwrap_or(name);
+    base.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' ' | '.'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 单根骨骼的绑定姿势
+#[derive(Debug, Clone, Copy)]
+pub struct BindBone {
+    /// 相对父骨骼的局部绑定变换
+    pub local: Transform,
+    /// 父骨骼在绑定姿势下的全局旋转
+    pub parent_rotation: Quat,
+    /// 绑定姿势下的全局位置
+    pub global_position: Vec3,
+}
+
+/// 骨架的绑定姿势 (按骨骼名称索引)
+#[derive(Debug, Clone, Default)]
+pub struct SkeletonBindPose {
+    pub bones: HashMap<String, BindBone>,
+    /// 根骨骼名称 (其父节点不属于该骨架)
+    pub root: Option<String>,
+}
+
+impl SkeletonBindPose {
+    /// 由蒙皮的逆绑定矩阵重建绑定姿势
+    pub fn from_skin(world: &World, skin_entity: Entity) -> Option<Self> {
+        let skin = world.get::<Skin>(skin_entity)?;
+        let globals: HashMap<Entity, Mat4> = skin
+            .joints
+            .iter()
+            .zip(&skin.inverse_bind_matrices)
+            .map(|(&joint, ibm)| (joint, ibm.inverse()))
+            .collect();
+
+        let mut pose = SkeletonBindPose::default();
+        for &joint in &skin.joints {
+            let (Some(name), Some(&global)) = (world.get::<Name>(joint), globals.get(&joint)) else { continue };
+            let parent_global = world.get::<Parent>(joint).and_then(|p| globals.get(&p.0).copied());
+            let (_, global_rotation, global_position) = global.to_scale_rotation_translation();
+            let bone = match parent_global {
+                Some(parent) => BindBone {
+                    local: Transform::from_matrix(parent.inverse() * global),
+                    parent_rotation: parent.to_scale_rotation_translation().1,
+                    global_position,
+                },
+                None => {
+                    // 根骨骼的父节点不在蒙皮内，以实体当前的局部变换作为绑定姿势
+                    let local = world.get::<Transform>(joint).copied().unwrap_or_default();
+                    if pose.root.is_none() {
+                        pose.root = Some(name.0.clone());
+                    }
+                    BindBone { local, parent_rotation: global_rotation * local.rotation.inverse(), global_position }
+                }
+            };
+            pose.bones.insert(name.0.clone(), bone);
+        }
+        Some(pose)
+    }
+
+    /// 所有骨骼名称
+    pub fn bone_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.bones.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 骨架在绑定姿势下的高度 (竖直方向包围范围)，用于比例缩放
+    pub fn height(&self) -> f32 {
+        let (lo, hi) = self
+            .bones
+            .values()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), b| (lo.min(b.global_position.y), hi.max(b.global_position.y)));
+        if hi > lo { hi - lo } else { 1.0 }
+    }
+}
+
+/// 在实体子树中查找第一个蒙皮组件所在的实体
+pub fn find_skin(world: &World, root: Entity) -> Option<Entity> {
+    if world.get::<Skin>(root).is_some() {
+        return Some(root);
+    }
+    world.get::<Children>(root)?.0.iter().find_map(|&child| find_skin(world, child))
+}
+
+/// 将源骨骼的局部旋转转换到目标骨骼
+///
+/// 先求出源骨骼相对绑定姿势的世界空间旋转增量，再把该增量施加到目标骨骼的绑定姿势上，
+/// 因此两套骨骼的局部坐标轴约定不同也能得到一致的动作。
+fn retarget_rotation(rotation: Quat, source: &BindBone, target: &BindBone) -> Quat {
+    let delta = source.parent_rotation * rotation * source.local.rotation.inverse() * source.parent_rotation.inverse();
+    (target.parent_rotation.inverse() * delta * target.parent_rotation * target.local.rotation).normalize()
+}
+
+/// 将剪辑重定向到目标骨架并烘焙为新剪辑
+///
+/// 非根骨骼只重定向旋转与缩放，保留目标骨骼自身的骨长；根骨骼的位移按骨架高度比例缩放。
+pub fn retarget_clip(clip: &AnimationClip, source: &SkeletonBindPose, target: &SkeletonBindPose, map: &BoneMap) -> AnimationClip {
+    let mut result = AnimationClip::new(format!("{} (Retargeted)", clip.name));
+    let scale = target.height() / source.height();
+
+    for channel in &clip.channels {
+        let Some(target_name) = map.target_for(&channel.target_name) else { continue };
+        let (Some(src), Some(tgt)) = (source.bones.get(&channel.target_name), target.bones.get(target_name)) else { continue };
+        let mut out = AnimationChannel::new(target_name.to_string());
+
+        out.rotation_track = channel.rotation_track.as_ref().map(|track| {
+            map_track(track, |r| retarget_rotation(r, src, tgt))
+        });
+
+        out.scale_track = channel.scale_track.as_ref().map(|track| {
+            let ratio = tgt.local.scale / src.local.scale;
+            map_track(track, |s| s * ratio)
+        });
+
+        if source.root.as_deref() == Some(channel.target_name.as_str()) {
+            out.position_track = channel.position_track.as_ref().map(|track| {
+                map_track(track, |p| {
+                    // 位移增量转到世界空间后再转入目标父空间
+                    let world_delta = src.parent_rotation * (p - src.local.position);
+                    tgt.local.position + tgt.parent_rotation.inverse() * world_delta * scale
+                })
+            });
+        }
+
+        result.channels.push(out);
+    }
+
+    result.update_duration();
+    result
+}
+
+/// 逐关键帧映射轨道的值，保留时间与切线模式
+///
+/// 自由切线是针对源数值绘制的，映射后不再适用，因此退化为自动切线。
+fn map_track<T: Copy>(track: &AnimationTrack<T>, f: impl Fn(T) -> T) -> AnimationTrack<T> {
+    AnimationTrack::new(
+        track
+            .keyframes
+            .iter()
+            .map(|k| Keyframe {
+                value: f(k.value),
+                tangent_mode: if k.tangent_mode == TangentMode::Free { TangentMode::Auto } else { k.tangent_mode },
+                ..Keyframe::new(k.time, k.value)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bone(local: Transform, parent_rotation: Quat, y: f32) -> BindBone {
+        BindBone { local, parent_rotation, global_position: Vec3::new(0.0, y, 0.0) }
+    }
+
+    #[test]
+    fn test_auto_map_normalizes_names() {
+        let map = BoneMap::auto_map(
+            "test".into(),
+            &["mixamorig:LeftUpLeg".into(), "Hips".into()],
+            &["left_up_leg".into(), "hips".into()],
+        );
+        assert_eq!(map.target_for("mixamorig:LeftUpLeg"), Some("left_up_leg"));
+        assert_eq!(map.target_for("Hips"), Some("hips"));
+    }
+
+    #[test]
+    fn test_retarget_compensates_bind_pose() {
+        // 目标骨骼的局部坐标轴相对源骨骼绕 Y 轴旋转了 90 度
+        let rest_rot = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let mut source = SkeletonBindPose { root: Some("hips".into()), ..Default::default() };
+        source.bones.insert("hips".into(), bone(Transform::from_translation(Vec3::Y), Quat::IDENTITY, 1.0));
+        source.bones.insert("head".into(), bone(Transform::from_translation(Vec3::Y), Quat::IDENTITY, 2.0));
+        let mut target = SkeletonBindPose { root: Some("pelvis".into()), ..Default::default() };
+        target.bones.insert("pelvis".into(), bone(Transform::from_translation(Vec3::Y * 2.0), Quat::IDENTITY, 2.0));
+        target.bones.insert("skull".into(), bone(Transform::from(Vec3::Y, rest_rot, Vec3::ONE), Quat::IDENTITY, 4.0));
+
+        let map = BoneMap {
+            name: "test".into(),
+            mappings: vec![
+                BoneMapping { source: "hips".into(), target: "pelvis".into() },
+                BoneMapping { source: "head".into(), target: "skull".into() },
+            ],
+        };
+
+        let anim_rot = Quat::from_rotation_x(0.5);
+        let mut clip = AnimationClip::new("walk".into());
+        let hips = clip.channel_mut("hips");
+        hips.position_track = Some(AnimationTrack::new(vec![Keyframe::new(1.0, Vec3::new(1.0, 1.0, 0.0))]));
+        clip.channel_mut("head").rotation_track = Some(AnimationTrack::new(vec![Keyframe::new(1.0, anim_rot)]));
+
+        let baked = retarget_clip(&clip, &source, &target, &map);
+        let pelvis = baked.channels.iter().find(|c| c.target_name == "pelvis").unwrap();
+        // 源骨架高度 1，目标高度 2：根位移增量加倍
+        let p = pelvis.position_track.as_ref().unwrap().keyframes[0].value;
+        assert!(p.abs_diff_eq(Vec3::new(2.0, 2.0, 0.0), 1e-5));
+
+        let skull = baked.channels.iter().find(|c| c.target_name == "skull").unwrap();
+        let r = skull.rotation_track.as_ref().unwrap().keyframes[0].value;
+        assert!(r.abs_diff_eq(anim_rot * rest_rot, 1e-5));
+    }
+}
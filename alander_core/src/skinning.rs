@@ -0,0 +1,138 @@
+//! 蒙皮关节调色板与校验
+//!
+//! 每个 [`Skin`] 每帧只计算一次世界空间的关节矩阵 (关节全局变换 × 逆绑定矩阵)，
+//! 由所有引用该蒙皮的网格共享。与 glTF 规范一致，蒙皮网格自身的变换不参与蒙皮计算。
+//! 关节数量不匹配、顶点索引越界等问题以 [`SkinningError`] 报告，而不是静默截断。
+//!
+//! 对偶四元数模式下，调色板的每个元素仍占用一个 4x4 矩阵的空间，
+//! 按列依次存放旋转四元数 (实部)、对偶部与缩放，见 [`pack_dual_quaternion`]。
+
+use crate::scene::{GlobalTransform, Skin, SkinnedMesh, SkinningMode, Vertex};
+use bevy_ecs::prelude::*;
+use glam::{Mat4, Quat, Vec4};
+
+/// 蒙皮错误
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SkinningError {
+    #[error("蒙皮 \"{skin}\" 的关节数 ({joints}) 与逆绑定矩阵数 ({inverse_bind_matrices}) 不一致")]
+    BindMatrixCountMismatch { skin: String, joints: usize, inverse_bind_matrices: usize },
+    #[error("蒙皮 \"{skin}\" 的第 {index} 个关节缺少全局变换 (关节实体可能已被删除)")]
+    MissingJoint { skin: String, index: usize },
+    #[error("顶点 {vertex} 引用了关节 {joint}，但蒙皮只有 {joint_count} 个关节")]
+    JointIndexOutOfRange { vertex: usize, joint: u32, joint_count: usize },
+    #[error("实体 {0:?} 引用的蒙皮不存在")]
+    MissingSkin(Entity),
+}
+
+/// 网格使用的蒙皮实体：优先使用 [`SkinnedMesh`] 的引用，其次是自身的 Skin
+pub fn mesh_skin(world: &World, mesh: Entity) -> Option<Entity> {
+    match world.get::<SkinnedMesh>(mesh) {
+        Some(skinned) => Some(skinned.skin),
+        None => world.get::<Skin>(mesh).is_some().then_some(mesh),
+    }
+}
+
+/// 检查蒙皮的关节与逆绑定矩阵是否一一对应
+pub fn validate_skin(skin: &Skin) -> Result<(), SkinningError> {
+    if skin.joints.len() != skin.inverse_bind_matrices.len() {
+        return Err(SkinningError::BindMatrixCountMismatch {
+            skin: skin.name.clone(),
+            joints: skin.joints.len(),
+            inverse_bind_matrices: skin.inverse_bind_matrices.len(),
+        });
+    }
+    Ok(())
+}
+
+/// 计算蒙皮的世界空间关节调色板
+pub fn joint_palette(world: &World, skin: &Skin) -> Result<Vec<Mat4>, SkinningError> {
+    validate_skin(skin)?;
+    skin.joints
+        .iter()
+        .zip(&skin.inverse_bind_matrices)
+        .enumerate()
+        .map(|(index, (&joint, ibm))| {
+            let global = world
+                .get::<GlobalTransform>(joint)
+                .ok_or_else(|| SkinningError::MissingJoint { skin: skin.name.clone(), index })?;
+            Ok(global.0 * *ibm)
+        })
+        .collect()
+}
+
+/// 按蒙皮方式生成上传到 GPU 的调色板
+pub fn gpu_palette(world: &World, skin: &Skin) -> Result<Vec<Mat4>, SkinningError> {
+    let palette = joint_palette(world, skin)?;
+    Ok(match skin.mode {
+        SkinningMode::LinearBlend => palette,
+        SkinningMode::DualQuaternion => palette.iter().map(|m| pack_dual_quaternion(*m)).collect(),
+    })
+}
+
+/// 将关节矩阵转换为对偶四元数并打包为 4x4 矩阵
+///
+/// 第 0 列为旋转四元数 (实部)，第 1 列为对偶部，第 2 列 xyz 为缩放 (着色器中线性混合后先于旋转应用)。
+pub fn pack_dual_quaternion(matrix: Mat4) -> Mat4 {
+    let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+    let dual = Quat::from_xyzw(translation.x, translation.y, translation.z, 0.0) * rotation * 0.5;
+    Mat4::from_cols(Vec4::from(rotation), Vec4::from(dual), scale.extend(0.0), Vec4::ZERO)
+}
+
+/// 检查顶点的骨骼索引 (两组影响) 是否都在蒙皮关节范围内
+pub fn validate_vertices(vertices: &[Vertex], joint_count: usize) -> Result<(), SkinningError> {
+    for (vertex, v) in vertices.iter().enumerate() {
+        if let Some((joint, _)) = v.influences().find(|(joint, _)| *joint as usize >= joint_count) {
+            return Err(SkinningError::JointIndexOutOfRange { vertex, joint, joint_count });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Vec2, Vec3};
+
+    #[test]
+    fn test_joint_palette_is_world_space() {
+        let mut world = World::new();
+        let offset = Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0));
+        let a = world.spawn(GlobalTransform(offset)).id();
+        let b = world.spawn(GlobalTransform(Mat4::IDENTITY)).id();
+        let skin = Skin::new("test".into(), vec![offset.inverse(), Mat4::IDENTITY], vec![a, b]);
+
+        let palette = joint_palette(&world, &skin).unwrap();
+        assert!(palette[0].abs_diff_eq(Mat4::IDENTITY, 1e-6));
+        assert_eq!(palette.len(), 2);
+
+        world.despawn(b);
+        assert_eq!(joint_palette(&world, &skin), Err(SkinningError::MissingJoint { skin: "test".into(), index: 1 }));
+
+        let broken = Skin::new("broken".into(), vec![Mat4::IDENTITY], vec![a, a]);
+        assert!(matches!(joint_palette(&world, &broken), Err(SkinningError::BindMatrixCountMismatch { .. })));
+    }
+
+    #[test]
+    fn test_pack_dual_quaternion_preserves_transform() {
+        let rotation = Quat::from_rotation_z(1.2);
+        let translation = Vec3::new(1.0, -2.0, 0.5);
+        let packed = pack_dual_quaternion(Mat4::from_rotation_translation(rotation, translation));
+
+        let real = Quat::from_vec4(packed.x_axis);
+        let dual = Quat::from_vec4(packed.y_axis);
+        assert!(real.abs_diff_eq(rotation, 1e-6));
+        // 平移 = 2 * 对偶部 * 实部的共轭
+        let t = dual * real.conjugate() * 2.0;
+        assert!(Vec3::new(t.x, t.y, t.z).abs_diff_eq(translation, 1e-5));
+        assert!(packed.z_axis.abs_diff_eq(Vec4::new(1.0, 1.0, 1.0, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn test_validate_vertices_checks_both_influence_sets() {
+        let v = Vertex::with_skinning(Vec3::ZERO, Vec3::Y, Vec2::ZERO, Vec4::X, [0, 1, 0, 0], [0.5, 0.3, 0.0, 0.0])
+            .with_secondary_influences([5, 0, 0, 0], [0.2, 0.0, 0.0, 0.0]);
+        assert_eq!(v.influences().count(), 3);
+        assert!(validate_vertices(&[v], 6).is_ok());
+        assert_eq!(validate_vertices(&[v], 4), Err(SkinningError::JointIndexOutOfRange { vertex: 0, joint: 5, joint_count: 4 }));
+    }
+}
@@ -0,0 +1,370 @@
+//! 动画状态机求值
+//!
+//! 根据参数、退出时间与优先级选择转换，支持 "任意状态" 转换、转换打断与子状态机。
+//! 求值结果以 [`StateChange`] 的形式交给 [`AnimationPlayer`] 执行交叉淡入淡出。
+
+use crate::scene::{
+    ActiveTransition, AnimCondition, AnimParamValue, AnimationPlayer, AnimationStateMachine, AnimationTransition, InterruptionSource,
+};
+use bevy_ecs::prelude::*;
+use std::collections::HashMap;
+
+/// 状态机求值产生的剪辑切换
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateChange {
+    /// 需要播放的剪辑索引
+    pub clip_index: usize,
+    /// 过渡时长 (0 表示直接切换)
+    pub duration: f32,
+}
+
+impl AnimCondition {
+    /// 条件引用的参数名
+    pub fn param(&self) -> &str {
+        match self {
+            AnimCondition::Greater(p, _)
+            | AnimCondition::Less(p, _)
+            | AnimCondition::Bool(p, _)
+            | AnimCondition::Trigger(p)
+            | AnimCondition::IntEquals(p, _)
+            | AnimCondition::IntNotEquals(p, _)
+            | AnimCondition::IntGreater(p, _)
+            | AnimCondition::IntLess(p, _)
+            | AnimCondition::EnumIs(p, _)
+            | AnimCondition::EnumIsNot(p, _) => p,
+        }
+    }
+
+    /// 判断条件是否满足，参数不存在或类型不符时视为不满足
+    pub fn evaluate(&self, params: &HashMap<String, AnimParamValue>) -> bool {
+        let Some(value) = params.get(self.param()) else { return false };
+        match (self, value) {
+            (AnimCondition::Greater(_, v), AnimParamValue::Float(f)) => f > v,
+            (AnimCondition::Less(_, v), AnimParamValue::Float(f)) => f < v,
+            (AnimCondition::Bool(_, v), AnimParamValue::Bool(b)) => b == v,
+            (AnimCondition::Trigger(_), AnimParamValue::Trigger(t)) => *t,
+            (AnimCondition::IntEquals(_, v), AnimParamValue::Int(i)) => i == v,
+            (AnimCondition::IntNotEquals(_, v), AnimParamValue::Int(i)) => i != v,
+            (AnimCondition::IntGreater(_, v), AnimParamValue::Int(i)) => i > v,
+            (AnimCondition::IntLess(_, v), AnimParamValue::Int(i)) => i < v,
+            (AnimCondition::EnumIs(_, option), AnimParamValue::Enum { value, options }) => options.get(*value) == Some(option),
+            (AnimCondition::EnumIsNot(_, option), AnimParamValue::Enum { value, options }) => options.get(*value) != Some(option),
+            _ => false,
+        }
+    }
+}
+
+/// 判断是否已到达退出时间
+///
+/// `normalized` 为源状态的归一化播放时间 (None 表示剪辑时长为 0，视为已播放完毕)。
+/// 退出时间小于 1 时在每次循环的该时刻之后生效，否则在累计播放到该时刻之后生效。
+pub fn exit_time_reached(normalized: Option<f32>, exit_time: f32) -> bool {
+    match normalized {
+        None => true,
+        Some(n) if exit_time < 1.0 => n.fract() >= exit_time,
+        Some(n) => n >= exit_time,
+    }
+}
+
+impl AnimationTransition {
+    /// 转换是否可以触发
+    pub fn is_ready(&self, params: &HashMap<String, AnimParamValue>, normalized: Option<f32>) -> bool {
+        if self.has_exit_time && !exit_time_reached(normalized, self.exit_time) {
+            return false;
+        }
+        self.conditions.iter().all(|c| c.evaluate(params))
+    }
+}
+
+impl AnimationStateMachine {
+    /// 推进状态机，返回需要切换的剪辑
+    ///
+    /// `clip_durations` 为 AnimationPlayer 中各剪辑的时长，用于计算归一化退出时间。
+    pub fn update(&mut self, dt: f32, clip_durations: &[f32]) -> Option<StateChange> {
+        // 子状态机共享根状态机的参数
+        let params = std::mem::take(&mut self.parameters);
+        let mut consumed = Vec::new();
+        let change = self.step(dt, clip_durations, &params, &mut consumed);
+        self.parameters = params;
+        for name in consumed {
+            if let Some(AnimParamValue::Trigger(t)) = self.parameters.get_mut(&name) {
+                *t = false;
+            }
+        }
+        change
+    }
+
+    /// 当前状态最终播放的剪辑 (递归进入子状态机)
+    pub fn leaf_clip(&self) -> Option<usize> {
+        let state = self.states.get(&self.current_state)?;
+        match &state.sub_machine {
+            Some(sub) => sub.leaf_clip(),
+            None => Some(state.clip_index),
+        }
+    }
+
+    /// 当前活跃状态的路径，例如 `["Locomotion", "Run"]`
+    pub fn active_path(&self) -> Vec<String> {
+        let mut path = vec![self.current_state.clone()];
+        if let Some(sub) = self.states.get(&self.current_state).and_then(|s| s.sub_machine.as_deref()) {
+            path.extend(sub.active_path());
+        }
+        path
+    }
+
+    /// 回到初始状态 (状态机启动或作为子状态机被进入时调用)
+    pub fn reset(&mut self) {
+        if self.states.contains_key(&self.entry_state) {
+            self.current_state = self.entry_state.clone();
+        }
+        self.state_time = 0.0;
+        self.active_transition = None;
+        self.previous_state = None;
+        self.initialized = true;
+        if let Some(sub) = self.states.get_mut(&self.current_state).and_then(|s| s.sub_machine.as_deref_mut()) {
+            sub.reset();
+        }
+    }
+
+    fn step(&mut self, dt: f32, durations: &[f32], params: &HashMap<String, AnimParamValue>, consumed: &mut Vec<String>) -> Option<StateChange> {
+        if !self.initialized {
+            self.reset();
+            return self.leaf_clip().map(|clip_index| StateChange { clip_index, duration: 0.0 });
+        }
+
+        self.state_time += dt;
+        if let Some(active) = &mut self.active_transition {
+            active.elapsed += dt;
+            active.source_time += dt;
+            if active.elapsed >= active.duration {
+                self.active_transition = None;
+            }
+        }
+
+        if let Some(transition) = self.select_transition(durations, params) {
+            consumed.extend(transition.conditions.iter().filter_map(|c| match c {
+                AnimCondition::Trigger(p) => Some(p.clone()),
+                _ => None,
+            }));
+            let from = std::mem::replace(&mut self.current_state, transition.target_state.clone());
+            let source_time = self.state_time;
+            self.state_time = 0.0;
+            self.active_transition = (transition.duration > 0.0).then(|| ActiveTransition {
+                from: from.clone(),
+                to: transition.target_state.clone(),
+                elapsed: 0.0,
+                duration: transition.duration,
+                interruption_source: transition.interruption_source,
+                source_time,
+            });
+            self.previous_state = Some(from);
+            if let Some(sub) = self.states.get_mut(&self.current_state).and_then(|s| s.sub_machine.as_deref_mut()) {
+                sub.reset();
+            }
+            return self.leaf_clip().map(|clip_index| StateChange { clip_index, duration: transition.duration });
+        }
+
+        // 外层没有转换时，再由当前状态的子状态机求值
+        let current = self.current_state.clone();
+        let sub = self.states.get_mut(&current)?.sub_machine.as_deref_mut()?;
+        sub.step(dt, durations, params, consumed)
+    }
+
+    /// 按优先级挑选第一个可触发的转换
+    fn select_transition(&self, durations: &[f32], params: &HashMap<String, AnimParamValue>) -> Option<AnimationTransition> {
+        let current_norm = self.normalized_time(&self.current_state, self.state_time, durations);
+        let mut candidates: Vec<(&AnimationTransition, Option<f32>)> = Vec::new();
+
+        // "任意状态" 转换总是参与检查，但不会转换到当前状态自身
+        for t in &self.any_state_transitions {
+            if t.target_state != self.current_state {
+                candidates.push((t, current_norm));
+            }
+        }
+
+        match &self.active_transition {
+            None => {
+                if let Some(state) = self.states.get(&self.current_state) {
+                    candidates.extend(state.transitions.iter().map(|t| (t, current_norm)));
+                }
+            }
+            Some(active) => {
+                // 转换进行中，只检查打断来源允许的转换
+                let source_norm = self.normalized_time(&active.from, active.source_time, durations);
+                let source = self.states.get(&active.from).map(|s| s.transitions.as_slice()).unwrap_or_default();
+                let destination = self.states.get(&active.to).map(|s| s.transitions.as_slice()).unwrap_or_default();
+                let order: &[bool] = match active.interruption_source {
+                    InterruptionSource::None => &[],
+                    InterruptionSource::Source => &[true],
+                    InterruptionSource::Destination => &[false],
+                    InterruptionSource::SourceThenDestination => &[true, false],
+                    InterruptionSource::DestinationThenSource => &[false, true],
+                };
+                for &from_source in order {
+                    if from_source {
+                        candidates.extend(source.iter().filter(|t| t.target_state != active.to).map(|t| (t, source_norm)));
+                    } else {
+                        candidates.extend(destination.iter().map(|t| (t, current_norm)));
+                    }
+                }
+            }
+        }
+
+        // 稳定排序：优先级相同时保持上面的检查顺序
+        candidates.sort_by_key(|(t, _)| std::cmp::Reverse(t.priority));
+        candidates
+            .into_iter()
+            .find(|(t, norm)| self.states.contains_key(&t.target_state) && t.is_ready(params, *norm))
+            .map(|(t, _)| t.clone())
+    }
+
+    /// 状态的归一化播放时间，剪辑时长为 0 时返回 None
+    fn normalized_time(&self, state_name: &str, time: f32, durations: &[f32]) -> Option<f32> {
+        let state = self.states.get(state_name)?;
+        let clip = match &state.sub_machine {
+            Some(sub) => sub.leaf_clip()?,
+            None => state.clip_index,
+        };
+        let duration = durations.get(clip).copied().unwrap_or(0.0);
+        (duration > 0.0).then(|| time / duration)
+    }
+}
+
+/// 推进所有实体的动画状态机，并驱动其 AnimationPlayer 切换剪辑
+pub fn update_state_machines(world: &mut World, dt: f32) {
+    let mut query = world.query::<(&mut AnimationStateMachine, &mut AnimationPlayer)>();
+    for (mut sm, mut player) in query.iter_mut(world) {
+        let durations: Vec<f32> = player.clips.iter().map(|c| c.duration).collect();
+        let Some(change) = sm.update(dt * player.playback_speed, &durations) else { continue };
+        if change.clip_index >= player.clips.len() {
+            continue;
+        }
+        if change.duration > 0.0 && player.active_clip_index.is_some() {
+            player.cross_fade(change.clip_index, change.duration);
+        } else {
+            player.play(change.clip_index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::AnimationState;
+
+    fn transition(target: &str, duration: f32, conditions: Vec<AnimCondition>) -> AnimationTransition {
+        AnimationTransition { conditions, ..AnimationTransition::new(target.to_string(), duration) }
+    }
+
+    /// 构建包含 (名称, 剪辑索引, 转换) 的状态机，并完成初始化
+    fn machine(states: Vec<(&str, usize, Vec<AnimationTransition>)>) -> AnimationStateMachine {
+        let mut sm = AnimationStateMachine::new(states[0].0.to_string());
+        for (name, clip, transitions) in states {
+            let mut state = AnimationState::new(name.to_string(), clip);
+            state.transitions = transitions;
+            sm.states.insert(name.to_string(), state);
+        }
+        assert_eq!(sm.update(0.0, &[1.0; 4]).map(|c| c.duration), Some(0.0));
+        sm
+    }
+
+    #[test]
+    fn test_exit_time_waits_for_clip_progress() {
+        let mut t = transition("B", 0.2, vec![]);
+        t.has_exit_time = true;
+        t.exit_time = 0.5;
+        let mut sm = machine(vec![("A", 0, vec![t]), ("B", 1, vec![])]);
+        let durations = [2.0, 1.0];
+
+        assert_eq!(sm.update(0.5, &durations), None);
+        assert_eq!(sm.update(0.6, &durations), Some(StateChange { clip_index: 1, duration: 0.2 }));
+        assert_eq!(sm.current_state, "B");
+    }
+
+    #[test]
+    fn test_priority_orders_transitions() {
+        let low = transition("B", 0.0, vec![]);
+        let mut high = transition("C", 0.0, vec![]);
+        high.priority = 5;
+        let mut sm = machine(vec![("A", 0, vec![low, high]), ("B", 1, vec![]), ("C", 2, vec![])]);
+        sm.update(0.1, &[1.0; 3]);
+        assert_eq!(sm.current_state, "C");
+    }
+
+    #[test]
+    fn test_any_state_trigger_is_consumed() {
+        let mut sm = machine(vec![("Idle", 0, vec![]), ("Hit", 1, vec![])]);
+        sm.any_state_transitions.push(transition("Hit", 0.0, vec![AnimCondition::Trigger("hit".into())]));
+        sm.parameters.insert("hit".into(), AnimParamValue::Trigger(false));
+
+        assert_eq!(sm.update(0.1, &[1.0; 2]), None);
+        sm.trigger("hit");
+        assert_eq!(sm.update(0.1, &[1.0; 2]).map(|c| c.clip_index), Some(1));
+        assert_eq!(sm.parameters["hit"], AnimParamValue::Trigger(false));
+        // 已在目标状态时不会再次进入自身
+        sm.trigger("hit");
+        assert_eq!(sm.update(0.1, &[1.0; 2]), None);
+    }
+
+    #[test]
+    fn test_interruption_source() {
+        let jump = AnimCondition::Bool("jump".into(), true);
+        for (source, expect_interrupt) in [(InterruptionSource::None, false), (InterruptionSource::Destination, true)] {
+            let mut to_b = transition("B", 1.0, vec![]);
+            to_b.interruption_source = source;
+            let mut sm = machine(vec![("A", 0, vec![to_b]), ("B", 1, vec![transition("C", 0.1, vec![jump.clone()])]), ("C", 2, vec![])]);
+            sm.set_bool("jump", false);
+
+            sm.update(0.1, &[1.0; 3]);
+            assert_eq!(sm.current_state, "B");
+            assert!(sm.active_transition.is_some());
+
+            sm.set_bool("jump", true);
+            sm.update(0.1, &[1.0; 3]);
+            assert_eq!(sm.current_state == "C", expect_interrupt);
+        }
+    }
+
+    #[test]
+    fn test_int_and_enum_conditions() {
+        let mut params = HashMap::new();
+        params.insert("ammo".to_string(), AnimParamValue::Int(3));
+        params.insert("stance".to_string(), AnimParamValue::Enum { value: 1, options: vec!["Stand".into(), "Crouch".into()] });
+
+        assert!(AnimCondition::IntGreater("ammo".into(), 2).evaluate(&params));
+        assert!(!AnimCondition::IntEquals("ammo".into(), 2).evaluate(&params));
+        assert!(AnimCondition::EnumIs("stance".into(), "Crouch".into()).evaluate(&params));
+        assert!(AnimCondition::EnumIsNot("stance".into(), "Stand".into()).evaluate(&params));
+        // 类型不符视为不满足
+        assert!(!AnimCondition::Greater("ammo".into(), 0.0).evaluate(&params));
+    }
+
+    #[test]
+    fn test_sub_state_machine() {
+        let mut locomotion = AnimationStateMachine::new("Walk".into());
+        locomotion.entry_state = "Walk".into();
+        let mut walk = AnimationState::new("Walk".into(), 1);
+        walk.transitions.push(transition("Run", 0.0, vec![AnimCondition::Greater("speed".into(), 2.0)]));
+        locomotion.states.insert("Walk".into(), walk);
+        locomotion.states.insert("Run".into(), AnimationState::new("Run".into(), 2));
+
+        let mut sm = machine(vec![
+            ("Idle", 0, vec![transition("Locomotion", 0.0, vec![AnimCondition::Greater("speed".into(), 0.1)])]),
+            ("Locomotion", 0, vec![transition("Idle", 0.0, vec![AnimCondition::Less("speed".into(), 0.1)])]),
+        ]);
+        sm.states.get_mut("Locomotion").unwrap().sub_machine = Some(Box::new(locomotion));
+        let durations = [1.0; 3];
+
+        sm.set_float("speed", 1.0);
+        assert_eq!(sm.update(0.1, &durations).map(|c| c.clip_index), Some(1));
+        sm.set_float("speed", 3.0);
+        assert_eq!(sm.update(0.1, &durations).map(|c| c.clip_index), Some(2));
+        assert_eq!(sm.active_path(), vec!["Locomotion".to_string(), "Run".to_string()]);
+
+        // 离开后再次进入时，子状态机回到入口状态
+        sm.set_float("speed", 0.0);
+        assert_eq!(sm.update(0.1, &durations).map(|c| c.clip_index), Some(0));
+        sm.set_float("speed", 1.0);
+        assert_eq!(sm.update(0.1, &durations).map(|c| c.clip_index), Some(1));
+    }
+}
@@ -12,6 +12,7 @@ use bevy_ecs::prelude::*;
 
 use crate::scene_manager::{SceneManager, Scene};
 use crate::physics_manager::PhysicsManager;
+use crate::physics_query::PhysicsQueryFilter;
 use crate::gizmo_manager::{GizmoManager, GizmoMode};
 use crate::camera_controller::OrbitController;
 use crate::ui::{EditorUI, MenuAction};
@@ -43,6 +44,12 @@ pub struct EditorState {
     pub selected_asset_path: Option<std::path::PathBuf>,
     /// 资源预览纹理 ID (egui)
     pub asset_preview_texture: Option<egui::TextureHandle>,
+    /// 项目的命名物理层
+    pub physics_layers: alander_core::scene::PhysicsLayers,
+    /// 停止模拟时是否保留选中实体的模拟结果
+    pub keep_simulated_selection: bool,
+    /// 控制台请求跳转到的脚本位置 (实体, 行, 列)
+    pub script_goto: Option<(bevy_ecs::entity::Entity, usize, usize)>,
 }
 
 /// 应用程序状态
@@ -110,6 +117,9 @@ pub struct AlanderApp {
     /// 系统信息 (供统计使用)
     pub system_info: System,
 
+    /// 已报告过错误的蒙皮实体 (避免每帧重复输出)
+    pub reported_skin_errors: std::collections::HashSet<Entity>,
+
     /// 脚本管理器
     pub script_manager: ScriptManager,
 }
@@ -175,6 +185,9 @@ impl AlanderApp {
                 bloom_intensity: 0.5,
                 selected_asset_path: None,
                 asset_preview_texture: None,
+                physics_layers: crate::physics_manager::load_physics_layers(crate::physics_manager::PHYSICS_LAYERS_FILE),
+                keep_simulated_selection: false,
+                script_goto: None,
             },
             command_manager: CommandManager::new(50),
             camera,
@@ -194,6 +207,7 @@ impl AlanderApp {
             fps_update_timer: 0.0,
             displayed_delta_time: 0.0,
             system_info: System::new_all(),
+            reported_skin_errors: std::collections::HashSet::new(),
             script_manager: ScriptManager::new(),
         };
 
@@ -353,9 +367,10 @@ impl AlanderApp {
         let y = mouse_pos.y / window_size.height as f32;
         let ray = self.renderer.screen_to_world_ray(glam::Vec2::new(x, y));
 
-        if let Some(hit_entity) = self.physics_manager.ray_cast(&ray) {
-            self.editor_state.selected_entity = Some(hit_entity);
-            info!("拾取到实体: {:?}", hit_entity);
+        let filter = PhysicsQueryFilter::default();
+        if let Some(hit) = self.physics_manager.queries().cast_ray(ray.origin, ray.direction, f32::MAX, &filter) {
+            self.editor_state.selected_entity = Some(hit.entity);
+            info!("拾取到实体: {:?}, 距离: {}", hit.entity, hit.distance);
         } else {
             self.editor_state.selected_entity = None;
         }
@@ -377,9 +392,13 @@ impl AlanderApp {
             self.fps_update_timer = 0.0;
         }
 
-        // 运行脚本
-        if let Some(mut scene) = self.scene_manager.active_scene_mut() {
-            self.script_manager.update_scripts(&mut scene, delta_time);
+        // 每帧只推进一次时间，脚本与之后的系统读取同一帧的时间
+        self.time.advance(delta_time);
+
+        // 运行脚本并应用脚本对场景的修改
+        if let Some(scene) = self.scene_manager.active_scene_mut() {
+            self.script_manager.update_scripts(scene, &mut self.physics_manager, &self.input, &self.time);
+            self.script_manager.apply_commands(scene, &mut self.renderer);
         }
 
         if let Some(scene) = self.scene_manager.active_scene_mut() {
@@ -460,15 +479,35 @@ impl AlanderApp {
             }
 
             // 1.5 更新动画系统
-            update_animation_state_machine(scene, delta_time);
+            alander_core::state_machine::update_state_machines(&mut scene.world, delta_time);
             update_animations(scene, delta_time);
 
+            // 1.6 方向键驱动角色控制器 (仅模拟运行时)
+            if self.physics_manager.is_running {
+                use winit::event::VirtualKeyCode;
+                let mut direction = glam::Vec3::ZERO;
+                if self.input.key_pressed(VirtualKeyCode::Up) { direction.z -= 1.0; }
+                if self.input.key_pressed(VirtualKeyCode::Down) { direction.z += 1.0; }
+                if self.input.key_pressed(VirtualKeyCode::Left) { direction.x -= 1.0; }
+                if self.input.key_pressed(VirtualKeyCode::Right) { direction.x += 1.0; }
+                let direction = direction.normalize_or_zero();
+                let mut character_query = scene.world.query::<&mut alander_core::scene::CharacterController>();
+                for mut character in character_query.iter_mut(&mut scene.world) {
+                    if let Some(speed) = character.input_speed {
+                        if direction != glam::Vec3::ZERO {
+                            character.desired_movement = direction * speed;
+                        }
+                    }
+                }
+            }
+
             // 2. 将逻辑变更同步到物理世界并执行步进
-            self.physics_manager.integration_parameters.dt = delta_time;
-            self.physics_manager.sync_ecs_to_physics(&mut scene.world);
-            self.physics_manager.step();
-            self.physics_manager.sync_physics_to_ecs(&mut scene.world);
-            self.physics_manager.update_query_pipeline();
+            self.physics_manager.advance(&mut scene.world, delta_time);
+
+            // 2.5 发布碰撞事件并调用脚本的碰撞回调
+            self.physics_manager.publish_events(&mut scene.world);
+            self.script_manager.dispatch_collision_events(scene, &mut self.physics_manager);
+            self.script_manager.apply_commands(scene, &mut self.renderer);
 
             // 3. 收集并更新调试线框 (碰撞体 + 视锥体)
             let mut debug_vertices = Vec::new();
@@ -476,6 +515,7 @@ impl AlanderApp {
             // 物理碰撞体
             if self.editor_state.show_colliders {
                 debug_vertices.extend(self.physics_manager.render_debug_lines());
+                debug_vertices.extend(crate::physics_manager::force_field_debug_lines(&mut scene.world));
             }
 
             // 相机视锥体
@@ -533,8 +573,6 @@ impl AlanderApp {
 
         self.input.clear_frame_state();
 
-        self.time.delta = delta_time;
-        self.time.elapsed += delta_time;
         self.fps_update_timer += delta_time;
         if self.fps_update_timer >= 0.2 {
             self.displayed_delta_time = delta_time;
@@ -690,52 +728,78 @@ impl AlanderApp {
             self.renderer.device(), self.renderer.queue(), &mut encoder, &paint_jobs, &screen_descriptor,
         );
 
-        // 渲染 3D 场景
-        // 获取所有可渲染对象
-        let objects: Vec<_> = self.renderer.resources.objects.values().collect();
-        self.renderer.render_shadow_pass(&mut encoder, &objects, self.renderer.shadow_view_proj);
-
-        // 简单的演示：如果有第一个点光源，渲染其全向阴影
-        // 实际开发中应该动态收集需要阴影的点光源
+        // 更新动画与蒙皮 (绑定组可能因调色板重建而更新，需在收集渲染对象之前完成)
+        let mut point_light = None;
         if let Some(scene) = self.scene_manager.active_scene_mut() {
             // 更新动画
             update_animations(scene, self.displayed_delta_time);
 
-            // 5. 更新已有对象的变换和骨骼
-            let mut query = scene.world.query::<(Entity, &alander_core::scene::GlobalTransform, &alander_core::scene::RenderId, Option<&alander_core::scene::Skin>)>();
-            for (entity, gt, rid, skin) in query.iter(&scene.world) {
-                if let Some(obj) = self.renderer.get_object(rid.0) {
-                    let cgmath_matrix = cgmath::Matrix4::from(gt.0.to_cols_array_2d());
-                    obj.update_model(self.renderer.queue(), cgmath_matrix, skin.is_some());
-
-                    // 如果有蒙皮，更新骨骼矩阵
-                    if let Some(skin_comp) = skin {
-                        let mut bone_buffer = alander_render::pipelines::common::BoneBuffer {
-                            matrices: [[[0.0; 4]; 4]; 128],
-                        };
-                        
-                        let mesh_inv_world = gt.0.inverse();
-
-                        for (i, &joint_entity) in skin_comp.joints.iter().enumerate() {
-                            if i >= 128 { break; }
-                            
-                            if let Some(joint_gt) = scene.world.get::<alander_core::scene::GlobalTransform>(joint_entity) {
-                                // 计算骨骼空间变换: World(Joint) * InvBind(Joint) * InvWorld(Mesh)
-                                let ibm = skin_comp.inverse_bind_matrices[i];
-                                let joint_matrix = mesh_inv_world * joint_gt.0 * ibm;
-                                bone_buffer.matrices[i] = cgmath::Matrix4::from(joint_matrix.to_cols_array_2d()).into();
-                            }
+            // IK 约束需要基于最新的动画姿态计算，并在蒙皮之前完成；渲染对象更新后恢复求解前的姿态
+            scene.update_hierarchy();
+            let ik_pose = alander_core::ik::solve_ik_constraints(&mut scene.world);
+
+            // 5. 每个蒙皮计算一次关节调色板，由引用它的所有网格共享
+            let mut skin_palettes = std::collections::HashMap::new();
+            let mut live_palettes = std::collections::HashSet::new();
+            let mut skin_query = scene.world.query::<(Entity, &alander_core::scene::Skin)>();
+            for (skin_entity, skin) in skin_query.iter(&scene.world) {
+                live_palettes.insert(skin.palette_id);
+                match alander_core::skinning::gpu_palette(&scene.world, skin) {
+                    Ok(palette) => {
+                        self.reported_skin_errors.remove(&skin_entity);
+                        let joints: Vec<_> = palette.iter().map(|m| m.to_cols_array_2d()).collect();
+                        self.renderer.update_skin_palette(skin.palette_id, &joints);
+                        skin_palettes.insert(skin_entity, (skin.palette_id, skin.mode));
+                    }
+                    Err(e) => {
+                        if self.reported_skin_errors.insert(skin_entity) {
+                            tracing::error!("{}", e);
                         }
-                        obj.update_bones(self.renderer.queue(), &bone_buffer);
                     }
                 }
             }
 
-            let mut point_light_query = scene.world.query::<(&alander_core::scene::GlobalTransform, &alander_core::scene::PointLight)>();
-            if let Some((gt, light)) = point_light_query.iter(&scene.world).next() {
-                let pos = gt.0.transform_point3(glam::Vec3::ZERO);
-                self.renderer.render_point_shadow_pass(&mut encoder, &objects, pos.into(), light.range);
+            // 释放已删除的蒙皮 (或已卸载场景中的蒙皮) 的调色板
+            let stale_palettes: Vec<_> = self.renderer.resources.skin_palettes.keys().filter(|id| !live_palettes.contains(*id)).copied().collect();
+            for palette_id in stale_palettes {
+                self.renderer.remove_skin_palette(&palette_id);
             }
+
+            // 更新已有对象的变换，并绑定蒙皮调色板
+            let mut query = scene.world.query::<(Entity, &GlobalTransform, &RenderId)>();
+            let objects: Vec<_> = query
+                .iter(&scene.world)
+                .map(|(entity, gt, rid)| (rid.0, gt.0, alander_core::skinning::mesh_skin(&scene.world, entity)))
+                .collect();
+            for (render_id, matrix, skin) in objects {
+                let palette = skin.and_then(|s| skin_palettes.get(&s).copied());
+                if let Some((palette_id, _)) = palette {
+                    self.renderer.bind_object_skin(render_id, palette_id);
+                }
+                if let Some(obj) = self.renderer.get_object(render_id) {
+                    let cgmath_matrix = cgmath::Matrix4::from(matrix.to_cols_array_2d());
+                    // 蒙皮无效时退回到未蒙皮渲染
+                    let mode = palette.map(|(_, mode)| mode).filter(|_| obj.has_skinning);
+                    obj.update_model_skinned(self.renderer.queue(), cgmath_matrix, mode);
+                }
+            }
+            ik_pose.restore(&mut scene.world);
+
+            // 简单的演示：如果有第一个点光源，渲染其全向阴影
+            // 实际开发中应该动态收集需要阴影的点光源
+            let mut point_light_query = scene.world.query::<(&GlobalTransform, &PointLight)>();
+            point_light = point_light_query
+                .iter(&scene.world)
+                .next()
+                .map(|(gt, light)| (gt.0.transform_point3(glam::Vec3::ZERO), light.range));
+        }
+
+        // 渲染 3D 场景
+        // 获取所有可渲染对象
+        let objects: Vec<_> = self.renderer.resources.objects.values().collect();
+        self.renderer.render_shadow_pass(&mut encoder, &objects, self.renderer.shadow_view_proj);
+        if let Some((pos, range)) = point_light {
+            self.renderer.render_point_shadow_pass(&mut encoder, &objects, pos.into(), range);
         }
 
         self.renderer.render_scene(&view, &mut encoder);
@@ -767,6 +831,7 @@ impl AlanderApp {
     }
 
     fn ui(&mut self, ctx: &egui::Context) {
+        let console = self.script_manager.console().clone();
         let action = self.editor_ui.draw(
             ctx,
             &mut self.scene_manager,
@@ -775,6 +840,7 @@ impl AlanderApp {
             &mut self.renderer,
             &mut self.command_manager,
             &mut self.editor_state,
+            &mut console.lock().unwrap(),
             self.displayed_delta_time
         );
 
@@ -877,89 +943,13 @@ impl AlanderApp {
 
 }
 
-/// 更新所有识体的动画系统 (独立于 AlanderApp 以避免借用冲突)
-fn update_animation_state_machine(scene: &mut Scene, _dt: f32) {
-    use alander_core::scene::{AnimationStateMachine, AnimationPlayer, AnimParamValue, AnimCondition};
-    use bevy_ecs::prelude::*;
-
-    let mut transitions_to_trigger = Vec::new();
-
-    {
-        let mut query = scene.world.query::<(Entity, &AnimationStateMachine, &AnimationPlayer)>();
-        for (entity, sm, _player) in query.iter(&scene.world) {
-            let current_state_name = sm.current_state.clone();
-            if let Some(state) = sm.states.get(&current_state_name) {
-                for transition in &state.transitions {
-                    let mut all_met = true;
-                    for condition in &transition.conditions {
-                        let met = match condition {
-                            AnimCondition::Greater(param, val) => {
-                                sm.parameters.get(param).map_or(false, |v| match v {
-                                    AnimParamValue::Float(f) => *f > *val,
-                                    _ => false,
-                                })
-                            }
-                            AnimCondition::Less(param, val) => {
-                                sm.parameters.get(param).map_or(false, |v| match v {
-                                    AnimParamValue::Float(f) => *f < *val,
-                                    _ => false,
-                                })
-                            }
-                            AnimCondition::Bool(param, val) => {
-                                sm.parameters.get(param).map_or(false, |v| match v {
-                                    AnimParamValue::Bool(b) => *b == *val,
-                                    _ => false,
-                                })
-                            }
-                            AnimCondition::Trigger(param) => {
-                                sm.parameters.get(param).map_or(false, |v| match v {
-                                    AnimParamValue::Trigger(t) => *t,
-                                    _ => false,
-                                })
-                            }
-                        };
-                        if !met {
-                            all_met = false;
-                            break;
-                        }
-                    }
-
-                    if all_met {
-                        if let Some(target_state) = sm.states.get(&transition.target_state) {
-                            let mut triggers_to_clear = Vec::new();
-                            for condition in &transition.conditions {
-                                if let AnimCondition::Trigger(param) = condition {
-                                    triggers_to_clear.push(param.clone());
-                                }
-                            }
-                            
-                            transitions_to_trigger.push((entity, target_state.clip_index, transition.duration, transition.target_state.clone(), triggers_to_clear));
-                            break; // 每次只触发一个转换
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    for (entity, clip_idx, duration, state_name, triggers) in transitions_to_trigger {
-        if let Some(mut player) = scene.world.get_mut::<AnimationPlayer>(entity) {
-            player.cross_fade(clip_idx, duration);
-        }
-        if let Some(mut sm) = scene.world.get_mut::<AnimationStateMachine>(entity) {
-            sm.current_state = state_name;
-            for param in triggers {
-                sm.parameters.insert(param, AnimParamValue::Trigger(false));
-            }
-        }
-    }
-}
 
 fn update_animations(scene: &mut Scene, dt: f32) {
     use alander_core::scene::{AnimationPlayer, Transform, Name, Children};
     use bevy_ecs::prelude::*;
 
     let mut animation_updates = Vec::new();
+    let mut property_updates = Vec::new();
 
     // 1. 采样所有活跃的播放器
     {
@@ -1051,6 +1041,38 @@ fn update_animations(scene: &mut Scene, dt: f32) {
                     }
                     animation_updates.push((root_entity, target_name.clone(), blended_pos, blended_rot, blended_sca));
                 }
+
+                // 属性通道: 按 (目标, 组件, 字段) 逐一混合
+                let mut property_keys = Vec::new();
+                for &(idx, _, _) in &sync_clips {
+                    if let Some(clip) = player.clips.get(idx) {
+                        for channel in &clip.property_channels {
+                            let key = (channel.target_name.clone(), channel.component.clone(), channel.field.clone());
+                            if !property_keys.contains(&key) {
+                                property_keys.push(key);
+                            }
+                        }
+                    }
+                }
+
+                for (target_name, component, field) in property_keys {
+                    let mut blended: Option<alander_core::property::PropertyValue> = None;
+                    let mut total_weight = 0.0;
+                    for &(idx, t, weight) in &sync_clips {
+                        let Some(clip) = player.clips.get(idx) else { continue };
+                        let Some(channel) = clip.property_channels.iter().find(|c| c.targets(&target_name, &component, &field)) else { continue };
+                        if let Some(v) = channel.track.sample(t) {
+                            blended = Some(match blended {
+                                Some(acc) if acc.kind() == v.kind() => acc.blend(&v, weight / (total_weight + weight)),
+                                _ => v,
+                            });
+                            total_weight += weight;
+                        }
+                    }
+                    if let Some(value) = blended {
+                        property_updates.push((root_entity, target_name, component, field, value));
+                    }
+                }
             }
         }
     }
@@ -1059,6 +1081,10 @@ fn update_animations(scene: &mut Scene, dt: f32) {
     for update in animation_updates {
         let (root, target_name, pos, rot, sca) = update;
         if let Some(target_entity) = find_entity_by_name_recursive(&scene.world, root, &target_name) {
+            // 模拟中的布娃娃骨骼由物理驱动
+            if alander_core::ragdoll::is_simulated_joint(&scene.world, target_entity) {
+                continue;
+            }
             if let Some(mut transform) = scene.world.get_mut::<Transform>(target_entity) {
                 if let Some(p) = pos { transform.position = p; }
                 if let Some(r) = rot { transform.rotation = r; }
@@ -1066,6 +1092,12 @@ fn update_animations(scene: &mut Scene, dt: f32) {
             }
         }
     }
+
+    for (root, target_name, component, field, value) in property_updates {
+        if let Some(target_entity) = find_entity_by_name_recursive(&scene.world, root, &target_name) {
+            alander_core::property::set_property(&mut scene.world, target_entity, &component, &field, value);
+        }
+    }
 }
 
 fn find_entity_by_name_recursive(world: &World, entity: Entity, name: &str) -> Option<Entity> {
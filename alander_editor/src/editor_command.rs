@@ -224,3 +224,97 @@ impl EditorCommand for DuplicateEntityCommand {
 
     fn name(&self) -> &str { "复制实体" }
 }
+
+/// 动画编辑命令: 记录实体 AnimationPlayer 剪辑集合在编辑前后的快照
+pub struct AnimationEditCommand {
+    entity: Entity,
+    old_clips: Vec<alander_core::scene::AnimationClip>,
+    new_clips: Vec<alander_core::scene::AnimationClip>,
+    /// 编辑前后的当前剪辑 (None 表示编辑不改变当前剪辑)
+    active_clip: Option<(Option<usize>, Option<usize>)>,
+    name: &'static str,
+}
+
+impl AnimationEditCommand {
+    pub fn new(
+        entity: Entity,
+        old_clips: Vec<alander_core::scene::AnimationClip>,
+        new_clips: Vec<alander_core::scene::AnimationClip>,
+        name: &'static str,
+    ) -> Self {
+        Self { entity, old_clips, new_clips, active_clip: None, name }
+    }
+
+    /// 同时切换当前剪辑，撤销时恢复为 `old`
+    pub fn with_active_clip(mut self, old: Option<usize>, new: Option<usize>) -> Self {
+        self.active_clip = Some((old, new));
+        self
+    }
+
+    fn apply(&self, scene: &mut Scene, clips: &[alander_core::scene::AnimationClip], active: Option<Option<usize>>) {
+        if let Some(mut player) = scene.world.get_mut::<alander_core::scene::AnimationPlayer>(self.entity) {
+            player.clips = clips.to_vec();
+            if let Some(active) = active.filter(|a| *a != player.active_clip_index) {
+                player.active_clip_index = active;
+                player.current_time = 0.0;
+            }
+            if player.active_clip_index.is_some_and(|i| i >= player.clips.len()) {
+                player.active_clip_index = if player.clips.is_empty() { None } else { Some(player.clips.len() - 1) };
+            }
+            if player.transition_target_index.is_some_and(|i| i >= player.clips.len()) {
+                player.transition_target_index = None;
+            }
+        }
+    }
+}
+
+impl EditorCommand for AnimationEditCommand {
+    fn execute(&mut self, scene: &mut Scene, _renderer: &mut Renderer) {
+        self.apply(scene, &self.new_clips, self.active_clip.map(|(_, new)| new));
+    }
+
+    fn undo(&mut self, scene: &mut Scene, _renderer: &mut Renderer) {
+        self.apply(scene, &self.old_clips, self.active_clip.map(|(old, _)| old));
+    }
+
+    fn name(&self) -> &str { self.name }
+}
+
+/// 状态机编辑命令: 记录实体 AnimationStateMachine 组件在编辑前后的快照 (None 表示无该组件)
+pub struct StateMachineEditCommand {
+    entity: Entity,
+    old: Option<alander_core::scene::AnimationStateMachine>,
+    new: Option<alander_core::scene::AnimationStateMachine>,
+    name: &'static str,
+}
+
+impl StateMachineEditCommand {
+    pub fn new(
+        entity: Entity,
+        old: Option<alander_core::scene::AnimationStateMachine>,
+        new: Option<alander_core::scene::AnimationStateMachine>,
+        name: &'static str,
+    ) -> Self {
+        Self { entity, old, new, name }
+    }
+
+    fn apply(&self, scene: &mut Scene, sm: &Option<alander_core::scene::AnimationStateMachine>) {
+        let Some(mut entity) = scene.world.get_entity_mut(self.entity) else { return };
+        match sm {
+            Some(sm) => { entity.insert(sm.clone()); }
+            None => { entity.remove::<alander_core::scene::AnimationStateMachine>(); }
+        }
+    }
+}
+
+impl EditorCommand for StateMachineEditCommand {
+    fn execute(&mut self, scene: &mut Scene, _renderer: &mut Renderer) {
+        self.apply(scene, &self.new);
+    }
+
+    fn undo(&mut self, scene: &mut Scene, _renderer: &mut Renderer) {
+        self.apply(scene, &self.old);
+    }
+
+    fn name(&self) -> &str { self.name }
+}
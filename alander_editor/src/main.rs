@@ -1,11 +1,14 @@
 pub mod scene_manager;
 pub mod physics_manager;
+pub mod physics_query;
 pub mod gizmo_manager;
 pub mod camera_controller;
 pub mod ui;
 pub mod app;
 pub mod editor_command;
 pub mod script_manager;
+pub mod script_api;
+pub mod script_console;
 
 use app::AlanderApp;
 use winit::{
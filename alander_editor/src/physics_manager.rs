@@ -1,11 +1,269 @@
 use rapier3d::prelude::*;
+use rapier3d::control::{CharacterAutostep, CharacterCollision, CharacterLength, KinematicCharacterController};
 use rapier3d::na::{Vector3, UnitQuaternion, Isometry3, Quaternion};
-use alander_core::scene::{Transform, RigidBody, Collider, RigidBodyType, ColliderShape, GlobalTransform, Parent};
+use alander_core::scene::{Transform, RigidBody, Collider, RigidBodyType, ColliderShape, GlobalTransform, Parent, Children, PhysicsJoint, PhysicsJointKind, CollisionLayers, PhysicsLayers, CharacterController};
+use alander_core::scene::{ForceField, ForceFieldKind, ForceFieldVolume};
+use alander_core::scene::{EntityUuid, RenderId, Script};
+use alander_core::ragdoll::{write_bone_poses, Ragdoll, RagdollBone, RagdollJointKind, RagdollMode};
+use alander_core::events::{CollisionEvent, ContactForceEvent, IntersectionEvent};
+use alander_core::SimulationRng;
 use glam::{Vec3, Quat, Mat4};
 use bevy_ecs::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::physics_query::{LentPhysics, PhysicsQueries};
+use crate::scene_manager::Scene;
 use rapier3d::pipeline::{DebugRenderPipeline, DebugRenderMode, DebugRenderStyle, DebugRenderBackend, DebugRenderObject};
 
+/// 项目物理层配置文件 (相对工作目录)
+pub const PHYSICS_LAYERS_FILE: &str = "physics_layers.json";
+
+/// 读取项目物理层配置，文件不存在或无效时使用默认配置
+pub fn load_physics_layers(path: &str) -> PhysicsLayers {
+    let Ok(json) = std::fs::read_to_string(path) else { return PhysicsLayers::default() };
+    match serde_json::from_str::<PhysicsLayers>(&json) {
+        Ok(mut layers) => {
+            layers.names.resize(PhysicsLayers::MAX_LAYERS, String::new());
+            layers
+        }
+        Err(e) => {
+            tracing::error!("物理层配置 {} 无效: {}", path, e);
+            PhysicsLayers::default()
+        }
+    }
+}
+
+/// 保存项目物理层配置
+pub fn save_physics_layers(path: &str, layers: &PhysicsLayers) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(layers).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// 固定时间步设置
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimestepSettings {
+    /// 每个物理步的时长 (秒)
+    pub fixed_dt: f32,
+    /// 每个物理步内的子步数
+    pub substeps: u32,
+    /// 每帧最多执行的物理步数，超出的积压时间被丢弃 (防止卡顿后连锁变慢)
+    pub max_steps_per_frame: u32,
+    /// 渲染时是否在最近两个物理状态之间插值
+    pub interpolate: bool,
+}
+
+impl Default for TimestepSettings {
+    fn default() -> Self {
+        Self { fixed_dt: 1.0 / 60.0, substeps: 1, max_steps_per_frame: 5, interpolate: true }
+    }
+}
+
+/// 一个角色控制器在某个物理步之前的输入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterInput {
+    pub entity: uuid::Uuid,
+    pub movement: Vec3,
+    pub vertical_speed: f32,
+}
+
+/// 施加到刚体上的力 (世界空间)
+///
+/// 持续的力与力矩只在施加它的那一帧的物理步中生效 (需每帧重新施加)，冲量只在下一个物理步中生效一次。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BodyForce {
+    Force(Vec3),
+    Torque(Vec3),
+    ForceAtPoint { force: Vec3, point: Vec3 },
+    Impulse(Vec3),
+    TorqueImpulse(Vec3),
+    ImpulseAtPoint { impulse: Vec3, point: Vec3 },
+}
+
+impl BodyForce {
+    /// 是否为一次性冲量
+    pub fn is_impulse(&self) -> bool {
+        matches!(self, BodyForce::Impulse(_) | BodyForce::TorqueImpulse(_) | BodyForce::ImpulseAtPoint { .. })
+    }
+}
+
+/// 某个物理步之前通过 [`PhysicsManager::apply`] 施加的力
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForceInput {
+    pub entity: uuid::Uuid,
+    pub force: BodyForce,
+}
+
+/// 一个物理步的全部外部输入
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepInput {
+    pub characters: Vec<CharacterInput>,
+    #[serde(default)]
+    pub forces: Vec<ForceInput>,
+}
+
+/// 模拟录制：从相同的初始场景出发，按相同输入回放可得到完全一致的结果
+///
+/// 只记录角色控制器的输入与通过 [`PhysicsManager::apply`] 施加的力；脚本直接修改 Transform 不会被录制。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationRecording {
+    pub seed: u64,
+    pub timestep: TimestepSettings,
+    pub gravity: Vec3,
+    pub steps: Vec<StepInput>,
+}
+
+/// 物理世界的完整状态 (开始模拟时保存，停止时恢复)
+#[derive(Clone)]
+struct PhysicsState {
+    rigid_body_set: RigidBodySet,
+    collider_set: ColliderSet,
+    island_manager: IslandManager,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    impulse_joint_set: ImpulseJointSet,
+    multibody_joint_set: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    query_pipeline: QueryPipeline,
+    shape_cache: HashMap<ColliderHandle, ShapeKey>,
+    collider_entities: HashMap<ColliderHandle, Entity>,
+    mass_cache: HashMap<RigidBodyHandle, MassKey>,
+    accumulator: f32,
+    previous_poses: HashMap<RigidBodyHandle, Isometry<f32>>,
+    written_poses: HashMap<RigidBodyHandle, Isometry<f32>>,
+    entity_bodies: HashMap<Entity, RigidBodyHandle>,
+    entity_colliders: HashMap<Entity, ColliderHandle>,
+    entity_joints: HashMap<Entity, ImpulseJointHandle>,
+}
+
+/// 开始模拟时场景中所有实体的组件
+///
+/// 模拟期间的任何修改 (物理、脚本、动画、检查器编辑) 在停止时都被撤销：删除的实体以原来的
+/// Entity ID 重新创建，新建的实体被删除。
+struct EcsSnapshot {
+    entities: HashSet<Entity>,
+    components: Vec<Box<dyn ComponentSnapshot>>,
+}
+
+/// 开始模拟时的编辑状态快照
+struct SimulationSnapshot {
+    physics: PhysicsState,
+    ecs: EcsSnapshot,
+}
+
+impl EcsSnapshot {
+    fn take(world: &mut World) -> Self {
+        use alander_core::ik::{FabrikChain, LookAtConstraint, TwoBoneIk};
+        use alander_core::scene::{
+            AnimationPlayer, AnimationStateMachine, AssetPath, BoundingBox, Camera, DirectionalLight, Joint, Material, Mesh, Name,
+            PBRMaterial, PointLight, Skin, SkinnedMesh, SpotLight,
+        };
+        Self {
+            entities: world.iter_entities().map(|e| e.id()).collect(),
+            components: vec![
+                snapshot::<Name>(world),
+                snapshot::<EntityUuid>(world),
+                snapshot::<Parent>(world),
+                snapshot::<Children>(world),
+                snapshot::<Transform>(world),
+                snapshot::<GlobalTransform>(world),
+                snapshot::<Mesh>(world),
+                snapshot::<Material>(world),
+                snapshot::<RenderId>(world),
+                snapshot::<BoundingBox>(world),
+                snapshot::<AssetPath>(world),
+                snapshot::<PBRMaterial>(world),
+                snapshot::<Camera>(world),
+                snapshot::<PointLight>(world),
+                snapshot::<DirectionalLight>(world),
+                snapshot::<SpotLight>(world),
+                snapshot::<Skin>(world),
+                snapshot::<SkinnedMesh>(world),
+                snapshot::<Joint>(world),
+                snapshot::<AnimationPlayer>(world),
+                snapshot::<AnimationStateMachine>(world),
+                snapshot::<TwoBoneIk>(world),
+                snapshot::<FabrikChain>(world),
+                snapshot::<LookAtConstraint>(world),
+                snapshot::<RigidBody>(world),
+                snapshot::<Collider>(world),
+                snapshot::<CharacterController>(world),
+                snapshot::<PhysicsJoint>(world),
+                snapshot::<ForceField>(world),
+                snapshot::<Script>(world),
+                snapshot::<Ragdoll>(world),
+                snapshot::<RagdollBone>(world),
+            ],
+        }
+    }
+
+    /// 删除模拟期间新建的实体，重新创建被删除的实体并恢复所有组件
+    ///
+    /// 返回被删除实体独占的渲染对象，由调用方释放。
+    fn restore(self, scene: &mut Scene) -> Vec<uuid::Uuid> {
+        let world = &mut scene.world;
+        let kept_objects: HashSet<uuid::Uuid> = world.query::<(Entity, &RenderId)>()
+            .iter(world)
+            .filter(|(e, _)| self.entities.contains(e))
+            .map(|(_, id)| id.0)
+            .collect();
+        let spawned: Vec<Entity> = world.iter_entities().map(|e| e.id()).filter(|e| !self.entities.contains(e)).collect();
+        let released: Vec<uuid::Uuid> = spawned.iter()
+            .filter_map(|&e| world.get::<RenderId>(e).map(|id| id.0))
+            .filter(|id| !kept_objects.contains(id))
+            .collect();
+        for entity in spawned {
+            // 父实体已删除时子实体随之删除
+            if scene.world.get_entity(entity).is_some() {
+                scene.remove_entity(entity);
+            }
+        }
+
+        // 删除的实体 (包括被新建实体连带删除的原有实体) 以原 ID 重新创建，组件在下面恢复
+        let world = &mut scene.world;
+        for &entity in &self.entities {
+            if world.get_entity(entity).is_none() && world.get_or_spawn(entity).is_none() {
+                tracing::warn!("无法恢复模拟期间删除的实体 {:?}", entity);
+            }
+        }
+        for components in self.components {
+            components.restore(world);
+        }
+        released
+    }
+}
+
+/// 一种组件类型的快照
+trait ComponentSnapshot {
+    fn restore(self: Box<Self>, world: &mut World);
+}
+
+struct Components<T>(Vec<(Entity, T)>);
+
+impl<T: Component + Clone> ComponentSnapshot for Components<T> {
+    fn restore(self: Box<Self>, world: &mut World) {
+        restore_components(world, self.0);
+    }
+}
+
+fn snapshot<T: Component + Clone>(world: &mut World) -> Box<dyn ComponentSnapshot> {
+    Box::new(Components(world.query::<(Entity, &T)>().iter(world).map(|(e, c)| (e, c.clone())).collect::<Vec<_>>()))
+}
+
+/// 将组件恢复为快照中的值：快照后新增的同类组件被移除，被移除的组件重新插入
+fn restore_components<T: Component>(world: &mut World, saved: Vec<(Entity, T)>) {
+    let saved_entities: HashSet<Entity> = saved.iter().map(|(e, _)| *e).collect();
+    let added: Vec<Entity> = world.query_filtered::<Entity, With<T>>().iter(world).filter(|e| !saved_entities.contains(e)).collect();
+    for entity in added {
+        world.entity_mut(entity).remove::<T>();
+    }
+    for (entity, component) in saved {
+        if let Some(mut entity_mut) = world.get_entity_mut(entity) {
+            entity_mut.insert(component);
+        }
+    }
+}
+
 /// 物理管理器，封装了 Rapier3D 的世界和模拟逻辑
 pub struct PhysicsManager {
     /// 刚体集合
@@ -36,6 +294,34 @@ pub struct PhysicsManager {
     pub query_pipeline: QueryPipeline,
     /// 模拟是否正在运行
     pub is_running: bool,
+    /// 每个碰撞体上次构建形状时使用的参数
+    shape_cache: HashMap<ColliderHandle, ShapeKey>,
+    /// 碰撞体所属实体 (碰撞体被删除后仍可映射其结束事件)
+    collider_entities: HashMap<ColliderHandle, Entity>,
+    /// 步进期间收集的碰撞/接触力事件
+    event_collector: PhysicsEventCollector,
+    /// 每个刚体上次计算质量时使用的参数
+    mass_cache: HashMap<RigidBodyHandle, MassKey>,
+    /// 固定时间步设置
+    pub timestep: TimestepSettings,
+    /// 尚未消耗的帧时间
+    accumulator: f32,
+    /// 最近一个物理步之前的刚体位姿 (用于插值)
+    previous_poses: HashMap<RigidBodyHandle, Isometry<f32>>,
+    /// 上次回读写入 ECS 的位姿，用于区分插值结果与用户编辑
+    written_poses: HashMap<RigidBodyHandle, Isometry<f32>>,
+    /// 正在进行的录制
+    recording: Option<SimulationRecording>,
+    /// 开始模拟前的编辑状态，停止模拟时恢复
+    snapshot: Option<SimulationSnapshot>,
+    /// 每个实体拥有的刚体 (包括角色控制器的刚体)、碰撞体和关节，用于在组件删除后找到对应的物理对象
+    entity_bodies: HashMap<Entity, RigidBodyHandle>,
+    entity_colliders: HashMap<Entity, ColliderHandle>,
+    entity_joints: HashMap<Entity, ImpulseJointHandle>,
+    /// 等待施加的力与冲量 (冲量在下一个物理步后移除，持续力在本帧结束后移除，不论本帧是否执行了物理步)
+    pending_forces: Vec<(Entity, BodyForce)>,
+    /// 上一个物理步中被施加了持续力的刚体 (下一步开始时重置)
+    forced_bodies: Vec<RigidBodyHandle>,
 }
 
 impl PhysicsManager {
@@ -59,126 +345,1038 @@ impl PhysicsManager {
             ),
             query_pipeline: QueryPipeline::new(),
             is_running: false,
+            shape_cache: HashMap::new(),
+            collider_entities: HashMap::new(),
+            event_collector: PhysicsEventCollector::default(),
+            mass_cache: HashMap::new(),
+            timestep: TimestepSettings::default(),
+            accumulator: 0.0,
+            previous_poses: HashMap::new(),
+            written_poses: HashMap::new(),
+            recording: None,
+            snapshot: None,
+            entity_bodies: HashMap::new(),
+            entity_colliders: HashMap::new(),
+            entity_joints: HashMap::new(),
+            pending_forces: Vec::new(),
+            forced_bodies: Vec::new(),
+        }
+    }
+
+    /// 推进一帧：同步 ECS 到物理世界，按固定时间步执行若干物理步，再将 (插值后的) 结果写回 ECS
+    pub fn advance(&mut self, world: &mut World, frame_dt: f32) {
+        self.sync_ecs_to_physics(world);
+
+        if self.is_running {
+            let fixed_dt = self.timestep.fixed_dt.max(1.0e-4);
+            self.accumulator += frame_dt.max(0.0);
+            let mut steps = 0;
+            while self.accumulator >= fixed_dt && steps < self.timestep.max_steps_per_frame {
+                self.step(world);
+                self.accumulator -= fixed_dt;
+                steps += 1;
+            }
+            if steps == self.timestep.max_steps_per_frame {
+                self.accumulator = self.accumulator.min(fixed_dt);
+            }
+            // 期望移动量在本帧的所有物理步中生效，之后清零等待脚本或输入重新设置
+            if steps > 0 {
+                for mut cc in world.query::<&mut CharacterController>().iter_mut(world) {
+                    cc.desired_movement = Vec3::ZERO;
+                }
+            }
+            // 持续力每帧重新施加，没有物理步的帧也要丢弃，否则下一步会叠加多帧的力；未消耗的冲量保留到下一步
+            self.pending_forces.retain(|(_, f)| f.is_impulse());
+        } else {
+            self.pending_forces.clear();
         }
+
+        self.sync_physics_to_ecs(world);
     }
 
-    pub fn step(&mut self) {
+    /// 执行一个固定时长的物理步：先移动角色控制器，再按子步推进物理流水线
+    pub fn step(&mut self, world: &mut World) {
         if !self.is_running {
             return;
         }
 
-        self.physics_pipeline.step(
-            &self.gravity,
-            &self.integration_parameters,
-            &mut self.island_manager,
-            &mut self.broad_phase,
-            &mut self.narrow_phase,
-            &mut self.rigid_body_set,
-            &mut self.collider_set,
-            &mut self.impulse_joint_set,
-            &mut self.multibody_joint_set,
-            &mut self.ccd_solver,
-            None,
-            &(),
-            &(),
+        self.record_step_input(world);
+        self.previous_poses = self.rigid_body_set.iter()
+            .filter(|(_, body)| !body.is_fixed())
+            .map(|(handle, body)| (handle, *body.position()))
+            .collect();
+
+        let fixed_dt = self.timestep.fixed_dt.max(1.0e-4);
+        self.move_characters(world, fixed_dt);
+        for mut ragdoll in world.query::<&mut Ragdoll>().iter_mut(world) {
+            if ragdoll.mode == RagdollMode::Simulated {
+                ragdoll.bypass_change_detection().blend_elapsed += fixed_dt;
+            }
+        }
+        self.apply_forces(world);
+
+        let substeps = self.timestep.substeps.max(1);
+        self.integration_parameters.dt = fixed_dt / substeps as f32;
+        for _ in 0..substeps {
+            self.physics_pipeline.step(
+                &self.gravity,
+                &self.integration_parameters,
+                &mut self.island_manager,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.rigid_body_set,
+                &mut self.collider_set,
+                &mut self.impulse_joint_set,
+                &mut self.multibody_joint_set,
+                &mut self.ccd_solver,
+                None,
+                &(),
+                &self.event_collector,
+            );
+        }
+        self.update_query_pipeline();
+    }
+
+    /// 对实体的刚体施加力、力矩或冲量，只影响动态刚体
+    ///
+    /// 在模拟运行时的下一个物理步生效；模拟未运行时被丢弃。
+    /// 同一帧内对同一实体重复施加的同类持续力只保留最后一次，冲量则累加。
+    pub fn apply(&mut self, entity: Entity, force: BodyForce) {
+        if !force.is_impulse() {
+            let kind = std::mem::discriminant(&force);
+            self.pending_forces.retain(|(e, f)| *e != entity || std::mem::discriminant(f) != kind);
+        }
+        self.pending_forces.push((entity, force));
+    }
+
+    /// 施加本步的外力：先重置上一步的持续力，再施加排队的力/冲量和力场
+    fn apply_forces(&mut self, world: &mut World) {
+        for handle in std::mem::take(&mut self.forced_bodies) {
+            if let Some(body) = self.rigid_body_set.get_mut(handle) {
+                body.reset_forces(false);
+                body.reset_torques(false);
+            }
+        }
+
+        let pending = std::mem::take(&mut self.pending_forces);
+        for &(entity, force) in &pending {
+            let Some(&handle) = self.entity_bodies.get(&entity) else { continue };
+            let Some(body) = self.rigid_body_set.get_mut(handle).filter(|b| b.is_dynamic()) else { continue };
+            match force {
+                BodyForce::Force(f) => body.add_force(to_vector(f), true),
+                BodyForce::Torque(t) => body.add_torque(to_vector(t), true),
+                BodyForce::ForceAtPoint { force, point } => body.add_force_at_point(to_vector(force), to_vector(point).into(), true),
+                BodyForce::Impulse(i) => body.apply_impulse(to_vector(i), true),
+                BodyForce::TorqueImpulse(i) => body.apply_torque_impulse(to_vector(i), true),
+                BodyForce::ImpulseAtPoint { impulse, point } => body.apply_impulse_at_point(to_vector(impulse), to_vector(point).into(), true),
+            }
+            if !force.is_impulse() && !self.forced_bodies.contains(&handle) {
+                self.forced_bodies.push(handle);
+            }
+        }
+        self.pending_forces = pending.into_iter().filter(|(_, f)| !f.is_impulse()).collect();
+
+        self.apply_force_fields(world);
+    }
+
+    /// 对启用的力场范围内的动态刚体施加力；爆炸施加一次冲量后禁用
+    fn apply_force_fields(&mut self, world: &mut World) {
+        let fields: Vec<(Entity, ForceField, Vec3, Quat)> = world.query::<(Entity, &ForceField, &GlobalTransform)>()
+            .iter(world)
+            .filter(|(_, field, _)| field.enabled)
+            .map(|(entity, field, global_transform)| {
+                let (_, rot, pos) = global_transform.0.to_scale_rotation_translation();
+                (entity, field.clone(), pos, rot)
+            })
+            .collect();
+
+        for (entity, field, center, rotation) in fields {
+            let extent = field.volume.extent();
+            for handle in self.bodies_in_field(&field, center, rotation) {
+                let body = &mut self.rigid_body_set[handle];
+                let offset = to_vec3(&body.center_of_mass().coords) - center;
+                let factor = field.falloff.factor(offset.length(), extent);
+                match field.kind {
+                    ForceFieldKind::Wind { direction, strength } => {
+                        body.add_force(to_vector(rotation * direction.normalize_or_zero() * strength * factor), true);
+                    }
+                    ForceFieldKind::Explosion { strength } => {
+                        let direction = offset.try_normalize().unwrap_or(Vec3::Y);
+                        body.apply_impulse(to_vector(direction * strength * factor), true);
+                        continue;
+                    }
+                    ForceFieldKind::Vortex { axis, strength, pull } => {
+                        let axis = (rotation * axis).normalize_or_zero();
+                        let radial = offset - axis * offset.dot(axis);
+                        let tangent = axis.cross(radial).normalize_or_zero();
+                        body.add_force(to_vector((tangent * strength - radial.normalize_or_zero() * pull) * factor), true);
+                    }
+                    ForceFieldKind::Drag { linear, angular } => {
+                        let (linvel, angvel) = (*body.linvel(), *body.angvel());
+                        body.add_force(-linvel * linear * factor, true);
+                        body.add_torque(-angvel * angular * factor, true);
+                    }
+                }
+                if !self.forced_bodies.contains(&handle) {
+                    self.forced_bodies.push(handle);
+                }
+            }
+            if matches!(field.kind, ForceFieldKind::Explosion { .. }) {
+                if let Some(mut field) = world.get_mut::<ForceField>(entity) {
+                    field.enabled = false;
+                }
+            }
+        }
+    }
+
+    /// 碰撞体与力场范围相交的动态刚体
+    fn bodies_in_field(&self, field: &ForceField, center: Vec3, rotation: Quat) -> Vec<RigidBodyHandle> {
+        let shape = match field.volume {
+            ForceFieldVolume::Sphere { radius } => SharedShape::ball(radius.max(0.0)),
+            ForceFieldVolume::Box { half_extents } => {
+                let h = half_extents.max(Vec3::ZERO);
+                SharedShape::cuboid(h.x, h.y, h.z)
+            }
+        };
+        let filter = QueryFilter::only_dynamic().groups(interaction_groups(field.layers)).exclude_sensors();
+        let mut bodies = Vec::new();
+        self.query_pipeline.intersections_with_shape(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &to_isometry(center, rotation),
+            shape.as_ref(),
+            filter,
+            |handle| {
+                if let Some(parent) = self.collider_set.get(handle).and_then(|c| c.parent()).filter(|p| !bodies.contains(p)) {
+                    bodies.push(parent);
+                }
+                true
+            },
         );
+        bodies
+    }
+
+    /// 开始录制模拟输入，录制从下一个物理步开始并以 `seed` 初始化 [`SimulationRng`]
+    pub fn start_recording(&mut self, seed: u64) {
+        self.recording = Some(SimulationRecording {
+            seed,
+            timestep: self.timestep,
+            gravity: to_vec3(&self.gravity),
+            steps: Vec::new(),
+        });
+    }
+
+    /// 结束录制并返回录制结果
+    pub fn stop_recording(&mut self) -> Option<SimulationRecording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// 记录本步的角色控制器输入 (以 UUID 标识实体，回放时可在重新加载的场景中找到)
+    fn record_step_input(&mut self, world: &mut World) {
+        let Some(recording) = &mut self.recording else { return };
+        if recording.steps.is_empty() {
+            world.insert_resource(SimulationRng::new(recording.seed));
+        }
+        let mut characters: Vec<CharacterInput> = world.query::<(&EntityUuid, &CharacterController)>()
+            .iter(world)
+            .map(|(uuid, cc)| CharacterInput { entity: uuid.0, movement: cc.desired_movement, vertical_speed: cc.vertical_speed })
+            .collect();
+        characters.sort_by_key(|c| c.entity);
+        let forces = self.pending_forces.iter()
+            .filter_map(|&(entity, force)| Some(ForceInput { entity: world.get::<EntityUuid>(entity)?.0, force }))
+            .collect();
+        recording.steps.push(StepInput { characters, forces });
+    }
+
+    /// 从当前场景状态开始，按录制的输入立即执行全部物理步
+    ///
+    /// 场景与物理世界需处于录制开始时的状态 (例如重新加载场景并使用新的物理管理器)。
+    pub fn replay(&mut self, world: &mut World, recording: &SimulationRecording) {
+        let active_recording = self.recording.take();
+        let was_running = self.is_running;
+        self.timestep = recording.timestep;
+        self.gravity = to_vector(recording.gravity);
+        world.insert_resource(SimulationRng::new(recording.seed));
+
+        self.sync_ecs_to_physics(world);
+        self.is_running = true;
+        for input in &recording.steps {
+            for character in &input.characters {
+                let Some(entity) = find_entity_by_uuid(world, character.entity) else { continue };
+                if let Some(mut cc) = world.get_mut::<CharacterController>(entity) {
+                    cc.desired_movement = character.movement;
+                    cc.vertical_speed = character.vertical_speed;
+                }
+            }
+            self.pending_forces = input.forces.iter()
+                .filter_map(|f| Some((find_entity_by_uuid(world, f.entity)?, f.force)))
+                .collect();
+            self.step(world);
+        }
+        self.accumulator = 0.0;
+        self.previous_poses.clear();
+        self.pending_forces.clear();
+        self.sync_physics_to_ecs(world);
+
+        self.is_running = was_running;
+        self.recording = active_recording;
+    }
+
+    /// 开始或继续模拟；从编辑状态开始时先保存快照
+    pub fn play(&mut self, world: &mut World) {
+        if self.snapshot.is_none() {
+            self.snapshot = Some(SimulationSnapshot { physics: self.save_state(), ecs: EcsSnapshot::take(world) });
+        }
+        self.is_running = true;
+    }
+
+    /// 暂停模拟，保留快照以便继续或停止
+    pub fn pause(&mut self) {
+        self.is_running = false;
+    }
+
+    /// 是否处于模拟中 (运行或暂停)，即存在可恢复的编辑状态
+    pub fn has_snapshot(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// 在暂停状态下执行一个物理步
+    pub fn step_once(&mut self, world: &mut World) {
+        if self.is_running {
+            return;
+        }
+        self.play(world);
+        self.sync_ecs_to_physics(world);
+        self.step(world);
+        for mut cc in world.query::<&mut CharacterController>().iter_mut(world) {
+            cc.desired_movement = Vec3::ZERO;
+        }
+        self.pending_forces.clear();
+        // 单步后直接显示最新状态，不插值
+        self.accumulator = 0.0;
+        self.previous_poses.clear();
+        self.sync_physics_to_ecs(world);
+        self.is_running = false;
+    }
+
+    /// 停止模拟并恢复开始模拟时的场景与物理状态
+    ///
+    /// `keep` 中的实体保留模拟后的 Transform (下次同步时刚体被移动到该位置)。
+    /// 返回模拟期间新建实体的渲染对象，调用方需从渲染器中移除。
+    pub fn stop(&mut self, scene: &mut Scene, keep: &[Entity]) -> Vec<uuid::Uuid> {
+        self.is_running = false;
+        let Some(snapshot) = self.snapshot.take() else { return Vec::new() };
+        let kept: Vec<(Entity, Transform)> = keep.iter()
+            .filter_map(|&e| scene.world.get::<Transform>(e).map(|t| (e, *t)))
+            .collect();
+
+        let released = snapshot.ecs.restore(scene);
+        self.restore_state(snapshot.physics);
+        self.event_collector.collisions.lock().unwrap().clear();
+        self.event_collector.contact_forces.lock().unwrap().clear();
+        self.pending_forces.clear();
+        self.forced_bodies.clear();
+
+        for (entity, transform) in kept {
+            if let Some(mut t) = scene.world.get_mut::<Transform>(entity) {
+                *t = transform;
+            }
+        }
+        released
+    }
+
+    fn save_state(&self) -> PhysicsState {
+        PhysicsState {
+            rigid_body_set: self.rigid_body_set.clone(),
+            collider_set: self.collider_set.clone(),
+            island_manager: self.island_manager.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            impulse_joint_set: self.impulse_joint_set.clone(),
+            multibody_joint_set: self.multibody_joint_set.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+            query_pipeline: self.query_pipeline.clone(),
+            shape_cache: self.shape_cache.clone(),
+            collider_entities: self.collider_entities.clone(),
+            mass_cache: self.mass_cache.clone(),
+            accumulator: self.accumulator,
+            previous_poses: self.previous_poses.clone(),
+            written_poses: self.written_poses.clone(),
+            entity_bodies: self.entity_bodies.clone(),
+            entity_colliders: self.entity_colliders.clone(),
+            entity_joints: self.entity_joints.clone(),
+        }
+    }
+
+    fn restore_state(&mut self, state: PhysicsState) {
+        self.rigid_body_set = state.rigid_body_set;
+        self.collider_set = state.collider_set;
+        self.island_manager = state.island_manager;
+        self.broad_phase = state.broad_phase;
+        self.narrow_phase = state.narrow_phase;
+        self.impulse_joint_set = state.impulse_joint_set;
+        self.multibody_joint_set = state.multibody_joint_set;
+        self.ccd_solver = state.ccd_solver;
+        self.query_pipeline = state.query_pipeline;
+        self.shape_cache = state.shape_cache;
+        self.collider_entities = state.collider_entities;
+        self.mass_cache = state.mass_cache;
+        self.accumulator = state.accumulator;
+        self.previous_poses = state.previous_poses;
+        self.written_poses = state.written_poses;
+        self.entity_bodies = state.entity_bodies;
+        self.entity_colliders = state.entity_colliders;
+        self.entity_joints = state.entity_joints;
+    }
+
+    /// 按期望移动量移动所有角色控制器，结果作为运动学刚体的下一位置
+    fn move_characters(&mut self, world: &mut World, dt: f32) {
+        let mut query = world.query_filtered::<&mut CharacterController, Without<RigidBody>>();
+        for mut cc in query.iter_mut(world) {
+            let Some(handle) = character_handle(&cc) else { continue };
+            let Some(body) = self.rigid_body_set.get(handle) else { continue };
+            let Some(shape) = body.colliders().first().and_then(|&c| self.collider_set.get(c)).map(|c| c.shared_shape().clone()) else { continue };
+            let position = *body.position();
+
+            if cc.apply_gravity && !cc.grounded {
+                cc.vertical_speed += self.gravity.y * dt;
+            }
+            let desired = (cc.desired_movement + Vec3::Y * cc.vertical_speed) * dt;
+            let controller = character_controller(&cc);
+            let filter = QueryFilter::new().exclude_rigid_body(handle).exclude_sensors().groups(interaction_groups(cc.layers));
+
+            let mut collisions: Vec<CharacterCollision> = Vec::new();
+            let movement = controller.move_shape(
+                dt,
+                &self.rigid_body_set,
+                &self.collider_set,
+                &self.query_pipeline,
+                shape.as_ref(),
+                &position,
+                to_vector(desired),
+                filter,
+                |collision| collisions.push(collision),
+            );
+            if let Some(mass) = cc.push_mass {
+                for collision in &collisions {
+                    controller.solve_character_collision_impulses(
+                        dt,
+                        &mut self.rigid_body_set,
+                        &self.collider_set,
+                        &self.query_pipeline,
+                        shape.as_ref(),
+                        mass,
+                        collision,
+                        filter,
+                    );
+                }
+            }
+            if let Some(body) = self.rigid_body_set.get_mut(handle) {
+                body.set_next_kinematic_translation(position.translation.vector + movement.translation);
+            }
+
+            cc.grounded = movement.grounded;
+            if cc.grounded && cc.vertical_speed < 0.0 {
+                cc.vertical_speed = 0.0;
+            }
+            let mut hit: Vec<Entity> = collisions.iter().filter_map(|c| self.collider_entities.get(&c.handle).copied()).collect();
+            hit.dedup();
+            cc.collisions = hit;
+        }
+    }
+
+    /// 将本帧收集的物理事件发布为 ECS 事件 ([`CollisionEvent`] / [`IntersectionEvent`] / [`ContactForceEvent`])
+    ///
+    /// 每帧调用一次 (包括模拟暂停时)，以便轮换事件缓冲区。
+    pub fn publish_events(&mut self, world: &mut World) {
+        world.init_resource::<Events<CollisionEvent>>();
+        world.init_resource::<Events<IntersectionEvent>>();
+        world.init_resource::<Events<ContactForceEvent>>();
+
+        let entity_of = |handle: ColliderHandle| {
+            self.collider_entities.get(&handle).copied().or_else(|| {
+                self.collider_set.get(handle).and_then(|c| collider_entity(c.user_data))
+            })
+        };
+
+        let mut collisions = Vec::new();
+        let mut intersections = Vec::new();
+        for event in std::mem::take(&mut *self.event_collector.collisions.lock().unwrap()) {
+            let (Some(entity1), Some(entity2)) = (entity_of(event.collider1()), entity_of(event.collider2())) else { continue };
+            if event.sensor() {
+                // 传感器碰撞体已被删除时无法判断哪一方是传感器，按组件判断
+                let first_is_sensor = match self.collider_set.get(event.collider1()) {
+                    Some(c) => c.is_sensor(),
+                    None => world.get::<Collider>(entity1).is_none_or(|c| c.is_sensor),
+                };
+                let (sensor, other) = if first_is_sensor { (entity1, entity2) } else { (entity2, entity1) };
+                intersections.push(IntersectionEvent { sensor, other, started: event.started() });
+            } else {
+                collisions.push(match event {
+                    rapier3d::geometry::CollisionEvent::Started(..) => CollisionEvent::Started { entity1, entity2 },
+                    rapier3d::geometry::CollisionEvent::Stopped(..) => CollisionEvent::Stopped { entity1, entity2, removed: event.removed() },
+                });
+            }
+        }
+
+        let forces: Vec<ContactForceEvent> = std::mem::take(&mut *self.event_collector.contact_forces.lock().unwrap())
+            .into_iter()
+            .filter_map(|event| {
+                Some(ContactForceEvent {
+                    entity1: entity_of(event.collider1)?,
+                    entity2: entity_of(event.collider2)?,
+                    total_force: Vec3::new(event.total_force.x, event.total_force.y, event.total_force.z),
+                    total_force_magnitude: event.total_force_magnitude,
+                    max_force_direction: Vec3::new(event.max_force_direction.x, event.max_force_direction.y, event.max_force_direction.z),
+                    max_force_magnitude: event.max_force_magnitude,
+                })
+            })
+            .collect();
+
+        // 已删除碰撞体的结束事件已经发出，不再需要保留映射
+        self.collider_entities.retain(|handle, _| self.collider_set.contains(*handle));
+
+        let mut collision_events = world.resource_mut::<Events<CollisionEvent>>();
+        collision_events.update();
+        collision_events.extend(collisions);
+        let mut intersection_events = world.resource_mut::<Events<IntersectionEvent>>();
+        intersection_events.update();
+        intersection_events.extend(intersections);
+        let mut force_events = world.resource_mut::<Events<ContactForceEvent>>();
+        force_events.update();
+        force_events.extend(forces);
     }
 
     /// 将 ECS 中的实体同步到物理世界
+    ///
+    /// 使用变更检测只同步自上次调用以来被修改的组件：刚体属性在 `RigidBody` 变化时应用，
+    /// 形状在 `Collider` 或缩放变化时重建，位姿在 `GlobalTransform` 变化且不同于上次写回的结果时推送。
+    /// 被删除的组件或实体对应的 Rapier 对象在这里移除。结束时调用 [`World::clear_trackers`]。
     pub fn sync_ecs_to_physics(&mut self, world: &mut World) {
-        let mut query = world.query::<(Entity, &GlobalTransform, &mut RigidBody, Option<&mut Collider>)>();
+        // 1. 移除已删除组件/实体的物理对象
+        self.remove_deleted(world);
+
+        // 复合碰撞体需要读取子实体，先在遍历刚体之前收集组成部分有变化的复合碰撞体
+        let mut compound_parts = self.changed_compound_parts(world);
+
+        let mut query = world.query::<(Entity, Ref<GlobalTransform>, &mut RigidBody, Option<&mut Collider>)>();
         
-        for (entity, global_transform, mut rb, mut collider) in query.iter_mut(world) {
-            let matrix = global_transform.0;
-            let (scale, rot, pos) = matrix.to_scale_rotation_translation();
-            // 如果还没有物理句柄，则创建物理对象
-            // 1. 如果还没有物理句柄，则创建物理对象
-            if rb.handle_index.is_none() {
-                let rb_type = match rb.body_type {
-                    RigidBodyType::Static => rapier3d::prelude::RigidBodyType::Fixed,
-                    RigidBodyType::Dynamic => rapier3d::prelude::RigidBodyType::Dynamic,
-                    RigidBodyType::KinematicVelocityBased => rapier3d::prelude::RigidBodyType::KinematicVelocityBased,
-                    RigidBodyType::KinematicPositionBased => rapier3d::prelude::RigidBodyType::KinematicPositionBased,
-                };
+        for (entity, global_transform, mut rb, collider) in query.iter_mut(world) {
+            let (scale, rot, pos) = global_transform.0.to_scale_rotation_translation();
+            let isometry = to_isometry(pos, rot);
+            let transform_changed = global_transform.is_changed();
+            let body_changed = rb.is_changed();
 
-                
-                
-                let rigid_body = RigidBodyBuilder::new(rb_type)
-                    .position(Isometry3::from_parts(
-                        Vector3::new(pos.x, pos.y, pos.z).into(),
-                        UnitQuaternion::from_quaternion(Quaternion::new(rot.w, rot.x, rot.y, rot.z))
-                    ))
-                    .build();
-                
-                let handle = self.rigid_body_set.insert(rigid_body);
-                rb.handle_index = Some(handle.into_raw_parts().0);
-                rb.handle_generation = Some(handle.into_raw_parts().1);
-
-                // 如果有碰撞体组件，则创建对应的碰撞体
-                if let Some(ref mut col) = collider {
-                    let abs_scale = Vec3::new(scale.x.abs(), scale.y.abs(), scale.z.abs());
-                    
-                    let shape = match col.shape {
-                        ColliderShape::Ball { radius } => SharedShape::ball(radius * abs_scale.x.max(abs_scale.y).max(abs_scale.z)),
-                        ColliderShape::Cuboid { half_extents } => {
-                            SharedShape::cuboid(half_extents.x * abs_scale.x, half_extents.y * abs_scale.y, half_extents.z * abs_scale.z)
-                        }
-                        ColliderShape::Capsule { half_height, radius } => {
-                            SharedShape::capsule_y(half_height * abs_scale.y, radius * abs_scale.x.max(abs_scale.z))
+            // 2. 没有属于本实体的物理句柄时创建刚体 (复制实体时组件中的句柄属于原实体)
+            let owned = rigid_body_handle(&rb)
+                .filter(|h| self.entity_bodies.get(&entity) == Some(h) && self.rigid_body_set.contains(*h));
+            let handle = match owned {
+                Some(handle) => {
+                    let body = &mut self.rigid_body_set[handle];
+                    // 与上次写回 ECS 的位姿 (可能是插值结果) 相同时说明未被编辑，不重新设置，
+                    // 以免打断模拟或唤醒睡眠中的刚体
+                    if transform_changed {
+                        let reference = self.written_poses.get(&handle).unwrap_or(body.position());
+                        if !isometry_approx_eq(reference, &isometry) {
+                            if body.is_kinematic() && self.is_running {
+                                body.set_next_kinematic_position(isometry);
+                            } else {
+                                body.set_position(isometry, true);
+                            }
+                            self.written_poses.remove(&handle);
+                            self.previous_poses.remove(&handle);
                         }
-                    };
-
-                    let collider_obj = ColliderBuilder::new(shape)
-                        .friction(col.friction)
-                        .restitution(col.restitution)
-                        .user_data(entity.to_bits() as u128)
+                    }
+                    if body_changed {
+                        apply_body_properties(body, &rb);
+                    }
+                    handle
+                }
+                None => {
+                    let rigid_body = RigidBodyBuilder::new(rapier_body_type(rb.body_type))
+                        .position(isometry)
+                        .linear_damping(rb.linear_damping)
+                        .angular_damping(rb.angular_damping)
+                        .gravity_scale(rb.gravity_scale)
+                        .linvel(to_vector(rb.linear_velocity))
+                        .angvel(to_vector(rb.angular_velocity))
+                        .locked_axes(locked_axes(&rb))
+                        .can_sleep(rb.can_sleep)
+                        .sleeping(rb.sleeping)
+                        .ccd_enabled(rb.ccd_enabled)
                         .build();
                     
-                    let col_handle = self.collider_set.insert_with_parent(collider_obj, handle, &mut self.rigid_body_set);
-                    col.handle_index = Some(col_handle.into_raw_parts().0);
-                    col.handle_generation = Some(col_handle.into_raw_parts().1);
+                    let handle = self.rigid_body_set.insert(rigid_body);
+                    self.entity_bodies.insert(entity, handle);
+                    rb.handle_index = Some(handle.into_raw_parts().0);
+                    rb.handle_generation = Some(handle.into_raw_parts().1);
+                    handle
                 }
-            }
+            };
 
-            // 2. 将 ECS GlobalTransform 同步到物理引擎 (手动编辑模式或强制同步)
-            if let (Some(idx), Some(gen)) = (rb.handle_index, rb.handle_generation) {
-                let handle = RigidBodyHandle::from_raw_parts(idx, gen);
-                if let Some(body) = self.rigid_body_set.get_mut(handle) {
-                    body.set_position(Isometry3::from_parts(
-                        Vector3::new(pos.x, pos.y, pos.z).into(),
-                        UnitQuaternion::from_quaternion(Quaternion::new(rot.w, rot.x, rot.y, rot.z))
-                    ), true);
-                }
+            // 3. 创建或同步碰撞体 (如果存在)
+            let mut collider_handle = None;
+            if let Some(mut col) = collider {
+                let collider_changed = col.is_changed();
+                let abs_scale = scale.abs();
+                let offset = Isometry::translation(col.offset.x * scale.x, col.offset.y * scale.y, col.offset.z * scale.z);
+                let owned = collider_handle_of(&col)
+                    .filter(|h| self.entity_colliders.get(&entity) == Some(h) && self.collider_set.contains(*h));
 
-                // 同步碰撞体形状 (如果存在)
-                if let Some(mut col) = collider {
-                    if let (Some(c_idx), Some(c_gen)) = (col.handle_index, col.handle_generation) {
-                        let col_handle = ColliderHandle::from_raw_parts(c_idx, c_gen);
-                        if let Some(col_obj) = self.collider_set.get_mut(col_handle) {
-                            // 重新计算形状以应用缩放
-                            let abs_scale = Vec3::new(scale.x.abs(), scale.y.abs(), scale.z.abs());
-                            let new_shape = match col.shape {
-                                ColliderShape::Ball { radius } => SharedShape::ball(radius * abs_scale.x.max(abs_scale.y).max(abs_scale.z)),
-                                ColliderShape::Cuboid { half_extents } => {
-                                    SharedShape::cuboid(half_extents.x * abs_scale.x, half_extents.y * abs_scale.y, half_extents.z * abs_scale.z)
-                                }
-                                ColliderShape::Capsule { half_height, radius } => {
-                                    SharedShape::capsule_y(half_height * abs_scale.y, radius * abs_scale.x.max(abs_scale.z))
-                                }
+                match owned {
+                    Some(col_handle) => {
+                        // 只有碰撞体、缩放或复合形状的组成部分变化时才构建完整的形状参数进行比较
+                        // (形状参数包含网格数据，复制开销较大)，参数变化时才重新构建形状
+                        let cached = self.shape_cache.get(&col_handle);
+                        // 旋转中的刚体从矩阵分解出的缩放有浮点误差，按容差比较
+                        let scale_changed = !cached.is_some_and(|key| key.scale.abs_diff_eq(abs_scale, 1.0e-5));
+                        let parts = compound_parts.remove(&entity);
+                        if collider_changed || scale_changed || parts.is_some() {
+                            let parts = match parts {
+                                Some(parts) => parts,
+                                None if col.shape == ColliderShape::Compound => cached.map(|key| key.parts.clone()).unwrap_or_default(),
+                                None => Vec::new(),
                             };
-                            col_obj.set_shape(new_shape);
-                            col_obj.user_data = entity.to_bits() as u128; // 确保 user_data 正确
+                            let key = ShapeKey { shape: col.shape.clone(), offset: col.offset, scale: abs_scale, parts };
+                            if cached != Some(&key) {
+                                if let Some(new_shape) = build_shape(&key.shape, abs_scale, &key.parts) {
+                                    self.collider_set[col_handle].set_shape(new_shape);
+                                    self.mass_cache.remove(&handle);
+                                }
+                                self.shape_cache.insert(col_handle, key);
+                            }
+                        }
+                        let col_obj = &mut self.collider_set[col_handle];
+                        if collider_changed || transform_changed {
+                            col_obj.set_position_wrt_parent(offset);
+                        }
+                        if collider_changed {
+                            col_obj.set_friction(col.friction);
+                            col_obj.set_restitution(col.restitution);
+                            col_obj.set_active_events(active_events(&col));
+                            col_obj.set_sensor(col.is_sensor);
+                            col_obj.set_active_collision_types(active_collision_types(&col));
+                            col_obj.set_collision_groups(interaction_groups(col.layers));
+                            col_obj.set_contact_force_event_threshold(col.contact_force_threshold.unwrap_or(0.0));
+                        }
+                        collider_handle = Some((col_handle, col.density));
+                    }
+                    None => {
+                        // 形状无效 (例如没有子碰撞体的复合形状) 时暂不创建，待数据有效后再创建
+                        let parts = compound_parts.remove(&entity).unwrap_or_default();
+                        let key = ShapeKey { shape: col.shape.clone(), offset: col.offset, scale: abs_scale, parts };
+                        if let Some(shape) = build_shape(&key.shape, abs_scale, &key.parts) {
+                            let collider_obj = ColliderBuilder::new(shape)
+                                .position(offset)
+                                .friction(col.friction)
+                                .restitution(col.restitution)
+                                .active_events(active_events(&col))
+                                .sensor(col.is_sensor)
+                                .active_collision_types(active_collision_types(&col))
+                                .collision_groups(interaction_groups(col.layers))
+                                .contact_force_event_threshold(col.contact_force_threshold.unwrap_or(0.0))
+                                .user_data(collider_user_data(entity))
+                                .build();
+
+                            let col_handle = self.collider_set.insert_with_parent(collider_obj, handle, &mut self.rigid_body_set);
+                            self.collider_entities.insert(col_handle, entity);
+                            self.entity_colliders.insert(entity, col_handle);
+                            col.handle_index = Some(col_handle.into_raw_parts().0);
+                            col.handle_generation = Some(col_handle.into_raw_parts().1);
+                            self.shape_cache.insert(col_handle, key);
+                            self.mass_cache.remove(&handle);
+                            collider_handle = Some((col_handle, col.density));
                         }
                     }
                 }
             }
+
+            self.sync_mass(handle, &rb, collider_handle);
         }
 
+        // 4. 创建/同步角色控制器与布娃娃
+        self.sync_characters(world);
+        self.sync_ragdolls(world);
+
+        // 5. 创建/同步关节 (需要两端刚体都已创建)
+        self.sync_joints(world);
+
         // 重要：手动设置物体位置后，必须传播到碰撞体
         self.rigid_body_set.propagate_modified_body_positions_to_colliders(&mut self.collider_set);
 
         // 重要：同步完成后必须更新查询管线
         self.update_query_pipeline();
+
+        // 之后的修改 (包括本管理器回读的结果) 在下次同步时被检测到
+        world.clear_trackers();
+    }
+
+    /// 收集组成部分有变化 (或尚未缓存) 的复合碰撞体的组成部分
+    ///
+    /// 遍历刚体实体的子树 (遇到带刚体的子实体时停止)，子实体上的碰撞体作为复合形状的一部分。
+    /// 只用子实体列表、相对变换和子碰撞体的变更标记判断是否变化，未变化的复合碰撞体不复制形状数据。
+    fn changed_compound_parts(&self, world: &mut World) -> HashMap<Entity, Vec<CompoundPart>> {
+        const EPSILON: f32 = 1.0e-4;
+        let mut roots = Vec::new();
+        {
+            let mut query = world.query::<(Entity, &GlobalTransform, &Collider, &RigidBody)>();
+            for (entity, gt, col, _) in query.iter(world) {
+                if col.shape == ColliderShape::Compound {
+                    let (_, rot, pos) = gt.0.to_scale_rotation_translation();
+                    roots.push((entity, Mat4::from_rotation_translation(rot, pos).inverse()));
+                }
+            }
+        }
+
+        let mut result = HashMap::new();
+        for (root, inv_body) in roots {
+            // (子实体, 相对变换, 碰撞体是否变化)
+            let mut poses = Vec::new();
+            let mut stack: Vec<Entity> = world.get::<Children>(root).map(|c| c.0.clone()).unwrap_or_default();
+            while let Some(child) = stack.pop() {
+                let Some(child_ref) = world.get_entity(child) else { continue };
+                if child_ref.contains::<RigidBody>() {
+                    continue;
+                }
+                if let (Some(col), Some(gt)) = (child_ref.get_ref::<Collider>(), child_ref.get::<GlobalTransform>()) {
+                    if col.shape != ColliderShape::Compound {
+                        poses.push((child, inv_body * gt.0, col.is_changed()));
+                    }
+                }
+                if let Some(children) = child_ref.get::<Children>() {
+                    stack.extend(children.0.iter().copied());
+                }
+            }
+
+            let cached = self.entity_colliders.get(&root).and_then(|handle| self.shape_cache.get(handle));
+            let unchanged = cached.is_some_and(|key| {
+                key.parts.len() == poses.len()
+                    && key.parts.iter().zip(&poses).all(|(part, (entity, relative, changed))| {
+                        part.entity == *entity && !changed && part.relative.abs_diff_eq(*relative, EPSILON)
+                    })
+            });
+            if !unchanged {
+                let parts = poses
+                    .into_iter()
+                    .filter_map(|(entity, relative, _)| {
+                        let col = world.get::<Collider>(entity)?;
+                        Some(CompoundPart { entity, relative, shape: col.shape.clone(), offset: col.offset })
+                    })
+                    .collect();
+                result.insert(root, parts);
+            }
+        }
+        result
+    }
+
+    /// 移除自上次同步以来被删除的刚体、角色控制器、碰撞体和关节 (包括整个实体被删除的情况)
+    fn remove_deleted(&mut self, world: &World) {
+        let removed_bodies: HashSet<Entity> = world.removed::<RigidBody>()
+            .chain(world.removed::<CharacterController>())
+            .chain(world.removed::<RagdollBone>())
+            .collect();
+        for entity in removed_bodies {
+            let Some(&handle) = self.entity_bodies.get(&entity) else { continue };
+            // 组件被删除后又重新添加 (例如恢复快照) 时仍使用原刚体
+            let still_owned = world.get::<RigidBody>(entity).and_then(rigid_body_handle) == Some(handle)
+                || world.get::<CharacterController>(entity).and_then(character_handle) == Some(handle)
+                || world.get::<RagdollBone>(entity).and_then(ragdoll_body_handle) == Some(handle);
+            if still_owned {
+                continue;
+            }
+            self.entity_bodies.remove(&entity);
+            self.entity_colliders.remove(&entity);
+            self.remove_body(handle);
+        }
+
+        let removed_colliders: HashSet<Entity> = world.removed::<Collider>().collect();
+        for entity in removed_colliders {
+            let Some(&handle) = self.entity_colliders.get(&entity) else { continue };
+            if world.get::<Collider>(entity).and_then(collider_handle_of) == Some(handle) {
+                continue;
+            }
+            self.entity_colliders.remove(&entity);
+            let removed = self.collider_set.remove(handle, &mut self.island_manager, &mut self.rigid_body_set, true);
+            self.shape_cache.remove(&handle);
+            if let Some(parent) = removed.and_then(|c| c.parent()) {
+                self.mass_cache.remove(&parent);
+            }
+        }
+
+        let removed_joints: HashSet<Entity> = world.removed::<PhysicsJoint>().collect();
+        for entity in removed_joints {
+            let Some(&handle) = self.entity_joints.get(&entity) else { continue };
+            if world.get::<PhysicsJoint>(entity).and_then(joint_handle_of) == Some(handle) {
+                continue;
+            }
+            self.entity_joints.remove(&entity);
+            self.impulse_joint_set.remove(handle, true);
+        }
+    }
+
+    /// 删除刚体及其附着的碰撞体和关节
+    fn remove_body(&mut self, handle: RigidBodyHandle) {
+        let removed = self.rigid_body_set.remove(
+            handle,
+            &mut self.island_manager,
+            &mut self.collider_set,
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
+            true,
+        );
+        if let Some(body) = removed {
+            for collider in body.colliders() {
+                self.shape_cache.remove(collider);
+            }
+        }
+        self.mass_cache.remove(&handle);
+        self.previous_poses.remove(&handle);
+        self.written_poses.remove(&handle);
+    }
+
+    /// 为角色控制器创建运动学刚体和胶囊体碰撞体，并同步位置与形状
+    fn sync_characters(&mut self, world: &mut World) {
+        let mut query = world.query_filtered::<(Entity, Ref<GlobalTransform>, &mut CharacterController), Without<RigidBody>>();
+        for (entity, global_transform, mut cc) in query.iter_mut(world) {
+            let (_, rot, pos) = global_transform.0.to_scale_rotation_translation();
+            let isometry = to_isometry(pos, rot);
+            let half_height = cc.half_height.max(0.0);
+            let radius = cc.radius.max(0.001);
+
+            let owned = character_handle(&cc)
+                .filter(|h| self.entity_bodies.get(&entity) == Some(h) && self.rigid_body_set.contains(*h));
+            let Some(handle) = owned else {
+                let body = RigidBodyBuilder::kinematic_position_based().position(isometry).build();
+                let handle = self.rigid_body_set.insert(body);
+                let collider = ColliderBuilder::capsule_y(half_height, radius)
+                    .collision_groups(interaction_groups(cc.layers))
+                    .user_data(collider_user_data(entity))
+                    .build();
+                let col_handle = self.collider_set.insert_with_parent(collider, handle, &mut self.rigid_body_set);
+                self.collider_entities.insert(col_handle, entity);
+                self.entity_bodies.insert(entity, handle);
+                cc.handle_index = Some(handle.into_raw_parts().0);
+                cc.handle_generation = Some(handle.into_raw_parts().1);
+                continue;
+            };
+
+            let body = &mut self.rigid_body_set[handle];
+            if global_transform.is_changed() {
+                let reference = self.written_poses.get(&handle).unwrap_or(body.position());
+                if !isometry_approx_eq(reference, &isometry) {
+                    body.set_position(isometry, true);
+                    self.written_poses.remove(&handle);
+                    self.previous_poses.remove(&handle);
+                }
+            }
+            if !cc.is_changed() {
+                continue;
+            }
+            let Some(&col_handle) = body.colliders().first() else { continue };
+            if let Some(col_obj) = self.collider_set.get_mut(col_handle) {
+                let current = col_obj.shape().as_capsule().map(|c| (c.half_height(), c.radius));
+                if current != Some((half_height, radius)) {
+                    col_obj.set_shape(SharedShape::capsule_y(half_height, radius));
+                }
+                col_obj.set_collision_groups(interaction_groups(cc.layers));
+            }
+        }
+    }
+
+    /// 为布娃娃骨骼创建刚体、胶囊体和关节，并按布娃娃模式切换运动学/动态刚体
+    ///
+    /// 动画模式下刚体跟随关节的动画姿态；切换到模拟模式时记录关节当前的位姿作为过渡起点。
+    fn sync_ragdolls(&mut self, world: &mut World) {
+        let ragdolls: HashMap<Entity, (RagdollMode, CollisionLayers, bool)> = world.query::<(Entity, Ref<Ragdoll>)>()
+            .iter(world)
+            .map(|(entity, ragdoll)| (entity, (ragdoll.mode, ragdoll.layers, ragdoll.is_changed())))
+            .collect();
+
+        let mut rebuild_joints = Vec::new();
+        let mut query = world.query_filtered::<(Entity, &GlobalTransform, &mut RagdollBone), (Without<RigidBody>, Without<CharacterController>)>();
+        for (entity, global_transform, mut bone) in query.iter_mut(world) {
+            let Some(&(mode, layers, ragdoll_changed)) = ragdolls.get(&bone.ragdoll) else { continue };
+            let (_, rot, pos) = global_transform.0.to_scale_rotation_translation();
+            let isometry = to_isometry(pos, rot);
+            let shape_position = to_isometry(bone.center, bone.rotation);
+            let half_height = bone.half_height.max(0.0);
+            let radius = bone.radius.max(0.001);
+
+            let owned = ragdoll_body_handle(&bone)
+                .filter(|h| self.entity_bodies.get(&entity) == Some(h) && self.rigid_body_set.contains(*h));
+            let Some(handle) = owned else {
+                let body = RigidBodyBuilder::kinematic_position_based().position(isometry).build();
+                let handle = self.rigid_body_set.insert(body);
+                let collider = ColliderBuilder::capsule_y(half_height, radius)
+                    .position(shape_position)
+                    .density(bone.density)
+                    .collision_groups(interaction_groups(layers))
+                    .user_data(collider_user_data(entity))
+                    .build();
+                let col_handle = self.collider_set.insert_with_parent(collider, handle, &mut self.rigid_body_set);
+                self.collider_entities.insert(col_handle, entity);
+                self.entity_bodies.insert(entity, handle);
+                bone.handle_index = Some(handle.into_raw_parts().0);
+                bone.handle_generation = Some(handle.into_raw_parts().1);
+                rebuild_joints.push(entity);
+                continue;
+            };
+
+            let bone_changed = bone.is_changed();
+            let body = &mut self.rigid_body_set[handle];
+            if bone_changed || ragdoll_changed {
+                if let Some(col_obj) = body.colliders().first().and_then(|&c| self.collider_set.get_mut(c)) {
+                    let current = col_obj.shape().as_capsule().map(|c| (c.half_height(), c.radius));
+                    if current != Some((half_height, radius)) {
+                        col_obj.set_shape(SharedShape::capsule_y(half_height, radius));
+                    }
+                    col_obj.set_position_wrt_parent(shape_position);
+                    col_obj.set_density(bone.density);
+                    col_obj.set_collision_groups(interaction_groups(layers));
+                }
+            }
+            if bone_changed {
+                rebuild_joints.push(entity);
+            }
+
+            let target_type = match mode {
+                RagdollMode::Animated => rapier3d::prelude::RigidBodyType::KinematicPositionBased,
+                RagdollMode::Simulated => rapier3d::prelude::RigidBodyType::Dynamic,
+            };
+            if body.body_type() != target_type {
+                bone.blend_from = (mode == RagdollMode::Simulated).then_some((pos, rot));
+                body.set_body_type(target_type, true);
+            }
+            if mode == RagdollMode::Animated && !isometry_approx_eq(body.position(), &isometry) {
+                if self.is_running {
+                    body.set_next_kinematic_position(isometry);
+                } else {
+                    body.set_position(isometry, true);
+                }
+            }
+        }
+
+        for entity in rebuild_joints {
+            self.rebuild_ragdoll_joint(world, entity);
+        }
+    }
+
+    /// 重新创建骨骼与父骨骼之间的关节，关节在两者当前的相对位姿下处于中立位置
+    fn rebuild_ragdoll_joint(&mut self, world: &mut World, entity: Entity) {
+        let Some(bone) = world.get::<RagdollBone>(entity) else { return };
+        let Some(body) = ragdoll_body_handle(bone) else { return };
+        if let Some(existing) = ragdoll_joint_handle(bone).filter(|h| self.impulse_joint_set.get(*h).is_some_and(|j| j.body2 == body)) {
+            self.impulse_joint_set.remove(existing, true);
+        }
+        let parent_body = bone.parent
+            .and_then(|parent| world.get::<RagdollBone>(parent).and_then(ragdoll_body_handle).filter(|h| self.entity_bodies.get(&parent) == Some(h)));
+
+        let joint = match (parent_body, self.rigid_body_set.get(body)) {
+            (Some(parent_body), Some(child)) if self.rigid_body_set.contains(parent_body) => {
+                let child_pose = *child.position();
+                let parent_pose = *self.rigid_body_set[parent_body].position();
+                let (position, rotation) = from_isometry(&child_pose);
+                let axis = (rotation * bone.joint_axis).try_normalize().unwrap_or(Vec3::X);
+                let frame = to_isometry(position, Quat::from_rotation_arc(Vec3::X, axis));
+                let data = build_ragdoll_joint(&bone.joint, parent_pose.inverse() * frame, child_pose.inverse() * frame);
+                Some(self.impulse_joint_set.insert(parent_body, body, data, true))
+            }
+            _ => None,
+        };
+        if let Some(mut bone) = world.get_mut::<RagdollBone>(entity) {
+            bone.joint_handle_index = joint.map(|h| h.into_raw_parts().0);
+            bone.joint_handle_generation = joint.map(|h| h.into_raw_parts().1);
+        }
+    }
+
+    /// 将 ECS 中的 PhysicsJoint 同步为 Rapier 冲量关节
+    ///
+    /// 关节参数在组件变化时重建；连接的刚体发生变化时删除旧关节并重新插入。
+    fn sync_joints(&mut self, world: &mut World) {
+        let body_handle = |world: &World, entity: Entity| world.get::<RigidBody>(entity).and_then(rigid_body_handle);
+
+        let mut joints = Vec::new();
+        {
+            let mut query = world.query::<(Entity, Ref<PhysicsJoint>)>();
+            for (entity, joint) in query.iter(world) {
+                let bodies = body_handle(world, joint.connected).zip(body_handle(world, entity));
+                joints.push((entity, bodies, joint.is_changed()));
+            }
+        }
+
+        for (entity, bodies, changed) in joints {
+            let Some(mut joint) = world.get_mut::<PhysicsJoint>(entity) else { continue };
+            let existing = joint_handle_of(&joint)
+                .filter(|h| self.entity_joints.get(&entity) == Some(h) && self.impulse_joint_set.contains(*h));
+
+            let Some((body1, body2)) = bodies else {
+                // 任一端没有刚体：移除已存在的关节
+                if let Some(handle) = existing {
+                    self.impulse_joint_set.remove(handle, true);
+                    self.entity_joints.remove(&entity);
+                    joint.handle_index = None;
+                    joint.handle_generation = None;
+                }
+                continue;
+            };
+
+            if let Some(handle) = existing {
+                if let Some(impulse_joint) = self.impulse_joint_set.get_mut(handle) {
+                    if impulse_joint.body1 == body1 && impulse_joint.body2 == body2 {
+                        if changed {
+                            impulse_joint.data = build_joint(&joint);
+                        }
+                        continue;
+                    }
+                }
+                self.impulse_joint_set.remove(handle, true);
+            }
+
+            let handle = self.impulse_joint_set.insert(body1, body2, build_joint(&joint), true);
+            self.entity_joints.insert(entity, handle);
+            joint.handle_index = Some(handle.into_raw_parts().0);
+            joint.handle_generation = Some(handle.into_raw_parts().1);
+        }
+    }
+
+    /// 同步刚体质量：设置了质量覆盖时忽略碰撞体密度，以碰撞体形状的质量分布缩放到指定质量
+    ///
+    /// 只在质量参数或碰撞体形状变化时重新计算。
+    fn sync_mass(&mut self, handle: RigidBodyHandle, rb: &RigidBody, collider: Option<(ColliderHandle, f32)>) {
+        let key = MassKey { mass: rb.mass, center_of_mass: rb.center_of_mass, density: collider.map(|(_, d)| d) };
+        if self.mass_cache.get(&handle) == Some(&key) {
+            return;
+        }
+
+        let collider_mprops = collider.and_then(|(col_handle, density)| {
+            let col_obj = self.collider_set.get_mut(col_handle)?;
+            col_obj.set_density(if rb.mass.is_some() { 0.0 } else { density.max(0.0) });
+            let local = col_obj.position_wrt_parent().copied().unwrap_or_else(Isometry::identity);
+            Some(col_obj.shape().mass_properties(1.0).transform_by(&local))
+        });
+
+        let additional = match rb.mass {
+            Some(mass) => {
+                let mass = mass.max(0.0);
+                let mut mprops = collider_mprops
+                    .filter(|mp| mp.mass() > 0.0)
+                    .unwrap_or_else(|| MassProperties::new(Point::origin(), 1.0, Vector3::repeat(0.4)));
+                mprops.set_mass(mass, true);
+                if let Some(com) = rb.center_of_mass {
+                    mprops.local_com = point![com.x, com.y, com.z];
+                }
+                mprops
+            }
+            None => MassProperties::default(),
+        };
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_additional_mass_properties(additional, false);
+        }
+        self.mass_cache.insert(handle, key);
     }
 
     /// 更新场景查询管线 (用于射线检测等)
@@ -187,7 +1385,9 @@ impl PhysicsManager {
     }
 
     /// 将物理世界的结果同步回 ECS Transform
-    pub fn sync_physics_to_ecs(&self, world: &mut World) {
+    ///
+    /// 启用插值时写回的是最近两个物理状态之间按剩余帧时间插值的位姿。
+    pub fn sync_physics_to_ecs(&mut self, world: &mut World) {
         if !self.is_running {
             return;
         }
@@ -201,28 +1401,45 @@ impl PhysicsManager {
             }
         }
 
-        // 2. 从物理世界收集所有 RigidBody 的当前状态
-        let mut results = Vec::new();
+        // 2. 收集所有刚体与角色控制器对应的物理句柄 (角色控制器只回读位置)
+        let mut bodies = Vec::new();
         {
             let mut query = world.query::<(Entity, &RigidBody, Option<&Parent>)>();
             for (entity, rb, parent) in query.iter(world) {
                 if let (Some(idx), Some(gen)) = (rb.handle_index, rb.handle_generation) {
-                    let handle = RigidBodyHandle::from_raw_parts(idx, gen);
-                    if let Some(body) = self.rigid_body_set.get(handle) {
-                        let world_pos = body.translation();
-                        let world_rot = body.rotation();
-                        
-                        let world_pos_glam = Vec3::new(world_pos.x, world_pos.y, world_pos.z);
-                        let world_rot_glam = Quat::from_xyzw(world_rot.i, world_rot.j, world_rot.k, world_rot.w);
-                        
-                        results.push((entity, world_pos_glam, world_rot_glam, parent.map(|p| p.0)));
-                    }
+                    bodies.push((entity, RigidBodyHandle::from_raw_parts(idx, gen), parent.map(|p| p.0), true));
+                }
+            }
+            let mut query = world.query_filtered::<(Entity, &CharacterController, Option<&Parent>), Without<RigidBody>>();
+            for (entity, cc, parent) in query.iter(world) {
+                if let Some(handle) = character_handle(cc) {
+                    bodies.push((entity, handle, parent.map(|p| p.0), false));
                 }
             }
         }
 
+        let fixed_dt = self.timestep.fixed_dt.max(1.0e-4);
+        let alpha = (self.accumulator / fixed_dt).clamp(0.0, 1.0);
+        let mut results = Vec::new();
+        for (entity, handle, parent, is_rigid_body) in bodies {
+            let Some(pose) = self.interpolated_pose(handle, alpha) else { continue };
+            let body = &self.rigid_body_set[handle];
+            self.written_poses.insert(handle, pose);
+
+            let (world_pos, world_rot) = from_isometry(&pose);
+            let state = is_rigid_body.then(|| (to_vec3(body.linvel()), to_vec3(body.angvel()), body.is_sleeping()));
+            results.push((entity, world_pos, world_rot, parent, state));
+        }
+
         // 3. 将数据应用回 ECS
-        for (entity, world_pos, world_rot, parent_entity) in results {
+        for (entity, world_pos, world_rot, parent_entity, state) in results {
+            // 回读的运行时状态不标记为修改，避免下次同步时被当作用户编辑
+            if let (Some((linvel, angvel, sleeping)), Some(mut rb)) = (state, world.get_mut::<RigidBody>(entity)) {
+                let rb = rb.bypass_change_detection();
+                rb.linear_velocity = linvel;
+                rb.angular_velocity = angvel;
+                rb.sleeping = sleeping;
+            }
             if let Some(mut transform) = world.get_mut::<Transform>(entity) {
                 if let Some(p) = parent_entity {
                     if let Some(&parent_global_matrix) = global_transforms.get(&p) {
@@ -240,40 +1457,64 @@ impl PhysicsManager {
                 }
             }
         }
+
+        self.sync_ragdolls_to_ecs(world, alpha);
     }
 
-    /// 执行射线投射，检测鼠标选中的物体
-    pub fn ray_cast(&self, ray: &alander_core::math::Ray) -> Option<Entity> {
-        let rapier_ray = Ray::new(
-            Point::new(ray.origin.x, ray.origin.y, ray.origin.z),
-            Vector::new(ray.direction.x, ray.direction.y, ray.direction.z),
-        );
+    /// 刚体在当前帧的位姿：按 `alpha` 在上一物理步与当前物理步之间插值
+    fn interpolated_pose(&self, handle: RigidBodyHandle, alpha: f32) -> Option<Isometry<f32>> {
+        let current = *self.rigid_body_set.get(handle)?.position();
+        Some(match self.previous_poses.get(&handle) {
+            Some(previous) if self.timestep.interpolate => previous.lerp_slerp(&current, alpha),
+            _ => current,
+        })
+    }
 
-        // 设置最大检测距离
-        let max_toi = 1000.0;
-        let solid = true;
-        let filter = QueryFilter::default();
+    /// 将模拟模式布娃娃的骨骼位姿写回关节，过渡期间与进入模拟时的动画姿态混合
+    fn sync_ragdolls_to_ecs(&mut self, world: &mut World, alpha: f32) {
+        let weights: HashMap<Entity, f32> = world.query::<(Entity, &Ragdoll)>()
+            .iter(world)
+            .filter(|(_, ragdoll)| ragdoll.mode == RagdollMode::Simulated)
+            .map(|(entity, ragdoll)| (entity, ragdoll.blend_weight()))
+            .collect();
+        if weights.is_empty() {
+            return;
+        }
 
-        if let Some((handle, toi)) = self.query_pipeline.cast_ray(
-            &self.rigid_body_set,
-            &self.collider_set,
-            &rapier_ray,
-            max_toi,
-            solid,
-            filter,
-        ) {
-            tracing::info!("射线命中: {:?}, 距离: {}", handle, toi);
-            
-            // 从 Collider 中取回 Entity ID
-            if let Some(collider) = self.collider_set.get(handle) {
-                let entity_bits = collider.user_data as u64;
-                if entity_bits != 0 {
-                    return Some(Entity::from_bits(entity_bits));
-                }
-            }
+        let mut poses = Vec::new();
+        for (entity, bone) in world.query::<(Entity, &RagdollBone)>().iter(world) {
+            let Some(&weight) = weights.get(&bone.ragdoll) else { continue };
+            let Some(handle) = ragdoll_body_handle(bone).filter(|h| self.entity_bodies.get(&entity) == Some(h)) else { continue };
+            let Some(pose) = self.interpolated_pose(handle, alpha) else { continue };
+            let (position, rotation) = from_isometry(&pose);
+            let (position, rotation) = match bone.blend_from {
+                Some((from_position, from_rotation)) => (from_position.lerp(position, weight), from_rotation.slerp(rotation, weight)),
+                None => (position, rotation),
+            };
+            poses.push((entity, position, rotation));
+        }
+        write_bone_poses(world, &poses);
+    }
+
+    /// 场景查询视图 (射线、形状扫掠、点投影与重叠检测)
+    pub fn queries(&self) -> PhysicsQueries<'_> {
+        PhysicsQueries { bodies: &self.rigid_body_set, colliders: &self.collider_set, pipeline: &self.query_pipeline }
+    }
+
+    /// 将查询所需的物理状态借出 (供脚本执行期间使用)，必须通过 [`Self::return_queries`] 归还
+    pub fn lend_queries(&mut self) -> LentPhysics {
+        LentPhysics {
+            bodies: std::mem::take(&mut self.rigid_body_set),
+            colliders: std::mem::take(&mut self.collider_set),
+            pipeline: std::mem::replace(&mut self.query_pipeline, QueryPipeline::new()),
         }
+    }
 
-        None
+    /// 归还 [`Self::lend_queries`] 借出的物理状态
+    pub fn return_queries(&mut self, lent: LentPhysics) {
+        self.rigid_body_set = lent.bodies;
+        self.collider_set = lent.colliders;
+        self.query_pipeline = lent.pipeline;
     }
 
     /// 提取物理世界的调试线框数据
@@ -289,10 +1530,484 @@ impl PhysicsManager {
             &self.narrow_phase,
         );
 
+        // Rapier 只绘制关节锚点与分离线，这里额外绘制旋转/滑动关节的轴
+        for (_, joint) in self.impulse_joint_set.iter() {
+            let data = &joint.data;
+            if data.locked_axes != JointAxesMask::LOCKED_REVOLUTE_AXES && data.locked_axes != JointAxesMask::LOCKED_PRISMATIC_AXES {
+                continue;
+            }
+            let Some(body) = self.rigid_body_set.get(joint.body1) else { continue };
+            let frame = body.position() * data.local_frame1;
+            let start = frame.translation.vector;
+            let end = start + frame * data.local_axis1().into_inner() * 0.5;
+            for p in [start, end] {
+                vertices.push(alander_render::pipelines::DebugVertex {
+                    position: [p.x, p.y, p.z],
+                    color: [1.0, 0.8, 0.0, 1.0],
+                });
+            }
+        }
+
         vertices
     }
 }
 
+/// 力场的调试线框：作用范围与表示方向的箭头
+pub fn force_field_debug_lines(world: &mut World) -> Vec<alander_render::pipelines::DebugVertex> {
+    let mut lines = DebugLines::default();
+    for (field, global_transform) in world.query::<(&ForceField, &GlobalTransform)>().iter(world) {
+        let (_, rot, center) = global_transform.0.to_scale_rotation_translation();
+        let color = match field.kind {
+            ForceFieldKind::Wind { .. } => [0.3, 0.8, 1.0, 1.0],
+            ForceFieldKind::Explosion { .. } => [1.0, 0.4, 0.1, 1.0],
+            ForceFieldKind::Vortex { .. } => [0.7, 0.4, 1.0, 1.0],
+            ForceFieldKind::Drag { .. } => [0.6, 0.6, 0.6, 1.0],
+        };
+        // 禁用的力场 (包括已引爆的爆炸) 以暗色显示
+        let color = if field.enabled { color } else { [color[0] * 0.4, color[1] * 0.4, color[2] * 0.4, 1.0] };
+
+        match field.volume {
+            ForceFieldVolume::Sphere { radius } => {
+                lines.circle(center, rot * Vec3::X, rot * Vec3::Y, radius, color);
+                lines.circle(center, rot * Vec3::Y, rot * Vec3::Z, radius, color);
+                lines.circle(center, rot * Vec3::Z, rot * Vec3::X, radius, color);
+            }
+            ForceFieldVolume::Box { half_extents } => {
+                let corner = |i: usize| {
+                    let sign = Vec3::new(
+                        if i & 1 == 0 { -1.0 } else { 1.0 },
+                        if i & 2 == 0 { -1.0 } else { 1.0 },
+                        if i & 4 == 0 { -1.0 } else { 1.0 },
+                    );
+                    center + rot * (half_extents * sign)
+                };
+                for i in 0..8 {
+                    for bit in [1, 2, 4] {
+                        if i & bit == 0 {
+                            lines.line(corner(i), corner(i | bit), color);
+                        }
+                    }
+                }
+            }
+        }
+
+        let size = field.volume.extent() * 0.5;
+        match field.kind {
+            ForceFieldKind::Wind { direction, .. } => {
+                lines.arrow(center, rot * direction.normalize_or_zero() * size, color);
+            }
+            ForceFieldKind::Explosion { .. } => {
+                for direction in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z] {
+                    lines.arrow(center, rot * direction * size, color);
+                }
+            }
+            ForceFieldKind::Vortex { axis, .. } => {
+                let axis = (rot * axis).normalize_or_zero();
+                let tangent = axis.any_orthonormal_vector();
+                lines.arrow(center, axis * size, color);
+                lines.circle(center, tangent, axis.cross(tangent), size, color);
+                lines.arrow(center + tangent * size, axis.cross(tangent) * size * 0.3, color);
+            }
+            ForceFieldKind::Drag { .. } => {}
+        }
+    }
+    lines.vertices
+}
+
+#[derive(Default)]
+struct DebugLines {
+    vertices: Vec<alander_render::pipelines::DebugVertex>,
+}
+
+impl DebugLines {
+    fn line(&mut self, a: Vec3, b: Vec3, color: [f32; 4]) {
+        for p in [a, b] {
+            self.vertices.push(alander_render::pipelines::DebugVertex { position: p.to_array(), color });
+        }
+    }
+
+    /// 由 `u`/`v` 张成的平面上的圆
+    fn circle(&mut self, center: Vec3, u: Vec3, v: Vec3, radius: f32, color: [f32; 4]) {
+        const SEGMENTS: usize = 24;
+        let point = |i: usize| {
+            let angle = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        };
+        for i in 0..SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    fn arrow(&mut self, start: Vec3, vector: Vec3, color: [f32; 4]) {
+        let Some(direction) = vector.try_normalize() else { return };
+        let end = start + vector;
+        let side = direction.any_orthonormal_vector() * vector.length() * 0.15;
+        let back = end - direction * vector.length() * 0.25;
+        self.line(start, end, color);
+        self.line(end, back + side, color);
+        self.line(end, back - side, color);
+    }
+}
+
+/// 收集 Rapier 步进期间产生的事件 (步进可能并行调用，因此使用互斥锁)
+#[derive(Default)]
+struct PhysicsEventCollector {
+    collisions: Mutex<Vec<rapier3d::geometry::CollisionEvent>>,
+    contact_forces: Mutex<Vec<rapier3d::geometry::ContactForceEvent>>,
+}
+
+impl EventHandler for PhysicsEventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: rapier3d::geometry::CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        self.collisions.lock().unwrap().push(event);
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        contact_pair: &ContactPair,
+        total_force_magnitude: Real,
+    ) {
+        let event = rapier3d::geometry::ContactForceEvent::from_contact_pair(dt, contact_pair, total_force_magnitude);
+        self.contact_forces.lock().unwrap().push(event);
+    }
+}
+
+/// 碰撞体需要上报的事件类型：始终上报碰撞事件，设置了阈值时上报接触力事件
+fn active_events(col: &Collider) -> ActiveEvents {
+    match col.contact_force_threshold {
+        Some(_) => ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS,
+        None => ActiveEvents::COLLISION_EVENTS,
+    }
+}
+
+/// 传感器需要检测与静态/运动学刚体的重叠 (例如运动学角色进入静态触发区域)
+fn active_collision_types(col: &Collider) -> ActiveCollisionTypes {
+    if col.is_sensor { ActiveCollisionTypes::all() } else { ActiveCollisionTypes::default() }
+}
+
+/// 将碰撞层转换为 Rapier 交互组
+pub fn interaction_groups(layers: CollisionLayers) -> InteractionGroups {
+    InteractionGroups::new(Group::from_bits_truncate(layers.memberships), Group::from_bits_truncate(layers.filter))
+}
+
+/// 碰撞体 user_data 中表示"不属于任何实体"的值；0 是合法的实体编码 (索引 0、世代 0)，不能用作标记
+pub(crate) const NO_ENTITY_USER_DATA: u128 = u128::MAX;
+
+/// 创建碰撞体时写入 user_data 的实体编码
+pub(crate) fn collider_user_data(entity: Entity) -> u128 {
+    entity.to_bits() as u128
+}
+
+/// 从碰撞体的 user_data 解码所属实体
+pub(crate) fn collider_entity(user_data: u128) -> Option<Entity> {
+    (user_data != NO_ENTITY_USER_DATA).then(|| Entity::from_bits(user_data as u64))
+}
+
+/// 刚体上次计算质量时使用的参数
+#[derive(Clone, PartialEq)]
+struct MassKey {
+    mass: Option<f32>,
+    center_of_mass: Option<Vec3>,
+    density: Option<f32>,
+}
+
+fn rapier_body_type(body_type: RigidBodyType) -> rapier3d::prelude::RigidBodyType {
+    match body_type {
+        RigidBodyType::Static => rapier3d::prelude::RigidBodyType::Fixed,
+        RigidBodyType::Dynamic => rapier3d::prelude::RigidBodyType::Dynamic,
+        RigidBodyType::KinematicVelocityBased => rapier3d::prelude::RigidBodyType::KinematicVelocityBased,
+        RigidBodyType::KinematicPositionBased => rapier3d::prelude::RigidBodyType::KinematicPositionBased,
+    }
+}
+
+pub(crate) fn to_vector(v: Vec3) -> Vector<f32> {
+    vector![v.x, v.y, v.z]
+}
+
+pub(crate) fn to_vec3(v: &Vector<f32>) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+fn locked_axes(rb: &RigidBody) -> LockedAxes {
+    let flags = [
+        LockedAxes::TRANSLATION_LOCKED_X,
+        LockedAxes::TRANSLATION_LOCKED_Y,
+        LockedAxes::TRANSLATION_LOCKED_Z,
+        LockedAxes::ROTATION_LOCKED_X,
+        LockedAxes::ROTATION_LOCKED_Y,
+        LockedAxes::ROTATION_LOCKED_Z,
+    ];
+    let locks = rb.locked_axes.translation.iter().chain(rb.locked_axes.rotation.iter());
+    flags.into_iter().zip(locks).filter(|(_, &locked)| locked).fold(LockedAxes::empty(), |acc, (flag, _)| acc | flag)
+}
+
+fn from_isometry(iso: &Isometry<f32>) -> (Vec3, Quat) {
+    let r = iso.rotation;
+    (to_vec3(&iso.translation.vector), Quat::from_xyzw(r.i, r.j, r.k, r.w))
+}
+
+pub(crate) fn to_isometry(pos: Vec3, rot: Quat) -> Isometry<f32> {
+    Isometry3::from_parts(
+        Vector3::new(pos.x, pos.y, pos.z).into(),
+        UnitQuaternion::from_quaternion(Quaternion::new(rot.w, rot.x, rot.y, rot.z))
+    )
+}
+
+fn character_handle(cc: &CharacterController) -> Option<RigidBodyHandle> {
+    Some(RigidBodyHandle::from_raw_parts(cc.handle_index?, cc.handle_generation?))
+}
+
+fn ragdoll_body_handle(bone: &RagdollBone) -> Option<RigidBodyHandle> {
+    Some(RigidBodyHandle::from_raw_parts(bone.handle_index?, bone.handle_generation?))
+}
+
+fn ragdoll_joint_handle(bone: &RagdollBone) -> Option<ImpulseJointHandle> {
+    Some(ImpulseJointHandle::from_raw_parts(bone.joint_handle_index?, bone.joint_handle_generation?))
+}
+
+fn rigid_body_handle(rb: &RigidBody) -> Option<RigidBodyHandle> {
+    Some(RigidBodyHandle::from_raw_parts(rb.handle_index?, rb.handle_generation?))
+}
+
+fn collider_handle_of(col: &Collider) -> Option<ColliderHandle> {
+    Some(ColliderHandle::from_raw_parts(col.handle_index?, col.handle_generation?))
+}
+
+fn joint_handle_of(joint: &PhysicsJoint) -> Option<ImpulseJointHandle> {
+    Some(ImpulseJointHandle::from_raw_parts(joint.handle_index?, joint.handle_generation?))
+}
+
+/// 由组件参数构建 Rapier 角色控制器 (向上方向固定为 +Y)
+fn character_controller(cc: &CharacterController) -> KinematicCharacterController {
+    KinematicCharacterController {
+        up: Vector::y_axis(),
+        offset: CharacterLength::Absolute(cc.offset.max(0.0)),
+        slide: cc.slide,
+        autostep: cc.max_step_height.map(|height| CharacterAutostep {
+            max_height: CharacterLength::Absolute(height),
+            min_width: CharacterLength::Absolute(cc.min_step_width),
+            include_dynamic_bodies: true,
+        }),
+        max_slope_climb_angle: cc.max_slope_climb_angle.to_radians(),
+        min_slope_slide_angle: cc.min_slope_slide_angle.to_radians(),
+        snap_to_ground: cc.snap_to_ground.map(CharacterLength::Absolute),
+    }
+}
+
+/// 位姿近似相等 (容忍经过 ECS 矩阵往返产生的浮点误差；q 与 -q 表示同一旋转)
+fn isometry_approx_eq(a: &Isometry<f32>, b: &Isometry<f32>) -> bool {
+    const EPSILON: f32 = 1.0e-4;
+    let (qa, qb) = (a.rotation.coords, b.rotation.coords);
+    (a.translation.vector - b.translation.vector).amax() <= EPSILON
+        && ((qa - qb).amax() <= EPSILON || (qa + qb).amax() <= EPSILON)
+}
+
+fn find_entity_by_uuid(world: &World, uuid: uuid::Uuid) -> Option<Entity> {
+    world.iter_entities()
+        .find(|e| e.get::<EntityUuid>().is_some_and(|id| id.0 == uuid))
+        .map(|e| e.id())
+}
+
+/// 将 ECS 刚体属性应用到 Rapier 刚体，只修改发生变化的属性
+///
+/// 速度与睡眠状态在模拟时每帧回读到组件，因此这里的差异只来自用户编辑。
+fn apply_body_properties(body: &mut rapier3d::prelude::RigidBody, rb: &RigidBody) {
+    const EPSILON: f32 = 1.0e-5;
+    let body_type = rapier_body_type(rb.body_type);
+    if body.body_type() != body_type {
+        body.set_body_type(body_type, true);
+    }
+    if body.linear_damping() != rb.linear_damping {
+        body.set_linear_damping(rb.linear_damping);
+    }
+    if body.angular_damping() != rb.angular_damping {
+        body.set_angular_damping(rb.angular_damping);
+    }
+    if body.gravity_scale() != rb.gravity_scale {
+        body.set_gravity_scale(rb.gravity_scale, true);
+    }
+    if !to_vec3(body.linvel()).abs_diff_eq(rb.linear_velocity, EPSILON) {
+        body.set_linvel(to_vector(rb.linear_velocity), true);
+    }
+    if !to_vec3(body.angvel()).abs_diff_eq(rb.angular_velocity, EPSILON) {
+        body.set_angvel(to_vector(rb.angular_velocity), true);
+    }
+    let locks = locked_axes(rb);
+    if body.locked_axes() != locks {
+        body.set_locked_axes(locks, true);
+    }
+    if body.is_ccd_enabled() != rb.ccd_enabled {
+        body.enable_ccd(rb.ccd_enabled);
+    }
+
+    // 阈值为负时刚体永不睡眠
+    let activation = body.activation_mut();
+    if (activation.linear_threshold >= 0.0) != rb.can_sleep {
+        if rb.can_sleep {
+            activation.linear_threshold = RigidBodyActivation::default_linear_threshold();
+            activation.angular_threshold = RigidBodyActivation::default_angular_threshold();
+        } else {
+            activation.linear_threshold = -1.0;
+            activation.angular_threshold = -1.0;
+        }
+    }
+    if body.is_sleeping() != rb.sleeping {
+        if rb.sleeping {
+            body.sleep();
+        } else {
+            body.wake_up(true);
+        }
+    }
+}
+
+/// 碰撞体形状的构建参数，用于判断是否需要重新构建 Rapier 形状
+#[derive(Clone, PartialEq)]
+struct ShapeKey {
+    shape: ColliderShape,
+    offset: Vec3,
+    scale: Vec3,
+    parts: Vec<CompoundPart>,
+}
+
+/// 复合碰撞体的组成部分：子实体相对刚体 (不含刚体缩放) 的变换及其碰撞体
+#[derive(Clone, PartialEq)]
+struct CompoundPart {
+    entity: Entity,
+    relative: Mat4,
+    shape: ColliderShape,
+    offset: Vec3,
+}
+
+fn to_point(v: Vec3, scale: Vec3) -> Point<f32> {
+    let p = v * scale;
+    point![p.x, p.y, p.z]
+}
+
+/// 按缩放构建 Rapier 形状；数据无效时返回 None
+fn build_shape(shape: &ColliderShape, abs_scale: Vec3, parts: &[CompoundPart]) -> Option<SharedShape> {
+    let radial = abs_scale.x.max(abs_scale.z);
+    let shape = match shape {
+        ColliderShape::Ball { radius } => SharedShape::ball(radius * abs_scale.max_element()),
+        ColliderShape::Cuboid { half_extents } => {
+            SharedShape::cuboid(half_extents.x * abs_scale.x, half_extents.y * abs_scale.y, half_extents.z * abs_scale.z)
+        }
+        ColliderShape::Capsule { half_height, radius } => SharedShape::capsule_y(half_height * abs_scale.y, radius * radial),
+        ColliderShape::Cylinder { half_height, radius } => SharedShape::cylinder(half_height * abs_scale.y, radius * radial),
+        ColliderShape::Cone { half_height, radius } => SharedShape::cone(half_height * abs_scale.y, radius * radial),
+        ColliderShape::ConvexHull { points } => {
+            let points: Vec<_> = points.iter().map(|p| to_point(*p, abs_scale)).collect();
+            SharedShape::convex_hull(&points)?
+        }
+        ColliderShape::ConvexDecomposition { vertices, indices } | ColliderShape::TriMesh { vertices, indices } => {
+            if indices.is_empty() || indices.iter().flatten().any(|&i| i as usize >= vertices.len()) {
+                return None;
+            }
+            let points: Vec<_> = vertices.iter().map(|p| to_point(*p, abs_scale)).collect();
+            if matches!(shape, ColliderShape::TriMesh { .. }) {
+                SharedShape::trimesh(points, indices.clone())
+            } else {
+                SharedShape::convex_decomposition(&points, indices)
+            }
+        }
+        ColliderShape::HeightField { rows, cols, heights, size } => {
+            if *rows < 2 || *cols < 2 || heights.len() != rows * cols {
+                return None;
+            }
+            let heights = rapier3d::na::DMatrix::from_row_slice(*rows, *cols, heights);
+            let scaled = *size * abs_scale;
+            SharedShape::heightfield(heights, vector![scaled.x, scaled.y, scaled.z])
+        }
+        ColliderShape::Compound => {
+            let shapes: Vec<_> = parts
+                .iter()
+                .filter_map(|part| {
+                    let (scale, rot, pos) = part.relative.to_scale_rotation_translation();
+                    let shape = build_shape(&part.shape, scale.abs(), &[])?;
+                    // 复合形状不能嵌套三角网格、高度场等组合形状
+                    if shape.as_composite_shape().is_some() {
+                        tracing::warn!("复合碰撞体不支持{}子形状，已忽略", part.shape.label());
+                        return None;
+                    }
+                    let center = pos + rot * (part.offset * scale);
+                    let iso = Isometry3::from_parts(
+                        Vector3::new(center.x, center.y, center.z).into(),
+                        UnitQuaternion::from_quaternion(Quaternion::new(rot.w, rot.x, rot.y, rot.z)),
+                    );
+                    Some((iso, shape))
+                })
+                .collect();
+            if shapes.is_empty() {
+                return None;
+            }
+            SharedShape::compound(shapes)
+        }
+    };
+    Some(shape)
+}
+
+/// 根据关节组件构建 Rapier 通用关节
+fn build_joint(joint: &PhysicsJoint) -> GenericJoint {
+    let anchor1 = point![joint.anchor1.x, joint.anchor1.y, joint.anchor1.z];
+    let anchor2 = point![joint.anchor2.x, joint.anchor2.y, joint.anchor2.z];
+    let axis = UnitVector::try_new(vector![joint.axis.x, joint.axis.y, joint.axis.z], 1.0e-6)
+        .unwrap_or_else(Vector::y_axis);
+
+    let mut data: GenericJoint = match joint.kind {
+        PhysicsJointKind::Fixed => FixedJointBuilder::new().into(),
+        PhysicsJointKind::Revolute => RevoluteJointBuilder::new(axis).into(),
+        PhysicsJointKind::Prismatic => PrismaticJointBuilder::new(axis).into(),
+        PhysicsJointKind::Spherical => SphericalJointBuilder::new().into(),
+        PhysicsJointKind::Rope { max_length } => RopeJointBuilder::new().limits([0.0, max_length]).into(),
+        // Rapier 0.17 没有弹簧关节：用耦合的自由平移轴 + 基于力的位置马达实现
+        PhysicsJointKind::Spring { rest_length, stiffness, damping } => GenericJointBuilder::new(JointAxesMask::empty())
+            .coupled_axes(JointAxesMask::LIN_AXES)
+            .motor_position(JointAxis::X, rest_length, stiffness, damping)
+            .motor_model(JointAxis::X, MotorModel::ForceBased)
+            .build(),
+    };
+
+    data.set_local_anchor1(anchor1)
+        .set_local_anchor2(anchor2)
+        .set_contacts_enabled(joint.contacts_enabled);
+
+    if joint.kind.has_axis() {
+        let joint_axis = if joint.kind == PhysicsJointKind::Revolute { JointAxis::AngX } else { JointAxis::X };
+        if let Some(limits) = joint.limits {
+            data.set_limits(joint_axis, limits);
+        }
+        if let Some(motor) = joint.motor {
+            data.set_motor(joint_axis, motor.target_position, motor.target_velocity, motor.stiffness, motor.damping)
+                .set_motor_max_force(joint_axis, motor.max_force);
+        }
+    }
+
+    data
+}
+
+/// 布娃娃关节：关节坐标系的 X 轴为扭转轴 (球形关节) 或旋转轴 (铰链关节)，限位从度转换为弧度
+fn build_ragdoll_joint(kind: &RagdollJointKind, frame1: Isometry<f32>, frame2: Isometry<f32>) -> GenericJoint {
+    let (axes, limits) = match *kind {
+        RagdollJointKind::Spherical { swing_limit, twist_limit } => (
+            JointAxesMask::LOCKED_SPHERICAL_AXES,
+            vec![(JointAxis::AngX, [-twist_limit, twist_limit]), (JointAxis::AngY, [-swing_limit, swing_limit]), (JointAxis::AngZ, [-swing_limit, swing_limit])],
+        ),
+        RagdollJointKind::Revolute { limits } => (JointAxesMask::LOCKED_REVOLUTE_AXES, vec![(JointAxis::AngX, limits)]),
+    };
+    let mut builder = GenericJointBuilder::new(axes).local_frame1(frame1).local_frame2(frame2).contacts_enabled(false);
+    for (axis, [min, max]) in limits {
+        builder = builder.limits(axis, [min.to_radians(), max.to_radians()]);
+    }
+    builder.build()
+}
+
 /// 内部结构，转换 Rapier3D 的调试线条到渲染器的顶点格式
 struct DebugCollector<'a> {
     vertices: &'a mut Vec<alander_render::pipelines::DebugVertex>,
@@ -310,3 +2025,334 @@ impl<'a> DebugRenderBackend for DebugCollector<'a> {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn spawn(world: &mut World, id: u128, position: Vec3) -> Entity {
+        world.spawn((
+            EntityUuid(Uuid::from_u128(id)),
+            Transform::from_translation(position),
+            GlobalTransform(Mat4::from_translation(position)),
+        )).id()
+    }
+
+    fn test_scene() -> World {
+        let mut world = World::new();
+        let ground = spawn(&mut world, 1, Vec3::ZERO);
+        world.entity_mut(ground).insert((RigidBody::new(RigidBodyType::Static), Collider::cuboid(20.0, 0.5, 20.0)));
+        let ball = spawn(&mut world, 2, Vec3::new(0.3, 4.0, 0.0));
+        world.entity_mut(ball).insert((RigidBody::new(RigidBodyType::Dynamic), Collider::ball(0.5)));
+        let character = spawn(&mut world, 3, Vec3::new(-3.0, 1.5, 0.0));
+        world.entity_mut(character).insert(CharacterController::default());
+        world
+    }
+
+    /// 每个实体对应刚体的当前位姿 (按 UUID 排序)
+    fn body_poses(physics: &PhysicsManager, world: &mut World) -> Vec<(Uuid, Isometry<f32>)> {
+        let mut poses: Vec<(Uuid, Isometry<f32>)> = world.query::<(&EntityUuid, Option<&RigidBody>, Option<&CharacterController>)>()
+            .iter(world)
+            .filter_map(|(uuid, rb, cc)| {
+                let handle = match (rb, cc) {
+                    (Some(rb), _) => RigidBodyHandle::from_raw_parts(rb.handle_index?, rb.handle_generation?),
+                    (None, Some(cc)) => character_handle(cc)?,
+                    _ => return None,
+                };
+                Some((uuid.0, *physics.rigid_body_set.get(handle)?.position()))
+            })
+            .collect();
+        poses.sort_by_key(|(uuid, _)| *uuid);
+        poses
+    }
+
+    #[test]
+    fn test_replay_reproduces_recorded_simulation() {
+        let mut world = test_scene();
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut world);
+        physics.is_running = true;
+        physics.start_recording(42);
+        for i in 0..120 {
+            for mut cc in world.query::<&mut CharacterController>().iter_mut(&mut world) {
+                cc.desired_movement = if i < 60 { Vec3::X * 2.0 } else { Vec3::Z };
+            }
+            physics.step(&mut world);
+        }
+        let recording = physics.stop_recording().unwrap();
+        assert_eq!(recording.steps.len(), 120);
+        let expected = body_poses(&physics, &mut world);
+
+        let mut replay_world = test_scene();
+        let mut replay_physics = PhysicsManager::new();
+        replay_physics.replay(&mut replay_world, &recording);
+        let actual = body_poses(&replay_physics, &mut replay_world);
+
+        assert_eq!(expected, actual);
+        // 小球落到地面上，角色先向 +X 移动 2 米，再向 +Z 移动 1 米
+        let ball = expected[1].1.translation.vector;
+        assert!(ball.y < 1.1 && ball.y > 0.9, "ball at {:?}", ball);
+        let character = expected[2].1.translation.vector;
+        assert!((character.x + 1.0).abs() < 0.05 && (character.z - 1.0).abs() < 0.05, "character at {:?}", character);
+    }
+
+    #[test]
+    fn test_forces_and_fields_are_replayed() {
+        fn scene_with_fields() -> World {
+            let mut world = test_scene();
+            let wind = spawn(&mut world, 4, Vec3::new(0.0, 2.0, 0.0));
+            world.entity_mut(wind).insert(ForceField {
+                kind: ForceFieldKind::Wind { direction: Vec3::X, strength: 5.0 },
+                volume: ForceFieldVolume::Box { half_extents: Vec3::splat(10.0) },
+                ..Default::default()
+            });
+            let explosion = spawn(&mut world, 5, Vec3::new(0.3, 2.0, 0.0));
+            world.entity_mut(explosion).insert(ForceField {
+                kind: ForceFieldKind::Explosion { strength: 3.0 },
+                volume: ForceFieldVolume::Sphere { radius: 5.0 },
+                ..Default::default()
+            });
+            world
+        }
+
+        let mut world = scene_with_fields();
+        let ball = find_entity_by_uuid(&world, Uuid::from_u128(2)).unwrap();
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut world);
+        physics.is_running = true;
+        physics.start_recording(7);
+        physics.apply(ball, BodyForce::Impulse(Vec3::Z * 2.0));
+        for _ in 0..60 {
+            physics.step(&mut world);
+        }
+        let recording = physics.stop_recording().unwrap();
+        assert_eq!(recording.steps[0].forces.len(), 1);
+        assert!(recording.steps[1].forces.is_empty());
+        // 爆炸只生效一次
+        assert!(world.query::<&ForceField>().iter(&world).all(|f| f.enabled != matches!(f.kind, ForceFieldKind::Explosion { .. })));
+        let expected = body_poses(&physics, &mut world);
+        let position = expected[1].1.translation.vector;
+        assert!(position.x > 1.0 && position.z > 0.5, "ball at {:?}", position);
+
+        let mut replay_world = scene_with_fields();
+        let mut replay_physics = PhysicsManager::new();
+        replay_physics.replay(&mut replay_world, &recording);
+        assert_eq!(body_poses(&replay_physics, &mut replay_world), expected);
+    }
+
+    #[test]
+    fn test_continuous_force_is_independent_of_frame_rate() {
+        /// 以给定帧时长推进若干帧，每帧重新施加相同的力；返回执行的物理步数与小球速度
+        fn run(frame_dt: f32, frames: usize) -> (usize, Vec3, f32) {
+            let mut world = World::new();
+            let ball = spawn(&mut world, 1, Vec3::ZERO);
+            world.entity_mut(ball).insert((RigidBody::new(RigidBodyType::Dynamic), Collider::ball(0.5)));
+            let mut physics = PhysicsManager::new();
+            physics.gravity = vector![0.0, 0.0, 0.0];
+            physics.is_running = true;
+            physics.start_recording(0);
+            for _ in 0..frames {
+                physics.apply(ball, BodyForce::Force(Vec3::X * 2.0));
+                physics.advance(&mut world, frame_dt);
+            }
+            let steps = physics.stop_recording().unwrap().steps.len();
+            let body = &physics.rigid_body_set[physics.entity_bodies[&ball]];
+            (steps, to_vec3(body.linvel()), body.mass())
+        }
+
+        let (steps, fast, mass) = run(1.0 / 144.0, 144);
+        let (_, reference, _) = run(1.0 / 60.0, steps);
+        assert!((fast - reference).length() < 1.0e-5, "{:?} != {:?}", fast, reference);
+        // 每个物理步恰好施加一次力
+        let expected = 2.0 / mass * steps as f32 / 60.0;
+        assert!((fast.x - expected).abs() < 1.0e-4, "{} != {}", fast.x, expected);
+    }
+
+    #[test]
+    fn test_stop_restores_edit_state() {
+        use alander_core::scene::PointLight;
+
+        let mut scene = Scene::new("test");
+        scene.world = test_scene();
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut scene.world);
+        let initial = body_poses(&physics, &mut scene.world);
+        let ground = find_entity_by_uuid(&scene.world, Uuid::from_u128(1)).unwrap();
+        let ball = find_entity_by_uuid(&scene.world, Uuid::from_u128(2)).unwrap();
+        scene.world.entity_mut(ground).insert(PointLight { intensity: 1.0, ..Default::default() });
+        let initial_position = scene.world.get::<Transform>(ball).unwrap().position;
+
+        physics.play(&mut scene.world);
+        for _ in 0..30 {
+            physics.advance(&mut scene.world, 1.0 / 60.0);
+        }
+        assert_ne!(scene.world.get::<Transform>(ball).unwrap().position, initial_position);
+
+        // 模拟期间修改的组件、删除和新建的实体都被恢复
+        scene.world.get_mut::<PointLight>(ground).unwrap().intensity = 5.0;
+        scene.remove_entity(ball);
+        let spawned = scene.world.spawn((Transform::default(), RenderId(Uuid::from_u128(99)))).id();
+
+        assert_eq!(physics.stop(&mut scene, &[]), vec![Uuid::from_u128(99)]);
+        assert!(!physics.is_running && !physics.has_snapshot());
+        assert!(scene.world.get_entity(spawned).is_none());
+        assert_eq!(scene.world.get::<PointLight>(ground).unwrap().intensity, 1.0);
+        assert_eq!(scene.world.get::<EntityUuid>(ball).unwrap().0, Uuid::from_u128(2));
+        assert_eq!(scene.world.get::<Transform>(ball).unwrap().position, initial_position);
+        assert_eq!(body_poses(&physics, &mut scene.world), initial);
+    }
+
+    #[test]
+    fn test_shapes_are_rebuilt_only_when_parameters_change() {
+        let mut world = World::new();
+        let body = spawn(&mut world, 1, Vec3::ZERO);
+        world.entity_mut(body).insert((RigidBody::new(RigidBodyType::Dynamic), Collider::new(ColliderShape::Compound)));
+        let part = spawn(&mut world, 2, Vec3::X);
+        world.entity_mut(part).insert((Parent(body), Collider::cuboid(0.5, 0.5, 0.5)));
+        world.entity_mut(body).insert(Children(vec![part]));
+
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut world);
+        let collider = physics.entity_colliders[&body];
+        let shape = |physics: &PhysicsManager| physics.collider_set[collider].shared_shape().0.clone();
+        let built = shape(&physics);
+
+        // 整体移动刚体不改变组成部分的相对变换，不重新构建
+        let moved = Mat4::from_rotation_translation(Quat::from_rotation_y(0.7), Vec3::new(2.0, 1.0, 0.0));
+        world.get_mut::<GlobalTransform>(body).unwrap().0 = moved;
+        world.get_mut::<GlobalTransform>(part).unwrap().0 = moved * Mat4::from_translation(Vec3::X);
+        physics.sync_ecs_to_physics(&mut world);
+        assert!(std::sync::Arc::ptr_eq(&built, &shape(&physics)));
+
+        // 修改子碰撞体后重新构建
+        world.get_mut::<Collider>(part).unwrap().shape = ColliderShape::Ball { radius: 0.5 };
+        physics.sync_ecs_to_physics(&mut world);
+        assert!(!std::sync::Arc::ptr_eq(&built, &shape(&physics)));
+    }
+
+    #[test]
+    fn test_deleted_components_release_physics_objects() {
+        let mut world = test_scene();
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut world);
+        assert_eq!((physics.rigid_body_set.len(), physics.collider_set.len()), (3, 3));
+
+        // 复制的组件带有原实体的句柄，应创建新的刚体而不是共享
+        let ball = find_entity_by_uuid(&world, Uuid::from_u128(2)).unwrap();
+        let copy = (world.get::<RigidBody>(ball).unwrap().clone(), world.get::<Collider>(ball).unwrap().clone());
+        let copy = world.spawn((Transform::default(), GlobalTransform::default(), copy.0, copy.1)).id();
+        physics.sync_ecs_to_physics(&mut world);
+        assert_eq!((physics.rigid_body_set.len(), physics.collider_set.len()), (4, 4));
+
+        world.entity_mut(ball).remove::<Collider>();
+        physics.sync_ecs_to_physics(&mut world);
+        assert_eq!((physics.rigid_body_set.len(), physics.collider_set.len()), (4, 3));
+
+        world.despawn(ball);
+        world.despawn(copy);
+        let character = world.query_filtered::<Entity, With<CharacterController>>().single(&world);
+        world.entity_mut(character).remove::<CharacterController>();
+        physics.sync_ecs_to_physics(&mut world);
+        assert_eq!((physics.rigid_body_set.len(), physics.collider_set.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_collider_user_data_round_trips_every_entity() {
+        let mut world = World::new();
+        let first = world.spawn_empty().id();
+        assert_eq!(collider_user_data(first), 0, "第一个实体的编码为 0");
+        assert_eq!(collider_entity(collider_user_data(first)), Some(first));
+        let second = world.spawn_empty().id();
+        assert_eq!(collider_entity(collider_user_data(second)), Some(second));
+        assert_eq!(collider_entity(NO_ENTITY_USER_DATA), None);
+    }
+
+    #[test]
+    fn test_scene_queries_report_hits_and_respect_filters() {
+        use crate::physics_query::{PhysicsQueryFilter, QueryShape};
+
+        let mut world = test_scene();
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut world);
+        let ground = find_entity_by_uuid(&world, Uuid::from_u128(1)).unwrap();
+        let ball = find_entity_by_uuid(&world, Uuid::from_u128(2)).unwrap();
+        let queries = physics.queries();
+        let filter = PhysicsQueryFilter::default();
+        let (origin, down) = (Vec3::new(0.3, 10.0, 0.0), Vec3::NEG_Y);
+
+        let hit = queries.cast_ray(origin, down, f32::MAX, &filter).unwrap();
+        assert_eq!(hit.entity, ball);
+        assert!((hit.distance - 5.5).abs() < 1e-4 && hit.normal.abs_diff_eq(Vec3::Y, 1e-4), "{:?}", hit);
+        assert!(queries.cast_ray(origin, down, 5.0, &filter).is_none());
+
+        let all: Vec<Entity> = queries.cast_ray_all(origin, down, f32::MAX, &filter).iter().map(|h| h.entity).collect();
+        assert_eq!(all, vec![ball, ground]);
+        let without_ball = PhysicsQueryFilter { exclude: vec![ball], ..Default::default() };
+        assert_eq!(queries.cast_ray(origin, down, f32::MAX, &without_ball).unwrap().entity, ground);
+
+        let sweep = queries.cast_shape(QueryShape::Sphere { radius: 0.5 }, origin, Quat::IDENTITY, down, f32::MAX, &filter).unwrap();
+        assert_eq!(sweep.entity, ball);
+        assert!((sweep.distance - 5.0).abs() < 1e-3 && sweep.point.abs_diff_eq(Vec3::new(0.3, 4.5, 0.0), 1e-3), "{:?}", sweep);
+
+        let projection = queries.project_point(Vec3::new(0.3, 6.0, 0.0), 2.0, &filter).unwrap();
+        assert_eq!(projection.entity, ball);
+        assert!((projection.distance - 1.5).abs() < 1e-4 && !projection.is_inside);
+        assert!(queries.project_point(Vec3::new(0.3, 6.0, 0.0), 1.0, &filter).is_none());
+
+        assert_eq!(queries.overlap(QueryShape::Box { half_extents: Vec3::splat(0.1) }, Vec3::new(5.0, 0.5, 0.0), Quat::IDENTITY, &filter), vec![ground]);
+        let none = PhysicsQueryFilter { layers: CollisionLayers::new(u32::MAX, 0), ..Default::default() };
+        assert!(queries.overlap(QueryShape::Sphere { radius: 0.1 }, Vec3::new(5.0, 0.5, 0.0), Quat::IDENTITY, &none).is_empty());
+    }
+
+    #[test]
+    fn test_ragdoll_blends_into_connected_simulation() {
+        use alander_core::ragdoll::{insert_ragdoll, RagdollSettings};
+        use alander_core::scene::{Joint, Name, Skin};
+
+        let mut world = test_scene();
+        // 髋 (y = 3) → 膝 → 踝 的腿部骨骼，每节 0.5 米
+        let mut joints: Vec<Entity> = Vec::new();
+        for (i, name) in ["hip", "knee", "ankle"].into_iter().enumerate() {
+            let local = if i == 0 { Vec3::new(5.0, 3.0, 0.0) } else { Vec3::new(0.0, -0.5, 0.0) };
+            let global = Vec3::new(5.0, 3.0 - 0.5 * i as f32, 0.0);
+            let mut joint = world.spawn((Name(name.into()), Transform::from_translation(local), GlobalTransform(Mat4::from_translation(global)), Joint { index: i }));
+            if let Some(&parent) = joints.last() {
+                joint.insert(Parent(parent));
+            }
+            let id = joint.id();
+            if let Some(&parent) = joints.last() {
+                world.entity_mut(parent).insert(Children(vec![id]));
+            }
+            joints.push(id);
+        }
+        let skin = world.spawn(Skin::new("leg".into(), vec![Mat4::IDENTITY; 3], joints.clone())).id();
+        assert_eq!(insert_ragdoll(&mut world, skin, &RagdollSettings::default()).unwrap(), 2);
+
+        let mut physics = PhysicsManager::new();
+        physics.sync_ecs_to_physics(&mut world);
+        assert_eq!(physics.rigid_body_set.len(), 5);
+        let joint_position = |world: &World, i: usize| world.get::<GlobalTransform>(joints[i]).unwrap().0.w_axis.truncate();
+
+        physics.play(&mut world);
+        world.get_mut::<Ragdoll>(skin).unwrap().set_mode(RagdollMode::Simulated);
+        physics.advance(&mut world, 1.0 / 60.0);
+        // 过渡开始时仍接近动画姿态
+        assert!(joint_position(&world, 0).abs_diff_eq(Vec3::new(5.0, 3.0, 0.0), 0.01));
+
+        for _ in 0..180 {
+            physics.advance(&mut world, 1.0 / 60.0);
+        }
+        let (hip, knee, ankle) = (joint_position(&world, 0), joint_position(&world, 1), joint_position(&world, 2));
+        assert!(hip.y < 1.5, "hip at {:?}", hip);
+        assert!((hip.distance(knee) - 0.5).abs() < 0.05, "hip {:?} knee {:?}", hip, knee);
+        // 没有刚体的踝关节跟随膝关节
+        assert!((knee.distance(ankle) - 0.5).abs() < 1e-3);
+
+        let mut scene = Scene::new("test");
+        scene.world = world;
+        physics.stop(&mut scene, &[]);
+        assert_eq!(scene.world.get::<Ragdoll>(skin).unwrap().mode, RagdollMode::Animated);
+        assert_eq!(scene.world.get::<Transform>(joints[0]).unwrap().position, Vec3::new(5.0, 3.0, 0.0));
+    }
+}
@@ -0,0 +1,259 @@
+//! 物理场景查询：射线、形状扫掠、点投影与重叠检测
+//!
+//! [`PhysicsQueries`] 是对物理世界的只读视图，可通过 [`PhysicsManager::queries`](crate::physics_manager::PhysicsManager::queries)
+//! 在 Rust 中使用；脚本执行期间物理状态以 [`LentPhysics`] 的形式借给脚本引擎。
+
+use rapier3d::prelude::*;
+use rapier3d::parry::query::TOIStatus;
+use alander_core::scene::CollisionLayers;
+use bevy_ecs::prelude::Entity;
+use glam::{Quat, Vec3};
+
+use crate::physics_manager::{collider_entity, interaction_groups, to_isometry, to_vec3, to_vector};
+
+/// 查询过滤条件
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicsQueryFilter {
+    /// 只检测与这些层交互的碰撞体
+    pub layers: CollisionLayers,
+    /// 排除的实体
+    pub exclude: Vec<Entity>,
+    /// 是否包括传感器碰撞体
+    pub include_sensors: bool,
+}
+
+impl Default for PhysicsQueryFilter {
+    fn default() -> Self {
+        Self { layers: CollisionLayers::ALL, exclude: Vec::new(), include_sensors: false }
+    }
+}
+
+/// 形状扫掠与重叠检测使用的查询形状
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryShape {
+    Sphere { radius: f32 },
+    Box { half_extents: Vec3 },
+    Capsule { half_height: f32, radius: f32 },
+}
+
+impl QueryShape {
+    fn to_shared_shape(self) -> SharedShape {
+        match self {
+            QueryShape::Sphere { radius } => SharedShape::ball(radius.max(0.0)),
+            QueryShape::Box { half_extents } => {
+                let h = half_extents.max(Vec3::ZERO);
+                SharedShape::cuboid(h.x, h.y, h.z)
+            }
+            QueryShape::Capsule { half_height, radius } => SharedShape::capsule_y(half_height.max(0.0), radius.max(0.0)),
+        }
+    }
+}
+
+/// 射线或形状扫掠的命中结果 (世界空间)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: Vec3,
+    /// 命中表面的外法线
+    pub normal: Vec3,
+    /// 沿方向移动的距离
+    pub distance: f32,
+}
+
+/// 点投影结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointHit {
+    pub entity: Entity,
+    /// 碰撞体表面上距离查询点最近的点 (查询点在实心碰撞体内部时为查询点本身)
+    pub point: Vec3,
+    pub is_inside: bool,
+    pub distance: f32,
+}
+
+/// 物理世界的只读查询视图
+pub struct PhysicsQueries<'a> {
+    pub(crate) bodies: &'a RigidBodySet,
+    pub(crate) colliders: &'a ColliderSet,
+    pub(crate) pipeline: &'a QueryPipeline,
+}
+
+/// 脚本执行期间从物理管理器借出的查询状态 (归还前物理管理器不可用)
+pub struct LentPhysics {
+    pub(crate) bodies: RigidBodySet,
+    pub(crate) colliders: ColliderSet,
+    pub(crate) pipeline: QueryPipeline,
+}
+
+impl LentPhysics {
+    pub fn queries(&self) -> PhysicsQueries<'_> {
+        PhysicsQueries { bodies: &self.bodies, colliders: &self.colliders, pipeline: &self.pipeline }
+    }
+}
+
+impl<'a> PhysicsQueries<'a> {
+    /// 沿射线查找最近的命中；`direction` 无需归一化，`max_distance` 可为 `f32::MAX`
+    pub fn cast_ray(&self, origin: Vec3, direction: Vec3, max_distance: f32, filter: &PhysicsQueryFilter) -> Option<RayHit> {
+        let ray = to_ray(origin, direction)?;
+        let predicate = exclude_predicate(filter);
+        let (handle, hit) = self.pipeline.cast_ray_and_get_normal(
+            self.bodies,
+            self.colliders,
+            &ray,
+            max_distance,
+            true,
+            to_rapier_filter(filter, &predicate),
+        )?;
+        Some(RayHit {
+            entity: self.entity(handle)?,
+            point: to_vec3(&ray.point_at(hit.toi).coords),
+            normal: to_vec3(&hit.normal),
+            distance: hit.toi,
+        })
+    }
+
+    /// 射线穿过的所有碰撞体，按距离由近到远排序
+    pub fn cast_ray_all(&self, origin: Vec3, direction: Vec3, max_distance: f32, filter: &PhysicsQueryFilter) -> Vec<RayHit> {
+        let Some(ray) = to_ray(origin, direction) else { return Vec::new() };
+        let predicate = exclude_predicate(filter);
+        let mut hits = Vec::new();
+        self.pipeline.intersections_with_ray(
+            self.bodies,
+            self.colliders,
+            &ray,
+            max_distance,
+            true,
+            to_rapier_filter(filter, &predicate),
+            |handle, hit| {
+                if let Some(entity) = self.entity(handle) {
+                    hits.push(RayHit {
+                        entity,
+                        point: to_vec3(&ray.point_at(hit.toi).coords),
+                        normal: to_vec3(&hit.normal),
+                        distance: hit.toi,
+                    });
+                }
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// 将形状从 `position` 沿 `direction` 扫掠，返回最先碰到的碰撞体
+    ///
+    /// 起始位置已与碰撞体重叠时距离为 0，法线为扫掠方向的反方向。
+    pub fn cast_shape(
+        &self,
+        shape: QueryShape,
+        position: Vec3,
+        rotation: Quat,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &PhysicsQueryFilter,
+    ) -> Option<RayHit> {
+        let direction = direction.try_normalize()?;
+        let predicate = exclude_predicate(filter);
+        let (handle, toi) = self.pipeline.cast_shape(
+            self.bodies,
+            self.colliders,
+            &to_isometry(position, rotation),
+            &to_vector(direction),
+            shape.to_shared_shape().as_ref(),
+            max_distance,
+            true,
+            to_rapier_filter(filter, &predicate),
+        )?;
+        // 查询管线的结果中 witness1/normal1 属于被命中的碰撞体，位于世界空间
+        let (point, normal) = match toi.status {
+            TOIStatus::Penetrating => (position, -direction),
+            _ => (to_vec3(&toi.witness1.coords), to_vec3(&toi.normal1)),
+        };
+        Some(RayHit { entity: self.entity(handle)?, point, normal, distance: toi.toi })
+    }
+
+    /// 将点投影到最近的碰撞体上，`max_distance` 以外的结果被忽略
+    pub fn project_point(&self, point: Vec3, max_distance: f32, filter: &PhysicsQueryFilter) -> Option<PointHit> {
+        let predicate = exclude_predicate(filter);
+        let (handle, projection) = self.pipeline.project_point(
+            self.bodies,
+            self.colliders,
+            &point![point.x, point.y, point.z],
+            true,
+            to_rapier_filter(filter, &predicate),
+        )?;
+        let projected = to_vec3(&projection.point.coords);
+        let distance = if projection.is_inside { 0.0 } else { projected.distance(point) };
+        (distance <= max_distance).then_some(())?;
+        Some(PointHit { entity: self.entity(handle)?, point: projected, is_inside: projection.is_inside, distance })
+    }
+
+    /// 与形状重叠的所有实体
+    pub fn overlap(&self, shape: QueryShape, position: Vec3, rotation: Quat, filter: &PhysicsQueryFilter) -> Vec<Entity> {
+        let predicate = exclude_predicate(filter);
+        let mut entities = Vec::new();
+        self.pipeline.intersections_with_shape(
+            self.bodies,
+            self.colliders,
+            &to_isometry(position, rotation),
+            shape.to_shared_shape().as_ref(),
+            to_rapier_filter(filter, &predicate),
+            |handle| {
+                if let Some(entity) = self.entity(handle).filter(|e| !entities.contains(e)) {
+                    entities.push(entity);
+                }
+                true
+            },
+        );
+        entities
+    }
+
+    /// 包含该点的所有实体
+    pub fn entities_at_point(&self, point: Vec3, filter: &PhysicsQueryFilter) -> Vec<Entity> {
+        let predicate = exclude_predicate(filter);
+        let mut entities = Vec::new();
+        self.pipeline.intersections_with_point(
+            self.bodies,
+            self.colliders,
+            &point![point.x, point.y, point.z],
+            to_rapier_filter(filter, &predicate),
+            |handle| {
+                if let Some(entity) = self.entity(handle).filter(|e| !entities.contains(e)) {
+                    entities.push(entity);
+                }
+                true
+            },
+        );
+        entities
+    }
+
+    fn entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.colliders.get(handle).and_then(|c| collider_entity(c.user_data))
+    }
+}
+
+type Predicate = Box<dyn Fn(ColliderHandle, &Collider) -> bool>;
+
+/// 排除实体的过滤谓词；没有排除实体时返回 None
+fn exclude_predicate(filter: &PhysicsQueryFilter) -> Option<Predicate> {
+    if filter.exclude.is_empty() {
+        return None;
+    }
+    let exclude = filter.exclude.clone();
+    Some(Box::new(move |_, collider: &Collider| !collider_entity(collider.user_data).is_some_and(|e| exclude.contains(&e))))
+}
+
+fn to_rapier_filter<'p>(filter: &PhysicsQueryFilter, predicate: &'p Option<Predicate>) -> QueryFilter<'p> {
+    let mut rapier_filter = QueryFilter::new().groups(interaction_groups(filter.layers));
+    if !filter.include_sensors {
+        rapier_filter = rapier_filter.exclude_sensors();
+    }
+    if let Some(predicate) = predicate {
+        rapier_filter = rapier_filter.predicate(predicate);
+    }
+    rapier_filter
+}
+
+fn to_ray(origin: Vec3, direction: Vec3) -> Option<Ray> {
+    let direction = direction.try_normalize()?;
+    Some(Ray::new(to_vector(origin).into(), to_vector(direction)))
+}
@@ -2,7 +2,7 @@
 //!
 //! 此模块负责管理ECS世界、场景和实体。
 
-use alander_core::scene::{Transform, Mesh, Name, RenderId, BoundingBox, PBRMaterial, PointLight, RigidBody, Collider, RigidBodyType, AssetPath, EntityUuid, Parent, Children, GlobalTransform, Camera, Material, Skin, Joint, AnimationPlayer};
+use alander_core::scene::{Transform, Mesh, Name, RenderId, BoundingBox, PBRMaterial, PointLight, RigidBody, Collider, RigidBodyType, AssetPath, EntityUuid, Parent, Children, GlobalTransform, Camera, Material, Skin, SkinnedMesh, Joint, AnimationPlayer, PhysicsJoint, CharacterController, ForceField, Script};
 use serde::{Serialize, Deserialize};
 use alander_core::math::AABB;
 use alander_render::renderer::{Renderer, create_cube};
@@ -73,6 +73,10 @@ impl Scene {
         let asset_path = self.world.get::<AssetPath>(entity).cloned();
         let rigid_body = self.world.get::<RigidBody>(entity).cloned();
         let collider = self.world.get::<Collider>(entity).cloned();
+        let physics_joint = self.world.get::<PhysicsJoint>(entity).cloned();
+        let character = self.world.get::<CharacterController>(entity).cloned();
+        let force_field = self.world.get::<ForceField>(entity).cloned();
+        let script = self.world.get::<Script>(entity).cloned();
 
         // 2. 创建新实体并应用组件
         let mut builder = self.world.spawn_empty();
@@ -86,6 +90,18 @@ impl Scene {
         if let Some(ap) = asset_path { builder.insert(ap); }
         if let Some(rb) = rigid_body { builder.insert(rb); }
         if let Some(col) = collider { builder.insert(col); }
+        if let Some(mut joint) = physics_joint {
+            joint.handle_index = None;
+            joint.handle_generation = None;
+            builder.insert(joint);
+        }
+        if let Some(mut cc) = character {
+            cc.handle_index = None;
+            cc.handle_generation = None;
+            builder.insert(cc);
+        }
+        if let Some(field) = force_field { builder.insert(field); }
+        if let Some(script) = script { builder.insert(script); }
 
         let new_entity = builder.id();
 
@@ -208,7 +224,10 @@ impl Scene {
         };
         let global_matrix = parent_global * local_matrix;
         if let Some(mut global) = self.world.get_mut::<GlobalTransform>(entity) {
-            global.0 = global_matrix;
+            // 只在变化时写入，使 GlobalTransform 的变更检测可用于增量物理同步
+            if global.0 != global_matrix {
+                global.0 = global_matrix;
+            }
         } else {
             self.world.entity_mut(entity).insert(GlobalTransform(global_matrix));
         }
@@ -238,10 +257,15 @@ impl Scene {
             self.spawn_gltf_node(root_idx, root, &model, renderer, &mut node_to_entity, asset_path);
         }
 
-        // 处理蒙皮 (Skin)
+        // 处理蒙皮 (Skin)：同一个 glTF 蒙皮只创建一次，引用它的网格通过 SkinnedMesh 共享
+        let mut skin_entities: HashMap<usize, Entity> = HashMap::new();
         for (node_idx, node_data) in model.nodes.iter().enumerate() {
-            if let Some(skin_idx) = node_data.skin_index {
-                if let (Some(&entity), Some(skin_data)) = (node_to_entity.get(&node_idx), model.skins.get(skin_idx)) {
+            let Some(skin_idx) = node_data.skin_index else { continue };
+            let (Some(&entity), Some(skin_data)) = (node_to_entity.get(&node_idx), model.skins.get(skin_idx)) else { continue };
+
+            let skin_entity = match skin_entities.get(&skin_idx) {
+                Some(&skin_entity) => skin_entity,
+                None => {
                     let mut joint_entities = Vec::new();
                     for (i, &joint_node_idx) in skin_data.joints.iter().enumerate() {
                         if let Some(&joint_entity) = node_to_entity.get(&joint_node_idx) {
@@ -249,13 +273,24 @@ impl Scene {
                             self.world.entity_mut(joint_entity).insert(Joint { index: i });
                         }
                     }
-
-                    self.world.entity_mut(entity).insert(Skin {
-                        name: skin_data.name.clone(),
-                        inverse_bind_matrices: skin_data.inverse_bind_matrices.clone(),
-                        joints: joint_entities,
-                    });
+                    let skin = Skin::new(skin_data.name.clone(), skin_data.inverse_bind_matrices.clone(), joint_entities);
+                    if let Err(e) = alander_core::skinning::validate_skin(&skin) {
+                        tracing::error!("{}", e);
+                    }
+                    self.world.entity_mut(entity).insert(skin);
+                    skin_entities.insert(skin_idx, entity);
+                    entity
                 }
+            };
+
+            // 顶点引用了不存在的关节时不绑定蒙皮，网格按未蒙皮渲染
+            let invalid = node_data.mesh_indices.iter().find_map(|&mesh_idx| {
+                let mesh = &model.meshes[mesh_idx].data;
+                alander_core::skinning::validate_vertices(&mesh.vertices, skin_data.joints.len()).err().map(|e| (mesh, e))
+            });
+            match invalid {
+                Some((mesh, e)) => tracing::error!("网格 \"{}\" 的蒙皮数据无效，按未蒙皮网格渲染: {}", mesh.name, e),
+                None => { self.world.entity_mut(entity).insert(SkinnedMesh { skin: skin_entity }); }
             }
         }
 
@@ -314,6 +349,8 @@ impl Scene {
                         tangent: v.tangent.to_array(),
                         joint_indices: v.joint_indices,
                         joint_weights: v.joint_weights,
+                        joint_indices_1: v.joint_indices_1,
+                        joint_weights_1: v.joint_weights_1,
                     }
                 }).collect();
 
@@ -361,6 +398,8 @@ impl Scene {
                         tangent: v.tangent.into(),
                         joint_indices: [0; 4],
                         joint_weights: [0.0; 4],
+                        joint_indices_1: [0; 4],
+                        joint_weights_1: [0.0; 4],
                     }
                 }).collect();
                 let scene_object = SceneObject::new(
@@ -402,6 +441,42 @@ impl Scene {
         }
         entities
     }
+
+    /// 获取实体的网格几何数据 (用于生成碰撞体)
+    ///
+    /// 优先使用 Mesh 组件引用的资源；glTF 节点则重新读取模型文件，并合并节点的所有 primitive。
+    pub fn mesh_data(&self, entity: Entity) -> Option<alander_core::scene::MeshData> {
+        if let Some(mesh) = self.world.get::<Mesh>(entity) {
+            if let Some(data) = self.mesh_manager.get(&mesh.handle) {
+                return Some((*data).clone());
+            }
+        }
+
+        let asset_path = self.world.get::<AssetPath>(entity)?;
+        if !(asset_path.path.ends_with(".glb") || asset_path.path.ends_with(".gltf")) {
+            return None;
+        }
+        let model = alander_core::assets::GltfLoader.load_scene(&asset_path.path).map_err(|e| tracing::error!("读取网格失败: {}", e)).ok()?;
+        let mesh_indices: Vec<usize> = match asset_path.sub_asset.as_deref() {
+            Some(node_name) => model.nodes.iter().find(|n| n.name == node_name)?.mesh_indices.clone(),
+            None => (0..model.meshes.len()).collect(),
+        };
+
+        let mut merged = alander_core::scene::MeshData { name: asset_path.sub_asset.clone().unwrap_or_default(), vertices: Vec::new(), indices: Vec::new() };
+        for gltf_mesh in mesh_indices.iter().filter_map(|&i| model.meshes.get(i)) {
+            let base = merged.vertices.len() as u32;
+            merged.vertices.extend_from_slice(&gltf_mesh.data.vertices);
+            merged.indices.extend(gltf_mesh.data.indices.iter().map(|i| i + base));
+        }
+        (!merged.vertices.is_empty()).then_some(merged)
+    }
+
+    /// 按 UUID 查找实体
+    pub fn find_entity_by_uuid(&self, uuid: Uuid) -> Option<Entity> {
+        self.world.iter_entities()
+            .find(|e| e.get::<EntityUuid>().is_some_and(|id| id.0 == uuid))
+            .map(|e| e.id())
+    }
     
     pub fn get_entity_transform(&self, entity: Entity) -> Option<Transform> {
         self.world.get::<Transform>(entity).cloned()
@@ -431,13 +506,20 @@ impl Scene {
                 let point_light = self.world.get::<PointLight>(curr).cloned();
                 let rigid_body = self.world.get::<RigidBody>(curr).cloned();
                 let collider = self.world.get::<Collider>(curr).cloned();
+                let physics_joint = self.world.get::<PhysicsJoint>(curr).cloned();
+                let joint_connected_uuid = physics_joint.as_ref()
+                    .and_then(|j| self.world.get::<EntityUuid>(j.connected))
+                    .map(|id| id.0);
+                let character_controller = self.world.get::<CharacterController>(curr).cloned();
+                let force_field = self.world.get::<ForceField>(curr).cloned();
+                let script = self.world.get::<Script>(curr).cloned();
                 let asset_path = self.world.get::<AssetPath>(curr).cloned();
                 let parent_uuid = if let Some(parent_comp) = self.world.get::<Parent>(curr) {
                     self.world.get::<EntityUuid>(parent_comp.0).map(|id| id.0)
                 } else {
                     None
                 };
-                entities_data.push(EntityData { name, uuid, transform, pbr_material, point_light, rigid_body, collider, asset_path, parent_uuid });
+                entities_data.push(EntityData { name, uuid, transform, pbr_material, point_light, rigid_body, collider, physics_joint, joint_connected_uuid, character_controller, force_field, script, asset_path, parent_uuid });
                 if let Some(children) = self.world.get::<Children>(curr) {
                     for &child in &children.0 { to_process.push(child); }
                 }
@@ -458,6 +540,10 @@ impl Scene {
             if let Some(ref t) = data.transform { builder.insert(*t); }
             if let Some(ref rb) = data.rigid_body { builder.insert(rb.clone()); }
             if let Some(ref col) = data.collider { builder.insert(col.clone()); }
+            if let Some(ref joint) = data.physics_joint { builder.insert(joint.clone()); }
+            if let Some(ref cc) = data.character_controller { builder.insert(cc.clone()); }
+            if let Some(ref field) = data.force_field { builder.insert(field.clone()); }
+            if let Some(ref script) = data.script { builder.insert(script.clone()); }
             if let Some(ref light) = data.point_light { builder.insert(light.clone()); }
             if let Some(ref mat) = data.pbr_material { builder.insert(mat.clone()); }
             if let Some(ref asset_path) = data.asset_path {
@@ -487,6 +573,8 @@ impl Scene {
                                     tangent: v.tangent.to_array(),
                                     joint_indices: v.joint_indices,
                                     joint_weights: v.joint_weights,
+                                    joint_indices_1: v.joint_indices_1,
+                                    joint_weights_1: v.joint_weights_1,
                                 }
                             }).collect();
                             let scene_object = SceneObject::new(
@@ -513,10 +601,33 @@ impl Scene {
                     self.set_parent(child, Some(parent));
                 }
             }
+            // 关节引用的刚体：优先在本次创建的实体中查找，否则在整个场景中按 UUID 查找
+            if let (Some(&entity), Some(connected_uuid)) = (uuid_to_entity.get(&data.uuid), data.joint_connected_uuid) {
+                let connected = uuid_to_entity.get(&connected_uuid).copied().or_else(|| self.find_entity_by_uuid(connected_uuid));
+                if let (Some(connected), Some(mut joint)) = (connected, self.world.get_mut::<PhysicsJoint>(entity)) {
+                    joint.connected = connected;
+                }
+            }
         }
         created_entities
     }
 
+    /// 从预制体文件 (场景 JSON) 生成实体，每个实体分配新的 UUID；返回预制体中的根实体
+    pub fn spawn_prefab(&mut self, path: &str, renderer: &mut Renderer) -> Result<Vec<Entity>, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let scene_data: SceneData = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        let mut entities_data = scene_data.entities;
+        let uuids: HashMap<Uuid, Uuid> = entities_data.iter().map(|data| (data.uuid, Uuid::new_v4())).collect();
+        let remap = |uuid: Uuid| uuids.get(&uuid).copied().unwrap_or(uuid);
+        for data in &mut entities_data {
+            data.uuid = remap(data.uuid);
+            data.parent_uuid = data.parent_uuid.map(remap);
+            data.joint_connected_uuid = data.joint_connected_uuid.map(remap);
+        }
+        let created = self.spawn_entity_subtree(entities_data, renderer);
+        Ok(created.into_iter().filter(|&e| self.world.get::<Parent>(e).is_none()).collect())
+    }
+
     pub fn to_json(&mut self) -> Result<String, String> {
         let mut entities_data = Vec::new();
         let mut query = self.world.query_filtered::<Entity, Without<Parent>>();
@@ -553,6 +664,17 @@ pub struct EntityData {
     pub point_light: Option<PointLight>,
     pub rigid_body: Option<RigidBody>,
     pub collider: Option<Collider>,
+    #[serde(default)]
+    pub physics_joint: Option<PhysicsJoint>,
+    /// 关节连接的刚体实体的 UUID
+    #[serde(default)]
+    pub joint_connected_uuid: Option<Uuid>,
+    #[serde(default)]
+    pub character_controller: Option<CharacterController>,
+    #[serde(default)]
+    pub force_field: Option<ForceField>,
+    #[serde(default)]
+    pub script: Option<Script>,
     pub asset_path: Option<AssetPath>,
     pub parent_uuid: Option<Uuid>,
 }
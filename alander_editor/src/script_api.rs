@@ -0,0 +1,374 @@
+//! 脚本 API：实体查找、组件读写、生成/销毁实体、输入与时间
+//!
+//! 脚本执行期间场景的 World 借给脚本引擎只读访问；对其他实体的修改作为 [`ScriptCommand`] 排队，
+//! 在本轮脚本全部执行完后统一应用，因此同一帧内的脚本看到的是一致的场景状态。
+
+use rhai::{Array, Dynamic, Engine};
+use alander_core::scene::{
+    AnimParamValue, AnimationStateMachine, Children, DirectionalLight, EntityUuid, GlobalTransform, Name, PBRMaterial, Parent,
+    PointLight, RigidBody, SpotLight, Transform,
+};
+use alander_core::{InputState, Time};
+use bevy_ecs::prelude::*;
+use glam::{Vec2, Vec3, Vec4};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// 脚本执行期间借给引擎的场景 World，脚本之外为 None
+pub type SharedWorld = Arc<RwLock<Option<World>>>;
+
+/// 脚本排队的修改
+pub type CommandQueue = Arc<Mutex<Vec<ScriptCommand>>>;
+
+/// 脚本可见的帧状态
+pub type SharedFrame = Arc<RwLock<FrameState>>;
+
+/// 动画状态机参数的新值
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnimParameter {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    Trigger,
+    /// 枚举选项名称
+    Enum(String),
+}
+
+/// 脚本对场景的延迟修改
+#[derive(Debug, Clone)]
+pub enum ScriptCommand {
+    SetTransform(Entity, Transform),
+    SetMaterial(Entity, PBRMaterial),
+    SetPointLight(Entity, PointLight),
+    SetSpotLight(Entity, SpotLight),
+    SetDirectionalLight(Entity, DirectionalLight),
+    /// 设置刚体速度，None 表示保持不变
+    SetVelocity { entity: Entity, linear: Option<Vec3>, angular: Option<Vec3> },
+    SetAnimParameter { entity: Entity, name: String, value: AnimParameter },
+    /// 在预留的实体上生成空实体
+    Spawn { entity: Entity, name: String, transform: Transform },
+    /// 在预留的实体上生成预制体实例，预制体的根实体成为其子节点
+    SpawnPrefab { entity: Entity, path: String, transform: Transform },
+    /// 删除实体及其子节点
+    Destroy(Entity),
+}
+
+impl ScriptCommand {
+    /// 将修改应用到 World；目标实体已不存在或缺少对应组件时忽略
+    ///
+    /// 生成预制体与删除实体需要场景与渲染器，由 [`ScriptManager::apply_commands`](crate::script_manager::ScriptManager::apply_commands) 处理。
+    pub fn apply(self, world: &mut World) {
+        match self {
+            ScriptCommand::SetTransform(entity, value) => set_component(world, entity, value),
+            ScriptCommand::SetMaterial(entity, value) => set_component(world, entity, value),
+            ScriptCommand::SetPointLight(entity, value) => set_component(world, entity, value),
+            ScriptCommand::SetSpotLight(entity, value) => set_component(world, entity, value),
+            ScriptCommand::SetDirectionalLight(entity, value) => set_component(world, entity, value),
+            ScriptCommand::SetVelocity { entity, linear, angular } => {
+                let Some(mut rb) = world.get_mut::<RigidBody>(entity) else { return };
+                if let Some(linear) = linear {
+                    rb.linear_velocity = linear;
+                }
+                if let Some(angular) = angular {
+                    rb.angular_velocity = angular;
+                }
+            }
+            ScriptCommand::SetAnimParameter { entity, name, value } => {
+                let Some(mut machine) = world.get_mut::<AnimationStateMachine>(entity) else { return };
+                match value {
+                    AnimParameter::Float(v) => machine.set_float(&name, v),
+                    AnimParameter::Int(v) => machine.set_int(&name, v),
+                    AnimParameter::Bool(v) => machine.set_bool(&name, v),
+                    AnimParameter::Trigger => machine.trigger(&name),
+                    AnimParameter::Enum(option) => {
+                        if !machine.set_enum(&name, &option) {
+                            tracing::warn!("动画参数 {} 没有选项 {}", name, option);
+                        }
+                    }
+                }
+            }
+            ScriptCommand::Spawn { entity, name, transform } => spawn_reserved(world, entity, name, transform),
+            ScriptCommand::SpawnPrefab { .. } | ScriptCommand::Destroy(_) => {
+                tracing::warn!("{:?} 需要通过场景应用", self);
+            }
+        }
+    }
+}
+
+fn set_component<T: Component>(world: &mut World, entity: Entity, value: T) {
+    if let Some(mut component) = world.get_mut::<T>(entity) {
+        *component = value;
+    }
+}
+
+/// 将脚本预留的实体变为带名称、UUID 与变换的实体
+pub fn spawn_reserved(world: &mut World, entity: Entity, name: String, transform: Transform) {
+    let Some(mut entity_mut) = world.get_or_spawn(entity) else { return };
+    entity_mut.insert((Name(name), EntityUuid(uuid::Uuid::new_v4()), transform, GlobalTransform::default()));
+}
+
+/// 脚本可见的帧状态：输入与时间 (每次执行脚本前复制)
+///
+/// 按键名与 winit `VirtualKeyCode` 的变体名一致，如 `"W"`、`"Space"`、`"LShift"`；
+/// 鼠标按键为 `"Left"`、`"Right"`、`"Middle"`。
+#[derive(Debug, Clone, Default)]
+pub struct FrameState {
+    pub keys: HashSet<String>,
+    pub just_pressed: HashSet<String>,
+    pub just_released: HashSet<String>,
+    pub mouse_buttons: HashSet<String>,
+    pub mouse_position: Vec2,
+    pub mouse_scroll: Vec2,
+    pub time: Time,
+}
+
+impl FrameState {
+    pub fn capture(input: &InputState, time: &Time) -> Self {
+        let pressed = |state: &winit::event::ElementState| *state == winit::event::ElementState::Pressed;
+        Self {
+            keys: input.keyboard.iter().filter(|(_, s)| pressed(s)).map(|(k, _)| format!("{:?}", k)).collect(),
+            just_pressed: input.just_pressed.iter().map(|k| format!("{:?}", k)).collect(),
+            just_released: input.just_released.iter().map(|k| format!("{:?}", k)).collect(),
+            mouse_buttons: input.mouse_buttons.iter().filter(|(_, s)| pressed(s)).map(|(b, _)| format!("{:?}", b)).collect(),
+            mouse_position: input.mouse_position,
+            mouse_scroll: input.mouse_scroll_delta,
+            time: time.clone(),
+        }
+    }
+}
+
+fn read<R>(world: &SharedWorld, f: impl FnOnce(&World) -> R) -> Option<R> {
+    world.read().ok()?.as_ref().map(f)
+}
+
+/// 读取实体的组件副本，实体或组件不存在时返回 `()`
+fn component<T: Component + Clone>(world: &SharedWorld, entity: Entity) -> Dynamic {
+    read(world, |w| w.get::<T>(entity).cloned().map_or(Dynamic::UNIT, Dynamic::from)).unwrap_or(Dynamic::UNIT)
+}
+
+fn entity_or_unit(entity: Option<Entity>) -> Dynamic {
+    entity.map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+/// 注册脚本 API
+///
+/// 读取函数返回调用时的组件副本 (不存在时为 `()`)，`set_*` 函数在本轮脚本执行完后生效，例如
+/// `let lamp = find_entity("Lamp"); let light = get_point_light(lamp); light.intensity = 2.0; set_point_light(lamp, light);`。
+pub fn register_script_api(engine: &mut Engine, world: &SharedWorld, commands: &CommandQueue, frame: &SharedFrame) {
+    register_types(engine, world);
+    register_entity_queries(engine, world);
+    register_components(engine, world, commands);
+    register_spawning(engine, world, commands);
+    register_input(engine, frame);
+}
+
+fn register_types(engine: &mut Engine, world: &SharedWorld) {
+    engine.register_type_with_name::<Vec2>("Vec2")
+        .register_fn("vec2", |x: f32, y: f32| Vec2::new(x, y))
+        .register_get_set("x", |v: &mut Vec2| v.x, |v: &mut Vec2, val: f32| v.x = val)
+        .register_get_set("y", |v: &mut Vec2| v.y, |v: &mut Vec2, val: f32| v.y = val);
+
+    engine.register_type_with_name::<Vec4>("Vec4")
+        .register_fn("vec4", |x: f32, y: f32, z: f32, w: f32| Vec4::new(x, y, z, w))
+        .register_get_set("x", |v: &mut Vec4| v.x, |v: &mut Vec4, val: f32| v.x = val)
+        .register_get_set("y", |v: &mut Vec4| v.y, |v: &mut Vec4, val: f32| v.y = val)
+        .register_get_set("z", |v: &mut Vec4| v.z, |v: &mut Vec4, val: f32| v.z = val)
+        .register_get_set("w", |v: &mut Vec4| v.w, |v: &mut Vec4, val: f32| v.w = val);
+
+    engine.register_type_with_name::<PBRMaterial>("Material")
+        .register_get_set("base_color", |m: &mut PBRMaterial| m.base_color, |m: &mut PBRMaterial, v: Vec4| m.base_color = v)
+        .register_get_set("metallic", |m: &mut PBRMaterial| m.metallic, |m: &mut PBRMaterial, v: f32| m.metallic = v)
+        .register_get_set("roughness", |m: &mut PBRMaterial| m.roughness, |m: &mut PBRMaterial, v: f32| m.roughness = v)
+        .register_get_set("emissive", |m: &mut PBRMaterial| m.emissive, |m: &mut PBRMaterial, v: Vec3| m.emissive = v);
+
+    engine.register_type_with_name::<PointLight>("PointLight")
+        .register_get_set("color", |l: &mut PointLight| l.color, |l: &mut PointLight, v: Vec3| l.color = v)
+        .register_get_set("intensity", |l: &mut PointLight| l.intensity, |l: &mut PointLight, v: f32| l.intensity = v)
+        .register_get_set("range", |l: &mut PointLight| l.range, |l: &mut PointLight, v: f32| l.range = v);
+
+    // 聚光灯角度在脚本中以角度表示
+    engine.register_type_with_name::<SpotLight>("SpotLight")
+        .register_get_set("color", |l: &mut SpotLight| l.color, |l: &mut SpotLight, v: Vec3| l.color = v)
+        .register_get_set("intensity", |l: &mut SpotLight| l.intensity, |l: &mut SpotLight, v: f32| l.intensity = v)
+        .register_get_set("range", |l: &mut SpotLight| l.range, |l: &mut SpotLight, v: f32| l.range = v)
+        .register_get_set("inner_angle", |l: &mut SpotLight| l.inner_angle.to_degrees(), |l: &mut SpotLight, v: f32| l.inner_angle = v.to_radians())
+        .register_get_set("outer_angle", |l: &mut SpotLight| l.outer_angle.to_degrees(), |l: &mut SpotLight, v: f32| l.outer_angle = v.to_radians());
+
+    // 平行光方向由实体的旋转决定 (渲染器不读取 `direction` 字段)，脚本通过 Transform 控制
+    engine.register_type_with_name::<DirectionalLight>("DirectionalLight")
+        .register_get_set("color", |l: &mut DirectionalLight| l.color, |l: &mut DirectionalLight, v: Vec3| l.color = v)
+        .register_get_set("intensity", |l: &mut DirectionalLight| l.intensity, |l: &mut DirectionalLight, v: f32| l.intensity = v);
+
+    engine.register_type_with_name::<RigidBody>("RigidBody")
+        .register_get("linear_velocity", |rb: &mut RigidBody| rb.linear_velocity)
+        .register_get("angular_velocity", |rb: &mut RigidBody| rb.angular_velocity)
+        .register_get("mass", |rb: &mut RigidBody| rb.mass.map_or(Dynamic::UNIT, Dynamic::from))
+        .register_get("sleeping", |rb: &mut RigidBody| rb.sleeping);
+
+    // 实体的名称与 UUID
+    let w = world.clone();
+    engine.register_get("name", move |e: &mut Entity| {
+        read(&w, |world| world.get::<Name>(*e).map(|n| n.0.clone())).flatten().unwrap_or_default()
+    });
+    let w = world.clone();
+    engine.register_get("uuid", move |e: &mut Entity| {
+        read(&w, |world| world.get::<EntityUuid>(*e).map(|id| id.0.to_string())).flatten().unwrap_or_default()
+    });
+}
+
+fn register_entity_queries(engine: &mut Engine, world: &SharedWorld) {
+    let w = world.clone();
+    engine.register_fn("find_entity", move |name: &str| {
+        entity_or_unit(read(&w, |world| find_by_name(world, name).next()).flatten())
+    });
+    let w = world.clone();
+    engine.register_fn("find_entities", move |name: &str| -> Array {
+        read(&w, |world| find_by_name(world, name).map(Dynamic::from).collect()).unwrap_or_default()
+    });
+    let w = world.clone();
+    engine.register_fn("find_entity_by_uuid", move |uuid: &str| {
+        let Ok(uuid) = uuid::Uuid::parse_str(uuid) else { return Dynamic::UNIT };
+        entity_or_unit(read(&w, |world| {
+            world.iter_entities().find(|e| e.get::<EntityUuid>().is_some_and(|id| id.0 == uuid)).map(|e| e.id())
+        }).flatten())
+    });
+    let w = world.clone();
+    engine.register_fn("exists", move |e: Entity| read(&w, |world| world.get_entity(e).is_some()).unwrap_or(false));
+    let w = world.clone();
+    engine.register_fn("parent", move |e: Entity| entity_or_unit(read(&w, |world| world.get::<Parent>(e).map(|p| p.0)).flatten()));
+    let w = world.clone();
+    engine.register_fn("children", move |e: Entity| -> Array {
+        read(&w, |world| world.get::<Children>(e).map(|c| c.0.iter().copied().map(Dynamic::from).collect()))
+            .flatten()
+            .unwrap_or_default()
+    });
+}
+
+fn find_by_name<'w>(world: &'w World, name: &'w str) -> impl Iterator<Item = Entity> + 'w {
+    world.iter_entities().filter(move |e| e.get::<Name>().is_some_and(|n| n.0 == name)).map(|e| e.id())
+}
+
+fn register_components(engine: &mut Engine, world: &SharedWorld, commands: &CommandQueue) {
+    let w = world.clone();
+    engine.register_fn("get_transform", move |e: Entity| component::<Transform>(&w, e));
+    let w = world.clone();
+    engine.register_fn("get_material", move |e: Entity| component::<PBRMaterial>(&w, e));
+    let w = world.clone();
+    engine.register_fn("get_point_light", move |e: Entity| component::<PointLight>(&w, e));
+    let w = world.clone();
+    engine.register_fn("get_spot_light", move |e: Entity| component::<SpotLight>(&w, e));
+    let w = world.clone();
+    engine.register_fn("get_directional_light", move |e: Entity| component::<DirectionalLight>(&w, e));
+    let w = world.clone();
+    engine.register_fn("get_rigid_body", move |e: Entity| component::<RigidBody>(&w, e));
+    let w = world.clone();
+    engine.register_fn("get_anim_param", move |e: Entity, name: &str| {
+        read(&w, |world| {
+            let machine = world.get::<AnimationStateMachine>(e)?;
+            Some(match machine.parameters.get(name)? {
+                AnimParamValue::Float(v) => Dynamic::from(*v),
+                AnimParamValue::Int(v) => Dynamic::from(*v as i64),
+                AnimParamValue::Bool(v) | AnimParamValue::Trigger(v) => Dynamic::from(*v),
+                AnimParamValue::Enum { value, options } => Dynamic::from(options.get(*value).cloned().unwrap_or_default()),
+            })
+        }).flatten().unwrap_or(Dynamic::UNIT)
+    });
+
+    let c = commands.clone();
+    engine.register_fn("set_transform", move |e: Entity, t: Transform| c.lock().unwrap().push(ScriptCommand::SetTransform(e, t)));
+    let c = commands.clone();
+    engine.register_fn("set_material", move |e: Entity, m: PBRMaterial| c.lock().unwrap().push(ScriptCommand::SetMaterial(e, m)));
+    let c = commands.clone();
+    engine.register_fn("set_point_light", move |e: Entity, l: PointLight| c.lock().unwrap().push(ScriptCommand::SetPointLight(e, l)));
+    let c = commands.clone();
+    engine.register_fn("set_spot_light", move |e: Entity, l: SpotLight| c.lock().unwrap().push(ScriptCommand::SetSpotLight(e, l)));
+    let c = commands.clone();
+    engine.register_fn("set_directional_light", move |e: Entity, l: DirectionalLight| {
+        c.lock().unwrap().push(ScriptCommand::SetDirectionalLight(e, l));
+    });
+
+    let c = commands.clone();
+    engine.register_fn("set_velocity", move |entity: Entity, linear: Vec3, angular: Vec3| {
+        c.lock().unwrap().push(ScriptCommand::SetVelocity { entity, linear: Some(linear), angular: Some(angular) });
+    });
+    let c = commands.clone();
+    engine.register_fn("set_linear_velocity", move |entity: Entity, linear: Vec3| {
+        c.lock().unwrap().push(ScriptCommand::SetVelocity { entity, linear: Some(linear), angular: None });
+    });
+    let c = commands.clone();
+    engine.register_fn("set_angular_velocity", move |entity: Entity, angular: Vec3| {
+        c.lock().unwrap().push(ScriptCommand::SetVelocity { entity, linear: None, angular: Some(angular) });
+    });
+
+    let anim = |commands: &CommandQueue| {
+        let c = commands.clone();
+        move |entity: Entity, name: &str, value: AnimParameter| {
+            c.lock().unwrap().push(ScriptCommand::SetAnimParameter { entity, name: name.to_string(), value });
+        }
+    };
+    let set = anim(commands);
+    engine.register_fn("set_anim_float", move |e: Entity, name: &str, v: f32| set(e, name, AnimParameter::Float(v)));
+    let set = anim(commands);
+    engine.register_fn("set_anim_int", move |e: Entity, name: &str, v: i64| set(e, name, AnimParameter::Int(v as i32)));
+    let set = anim(commands);
+    engine.register_fn("set_anim_bool", move |e: Entity, name: &str, v: bool| set(e, name, AnimParameter::Bool(v)));
+    let set = anim(commands);
+    engine.register_fn("set_anim_enum", move |e: Entity, name: &str, option: &str| set(e, name, AnimParameter::Enum(option.to_string())));
+    let set = anim(commands);
+    engine.register_fn("anim_trigger", move |e: Entity, name: &str| set(e, name, AnimParameter::Trigger));
+}
+
+/// 生成函数立即返回预留的实体，实体在本轮脚本执行完后才拥有组件
+fn register_spawning(engine: &mut Engine, world: &SharedWorld, commands: &CommandQueue) {
+    let reserve = |world: &SharedWorld| read(world, |w| w.entities().reserve_entity());
+
+    let (w, c) = (world.clone(), commands.clone());
+    let spawn = move |name: &str, position: Vec3| {
+        let Some(entity) = reserve(&w) else { return Dynamic::UNIT };
+        let transform = Transform::from_translation(position);
+        c.lock().unwrap().push(ScriptCommand::Spawn { entity, name: name.to_string(), transform });
+        Dynamic::from(entity)
+    };
+    let f = spawn.clone();
+    engine.register_fn("spawn_entity", move |name: &str| f(name, Vec3::ZERO));
+    engine.register_fn("spawn_entity", spawn);
+
+    let (w, c) = (world.clone(), commands.clone());
+    let spawn_prefab = move |path: &str, position: Vec3| {
+        let Some(entity) = reserve(&w) else { return Dynamic::UNIT };
+        let transform = Transform::from_translation(position);
+        c.lock().unwrap().push(ScriptCommand::SpawnPrefab { entity, path: path.to_string(), transform });
+        Dynamic::from(entity)
+    };
+    let f = spawn_prefab.clone();
+    engine.register_fn("spawn_prefab", move |path: &str| f(path, Vec3::ZERO));
+    engine.register_fn("spawn_prefab", spawn_prefab);
+
+    let c = commands.clone();
+    engine.register_fn("destroy", move |e: Entity| c.lock().unwrap().push(ScriptCommand::Destroy(e)));
+}
+
+fn register_input(engine: &mut Engine, frame: &SharedFrame) {
+    let with_frame = |frame: &SharedFrame| {
+        let frame = frame.clone();
+        move |f: &dyn Fn(&FrameState) -> bool| frame.read().map(|s| f(&s)).unwrap_or(false)
+    };
+    let r = with_frame(frame);
+    engine.register_fn("key_pressed", move |key: &str| r(&|s| s.keys.contains(key)));
+    let r = with_frame(frame);
+    engine.register_fn("key_just_pressed", move |key: &str| r(&|s| s.just_pressed.contains(key)));
+    let r = with_frame(frame);
+    engine.register_fn("key_just_released", move |key: &str| r(&|s| s.just_released.contains(key)));
+    let r = with_frame(frame);
+    engine.register_fn("mouse_pressed", move |button: &str| r(&|s| s.mouse_buttons.contains(button)));
+
+    let f = frame.clone();
+    engine.register_fn("mouse_position", move || f.read().map(|s| s.mouse_position).unwrap_or_default());
+    let f = frame.clone();
+    engine.register_fn("mouse_scroll", move || f.read().map(|s| s.mouse_scroll).unwrap_or_default());
+    // 时间 (秒)
+    let f = frame.clone();
+    engine.register_fn("elapsed_time", move || f.read().map(|s| s.time.elapsed).unwrap_or_default());
+    let f = frame.clone();
+    engine.register_fn("delta_time", move || f.read().map(|s| s.time.delta).unwrap_or_default());
+}
@@ -0,0 +1,132 @@
+//! 脚本控制台：收集脚本的 `print` / `debug` 输出与错误
+//!
+//! 脚本执行前 [`ScriptManager`](crate::script_manager::ScriptManager) 记录正在运行的实体，
+//! 输出和错误据此关联到实体与源码位置，控制台面板点击条目即可定位。
+
+use alander_core::scene::ScriptError;
+use bevy_ecs::prelude::Entity;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// 脚本引擎与编辑器共享的控制台
+pub type SharedConsole = Arc<Mutex<ScriptConsole>>;
+
+/// 控制台最多保留的条目数
+const MAX_ENTRIES: usize = 500;
+
+/// 错误位置前后显示的源码行数
+const EXCERPT_RADIUS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLevel {
+    Print,
+    Debug,
+    Error,
+}
+
+/// 控制台条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleEntry {
+    pub level: ConsoleLevel,
+    pub message: String,
+    /// 产生输出的脚本实体
+    pub entity: Option<Entity>,
+    /// 脚本文件路径 (内联代码为 None)
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// 出错位置附近的源码 (行号, 内容)
+    pub excerpt: Vec<(usize, String)>,
+    /// 相同条目连续出现的次数
+    pub count: usize,
+}
+
+impl ConsoleEntry {
+    /// 错误条目，附带出错位置附近的源码
+    pub fn error(error: &ScriptError, entity: Option<Entity>, path: Option<String>, source: &str) -> Self {
+        Self {
+            level: ConsoleLevel::Error,
+            message: error.to_string(),
+            entity,
+            path,
+            line: error.line,
+            column: error.column,
+            excerpt: error.line.map(|line| source_excerpt(source, line)).unwrap_or_default(),
+            count: 1,
+        }
+    }
+
+    /// 是否为同一条输出 (不比较计数)
+    pub fn same_as(&self, other: &ConsoleEntry) -> bool {
+        self.level == other.level
+            && self.message == other.message
+            && self.entity == other.entity
+            && self.line == other.line
+            && self.column == other.column
+    }
+}
+
+/// 脚本输出与错误的记录
+#[derive(Debug, Default)]
+pub struct ScriptConsole {
+    entries: VecDeque<ConsoleEntry>,
+    /// 正在执行的脚本 (实体, 文件路径)
+    current: Option<(Entity, Option<String>)>,
+}
+
+impl ScriptConsole {
+    /// 添加条目；与上一条相同时只增加计数
+    pub fn push(&mut self, entry: ConsoleEntry) {
+        if let Some(last) = self.entries.back_mut().filter(|last| last.same_as(&entry)) {
+            last.count += 1;
+            return;
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// 以正在执行的脚本为来源添加 `print` / `debug` 输出
+    pub fn output(&mut self, level: ConsoleLevel, message: &str, line: Option<usize>, column: Option<usize>) {
+        let (entity, path) = self.current.clone().unzip();
+        self.push(ConsoleEntry {
+            level,
+            message: message.to_string(),
+            entity,
+            path: path.flatten(),
+            line,
+            column,
+            excerpt: Vec::new(),
+            count: 1,
+        });
+    }
+
+    /// 设置正在执行的脚本，脚本之外为 None
+    pub fn set_current(&mut self, current: Option<(Entity, Option<String>)>) {
+        self.current = current;
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &ConsoleEntry> {
+        self.entries.iter()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.level == ConsoleLevel::Error).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// 第 `line` 行 (从 1 开始) 前后的源码
+pub fn source_excerpt(source: &str, line: usize) -> Vec<(usize, String)> {
+    let first = line.saturating_sub(EXCERPT_RADIUS).max(1);
+    source.lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text.to_string()))
+        .skip(first - 1)
+        .take(line + EXCERPT_RADIUS + 1 - first)
+        .collect()
+}
@@ -1,17 +1,136 @@
-use rhai::{Engine, Scope};
-use alander_core::scene::{Transform, Script};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, ImmutableString, Module, Scope, AST};
+use rhai::module_resolvers::FileModuleResolver;
+use alander_core::scene::{Transform, Script, ScriptError, ScriptErrorKind, ScriptParameter, ScriptValue, CharacterController, CollisionLayers};
+use alander_core::events::{CollisionEvent, IntersectionEvent};
 use alander_core::math::Vec3;
-use crate::scene_manager::Scene;
+use bevy_ecs::event::{Events, ManualEventReader};
+use alander_core::{InputState, Time};
+use alander_render::renderer::Renderer;
+use bevy_ecs::prelude::{Entity, World};
+use crate::scene_manager::{Scene, SceneHandle};
+use crate::script_api::{register_script_api, spawn_reserved, CommandQueue, FrameState, ScriptCommand, SharedFrame, SharedWorld};
+use crate::script_console::{ConsoleEntry, ConsoleLevel, SharedConsole};
+use crate::physics_manager::{BodyForce, PhysicsManager};
+use crate::physics_query::{LentPhysics, PhysicsQueries, PhysicsQueryFilter, PointHit, QueryShape, RayHit};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 项目脚本目录：脚本文件路径与 `import` 均相对于此目录
+pub const SCRIPTS_DIR: &str = "assets/scripts";
+
+/// 共享辅助模块目录 (位于脚本目录下)，其中每个文件以文件名注册为全局模块
+const LIBRARY_DIR: &str = "lib";
+
+/// 检查脚本文件修改的间隔 (秒)
+const HOT_RELOAD_INTERVAL: f32 = 0.5;
+
+/// 脚本执行期间借给引擎的物理查询状态，脚本之外为 None
+type SharedPhysics = Arc<RwLock<Option<LentPhysics>>>;
+
+/// 脚本施加的力，脚本执行结束后交给物理管理器
+type QueuedForces = Arc<Mutex<Vec<(Entity, BodyForce)>>>;
+
+/// 本次执行中 `param` 声明的参数名与默认值
+type DeclaredParams = Arc<Mutex<Vec<(String, Dynamic)>>>;
+
+/// 正在执行的脚本的截止时间，脚本之外为 None
+type Deadline = Arc<Mutex<Option<Instant>>>;
+
+/// 脚本的资源限制，超出时脚本以 [`ScriptErrorKind::Limit`] 错误停止并停用
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptLimits {
+    /// 每次调用 (顶层语句、`update` 或回调) 的最大操作次数，0 为不限
+    pub max_operations: u64,
+    /// 每个脚本每帧 (或每次回调) 的执行时间预算
+    pub time_budget_ms: u64,
+    /// 函数调用的最大嵌套深度
+    pub max_call_depth: usize,
+    /// 表达式的最大嵌套深度
+    pub max_expr_depth: usize,
+    /// 字符串的最大长度 (字节)
+    pub max_string_size: usize,
+    /// 数组的最大元素数
+    pub max_array_size: usize,
+    /// 对象映射的最大属性数
+    pub max_map_size: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            time_budget_ms: 100,
+            max_call_depth: 64,
+            max_expr_depth: 64,
+            max_string_size: 1 << 20,
+            max_array_size: 100_000,
+            max_map_size: 100_000,
+        }
+    }
+}
 
 /// 脚本管理器，负责 Rhai 引擎的生命周期和绑定
 pub struct ScriptManager {
     engine: Engine,
+    /// 碰撞事件读取位置 (每个事件只分发一次)
+    collision_reader: ManualEventReader<CollisionEvent>,
+    /// 传感器相交事件读取位置
+    intersection_reader: ManualEventReader<IntersectionEvent>,
+    /// 物理场景查询 (`ray_cast` 等脚本函数使用)
+    physics: SharedPhysics,
+    /// `apply_force` 等脚本函数施加的力
+    forces: QueuedForces,
+    /// 按代码哈希缓存的 AST
+    asts: HashMap<u64, Arc<AST>>,
+    /// 每个场景中每个实体的脚本实例
+    instances: HashMap<(SceneHandle, Entity), ScriptInstance>,
+    /// 上次更新时是否处于模拟中 (进入或退出模拟时重新初始化脚本)
+    simulating: bool,
+    /// 脚本执行期间借出的场景 World
+    world: SharedWorld,
+    /// 脚本排队的场景修改
+    commands: CommandQueue,
+    /// 本帧的输入与时间
+    frame: SharedFrame,
+    /// `param` 语句声明的参数
+    declared: DeclaredParams,
+    /// 脚本目录
+    scripts_dir: PathBuf,
+    /// 已读取的脚本文件源码与哈希 (按相对路径)
+    sources: HashMap<String, (String, u64)>,
+    /// 脚本目录中文件的修改时间，用于检测热重载
+    file_times: HashMap<PathBuf, SystemTime>,
+    /// 距离上次检查文件修改的时间
+    since_poll: f32,
+    /// 脚本文件每次重新加载后递增，所有脚本随之重新编译 (保留脚本变量)
+    generation: u64,
+    /// 资源限制
+    limits: ScriptLimits,
+    /// 当前脚本执行的截止时间
+    deadline: Deadline,
+    /// 脚本输出与错误
+    console: SharedConsole,
 }
 
 impl ScriptManager {
     pub fn new() -> Self {
+        Self::with_scripts_dir(SCRIPTS_DIR)
+    }
+
+    /// 使用指定的脚本目录创建脚本管理器
+    pub fn with_scripts_dir(scripts_dir: impl Into<PathBuf>) -> Self {
+        let scripts_dir = scripts_dir.into();
         let mut engine = Engine::new();
 
+        // `import "enemy/ai" as ai;` 相对于脚本目录解析；关闭缓存以便热重载时重新读取
+        let mut resolver = FileModuleResolver::new_with_path(&scripts_dir);
+        resolver.enable_cache(false);
+        engine.set_module_resolver(resolver);
+
         // 1. 注册基础数学类型 Vec3
         engine.register_type_with_name::<Vec3>("Vec3")
             .register_fn("vec3", |x: f32, y: f32, z: f32| Vec3::new(x, y, z))
@@ -29,48 +148,1052 @@ impl ScriptManager {
             t.rotation = glam::Quat::from_euler(glam::EulerRot::YXZ, y.to_radians(), x.to_radians(), z.to_radians());
         });
 
-        Self { engine }
+        // 3. 注册实体句柄 (碰撞回调的参数)
+        engine.register_type_with_name::<Entity>("Entity")
+            .register_get("id", |e: &mut Entity| e.to_bits() as i64)
+            .register_fn("to_string", |e: &mut Entity| format!("{:?}", e))
+            .register_fn("==", |a: Entity, b: Entity| a == b);
+
+        // 4. 注册角色控制器 (脚本中的 `character` 变量)
+        engine.register_type_with_name::<CharacterController>("CharacterController")
+            .register_get_set("movement", |c: &mut CharacterController| c.desired_movement, |c: &mut CharacterController, v: Vec3| c.desired_movement = v)
+            .register_get("grounded", |c: &mut CharacterController| c.grounded)
+            .register_get("vertical_speed", |c: &mut CharacterController| c.vertical_speed)
+            .register_fn("jump", |c: &mut CharacterController, speed: f32| c.vertical_speed = speed);
+
+        // 5. 注册物理场景查询
+        let physics = SharedPhysics::default();
+        register_physics_queries(&mut engine, &physics);
+
+        // 6. 注册施加力/冲量的函数
+        let forces = QueuedForces::default();
+        register_body_forces(&mut engine, &forces);
+
+        // 7. 注册实体、组件、生成/销毁、输入与时间 API
+        let (world, commands, frame) = (SharedWorld::default(), CommandQueue::default(), SharedFrame::default());
+        register_script_api(&mut engine, &world, &commands, &frame);
+
+        // 8. `param speed = 5.0;` 声明可在检查器中调节的参数，作用域中已有同名变量 (检查器设置的值) 时保留
+        let declared = DeclaredParams::default();
+        let declared_params = declared.clone();
+        engine.register_custom_syntax(["param", "$ident$", "=", "$expr$"], true, move |context, inputs| {
+            let name = inputs[0].get_string_value().unwrap_or_default().to_string();
+            let default = context.eval_expression_tree(&inputs[1])?;
+            if !context.scope().contains(&name) {
+                context.scope_mut().push_dynamic(name.clone(), default.clone());
+            }
+            declared_params.lock().unwrap().push((name, default));
+            Ok(Dynamic::UNIT)
+        }).expect("注册 param 语法失败");
+
+        // 9. 执行时间预算与 `print` / `debug` 输出
+        let deadline = Deadline::default();
+        let progress_deadline = deadline.clone();
+        engine.on_progress(move |operations| {
+            if operations % 256 != 0 {
+                return None;
+            }
+            progress_deadline.lock().unwrap()
+                .is_some_and(|deadline| Instant::now() >= deadline)
+                .then_some(Dynamic::UNIT)
+        });
+        let console = SharedConsole::default();
+        let print_console = console.clone();
+        engine.on_print(move |text| print_console.lock().unwrap().output(ConsoleLevel::Print, text, None, None));
+        let debug_console = console.clone();
+        engine.on_debug(move |text, _, position| {
+            debug_console.lock().unwrap().output(ConsoleLevel::Debug, text, position.line(), position.position());
+        });
+
+        let mut manager = Self {
+            engine,
+            collision_reader: ManualEventReader::default(),
+            intersection_reader: ManualEventReader::default(),
+            physics,
+            forces,
+            asts: HashMap::new(),
+            instances: HashMap::new(),
+            simulating: false,
+            world,
+            commands,
+            frame,
+            declared,
+            file_times: scan_script_files(&scripts_dir),
+            scripts_dir,
+            sources: HashMap::new(),
+            since_poll: 0.0,
+            generation: 0,
+            limits: ScriptLimits::default(),
+            deadline,
+            console,
+        };
+        manager.set_limits(ScriptLimits::default());
+        manager.load_library();
+        manager
+    }
+
+    pub fn limits(&self) -> &ScriptLimits {
+        &self.limits
+    }
+
+    /// 设置脚本的资源限制 (对之后的执行生效)
+    pub fn set_limits(&mut self, limits: ScriptLimits) {
+        self.engine
+            .set_max_operations(limits.max_operations)
+            .set_max_call_levels(limits.max_call_depth)
+            .set_max_expr_depths(limits.max_expr_depth, limits.max_expr_depth)
+            .set_max_string_size(limits.max_string_size)
+            .set_max_array_size(limits.max_array_size)
+            .set_max_map_size(limits.max_map_size);
+        self.limits = limits;
+    }
+
+    /// 脚本的 `print` / `debug` 输出与错误
+    pub fn console(&self) -> &SharedConsole {
+        &self.console
+    }
+
+    /// 开始执行脚本：计时并将之后的输出归于该脚本
+    fn begin(&self, current: Option<(Entity, Option<String>)>) {
+        *self.deadline.lock().unwrap() = Some(Instant::now() + Duration::from_millis(self.limits.time_budget_ms));
+        self.console.lock().unwrap().set_current(current);
+    }
+
+    fn end(&self) {
+        *self.deadline.lock().unwrap() = None;
+        self.console.lock().unwrap().set_current(None);
+    }
+
+    /// 编译 `lib/` 下的共享模块并以文件名注册，如 `lib/math.rhai` 中的函数以 `math::lerp(..)` 调用
+    fn load_library(&mut self) {
+        let Ok(entries) = std::fs::read_dir(self.scripts_dir.join(LIBRARY_DIR)) else { return };
+        let mut modules = Vec::new();
+        for path in entries.flatten().map(|e| e.path()).filter(|p| is_script_file(p)) {
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else { continue };
+            let Ok(source) = std::fs::read_to_string(&path) else { continue };
+            self.begin(None);
+            let module = self.engine.compile_into_self_contained(&Scope::new(), &source)
+                .and_then(|ast| Module::eval_ast_as_new(Scope::new(), &ast, &self.engine));
+            self.end();
+            match module {
+                Ok(module) => modules.push((name, module)),
+                Err(e) => {
+                    let error = script_error(&e, None, &self.limits);
+                    tracing::warn!("加载脚本模块 {} 失败: {}", path.display(), error);
+                    let relative = path.strip_prefix(&self.scripts_dir).unwrap_or(&path).to_string_lossy().into_owned();
+                    self.console.lock().unwrap().push(ConsoleEntry::error(&error, None, Some(relative), &source));
+                }
+            }
+        }
+        for (name, module) in modules {
+            self.engine.register_static_module(name, module.into());
+        }
+    }
+
+    /// 重新读取脚本文件和共享模块；下次更新时所有脚本重新编译，已运行脚本的变量保留
+    pub fn reload_scripts(&mut self) {
+        self.sources.clear();
+        self.load_library();
+        self.generation += 1;
+    }
+
+    /// 每隔 [`HOT_RELOAD_INTERVAL`] 检查脚本目录，有文件修改时重新加载
+    fn poll_hot_reload(&mut self, delta_time: f32) {
+        self.since_poll += delta_time;
+        if self.since_poll < HOT_RELOAD_INTERVAL {
+            return;
+        }
+        self.since_poll = 0.0;
+        let file_times = scan_script_files(&self.scripts_dir);
+        if file_times != self.file_times {
+            self.file_times = file_times;
+            tracing::info!("脚本文件已修改，重新加载");
+            self.reload_scripts();
+        }
+    }
+
+    /// 读取脚本文件 (带缓存)，返回源码哈希
+    fn load_source(&mut self, path: &str) -> Result<u64, ScriptError> {
+        if let Some((_, hash)) = self.sources.get(path) {
+            return Ok(*hash);
+        }
+        let source = std::fs::read_to_string(self.scripts_dir.join(path)).map_err(|e| ScriptError {
+            kind: ScriptErrorKind::Compile,
+            hook: None,
+            message: format!("无法读取脚本文件 {}: {}", path, e),
+            line: None,
+            column: None,
+        })?;
+        let hash = code_hash(&source);
+        self.sources.insert(path.to_string(), (source, hash));
+        Ok(hash)
+    }
+
+    /// 脚本的源码：文件脚本取已读取的文件内容，否则取组件中的代码
+    fn source(&self, entity: Entity, path: Option<&str>) -> String {
+        match path {
+            Some(path) => self.sources.get(path).map(|(source, _)| source.clone()).unwrap_or_default(),
+            None => self.read_world(|world| world.get::<Script>(entity).map(|s| s.code.clone())).flatten().unwrap_or_default(),
+        }
+    }
+
+    /// 在脚本执行期间把物理状态借给查询函数，结束后归还并施加脚本排队的力
+    fn with_physics<R>(&mut self, physics: &mut PhysicsManager, f: impl FnOnce(&mut Self) -> R) -> R {
+        *self.physics.write().unwrap() = Some(physics.lend_queries());
+        let result = f(self);
+        if let Some(lent) = self.physics.write().unwrap().take() {
+            physics.return_queries(lent);
+        }
+        for (entity, force) in self.forces.lock().unwrap().drain(..) {
+            physics.apply(entity, force);
+        }
+        result
+    }
+
+    /// 在脚本执行期间把场景的 World 借给脚本 API (只读)，结束后放回场景
+    fn with_world<R>(&mut self, scene: &mut Scene, f: impl FnOnce(&mut Self) -> R) -> R {
+        *self.world.write().unwrap() = Some(std::mem::take(&mut scene.world));
+        let result = f(self);
+        if let Some(world) = self.world.write().unwrap().take() {
+            scene.world = world;
+        }
+        result
+    }
+
+    /// 读取借出的 World (脚本函数执行期间不可写)
+    fn read_world<R>(&self, f: impl FnOnce(&World) -> R) -> Option<R> {
+        self.world.read().unwrap().as_ref().map(f)
+    }
+
+    fn write_world<R>(&self, f: impl FnOnce(&mut World) -> R) -> Option<R> {
+        self.world.write().unwrap().as_mut().map(f)
     }
 
     /// 执行脚本更新
-    pub fn update_scripts(&self, scene: &mut Scene, delta_time: f32) {
-        let mut query = scene.world.query::<(&mut Script, &mut Transform)>();
-        
-        for (mut script, mut transform) in query.iter_mut(&mut scene.world) {
-            if !script.active || script.code.is_empty() {
-                continue;
+    ///
+    /// 脚本首次运行 (或代码修改后) 时执行顶层语句并调用 `init()`，之后每帧调用 `update(dt)`；
+    /// 顶层 `let` 定义的变量在帧之间保留。没有 `update` 函数的脚本每帧执行全部顶层语句。
+    /// 脚本文件或其导入的模块修改后，脚本热重载：替换代码但保留变量，新增的顶层变量会被补充。
+    /// `param` 声明的参数同步到组件的 `parameters`，检查器中修改的值每帧写入脚本变量。
+    /// 脚本被移除、停用或实体被删除时调用 `destroy()`；开始和停止模拟时所有脚本重新初始化。
+    ///
+    /// 脚本中的 `entity` 为脚本所在的实体，可用于在场景查询中排除自身。对其他实体的修改
+    /// 在调用 [`apply_commands`](Self::apply_commands) 时生效。
+    pub fn update_scripts(&mut self, scene: &mut Scene, physics: &mut PhysicsManager, input: &InputState, time: &Time) {
+        *self.frame.write().unwrap() = FrameState::capture(input, time);
+        self.poll_hot_reload(time.delta);
+        let handle = scene.handle;
+        let simulating = physics.has_snapshot();
+        self.with_physics(physics, |manager| {
+            manager.with_world(scene, |manager| {
+                if simulating != manager.simulating {
+                    manager.simulating = simulating;
+                    manager.destroy_all(handle);
+                }
+                manager.destroy_stale(handle);
+                manager.run_update(handle, time.delta);
+            });
+        });
+        self.asts.retain(|_, ast| Arc::strong_count(ast) > 1);
+    }
+
+    /// 对场景中的所有脚本调用 `destroy()` 并丢弃其状态，下次更新时重新初始化
+    fn destroy_all(&mut self, handle: SceneHandle) {
+        let keys: Vec<_> = self.instances.keys().filter(|(h, _)| *h == handle).copied().collect();
+        for key in keys {
+            if let Some(mut instance) = self.instances.remove(&key) {
+                self.destroy(key.1, &mut instance);
             }
+        }
+    }
+
+    /// 销毁脚本已被移除、停用或实体已被删除的实例
+    fn destroy_stale(&mut self, handle: SceneHandle) {
+        let stale: Vec<_> = self.read_world(|world| {
+            self.instances.keys()
+                .filter(|(h, entity)| {
+                    *h == handle && !world.get::<Script>(*entity).is_some_and(|s| s.active && s.has_source())
+                })
+                .copied()
+                .collect()
+        }).unwrap_or_default();
+        for key in stale {
+            if let Some(mut instance) = self.instances.remove(&key) {
+                self.destroy(key.1, &mut instance);
+            }
+        }
+    }
+
+    /// 调用 `destroy()`；销毁时对 `transform` 等变量的修改不会写回
+    fn destroy(&self, entity: Entity, instance: &mut ScriptInstance) {
+        self.begin(Some((entity, instance.path.clone())));
+        let result = self.call_lifecycle(instance, "destroy", 0.0);
+        self.end();
+        if let Err(e) = result {
+            let error = script_error(&e, Some("destroy"), &self.limits);
+            self.report_error(entity, &error);
+            self.write_world(|world| {
+                if let Some(mut script) = world.get_mut::<Script>(entity) {
+                    script.last_error = Some(error);
+                }
+            });
+        }
+    }
+
+    /// 按代码哈希取得编译后的 AST，只在代码首次出现时 (或重新加载后) 编译；`import` 的模块在编译时嵌入
+    fn compile(&mut self, code: &str, hash: u64) -> Result<Arc<AST>, Box<EvalAltResult>> {
+        let key = code_hash(&(self.generation, hash));
+        if let Some(ast) = self.asts.get(&key) {
+            return Ok(ast.clone());
+        }
+        let ast = Arc::new(self.engine.compile_into_self_contained(&Scope::new(), code)?);
+        self.asts.insert(key, ast.clone());
+        Ok(ast)
+    }
+
+    fn run_update(&mut self, handle: SceneHandle, delta_time: f32) {
+        let entities: Vec<Entity> = self.read_world(|world| {
+            world.iter_entities()
+                .filter(|e| e.contains::<Transform>() && e.get::<Script>().is_some_and(|s| s.active && s.has_source()))
+                .map(|e| e.id())
+                .collect()
+        }).unwrap_or_default();
+
+        for entity in entities {
+            self.run_entity_update(handle, entity, delta_time);
+        }
+    }
 
-            // 编译 AST (实际应用中应缓存 AST)
-            let ast = match self.engine.compile(&script.code) {
-                Ok(ast) => ast,
+    fn run_entity_update(&mut self, handle: SceneHandle, entity: Entity, delta_time: f32) {
+        let key = (handle, entity);
+        let Some((path, inline_hash, transform, character, parameters)) = self.read_world(|world| {
+            let script = world.get::<Script>(entity)?;
+            Some((
+                script.path.clone(),
+                code_hash(&script.code),
+                *world.get::<Transform>(entity)?,
+                world.get::<CharacterController>(entity).cloned(),
+                script.parameters.clone(),
+            ))
+        }).flatten() else { return };
+        let hash = match path.as_deref() {
+            Some(file) => match self.load_source(file) {
+                Ok(hash) => hash,
                 Err(e) => {
-                    script.last_error = Some(format!("编译错误: {}", e));
-                    script.active = false;
-                    continue;
+                    self.set_error(entity, e);
+                    return;
                 }
-            };
-
-            // 创建 Scope 并注入变量
-            let mut scope = Scope::new();
-            scope.push("dt", delta_time);
-            
-            // 将 Transform 克隆进脚本环境（Rhai 无法直接操作 Rust 引用，需要这种方式）
-            scope.push("transform", transform.clone());
-
-            // 运行脚本
-            if let Err(e) = self.engine.run_ast_with_scope(&mut scope, &ast) {
-                script.last_error = Some(format!("运行错误: {}", e));
-                script.active = false;
-                continue;
+            },
+            None => inline_hash,
+        };
+
+        let mut reloaded = false;
+        let mut instance = match self.instances.remove(&key) {
+            Some(instance) if instance.code_hash == hash && instance.generation == self.generation => instance,
+            // 脚本文件或导入的模块被修改：替换代码，保留变量
+            Some(mut instance) if instance.code_hash == hash || (path.is_some() && instance.path == path) => {
+                let code = self.source(entity, path.as_deref());
+                match self.compile(&code, hash) {
+                    Ok(ast) => {
+                        instance.ast = ast;
+                        instance.reload_error = None;
+                        reloaded = true;
+                    }
+                    // 编译失败时继续运行旧代码，直到文件再次修改
+                    Err(e) => {
+                        let error = script_error(&e, None, &self.limits);
+                        self.report_error(entity, &error);
+                        instance.reload_error = Some(error);
+                    }
+                }
+                instance.code_hash = hash;
+                instance.generation = self.generation;
+                instance
+            }
+            previous => {
+                if let Some(mut previous) = previous {
+                    self.destroy(entity, &mut previous);
+                }
+                let code = self.source(entity, path.as_deref());
+                let ast = match self.compile(&code, hash) {
+                    Ok(ast) => ast,
+                    Err(e) => {
+                        self.set_error(entity, script_error(&e, None, &self.limits));
+                        return;
+                    }
+                };
+                let mut instance = ScriptInstance {
+                    code_hash: hash,
+                    generation: self.generation,
+                    path: path.clone(),
+                    ast,
+                    scope: Scope::new(),
+                    initialized: false,
+                    reload_error: None,
+                };
+                instance.scope.push_constant("entity", entity);
+                instance
             }
+        };
 
-            // 写回修改后的 Transform
-            if let Some(new_transform) = scope.get_value::<Transform>("transform") {
+        // 将组件克隆进脚本环境（Rhai 无法直接操作 Rust 引用，需要这种方式）
+        instance.scope.set_value("dt", delta_time);
+        instance.scope.set_value("transform", transform);
+        if let Some(character) = character {
+            instance.scope.set_value("character", character);
+        }
+        for parameter in &parameters {
+            instance.scope.set_value(parameter.name.clone(), to_dynamic(&parameter.value));
+        }
+
+        // 初始化、热重载和每帧执行顶层语句时，`param` 声明决定组件的参数列表
+        let declares_parameters = !instance.initialized || reloaded || !instance.has_update();
+        self.declared.lock().unwrap().clear();
+        self.begin(Some((entity, path.clone())));
+        let result = if !instance.initialized {
+            instance.initialized = true;
+            self.init(&mut instance, delta_time)
+        } else {
+            let added = if reloaded && instance.has_update() { self.add_new_variables(&mut instance) } else { Ok(()) };
+            added.map_err(|e| ("", e)).and_then(|_| if instance.has_update() {
+                self.call_lifecycle(&mut instance, "update", delta_time).map_err(|e| ("update", e))
+            } else {
+                self.run_top_level(&mut instance).map_err(|e| ("", e))
+            })
+        };
+        self.end();
+        if let Err((hook, e)) = result {
+            self.set_error(entity, script_error(&e, Some(hook).filter(|h| !h.is_empty()), &self.limits));
+            return;
+        }
+        let declared = std::mem::take(&mut *self.declared.lock().unwrap());
+
+        // 写回修改后的组件
+        let new_transform = instance.scope.get_value::<Transform>("transform");
+        let new_character = instance.scope.get_value::<CharacterController>("character");
+        self.write_world(|world| {
+            if let (Some(new_transform), Some(mut transform)) = (new_transform, world.get_mut::<Transform>(entity)) {
                 *transform = new_transform;
             }
-            
-            script.last_error = None;
+            if let (Some(new_character), Some(mut character)) = (new_character, world.get_mut::<CharacterController>(entity)) {
+                *character = new_character;
+            }
+            if let Some(mut script) = world.get_mut::<Script>(entity) {
+                if script.last_error != instance.reload_error {
+                    script.last_error = instance.reload_error.clone();
+                }
+                if declares_parameters {
+                    let synced = sync_parameters(&script.parameters, declared);
+                    if synced != script.parameters {
+                        script.parameters = synced;
+                    }
+                }
+            }
+        });
+        self.instances.insert(key, instance);
+    }
+
+    /// 热重载后补充新增的顶层变量：在作用域副本中执行顶层语句，只保留原作用域中没有的变量
+    ///
+    /// 这次执行排队的场景修改和力会被丢弃。
+    fn add_new_variables(&self, instance: &mut ScriptInstance) -> Result<(), Box<EvalAltResult>> {
+        let (commands, forces) = (self.commands.lock().unwrap().len(), self.forces.lock().unwrap().len());
+        let mut scope = instance.scope.clone();
+        let len = scope.len();
+        let result = self.engine.run_ast_with_scope(&mut scope, &instance.ast);
+        self.commands.lock().unwrap().truncate(commands);
+        self.forces.lock().unwrap().truncate(forces);
+        result?;
+        for (name, is_constant, value) in scope.iter().skip(len) {
+            if instance.scope.contains(name) {
+                continue;
+            }
+            if is_constant {
+                instance.scope.push_constant_dynamic(name.to_string(), value);
+            } else {
+                instance.scope.push_dynamic(name.to_string(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录错误并停用脚本 (在检查器中重新启用)
+    fn set_error(&self, entity: Entity, error: ScriptError) {
+        self.report_error(entity, &error);
+        self.write_world(|world| {
+            if let Some(mut script) = world.get_mut::<Script>(entity) {
+                script.last_error = Some(error);
+                script.active = false;
+            }
+        });
+    }
+
+    /// 将错误连同出错位置附近的源码写入控制台
+    fn report_error(&self, entity: Entity, error: &ScriptError) {
+        let path = self.read_world(|world| world.get::<Script>(entity).and_then(|s| s.path.clone())).flatten();
+        let source = self.source(entity, path.as_deref());
+        self.console.lock().unwrap().push(ConsoleEntry::error(error, Some(entity), path, &source));
+    }
+
+    /// 首次运行：有 `update` 函数时执行一次顶层语句 (定义持久变量) 并调用 `init()`，然后执行第一次 `update`
+    fn init(&self, instance: &mut ScriptInstance, delta_time: f32) -> Result<(), (&'static str, Box<EvalAltResult>)> {
+        if !instance.has_update() {
+            return self.call_lifecycle(instance, "init", delta_time)
+                .map_err(|e| ("init", e))
+                .and_then(|_| self.run_top_level(instance).map_err(|e| ("", e)));
+        }
+        self.engine.run_ast_with_scope(&mut instance.scope, &instance.ast).map_err(|e| ("", e))?;
+        self.call_lifecycle(instance, "init", delta_time).map_err(|e| ("init", e))?;
+        self.call_lifecycle(instance, "update", delta_time).map_err(|e| ("update", e))
+    }
+
+    /// 执行顶层语句，其中定义的变量在执行后丢弃
+    fn run_top_level(&self, instance: &mut ScriptInstance) -> Result<(), Box<EvalAltResult>> {
+        let len = instance.scope.len();
+        let result = self.engine.run_ast_with_scope(&mut instance.scope, &instance.ast);
+        instance.scope.rewind(len);
+        result
+    }
+
+    /// 调用脚本定义的生命周期函数 (未定义时忽略)，函数可以声明零个参数或一个 `dt` 参数
+    fn call_lifecycle(&self, instance: &mut ScriptInstance, name: &str, delta_time: f32) -> Result<(), Box<EvalAltResult>> {
+        let Some(arity) = instance.ast.iter_functions().find(|f| f.name == name).map(|f| f.params.len()) else { return Ok(()) };
+        let options = CallFnOptions::new().eval_ast(false);
+        match arity {
+            0 => self.engine.call_fn_with_options::<Dynamic>(options, &mut instance.scope, &instance.ast, name, ()),
+            _ => self.engine.call_fn_with_options::<Dynamic>(options, &mut instance.scope, &instance.ast, name, (delta_time,)),
+        }
+        .map(|_| ())
+    }
+
+    /// 应用脚本排队的场景修改 (设置组件、生成/销毁实体)，在执行脚本或碰撞回调后调用
+    pub fn apply_commands(&mut self, scene: &mut Scene, renderer: &mut Renderer) {
+        let commands = std::mem::take(&mut *self.commands.lock().unwrap());
+        for command in commands {
+            match command {
+                ScriptCommand::SpawnPrefab { entity, path, transform } => {
+                    let name = std::path::Path::new(&path).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+                    spawn_reserved(&mut scene.world, entity, name, transform);
+                    match scene.spawn_prefab(&path, renderer) {
+                        Ok(roots) => {
+                            for &root in &roots {
+                                scene.world.entity_mut(root).insert(alander_core::scene::Parent(entity));
+                            }
+                            scene.world.entity_mut(entity).insert(alander_core::scene::Children(roots));
+                        }
+                        Err(e) => tracing::warn!("生成预制体 {} 失败: {}", path, e),
+                    }
+                }
+                ScriptCommand::Destroy(entity) => {
+                    if scene.world.get_entity(entity).is_some() {
+                        scene.remove_entity(entity);
+                    }
+                }
+                command => command.apply(&mut scene.world),
+            }
+        }
+    }
+
+    /// 将物理碰撞事件分发给脚本的 `on_collision_enter(other)` / `on_collision_exit(other)` 回调，
+    /// 传感器相交事件分发给双方的 `on_trigger_enter(other)` / `on_trigger_exit(other)` 回调
+    ///
+    /// 回调中 `this` 绑定为实体的 Transform，修改会写回组件；`other` 为另一个实体。
+    pub fn dispatch_collision_events(&mut self, scene: &mut Scene, physics: &mut PhysicsManager) {
+        let mut calls = Vec::new();
+        if let Some(events) = scene.world.get_resource::<Events<CollisionEvent>>() {
+            for event in self.collision_reader.read(events) {
+                let hook = if event.is_started() { "on_collision_enter" } else { "on_collision_exit" };
+                let (a, b) = event.entities();
+                calls.push((a, b, hook));
+                calls.push((b, a, hook));
+            }
+        }
+        if let Some(events) = scene.world.get_resource::<Events<IntersectionEvent>>() {
+            for event in self.intersection_reader.read(events) {
+                let hook = if event.started { "on_trigger_enter" } else { "on_trigger_exit" };
+                calls.push((event.sensor, event.other, hook));
+                calls.push((event.other, event.sensor, hook));
+            }
+        }
+
+        if calls.is_empty() {
+            return;
+        }
+        let handle = scene.handle;
+        self.with_physics(physics, |manager| {
+            manager.with_world(scene, |manager| {
+                for (entity, other, hook) in calls {
+                    manager.call_collision_hook(handle, entity, other, hook);
+                }
+            });
+        });
+    }
+
+    /// 回调与 `update` 共享实体的脚本变量；脚本尚未初始化 (或编译失败) 时忽略
+    fn call_collision_hook(&mut self, handle: SceneHandle, entity: Entity, other: Entity, hook: &str) {
+        let Some((transform, path)) = self.read_world(|world| {
+            let script = world.get::<Script>(entity).filter(|s| s.active)?;
+            Some((world.get::<Transform>(entity).map(|t| Dynamic::from(*t)).unwrap_or(Dynamic::UNIT), script.path.clone()))
+        }).flatten() else { return };
+        if !self.instances.get(&(handle, entity)).is_some_and(|i| i.ast.iter_functions().any(|f| f.name == hook && f.params.len() == 1)) {
+            return;
+        }
+
+        self.begin(Some((entity, path)));
+        let Some(instance) = self.instances.get_mut(&(handle, entity)) else { return };
+        let mut this = transform;
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut instance.scope, &instance.ast, hook, (other,));
+        self.end();
+
+        match result {
+            Ok(_) => {
+                self.write_world(|world| {
+                    if let (Some(new_transform), Some(mut transform)) = (this.try_cast::<Transform>(), world.get_mut::<Transform>(entity)) {
+                        *transform = new_transform;
+                    }
+                });
+            }
+            Err(e) => self.set_error(entity, script_error(&e, Some(hook), &self.limits)),
+        }
+    }
+}
+
+/// 实体的脚本实例：编译后的 AST 与跨帧保留的脚本变量
+struct ScriptInstance {
+    code_hash: u64,
+    /// 编译时的脚本加载代数
+    generation: u64,
+    /// 脚本文件路径 (内联代码为 None)
+    path: Option<String>,
+    ast: Arc<AST>,
+    scope: Scope<'static>,
+    /// 是否已执行初始化
+    initialized: bool,
+    /// 热重载失败的错误 (继续运行旧代码)
+    reload_error: Option<ScriptError>,
+}
+
+impl ScriptInstance {
+    fn has_update(&self) -> bool {
+        self.ast.iter_functions().any(|f| f.name == "update")
+    }
+}
+
+fn code_hash(code: &(impl Hash + ?Sized)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 将 Rhai 错误转换为带位置的脚本错误；脚本自身函数中的错误取函数内部的出错位置
+fn script_error(error: &EvalAltResult, hook: Option<&str>, limits: &ScriptLimits) -> ScriptError {
+    let mut error = error;
+    while let EvalAltResult::ErrorInFunctionCall(_, source, inner, _) = error {
+        if !source.is_empty() {
+            break;
+        }
+        error = inner;
+    }
+    let position = error.position();
+    let (kind, message) = match error {
+        EvalAltResult::ErrorParsing(..) => (ScriptErrorKind::Compile, message_without_position(error)),
+        EvalAltResult::ErrorTerminated(..) => (ScriptErrorKind::Limit, format!("执行时间超过 {} ms", limits.time_budget_ms)),
+        EvalAltResult::ErrorTooManyOperations(..) => (ScriptErrorKind::Limit, format!("操作次数超过 {}", limits.max_operations)),
+        EvalAltResult::ErrorStackOverflow(..) => (ScriptErrorKind::Limit, format!("函数调用深度超过 {}", limits.max_call_depth)),
+        EvalAltResult::ErrorDataTooLarge(..) | EvalAltResult::ErrorTooManyModules(..) => {
+            (ScriptErrorKind::Limit, message_without_position(error))
+        }
+        _ => (ScriptErrorKind::Runtime, message_without_position(error)),
+    };
+    ScriptError { kind, hook: hook.map(str::to_string), message, line: position.line(), column: position.position() }
+}
+
+/// 错误信息 (去掉末尾的位置，位置单独记录)
+fn message_without_position(error: &EvalAltResult) -> String {
+    let message = error.to_string();
+    let position = error.position();
+    match message.strip_suffix(&format!(" ({})", position)) {
+        Some(message) if !position.is_none() => message.to_string(),
+        _ => message,
+    }
+}
+
+fn is_script_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "rhai")
+}
+
+/// 递归列出目录下所有脚本文件 (含共享模块) 的修改时间
+fn scan_script_files(dir: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut files = HashMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if is_script_file(&path) {
+                if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                    files.insert(path, modified);
+                }
+            }
+        }
+    }
+    files
+}
+
+/// 列出脚本目录下的脚本文件 (相对路径，不含共享模块)，供检查器选择
+pub fn list_script_files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = scan_script_files(dir)
+        .into_keys()
+        .filter_map(|path| path.strip_prefix(dir).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .filter(|path| !path.starts_with(&format!("{}/", LIBRARY_DIR)))
+        .collect();
+    files.sort();
+    files
+}
+
+fn to_dynamic(value: &ScriptValue) -> Dynamic {
+    match value {
+        ScriptValue::Float(v) => Dynamic::from(*v),
+        ScriptValue::Int(v) => Dynamic::from(*v),
+        ScriptValue::Bool(v) => Dynamic::from(*v),
+        ScriptValue::String(v) => Dynamic::from(v.clone()),
+        ScriptValue::Vec3(v) => Dynamic::from(*v),
+    }
+}
+
+fn from_dynamic(value: &Dynamic) -> Option<ScriptValue> {
+    if let Ok(v) = value.as_float() {
+        Some(ScriptValue::Float(v))
+    } else if let Ok(v) = value.as_int() {
+        Some(ScriptValue::Int(v))
+    } else if let Ok(v) = value.as_bool() {
+        Some(ScriptValue::Bool(v))
+    } else if let Some(v) = value.clone().try_cast::<ImmutableString>() {
+        Some(ScriptValue::String(v.to_string()))
+    } else {
+        value.clone().try_cast::<Vec3>().map(ScriptValue::Vec3)
+    }
+}
+
+/// 按脚本声明的顺序生成参数列表：保留类型相同的已有值，新参数取默认值，不再声明的参数被移除
+fn sync_parameters(current: &[ScriptParameter], declared: Vec<(String, Dynamic)>) -> Vec<ScriptParameter> {
+    let mut parameters: Vec<ScriptParameter> = Vec::new();
+    for (name, default) in declared {
+        let Some(default) = from_dynamic(&default) else { continue };
+        if parameters.iter().any(|p| p.name == name) {
+            continue;
+        }
+        let value = current.iter()
+            .find(|p| p.name == name && p.value.same_type(&default))
+            .map(|p| p.value.clone())
+            .unwrap_or(default);
+        parameters.push(ScriptParameter { name, value });
+    }
+    parameters
+}
+
+fn with_queries<R>(physics: &SharedPhysics, f: impl FnOnce(PhysicsQueries) -> R) -> Option<R> {
+    physics.read().ok()?.as_ref().map(|lent| f(lent.queries()))
+}
+
+fn hit_or_unit<T: Clone + Send + Sync + 'static>(hit: Option<Option<T>>) -> Dynamic {
+    hit.flatten().map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+fn to_array<T: Clone + Send + Sync + 'static>(items: Option<Vec<T>>) -> Array {
+    items.unwrap_or_default().into_iter().map(Dynamic::from).collect()
+}
+
+/// 注册场景查询函数；未命中时返回 `()`，多结果查询返回数组
+///
+/// 每个查询函数都有一个以 `QueryFilter` 结尾的重载，例如
+/// `ray_cast(origin, dir, 100.0, query_filter().exclude(entity))`。
+fn register_physics_queries(engine: &mut Engine, physics: &SharedPhysics) {
+    engine.register_type_with_name::<PhysicsQueryFilter>("QueryFilter")
+        .register_fn("query_filter", PhysicsQueryFilter::default)
+        // 只检测所属层与掩码有交集的碰撞体
+        .register_fn("with_layer_mask", |mut f: PhysicsQueryFilter, mask: i64| {
+            f.layers = CollisionLayers::new(u32::MAX, mask as u32);
+            f
+        })
+        .register_fn("exclude", |mut f: PhysicsQueryFilter, e: Entity| {
+            f.exclude.push(e);
+            f
+        })
+        .register_fn("include_sensors", |mut f: PhysicsQueryFilter| {
+            f.include_sensors = true;
+            f
+        });
+
+    engine.register_type_with_name::<RayHit>("RayHit")
+        .register_get("entity", |h: &mut RayHit| h.entity)
+        .register_get("point", |h: &mut RayHit| h.point)
+        .register_get("normal", |h: &mut RayHit| h.normal)
+        .register_get("distance", |h: &mut RayHit| h.distance);
+
+    engine.register_type_with_name::<PointHit>("PointHit")
+        .register_get("entity", |h: &mut PointHit| h.entity)
+        .register_get("point", |h: &mut PointHit| h.point)
+        .register_get("is_inside", |h: &mut PointHit| h.is_inside)
+        .register_get("distance", |h: &mut PointHit| h.distance);
+
+    let p = physics.clone();
+    let ray_cast = move |origin: Vec3, dir: Vec3, max: f32, f: &PhysicsQueryFilter| {
+        hit_or_unit(with_queries(&p, |q| q.cast_ray(origin, dir, max, f)))
+    };
+    let p = physics.clone();
+    let ray_cast_all = move |origin: Vec3, dir: Vec3, max: f32, f: &PhysicsQueryFilter| {
+        to_array(with_queries(&p, |q| q.cast_ray_all(origin, dir, max, f)))
+    };
+    let p = physics.clone();
+    let shape_cast = move |shape: QueryShape, origin: Vec3, dir: Vec3, max: f32, f: &PhysicsQueryFilter| {
+        hit_or_unit(with_queries(&p, |q| q.cast_shape(shape, origin, glam::Quat::IDENTITY, dir, max, f)))
+    };
+    let p = physics.clone();
+    let project_point = move |point: Vec3, max: f32, f: &PhysicsQueryFilter| {
+        hit_or_unit(with_queries(&p, |q| q.project_point(point, max, f)))
+    };
+    let p = physics.clone();
+    let overlap = move |shape: QueryShape, center: Vec3, f: &PhysicsQueryFilter| {
+        to_array(with_queries(&p, |q| q.overlap(shape, center, glam::Quat::IDENTITY, f)))
+    };
+
+    let f = ray_cast.clone();
+    engine.register_fn("ray_cast", move |o: Vec3, d: Vec3, max: f32| f(o, d, max, &PhysicsQueryFilter::default()));
+    engine.register_fn("ray_cast", move |o: Vec3, d: Vec3, max: f32, filter: PhysicsQueryFilter| ray_cast(o, d, max, &filter));
+    let f = ray_cast_all.clone();
+    engine.register_fn("ray_cast_all", move |o: Vec3, d: Vec3, max: f32| f(o, d, max, &PhysicsQueryFilter::default()));
+    engine.register_fn("ray_cast_all", move |o: Vec3, d: Vec3, max: f32, filter: PhysicsQueryFilter| ray_cast_all(o, d, max, &filter));
+
+    let f = shape_cast.clone();
+    engine.register_fn("sphere_cast", move |o: Vec3, radius: f32, d: Vec3, max: f32| {
+        f(QueryShape::Sphere { radius }, o, d, max, &PhysicsQueryFilter::default())
+    });
+    let f = shape_cast.clone();
+    engine.register_fn("sphere_cast", move |o: Vec3, radius: f32, d: Vec3, max: f32, filter: PhysicsQueryFilter| {
+        f(QueryShape::Sphere { radius }, o, d, max, &filter)
+    });
+    let f = shape_cast.clone();
+    engine.register_fn("box_cast", move |o: Vec3, half_extents: Vec3, d: Vec3, max: f32| {
+        f(QueryShape::Box { half_extents }, o, d, max, &PhysicsQueryFilter::default())
+    });
+    let f = shape_cast.clone();
+    engine.register_fn("box_cast", move |o: Vec3, half_extents: Vec3, d: Vec3, max: f32, filter: PhysicsQueryFilter| {
+        f(QueryShape::Box { half_extents }, o, d, max, &filter)
+    });
+    let f = shape_cast.clone();
+    engine.register_fn("capsule_cast", move |o: Vec3, half_height: f32, radius: f32, d: Vec3, max: f32| {
+        f(QueryShape::Capsule { half_height, radius }, o, d, max, &PhysicsQueryFilter::default())
+    });
+    engine.register_fn("capsule_cast", move |o: Vec3, half_height: f32, radius: f32, d: Vec3, max: f32, filter: PhysicsQueryFilter| {
+        shape_cast(QueryShape::Capsule { half_height, radius }, o, d, max, &filter)
+    });
+
+    let f = project_point.clone();
+    engine.register_fn("project_point", move |point: Vec3, max: f32| f(point, max, &PhysicsQueryFilter::default()));
+    engine.register_fn("project_point", move |point: Vec3, max: f32, filter: PhysicsQueryFilter| project_point(point, max, &filter));
+
+    let f = overlap.clone();
+    engine.register_fn("overlap_sphere", move |center: Vec3, radius: f32| {
+        f(QueryShape::Sphere { radius }, center, &PhysicsQueryFilter::default())
+    });
+    let f = overlap.clone();
+    engine.register_fn("overlap_sphere", move |center: Vec3, radius: f32, filter: PhysicsQueryFilter| {
+        f(QueryShape::Sphere { radius }, center, &filter)
+    });
+    let f = overlap.clone();
+    engine.register_fn("overlap_box", move |center: Vec3, half_extents: Vec3| {
+        f(QueryShape::Box { half_extents }, center, &PhysicsQueryFilter::default())
+    });
+    engine.register_fn("overlap_box", move |center: Vec3, half_extents: Vec3, filter: PhysicsQueryFilter| {
+        overlap(QueryShape::Box { half_extents }, center, &filter)
+    });
+}
+
+/// 注册施加力的函数，例如 `apply_impulse(entity, vec3(0.0, 5.0, 0.0))`
+///
+/// 力与力矩在本帧的物理步中持续生效，冲量只生效一次；只影响动态刚体。
+fn register_body_forces(engine: &mut Engine, forces: &QueuedForces) {
+    let queue = |forces: &QueuedForces, f: fn(Vec3) -> BodyForce| {
+        let forces = forces.clone();
+        move |entity: Entity, v: Vec3| forces.lock().unwrap().push((entity, f(v)))
+    };
+    engine.register_fn("apply_force", queue(forces, BodyForce::Force));
+    engine.register_fn("apply_torque", queue(forces, BodyForce::Torque));
+    engine.register_fn("apply_impulse", queue(forces, BodyForce::Impulse));
+    engine.register_fn("apply_torque_impulse", queue(forces, BodyForce::TorqueImpulse));
+
+    let f = forces.clone();
+    engine.register_fn("apply_force_at_point", move |entity: Entity, force: Vec3, point: Vec3| {
+        f.lock().unwrap().push((entity, BodyForce::ForceAtPoint { force, point }));
+    });
+    let f = forces.clone();
+    engine.register_fn("apply_impulse_at_point", move |entity: Entity, impulse: Vec3, point: Vec3| {
+        f.lock().unwrap().push((entity, BodyForce::ImpulseAtPoint { impulse, point }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_state_persists_between_frames() {
+        let mut scene = Scene::new("test");
+        let mut physics = PhysicsManager::new();
+        let mut manager = ScriptManager::new();
+        let time = Time { elapsed: 0.0, delta: 0.1 };
+        let code = "let frames = 0.0;\nfn init() { transform.position.y = 10.0; }\nfn update(dt) { frames += 1.0; transform.position.x = frames; }";
+        let script = Script { code: code.to_string(), active: true, ..Default::default() };
+        let entity = scene.world.spawn((Transform::default(), script.clone())).id();
+        let other = scene.world.spawn((Transform::default(), script)).id();
+
+        for _ in 0..3 {
+            manager.update_scripts(&mut scene, &mut physics, &InputState::default(), &time);
+        }
+        assert_eq!(scene.world.get::<Script>(entity).unwrap().last_error, None);
+        assert_eq!(scene.world.get::<Transform>(entity).unwrap().position, Vec3::new(3.0, 10.0, 0.0));
+        // 相同代码共享一个 AST，但各自保留变量
+        assert_eq!((manager.asts.len(), manager.instances.len()), (1, 2));
+
+        // 修改代码后重新初始化
+        scene.world.get_mut::<Script>(entity).unwrap().code = code.replace("frames += 1.0", "frames += 2.0");
+        manager.update_scripts(&mut scene, &mut physics, &InputState::default(), &time);
+        assert_eq!(scene.world.get::<Transform>(entity).unwrap().position.x, 2.0);
+        assert_eq!(scene.world.get::<Transform>(other).unwrap().position.x, 4.0);
+        assert_eq!(manager.asts.len(), 2);
+
+        scene.world.despawn(other);
+        manager.update_scripts(&mut scene, &mut physics, &InputState::default(), &time);
+        assert_eq!((manager.asts.len(), manager.instances.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_script_files_hot_reload_keeps_state() {
+        let dir = std::env::temp_dir().join(format!("alander_scripts_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(LIBRARY_DIR)).unwrap();
+        std::fs::write(dir.join(LIBRARY_DIR).join("util.rhai"), "fn double(x) { x * 2.0 }").unwrap();
+        std::fs::write(dir.join("offsets.rhai"), "fn offset() { 100.0 }").unwrap();
+        std::fs::write(dir.join("mover.rhai"), "param speed = 1.0;\nlet frames = 0.0;\nfn update(dt) { frames += 1.0; transform.position.x = util::double(speed) * frames; }").unwrap();
+
+        let mut scene = Scene::new("test");
+        let mut physics = PhysicsManager::new();
+        let mut manager = ScriptManager::with_scripts_dir(&dir);
+        let time = Time { elapsed: 0.0, delta: 0.0 };
+        let entity = scene.world.spawn((Transform::default(), Script::from_file("mover.rhai"))).id();
+        let mut update = |manager: &mut ScriptManager, scene: &mut Scene| {
+            manager.update_scripts(scene, &mut physics, &InputState::default(), &time);
+            let script = scene.world.get::<Script>(entity).unwrap();
+            assert_eq!(script.last_error, None);
+            (scene.world.get::<Transform>(entity).unwrap().position.x, script.parameters.clone())
+        };
+
+        for _ in 0..2 {
+            update(&mut manager, &mut scene);
+        }
+        let (x, parameters) = update(&mut manager, &mut scene);
+        assert_eq!(x, 6.0);
+        assert_eq!(parameters, vec![ScriptParameter { name: "speed".to_string(), value: ScriptValue::Float(1.0) }]);
+
+        // 检查器修改的参数值在下一帧生效
+        scene.world.get_mut::<Script>(entity).unwrap().parameters[0].value = ScriptValue::Float(2.0);
+        assert_eq!(update(&mut manager, &mut scene).0, 16.0);
+
+        // 修改文件：frames 保留，新增的变量与导入的模块可用，不再声明的参数被移除
+        std::fs::write(dir.join("mover.rhai"), "import \"offsets\" as o;\nlet offset = o::offset();\nfn update(dt) { frames += 1.0; transform.position.x = frames + offset; }").unwrap();
+        manager.reload_scripts();
+        let (x, parameters) = update(&mut manager, &mut scene);
+        assert_eq!(x, 105.0);
+        assert!(parameters.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_script_limits_and_error_reporting() {
+        use crate::script_console::ConsoleLevel;
+
+        let mut scene = Scene::new("test");
+        let mut physics = PhysicsManager::new();
+        let mut manager = ScriptManager::new();
+        manager.set_limits(ScriptLimits { max_operations: 10_000, ..Default::default() });
+        let time = Time { elapsed: 0.0, delta: 0.1 };
+        let inline = |code: &str| (Transform::default(), Script { code: code.to_string(), active: true, ..Default::default() });
+        let looping = scene.world.spawn(inline("print(\"start\");\nloop { }")).id();
+        let failing = scene.world.spawn(inline("fn update(dt) {\n    let x = 1;\n    x.missing();\n}")).id();
+        let printing = scene.world.spawn(inline("debug(\"tick\");")).id();
+
+        for _ in 0..2 {
+            manager.update_scripts(&mut scene, &mut physics, &InputState::default(), &time);
+        }
+        let error = |scene: &Scene, entity| scene.world.get::<Script>(entity).unwrap().last_error.clone().unwrap();
+        assert_eq!(error(&scene, looping).kind, ScriptErrorKind::Limit);
+        let failure = error(&scene, failing);
+        assert_eq!((failure.kind, failure.hook.as_deref(), failure.line, failure.column), (ScriptErrorKind::Runtime, Some("update"), Some(3), Some(7)));
+        assert!(!scene.world.get::<Script>(failing).unwrap().active);
+
+        let console = manager.console().lock().unwrap();
+        let entries: Vec<_> = console.entries().map(|e| (e.level, e.entity, e.count)).collect();
+        assert_eq!(entries, vec![
+            (ConsoleLevel::Print, Some(looping), 1),
+            (ConsoleLevel::Error, Some(looping), 1),
+            (ConsoleLevel::Error, Some(failing), 1),
+            (ConsoleLevel::Debug, Some(printing), 2),
+        ]);
+        let excerpt = &console.entries().nth(2).unwrap().excerpt;
+        assert_eq!(excerpt.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        drop(console);
+
+        // 时间预算：不限制操作次数时由执行时间终止
+        manager.set_limits(ScriptLimits { max_operations: 0, time_budget_ms: 10, ..Default::default() });
+        let mut script = scene.world.get_mut::<Script>(looping).unwrap();
+        script.last_error = None;
+        script.active = true;
+        manager.update_scripts(&mut scene, &mut physics, &InputState::default(), &time);
+        assert_eq!(error(&scene, looping).message, "执行时间超过 10 ms");
+    }
+
+    #[test]
+    fn test_script_api_defers_changes_to_other_entities() {
+        use alander_core::scene::{Name, PointLight};
+
+        let mut scene = Scene::new("test");
+        let mut physics = PhysicsManager::new();
+        let mut manager = ScriptManager::new();
+        let lamp = scene.world.spawn((Name("Lamp".to_string()), PointLight::default())).id();
+        let code = r#"
+            let lamp = find_entity("Lamp");
+            let light = get_point_light(lamp);
+            light.intensity += 1.0;
+            set_point_light(lamp, light);
+            if key_pressed("Space") { spawn_entity("Bullet", vec3(0.0, 1.0, 0.0)); }
+        "#;
+        scene.world.spawn((Transform::default(), Script { code: code.to_string(), active: true, ..Default::default() }));
+
+        let mut input = InputState::default();
+        input.keyboard.insert(winit::event::VirtualKeyCode::Space, winit::event::ElementState::Pressed);
+        let time = Time { elapsed: 0.0, delta: 0.1 };
+        for _ in 0..2 {
+            manager.update_scripts(&mut scene, &mut physics, &input, &time);
+        }
+        assert_eq!(scene.world.query::<&Script>().single(&scene.world).last_error, None);
+        // 两次执行都读取到修改前的值
+        assert_eq!(scene.world.get::<PointLight>(lamp).unwrap().intensity, 1.0);
+
+        let commands = std::mem::take(&mut *manager.commands.lock().unwrap());
+        assert_eq!(commands.len(), 4);
+        for command in commands {
+            command.apply(&mut scene.world);
         }
+        assert_eq!(scene.world.get::<PointLight>(lamp).unwrap().intensity, 2.0);
+        let bullets: Vec<Vec3> = scene.world.query::<(&Name, &Transform)>()
+            .iter(&scene.world)
+            .filter(|(name, _)| name.0 == "Bullet")
+            .map(|(_, t)| t.position)
+            .collect();
+        assert_eq!(bullets, vec![Vec3::Y, Vec3::Y]);
     }
 }
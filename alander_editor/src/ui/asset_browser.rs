@@ -49,6 +49,9 @@ pub fn show_asset_browser(
             "json" => {
                 ui.label("📝 场景/数据文件");
             }
+            "rhai" => {
+                ui.label("📜 脚本文件 (位于 scripts/ 下时可在脚本组件中引用)");
+            }
             _ => {
                 ui.label("❓ 未知类型");
             }
@@ -0,0 +1,123 @@
+use egui;
+use alander_core::scene::{Name, Script};
+use crate::app::EditorState;
+use crate::scene_manager::Scene;
+use crate::script_console::{ConsoleEntry, ConsoleLevel, ScriptConsole};
+
+/// 控制台面板状态
+pub struct ConsoleState {
+    pub show_print: bool,
+    pub show_debug: bool,
+    pub show_errors: bool,
+    /// 展开源码的条目
+    selected: Option<ConsoleEntry>,
+}
+
+impl Default for ConsoleState {
+    fn default() -> Self {
+        Self { show_print: true, show_debug: true, show_errors: true, selected: None }
+    }
+}
+
+/// 渲染脚本控制台：点击条目选中脚本实体，并在代码编辑器中跳转到对应位置
+pub fn show_console(
+    ui: &mut egui::Ui,
+    console: &mut ScriptConsole,
+    mut scene: Option<&mut Scene>,
+    editor_state: &mut EditorState,
+    state: &mut ConsoleState,
+) {
+    ui.horizontal(|ui| {
+        ui.heading("控制台");
+        ui.separator();
+        ui.checkbox(&mut state.show_print, "输出");
+        ui.checkbox(&mut state.show_debug, "调试");
+        ui.checkbox(&mut state.show_errors, format!("错误 ({})", console.error_count()));
+        if ui.button("🗑 清空").clicked() {
+            console.clear();
+            state.selected = None;
+        }
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for entry in console.entries() {
+                let (visible, icon, color) = match entry.level {
+                    ConsoleLevel::Print => (state.show_print, "💬", ui.visuals().text_color()),
+                    ConsoleLevel::Debug => (state.show_debug, "🐞", egui::Color32::GRAY),
+                    ConsoleLevel::Error => (state.show_errors, "❌", egui::Color32::RED),
+                };
+                if !visible {
+                    continue;
+                }
+
+                let source = match (&entry.path, entry.entity) {
+                    (Some(path), _) => path.clone(),
+                    (None, Some(entity)) => scene.as_ref()
+                        .and_then(|scene| scene.world.get::<Name>(entity))
+                        .map(|name| name.0.clone())
+                        .unwrap_or_else(|| format!("{:?}", entity)),
+                    (None, None) => String::new(),
+                };
+                let mut text = format!("{} [{}] {}", icon, source, entry.message);
+                if entry.level != ConsoleLevel::Error {
+                    if let Some(line) = entry.line {
+                        text.push_str(&format!(" (行 {})", line));
+                    }
+                }
+                if entry.count > 1 {
+                    text.push_str(&format!(" ×{}", entry.count));
+                }
+
+                let selected = state.selected.as_ref().is_some_and(|s| s.same_as(entry));
+                ui.horizontal(|ui| {
+                    let response = ui.selectable_label(selected, egui::RichText::new(text).monospace().color(color));
+                    if response.clicked() {
+                        state.selected = (!selected).then(|| entry.clone());
+                        if let Some(entity) = entry.entity.filter(|e| scene.as_ref().is_some_and(|s| s.world.get_entity(*e).is_some())) {
+                            editor_state.selected_entity = Some(entity);
+                            editor_state.script_goto = entry.line.map(|line| (entity, line, entry.column.unwrap_or(1)));
+                        }
+                    }
+
+                    // 出错停用的脚本可以直接在控制台重新启用
+                    let disabled_script = entry.entity
+                        .filter(|_| entry.level == ConsoleLevel::Error)
+                        .and_then(|entity| scene.as_deref_mut()?.world.get_mut::<Script>(entity))
+                        .filter(|script| !script.active && script.last_error.is_some());
+                    if let Some(mut script) = disabled_script {
+                        if ui.small_button("▶ 重新启用").clicked() {
+                            script.last_error = None;
+                            script.active = true;
+                        }
+                    }
+                });
+
+                if selected && !entry.excerpt.is_empty() {
+                    show_excerpt(ui, entry);
+                }
+            }
+        });
+}
+
+/// 出错位置附近的源码，出错行高亮
+fn show_excerpt(ui: &mut egui::Ui, entry: &ConsoleEntry) {
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        for (line, text) in &entry.excerpt {
+            let mut row = egui::RichText::new(format!("{:>4} │ {}", line, text)).monospace();
+            if Some(*line) == entry.line {
+                row = row.color(egui::Color32::RED).strong();
+            }
+            ui.label(row);
+            if Some(*line) == entry.line {
+                if let Some(column) = entry.column {
+                    // 列指示符与 "行号 │ " 前缀对齐
+                    ui.label(egui::RichText::new(format!("{}^", " ".repeat(column + 6))).monospace().color(egui::Color32::RED));
+                }
+            }
+        }
+    });
+}
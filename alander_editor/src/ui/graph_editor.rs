@@ -0,0 +1,462 @@
+use egui;
+use super::timeline::{self, KeyRef, KeyTrack, TimelineState};
+use alander_core::curve::{CurveValue, TangentMode};
+use alander_core::property::PropertyTrack;
+use alander_core::scene::{AnimationClip, AnimationPlayer, AnimationTrack, TransformTrack};
+use glam::{EulerRot, Quat, Vec3, Vec4};
+
+/// 曲线编辑器中可拖动的对象
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CurveHandle {
+    /// 关键帧本身 (关键帧索引, 分量)
+    Key(usize, usize),
+    /// 入切线控制柄
+    InTangent(usize, usize),
+    /// 出切线控制柄
+    OutTangent(usize, usize),
+}
+
+/// 正在拖动曲线对象的状态
+struct CurveDrag {
+    /// 拖动开始前的剪辑快照 (用于撤销)
+    original_clips: Vec<AnimationClip>,
+    handle: CurveHandle,
+    /// 拖动起点的指针位置
+    start: egui::Pos2,
+    /// 拖动期间冻结的数值范围，避免视图随编辑跳动
+    value_range: (f32, f32),
+}
+
+/// 曲线编辑器的持久化状态
+#[derive(Default)]
+pub struct GraphEditorState {
+    /// 当前显示的曲线 (通道索引, 轨道)
+    pub curve: Option<(usize, KeyTrack)>,
+    /// 分量可见性
+    pub hidden: [bool; 4],
+    drag: Option<CurveDrag>,
+}
+
+const COMPONENT_COLORS: [egui::Color32; 4] = [
+    egui::Color32::from_rgb(230, 80, 80),
+    egui::Color32::from_rgb(80, 200, 80),
+    egui::Color32::from_rgb(90, 140, 240),
+    egui::Color32::from_rgb(200, 200, 200),
+];
+const KEY_RADIUS: f32 = 4.0;
+const HANDLE_RADIUS: f32 = 3.0;
+const MARGIN: f32 = 12.0;
+
+/// 曲线编辑器显示的轨道
+///
+/// 旋转按 XYZ 欧拉角 (度) 显示，布尔值显示为 0/1 阶跃。
+enum CurveRef<'a> {
+    Float(&'a AnimationTrack<f32>),
+    Vec3(&'a AnimationTrack<Vec3>),
+    Color(&'a AnimationTrack<Vec4>),
+    Bool(&'a AnimationTrack<bool>),
+    Rotation(&'a AnimationTrack<Quat>),
+}
+
+/// 按分量展开的关键帧
+struct CurveKey {
+    time: f32,
+    value: [f32; 4],
+    /// (入切线, 出切线)；旋转与布尔轨道不使用切线
+    tangents: Option<([f32; 4], [f32; 4])>,
+    mode: TangentMode,
+}
+
+impl<'a> CurveRef<'a> {
+    fn new(clip: &'a AnimationClip, channel: usize, track: KeyTrack) -> Option<Self> {
+        match track {
+            KeyTrack::Transform(TransformTrack::Rotation) => clip.channels.get(channel)?.rotation_track.as_ref().map(CurveRef::Rotation),
+            KeyTrack::Transform(track) => clip.channels.get(channel)?.vec3_track(track).map(CurveRef::Vec3),
+            KeyTrack::Property => Some(match &clip.property_channels.get(channel)?.track {
+                PropertyTrack::Float(t) => CurveRef::Float(t),
+                PropertyTrack::Vec3(t) => CurveRef::Vec3(t),
+                PropertyTrack::Color(t) => CurveRef::Color(t),
+                PropertyTrack::Bool(t) => CurveRef::Bool(t),
+            }),
+        }
+    }
+
+    fn component_names(&self) -> &'static [&'static str] {
+        match self {
+            CurveRef::Float(_) | CurveRef::Bool(_) => &["值"],
+            CurveRef::Vec3(_) | CurveRef::Rotation(_) => &["X", "Y", "Z"],
+            CurveRef::Color(_) => &["R", "G", "B", "A"],
+        }
+    }
+
+    /// 使用运行时的采样函数，面板上看到的形状与播放结果一致
+    fn sample(&self, time: f32) -> Option<[f32; 4]> {
+        match self {
+            CurveRef::Float(t) => t.sample(time).map(components),
+            CurveRef::Vec3(t) => t.sample(time).map(components),
+            CurveRef::Color(t) => t.sample(time).map(components),
+            CurveRef::Bool(t) => t.sample_step(time).map(bool_components),
+            CurveRef::Rotation(t) => t.sample_rotation(time).map(euler_components),
+        }
+    }
+
+    fn keys(&self) -> Vec<CurveKey> {
+        match self {
+            CurveRef::Float(t) => curve_keys(t),
+            CurveRef::Vec3(t) => curve_keys(t),
+            CurveRef::Color(t) => curve_keys(t),
+            CurveRef::Bool(t) => t.keyframes.iter()
+                .map(|k| CurveKey { time: k.time, value: bool_components(k.value), tangents: None, mode: TangentMode::Constant })
+                .collect(),
+            CurveRef::Rotation(t) => t.keyframes.iter()
+                .map(|k| CurveKey { time: k.time, value: euler_components(k.value), tangents: None, mode: k.tangent_mode })
+                .collect(),
+        }
+    }
+}
+
+/// 可编辑的轨道，分量含义与 [`CurveRef`] 相同
+enum CurveMut<'a> {
+    Float(&'a mut AnimationTrack<f32>),
+    Vec3(&'a mut AnimationTrack<Vec3>),
+    Color(&'a mut AnimationTrack<Vec4>),
+    Bool(&'a mut AnimationTrack<bool>),
+    Rotation(&'a mut AnimationTrack<Quat>),
+}
+
+impl<'a> CurveMut<'a> {
+    fn new(clip: &'a mut AnimationClip, channel: usize, track: KeyTrack) -> Option<Self> {
+        match track {
+            KeyTrack::Transform(TransformTrack::Rotation) => clip.channels.get_mut(channel)?.rotation_track.as_mut().map(CurveMut::Rotation),
+            KeyTrack::Transform(track) => clip.channels.get_mut(channel)?.vec3_track_mut(track).map(CurveMut::Vec3),
+            KeyTrack::Property => Some(match &mut clip.property_channels.get_mut(channel)?.track {
+                PropertyTrack::Float(t) => CurveMut::Float(t),
+                PropertyTrack::Vec3(t) => CurveMut::Vec3(t),
+                PropertyTrack::Color(t) => CurveMut::Color(t),
+                PropertyTrack::Bool(t) => CurveMut::Bool(t),
+            }),
+        }
+    }
+
+    /// 修改关键帧的时间与一个分量的值
+    fn move_key(self, i: usize, time: f32, c: usize, value: f32) {
+        match self {
+            CurveMut::Float(t) => move_component(t, i, time, c, value),
+            CurveMut::Vec3(t) => move_component(t, i, time, c, value),
+            CurveMut::Color(t) => move_component(t, i, time, c, value),
+            CurveMut::Bool(t) => if let Some(key) = t.keyframes.get_mut(i) {
+                key.time = time.max(0.0);
+                key.value = value >= 0.5;
+            },
+            CurveMut::Rotation(t) => if let Some(key) = t.keyframes.get_mut(i) {
+                let mut euler = euler_components(key.value);
+                euler[c] = value;
+                key.time = time.max(0.0);
+                key.value = Quat::from_euler(EulerRot::XYZ, euler[0].to_radians(), euler[1].to_radians(), euler[2].to_radians());
+            },
+        }
+    }
+
+    fn set_component_tangent(self, i: usize, c: usize, in_slope: Option<f32>, out_slope: Option<f32>) {
+        match self {
+            CurveMut::Float(t) => t.set_component_tangent(i, c, in_slope, out_slope),
+            CurveMut::Vec3(t) => t.set_component_tangent(i, c, in_slope, out_slope),
+            CurveMut::Color(t) => t.set_component_tangent(i, c, in_slope, out_slope),
+            CurveMut::Bool(_) | CurveMut::Rotation(_) => {}
+        }
+    }
+}
+
+fn components<T: CurveValue>(value: T) -> [f32; 4] {
+    let mut out = [0.0; 4];
+    for (c, v) in out.iter_mut().enumerate().take(T::COMPONENTS) {
+        *v = value.component(c);
+    }
+    out
+}
+
+fn bool_components(value: bool) -> [f32; 4] {
+    [if value { 1.0 } else { 0.0 }, 0.0, 0.0, 0.0]
+}
+
+fn euler_components(value: Quat) -> [f32; 4] {
+    let (x, y, z) = value.to_euler(EulerRot::XYZ);
+    [x.to_degrees(), y.to_degrees(), z.to_degrees(), 0.0]
+}
+
+fn curve_keys<T: CurveValue>(track: &AnimationTrack<T>) -> Vec<CurveKey> {
+    track.keyframes.iter().enumerate()
+        .map(|(i, k)| {
+            let (t_in, t_out) = track.tangents(i);
+            CurveKey { time: k.time, value: components(k.value), tangents: Some((components(t_in), components(t_out))), mode: k.tangent_mode }
+        })
+        .collect()
+}
+
+fn move_component<T: CurveValue>(track: &mut AnimationTrack<T>, i: usize, time: f32, c: usize, value: f32) {
+    if let Some(key) = track.keyframes.get_mut(i) {
+        key.time = time.max(0.0);
+        key.value.set_component(c, value);
+    }
+}
+
+/// 绘制曲线编辑器
+///
+/// 曲线直接通过运行时的采样函数绘制，因此面板上看到的形状与播放结果一致。
+pub fn show_graph_editor(
+    ui: &mut egui::Ui,
+    player: &mut AnimationPlayer,
+    clip_idx: usize,
+    timeline: &mut TimelineState,
+) -> Option<(Vec<AnimationClip>, Vec<AnimationClip>, &'static str)> {
+    let state = &mut timeline.graph;
+    let rows = timeline::curve_rows(&player.clips[clip_idx]);
+    if rows.is_empty() {
+        ui.label("该剪辑没有动画通道");
+        return None;
+    }
+    let (channel_idx, track_kind) = match state.curve.filter(|c| rows.iter().any(|(ci, t, _)| (*ci, *t) == *c)) {
+        Some(curve) => curve,
+        None => {
+            let (ci, t, _) = &rows[0];
+            state.curve = Some((*ci, *t));
+            (*ci, *t)
+        }
+    };
+    let names = CurveRef::new(&player.clips[clip_idx], channel_idx, track_kind)?.component_names();
+
+    // 曲线与分量选择
+    ui.horizontal(|ui| {
+        let selected = rows.iter().find(|(ci, t, _)| (*ci, *t) == (channel_idx, track_kind)).map(|(_, _, label)| label.clone());
+        egui::ComboBox::from_id_source("graph_curve")
+            .selected_text(selected.unwrap_or_default())
+            .show_ui(ui, |ui| {
+                for (ci, t, label) in &rows {
+                    ui.selectable_value(&mut state.curve, Some((*ci, *t)), label);
+                }
+            });
+        ui.separator();
+        for (c, name) in names.iter().enumerate() {
+            let mut visible = !state.hidden[c];
+            if ui.checkbox(&mut visible, egui::RichText::new(*name).color(COMPONENT_COLORS[c])).changed() {
+                state.hidden[c] = !visible;
+            }
+        }
+        if track_kind == KeyTrack::Transform(TransformTrack::Rotation) {
+            ui.label(egui::RichText::new("欧拉角 (度)").weak());
+        }
+    });
+    let visible: Vec<usize> = (0..names.len()).filter(|c| !state.hidden[*c]).collect();
+
+    let duration = player.clips[clip_idx].duration.max(1.0);
+    let size = egui::vec2(ui.available_width(), ui.available_height().max(160.0));
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
+    let rect = response.rect;
+    let plot = rect.shrink(MARGIN);
+    let visuals = ui.visuals().clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+
+    let curve = CurveRef::new(&player.clips[clip_idx], channel_idx, track_kind)?;
+
+    // 数值范围：拖动时冻结，否则自动适配曲线
+    let (v_min, v_max) = match &state.drag {
+        Some(drag) => drag.value_range,
+        None => {
+            let mut lo = f32::INFINITY;
+            let mut hi = f32::NEG_INFINITY;
+            let samples = plot.width().max(2.0) as usize / 2;
+            for s in 0..=samples {
+                if let Some(v) = curve.sample(duration * s as f32 / samples as f32) {
+                    for &c in &visible {
+                        lo = lo.min(v[c]);
+                        hi = hi.max(v[c]);
+                    }
+                }
+            }
+            if !lo.is_finite() {
+                (lo, hi) = (-1.0, 1.0);
+            }
+            let pad = ((hi - lo) * 0.1).max(0.1);
+            (lo - pad, hi + pad)
+        }
+    };
+
+    let time_to_x = |t: f32| plot.left() + t / duration * plot.width();
+    let x_to_time = |x: f32| ((x - plot.left()) / plot.width() * duration).max(0.0);
+    let value_to_y = |v: f32| plot.bottom() - (v - v_min) / (v_max - v_min) * plot.height();
+    let y_to_value = |y: f32| v_min + (plot.bottom() - y) / plot.height() * (v_max - v_min);
+    // 屏幕空间中的单位换算 (值/秒 与 像素斜率)
+    let seconds_per_px = duration / plot.width();
+    let value_per_px = (v_max - v_min) / plot.height();
+
+    // 零线与时间刻度
+    let grid = visuals.widgets.noninteractive.bg_stroke.color;
+    if (v_min..=v_max).contains(&0.0) {
+        painter.line_segment([egui::pos2(plot.left(), value_to_y(0.0)), egui::pos2(plot.right(), value_to_y(0.0))], (1.0, grid));
+    }
+    let mut t = 0.0;
+    while t <= duration + f32::EPSILON {
+        let x = time_to_x(t);
+        painter.line_segment([egui::pos2(x, plot.top()), egui::pos2(x, plot.bottom())], (0.5, grid));
+        painter.text(egui::pos2(x + 2.0, rect.top()), egui::Align2::LEFT_TOP, format!("{:.1}", t), egui::FontId::monospace(10.0), visuals.text_color());
+        t += if duration > 5.0 { 1.0 } else { 0.5 };
+    }
+    painter.text(plot.left_top(), egui::Align2::LEFT_TOP, format!("{:.2}", v_max), egui::FontId::monospace(10.0), visuals.weak_text_color());
+    painter.text(plot.left_bottom(), egui::Align2::LEFT_BOTTOM, format!("{:.2}", v_min), egui::FontId::monospace(10.0), visuals.weak_text_color());
+
+    // 曲线
+    let columns = plot.width().max(2.0) as usize / 2;
+    let samples: Vec<(f32, [f32; 4])> = (0..=columns)
+        .filter_map(|s| {
+            let t = duration * s as f32 / columns as f32;
+            curve.sample(t).map(|v| (t, v))
+        })
+        .collect();
+    for &c in &visible {
+        let points: Vec<egui::Pos2> = samples.iter().map(|(t, v)| egui::pos2(time_to_x(*t), value_to_y(v[c]))).collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, COMPONENT_COLORS[c])));
+    }
+
+    // 关键帧与切线控制柄
+    let keys = curve.keys();
+    let mut hit_targets: Vec<(CurveHandle, egui::Pos2)> = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let selected = timeline.selected_keys.contains(&KeyRef { channel: channel_idx, track: track_kind, key: i });
+        for &c in &visible {
+            let center = egui::pos2(time_to_x(key.time), value_to_y(key.value[c]));
+            if let Some((t_in, t_out)) = key.tangents.filter(|_| selected && key.mode != TangentMode::Constant) {
+                // Bezier 控制点位于相邻段时长的 1/3 处
+                if i > 0 {
+                    let h = (key.time - keys[i - 1].time) / 3.0;
+                    let p = egui::pos2(time_to_x(key.time - h), value_to_y(key.value[c] - t_in[c] * h));
+                    painter.line_segment([center, p], (1.0, visuals.weak_text_color()));
+                    painter.circle_filled(p, HANDLE_RADIUS, COMPONENT_COLORS[c]);
+                    hit_targets.push((CurveHandle::InTangent(i, c), p));
+                }
+                if i + 1 < keys.len() {
+                    let h = (keys[i + 1].time - key.time) / 3.0;
+                    let p = egui::pos2(time_to_x(key.time + h), value_to_y(key.value[c] + t_out[c] * h));
+                    painter.line_segment([center, p], (1.0, visuals.weak_text_color()));
+                    painter.circle_filled(p, HANDLE_RADIUS, COMPONENT_COLORS[c]);
+                    hit_targets.push((CurveHandle::OutTangent(i, c), p));
+                }
+            }
+            let fill = if selected { egui::Color32::YELLOW } else { egui::Color32::LIGHT_GRAY };
+            painter.circle(center, KEY_RADIUS, fill, egui::Stroke::new(1.0, COMPONENT_COLORS[c]));
+            hit_targets.push((CurveHandle::Key(i, c), center));
+        }
+    }
+
+    // 播放头
+    let head_x = time_to_x(player.current_time);
+    painter.line_segment([egui::pos2(head_x, rect.top()), egui::pos2(head_x, rect.bottom())], (2.0, egui::Color32::RED));
+
+    let pointer = response.interact_pointer_pos().or(response.hover_pos());
+    // 倒序查找，优先命中最后绘制的对象
+    let hit = pointer.and_then(|p| {
+        hit_targets
+            .iter()
+            .rev()
+            .find(|(_, pos)| pos.distance(p) <= KEY_RADIUS + 2.0)
+            .map(|(h, _)| *h)
+    });
+    let shift = ui.input(|i| i.modifiers.shift);
+
+    let mut edit = None;
+    if response.drag_started() {
+        if let (Some(handle), Some(p)) = (hit, pointer) {
+            if let CurveHandle::Key(i, _) = handle {
+                let key = KeyRef { channel: channel_idx, track: track_kind, key: i };
+                if !timeline.selected_keys.contains(&key) {
+                    if !shift { timeline.selected_keys.clear(); }
+                    timeline.selected_keys.insert(key);
+                }
+            }
+            state.drag = Some(CurveDrag { original_clips: player.clips.clone(), handle, start: p, value_range: (v_min, v_max) });
+        }
+    }
+
+    if let (Some(drag), Some(p)) = (&state.drag, pointer) {
+        // 基于拖动前快照计算，避免误差累积
+        let original = CurveRef::new(&drag.original_clips[clip_idx], channel_idx, track_kind).map(|c| c.keys()).unwrap_or_default();
+        let mut clip = drag.original_clips[clip_idx].clone();
+        if let Some(track) = CurveMut::new(&mut clip, channel_idx, track_kind) {
+            match drag.handle {
+                CurveHandle::Key(i, c) => {
+                    let key = &original[i];
+                    let time = key.time + (p.x - drag.start.x) * seconds_per_px;
+                    track.move_key(i, time, c, key.value[c] + (drag.start.y - p.y) * value_per_px);
+                }
+                CurveHandle::InTangent(i, c) => {
+                    let key = &original[i];
+                    // 入切线控制柄必须位于关键帧左侧
+                    let dt = (x_to_time(p.x) - key.time).min(-1e-3);
+                    let slope = (y_to_value(p.y) - key.value[c]) / dt;
+                    let out = (!shift).then_some(slope);
+                    track.set_component_tangent(i, c, Some(slope), out);
+                }
+                CurveHandle::OutTangent(i, c) => {
+                    let key = &original[i];
+                    let dt = (x_to_time(p.x) - key.time).max(1e-3);
+                    let slope = (y_to_value(p.y) - key.value[c]) / dt;
+                    let inp = (!shift).then_some(slope);
+                    track.set_component_tangent(i, c, inp, Some(slope));
+                }
+            }
+        }
+        player.clips[clip_idx] = clip;
+    }
+
+    if state.drag.is_some() {
+        if response.drag_released() {
+            let drag = state.drag.take().unwrap();
+            let mut new = player.clips.clone();
+            if let CurveHandle::Key(i, _) = drag.handle {
+                let moved = std::collections::HashSet::from([KeyRef { channel: channel_idx, track: track_kind, key: i }]);
+                timeline::merge_moved_keys(&mut new[clip_idx], &moved);
+            }
+            timeline::sort_keys(&mut new[clip_idx]);
+            new[clip_idx].update_duration();
+            if matches!(drag.handle, CurveHandle::Key(..)) {
+                timeline.selected_keys.clear();
+            }
+            edit = Some((drag.original_clips, new, "编辑曲线"));
+        }
+    } else if response.clicked() {
+        match hit {
+            Some(CurveHandle::Key(i, _)) => {
+                let key = KeyRef { channel: channel_idx, track: track_kind, key: i };
+                if !shift { timeline.selected_keys.clear(); }
+                if !timeline.selected_keys.remove(&key) || !shift {
+                    timeline.selected_keys.insert(key);
+                }
+            }
+            Some(_) => {}
+            None => {
+                timeline.selected_keys.clear();
+                if let Some(p) = pointer {
+                    player.current_time = x_to_time(p.x);
+                }
+            }
+        }
+    }
+
+    if let Some(p) = pointer.filter(|_| response.hovered()) {
+        response.on_hover_text_at_pointer(format!("{:.2}s, {:.3}", x_to_time(p.x), y_to_value(p.y)));
+    }
+    edit
+}
+
+/// 为选中的关键帧设置切线模式
+pub fn set_tangent_mode(clip: &mut AnimationClip, keys: &std::collections::HashSet<KeyRef>, mode: TangentMode) {
+    for key in keys {
+        match key.track {
+            KeyTrack::Transform(track) => if let Some(channel) = clip.channels.get_mut(key.channel) {
+                channel.set_tangent_mode(track, &[key.key], mode);
+            },
+            KeyTrack::Property => if let Some(channel) = clip.property_channels.get_mut(key.channel) {
+                channel.track.set_tangent_mode(&[key.key], mode);
+            },
+        }
+    }
+}
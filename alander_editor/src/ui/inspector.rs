@@ -1,9 +1,13 @@
 use egui;
 use bevy_ecs::prelude::*;
 use crate::scene_manager::Scene;
-use alander_core::scene::{Name, Transform, PointLight, PBRMaterial, RigidBody, Collider, RigidBodyType, Camera, Projection, AnimationPlayer, Script};
+use alander_core::scene::{Name, Transform, PointLight, PBRMaterial, RigidBody, Collider, RigidBodyType, Camera, Projection, AnimationPlayer, Script, ScriptValue, Skin, SkinningMode, PhysicsJoint, PhysicsJointKind, JointMotor, ColliderShape, Mesh, AssetPath, PhysicsLayers, CharacterController};
+use alander_core::scene::{ForceField, ForceFieldFalloff, ForceFieldKind, ForceFieldVolume};
+use alander_core::collider::{fit_collider, ColliderFit};
+use alander_core::ragdoll::{insert_ragdoll, remove_ragdoll, Ragdoll, RagdollBone, RagdollJointKind, RagdollMode, RagdollSettings};
 use glam::{EulerRot, Vec3, Vec4, Quat};
 use crate::app::EditorState;
+use crate::script_manager::{list_script_files, SCRIPTS_DIR};
 
 /// 渲染属性面板
 pub fn show_inspector(
@@ -29,6 +33,8 @@ pub fn show_inspector(
                 });
             });
 
+            show_physics_layers(ui, &mut editor_state.physics_layers);
+
             ui.vertical_centered(|ui| {
                 ui.label("未选中任何实体");
             });
@@ -174,22 +180,89 @@ pub fn show_inspector(
     let mut script_query = scene.world.query::<&mut Script>();
     if let Ok(mut script) = script_query.get_mut(&mut scene.world, entity) {
         ui.collapsing("脚本 (Script)", |ui| {
-            ui.checkbox(&mut script.active, "激活脚本");
-            
+            if ui.checkbox(&mut script.active, "激活脚本").changed() && script.active {
+                script.last_error = None;
+            }
+
             if let Some(err) = &script.last_error {
-                ui.colored_label(egui::Color32::RED, format!("错误: {}", err));
+                ui.colored_label(egui::Color32::RED, err.to_string());
+                // 出错后脚本已停用：清除错误并从头运行
+                if !script.active && ui.button("▶ 重新启用").clicked() {
+                    script.last_error = None;
+                    script.active = true;
+                }
             }
 
-            ui.label("脚本代码:");
-            let editor = egui::TextEdit::multiline(&mut script.code)
-                .font(egui::TextStyle::Monospace)
-                .code_editor()
-                .desired_width(f32::INFINITY)
-                .lock_focus(true);
-            
-            if ui.add(editor).changed() {
-                // 代码修改时重置运行错误
-                script.last_error = None;
+            ui.horizontal(|ui| {
+                ui.label("来源");
+                if ui.radio(script.path.is_none(), "内联代码").clicked() && script.path.is_some() {
+                    script.path = None;
+                    script.last_error = None;
+                }
+                if ui.radio(script.path.is_some(), "脚本文件").clicked() && script.path.is_none() {
+                    script.path = Some(String::new());
+                    script.last_error = None;
+                }
+            });
+
+            if let Some(path) = script.path.clone() {
+                ui.horizontal(|ui| {
+                    ui.label("文件");
+                    egui::ComboBox::from_id_source("script_file_inspector")
+                        .selected_text(if path.is_empty() { "(未选择)" } else { path.as_str() })
+                        .show_ui(ui, |ui| {
+                            for file in list_script_files(std::path::Path::new(SCRIPTS_DIR)) {
+                                if ui.selectable_label(file == path, &file).clicked() {
+                                    script.path = Some(file);
+                                    script.last_error = None;
+                                }
+                            }
+                        });
+                });
+                ui.weak(format!("脚本目录: {} (修改后自动重新加载)", SCRIPTS_DIR));
+            } else {
+                ui.label("脚本代码:");
+                let editor = egui::TextEdit::multiline(&mut script.code)
+                    .id_source("script_code_editor")
+                    .font(egui::TextStyle::Monospace)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .lock_focus(true);
+
+                let response = ui.add(editor);
+                if response.changed() {
+                    // 代码修改时重置运行错误
+                    script.last_error = None;
+                }
+                if let Some((_, line, column)) = editor_state.script_goto.filter(|(target, ..)| *target == entity) {
+                    move_code_cursor(ui, response.id, &script.code, line, column);
+                }
+            }
+            if editor_state.script_goto.is_some_and(|(target, ..)| target == entity) {
+                editor_state.script_goto = None;
+            }
+
+            // 脚本中 `param` 声明的参数 (脚本运行后出现)
+            if !script.parameters.is_empty() {
+                ui.separator();
+                ui.label("参数:");
+                for parameter in script.parameters.iter_mut() {
+                    match &mut parameter.value {
+                        ScriptValue::Vec3(value) => vec3_row(ui, &parameter.name, value),
+                        value => {
+                            ui.horizontal(|ui| {
+                                ui.label(&parameter.name);
+                                match value {
+                                    ScriptValue::Float(v) => { ui.add(egui::DragValue::new(v).speed(0.1)); }
+                                    ScriptValue::Int(v) => { ui.add(egui::DragValue::new(v)); }
+                                    ScriptValue::Bool(v) => { ui.checkbox(v, ""); }
+                                    ScriptValue::String(v) => { ui.text_edit_singleline(v); }
+                                    ScriptValue::Vec3(_) => {}
+                                }
+                            });
+                        }
+                    }
+                }
             }
         });
     } else {
@@ -203,9 +276,9 @@ pub fn show_inspector(
         }
     }
 
-    // 5. 刚体 (RigidBody) 编辑
-    let mut rb_query = scene.world.query::<&mut RigidBody>();
-    if let Ok(mut rb) = rb_query.get_mut(&mut scene.world, entity) {
+    // 5. 刚体 (RigidBody) 编辑 (编辑副本，只有实际修改时才写回，以免每帧标记组件已修改)
+    if let Some(original) = scene.world.get::<RigidBody>(entity).cloned() {
+        let mut rb = original.clone();
         ui.collapsing("刚体 (RigidBody)", |ui| {
             ui.horizontal(|ui| {
                 ui.label("类型");
@@ -233,24 +306,75 @@ pub fn show_inspector(
                         if ui.selectable_value(&mut rb_type_idx, 3, "运动学 (位置)").clicked() { rb.body_type = RigidBodyType::KinematicPositionBased; }
                     });
             });
-        });
-    }
 
-    // 6. 碰撞体 (Collider) 编辑
-    let mut col_query = scene.world.query::<&mut Collider>();
-    if let Ok(mut col) = col_query.get_mut(&mut scene.world, entity) {
-        ui.collapsing("碰撞体 (Collider)", |ui| {
             ui.horizontal(|ui| {
-                ui.label("摩擦力");
-                ui.add(egui::DragValue::new(&mut col.friction).speed(0.01).clamp_range(0.0..=2.0));
+                let mut override_mass = rb.mass.is_some();
+                if ui.checkbox(&mut override_mass, "质量").changed() {
+                    rb.mass = override_mass.then_some(1.0);
+                }
+                match &mut rb.mass {
+                    Some(mass) => { ui.add(egui::DragValue::new(mass).speed(0.1).clamp_range(0.001..=f32::MAX)); }
+                    None => { ui.weak("由碰撞体密度计算"); }
+                }
             });
+            let mut override_com = rb.center_of_mass.is_some();
+            if ui.checkbox(&mut override_com, "自定义质心").changed() {
+                rb.center_of_mass = override_com.then_some(Vec3::ZERO);
+            }
+            if let Some(com) = &mut rb.center_of_mass {
+                vec3_row(ui, "质心", com);
+            }
+            ui.horizontal(|ui| {
+                ui.label("线性阻尼");
+                ui.add(egui::DragValue::new(&mut rb.linear_damping).speed(0.01).clamp_range(0.0..=100.0));
+                ui.label("角阻尼");
+                ui.add(egui::DragValue::new(&mut rb.angular_damping).speed(0.01).clamp_range(0.0..=100.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("重力缩放");
+                ui.add(egui::DragValue::new(&mut rb.gravity_scale).speed(0.01));
+            });
+            vec3_row(ui, "线速度", &mut rb.linear_velocity);
+            vec3_row(ui, "角速度", &mut rb.angular_velocity);
+
+            let locks = &mut rb.locked_axes;
             ui.horizontal(|ui| {
-                ui.label("弹性");
-                ui.add(egui::DragValue::new(&mut col.restitution).speed(0.01).clamp_range(0.0..=1.0));
+                ui.label("锁定位移");
+                ui.checkbox(&mut locks.translation[0], "X");
+                ui.checkbox(&mut locks.translation[1], "Y");
+                ui.checkbox(&mut locks.translation[2], "Z");
             });
+            ui.horizontal(|ui| {
+                ui.label("锁定旋转");
+                ui.checkbox(&mut locks.rotation[0], "X");
+                ui.checkbox(&mut locks.rotation[1], "Y");
+                ui.checkbox(&mut locks.rotation[2], "Z");
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut rb.can_sleep, "允许睡眠");
+                ui.checkbox(&mut rb.sleeping, "睡眠中");
+            });
+            ui.checkbox(&mut rb.ccd_enabled, "连续碰撞检测 (CCD)");
         });
+        if rb != original {
+            scene.world.entity_mut(entity).insert(rb);
+        }
+    }
+
+    // 6. 碰撞体 (Collider) 编辑
+    show_collider(ui, scene, entity, &editor_state.physics_layers);
+
+    // 6.5 物理关节 (PhysicsJoint) 编辑
+    if scene.world.get::<RigidBody>(entity).is_some() {
+        show_physics_joint(ui, scene, entity);
+    } else {
+        // 6.6 角色控制器 (CharacterController) 编辑，与刚体互斥
+        show_character_controller(ui, scene, entity, &editor_state.physics_layers);
     }
 
+    // 6.7 力场 (ForceField) 编辑
+    show_force_field(ui, scene, entity, &editor_state.physics_layers);
+
     // 7. 相机 (Camera) 编辑
     let mut camera_query = scene.world.query::<&mut Camera>();
     if let Ok(mut camera) = camera_query.get_mut(&mut scene.world, entity) {
@@ -287,6 +411,30 @@ pub fn show_inspector(
         });
     }
 
+    // 7.5 蒙皮 (Skin) 编辑
+    if let Some(mut skin) = scene.world.get_mut::<Skin>(entity) {
+        ui.collapsing("蒙皮 (Skin)", |ui| {
+            ui.label(format!("名称: {}", skin.name));
+            ui.label(format!("关节数: {}", skin.joints.len()));
+            ui.horizontal(|ui| {
+                ui.label("蒙皮方式");
+                egui::ComboBox::from_id_source("skin_mode")
+                    .selected_text(skin.mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in SkinningMode::ALL {
+                            ui.selectable_value(&mut skin.mode, mode, mode.label());
+                        }
+                    });
+            });
+        });
+    }
+    if scene.world.get::<Skin>(entity).is_some() {
+        show_ragdoll(ui, scene, entity, &editor_state.physics_layers);
+    }
+
+    // 7.6 布娃娃骨骼 (RagdollBone) 编辑
+    show_ragdoll_bone(ui, scene, entity);
+
     // 8. 动画播放器 (AnimationPlayer) 编辑
     let current_transform = scene.world.get::<Transform>(entity).cloned();
     
@@ -356,17 +504,17 @@ pub fn show_inspector(
 
                         // 捕捉位置
                         let pos_track = channel.position_track.get_or_insert(alander_core::scene::AnimationTrack::new(Vec::new()));
-                        pos_track.keyframes.push(alander_core::scene::Keyframe { time, value: transform.position });
+                        pos_track.keyframes.push(alander_core::scene::Keyframe::new(time, transform.position));
                         pos_track.keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
                         
                         // 捕捉旋转
                         let rot_track = channel.rotation_track.get_or_insert(alander_core::scene::AnimationTrack::new(Vec::new()));
-                        rot_track.keyframes.push(alander_core::scene::Keyframe { time, value: transform.rotation });
+                        rot_track.keyframes.push(alander_core::scene::Keyframe::new(time, transform.rotation));
                         rot_track.keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
 
                         // 捕捉缩放
                         let sca_track = channel.scale_track.get_or_insert(alander_core::scene::AnimationTrack::new(Vec::new()));
-                        sca_track.keyframes.push(alander_core::scene::Keyframe { time, value: transform.scale });
+                        sca_track.keyframes.push(alander_core::scene::Keyframe::new(time, transform.scale));
                         sca_track.keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
 
                         clip.update_duration();
@@ -380,3 +528,688 @@ pub fn show_inspector(
          }
     }
 }
+
+/// 可直接创建的基本形状 (网格类形状通过自动适配生成)
+fn primitive_shapes() -> [ColliderShape; 7] {
+    [
+        ColliderShape::Ball { radius: 0.5 },
+        ColliderShape::Cuboid { half_extents: Vec3::splat(0.5) },
+        ColliderShape::Capsule { half_height: 0.5, radius: 0.5 },
+        ColliderShape::Cylinder { half_height: 0.5, radius: 0.5 },
+        ColliderShape::Cone { half_height: 0.5, radius: 0.5 },
+        ColliderShape::flat_height_field(16, 16, Vec3::new(10.0, 1.0, 10.0)),
+        ColliderShape::Compound,
+    ]
+}
+
+/// 碰撞体编辑，包括从网格自动适配形状
+fn show_collider(ui: &mut egui::Ui, scene: &mut Scene, entity: Entity, physics_layers: &PhysicsLayers) {
+    let body_type = scene.world.get::<RigidBody>(entity).map(|rb| rb.body_type);
+    let has_mesh = scene.world.get::<Mesh>(entity).is_some() || scene.world.get::<AssetPath>(entity).is_some();
+    let mut fit_request = None;
+
+    let fit_menu = |ui: &mut egui::Ui, fit_request: &mut Option<ColliderFit>| {
+        let exact = ColliderFit::exact_for(body_type.unwrap_or(RigidBodyType::Static));
+        if ui.button(format!("精确 ({})", exact.label())).clicked() {
+            *fit_request = Some(exact);
+            ui.close_menu();
+        }
+        ui.separator();
+        for fit in ColliderFit::ALL {
+            if ui.button(fit.label()).clicked() {
+                *fit_request = Some(fit);
+                ui.close_menu();
+            }
+        }
+    };
+
+    match scene.world.get::<Collider>(entity).cloned() {
+        Some(original) => {
+            let mut col = original.clone();
+            ui.collapsing("碰撞体 (Collider)", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("形状");
+                    egui::ComboBox::from_id_source("collider_shape")
+                        .selected_text(col.shape.label())
+                        .show_ui(ui, |ui| {
+                            for shape in primitive_shapes() {
+                                let selected = std::mem::discriminant(&col.shape) == std::mem::discriminant(&shape);
+                                if ui.selectable_label(selected, shape.label()).clicked() && !selected {
+                                    col.shape = shape;
+                                }
+                            }
+                        });
+                });
+
+                match &mut col.shape {
+                    ColliderShape::Ball { radius } => {
+                        ui.horizontal(|ui| {
+                            ui.label("半径");
+                            ui.add(egui::DragValue::new(radius).speed(0.01).clamp_range(0.001..=1000.0));
+                        });
+                    }
+                    ColliderShape::Cuboid { half_extents } => vec3_row(ui, "半尺寸", half_extents),
+                    ColliderShape::Capsule { half_height, radius }
+                    | ColliderShape::Cylinder { half_height, radius }
+                    | ColliderShape::Cone { half_height, radius } => {
+                        ui.horizontal(|ui| {
+                            ui.label("半高");
+                            ui.add(egui::DragValue::new(half_height).speed(0.01).clamp_range(0.0..=1000.0));
+                            ui.label("半径");
+                            ui.add(egui::DragValue::new(radius).speed(0.01).clamp_range(0.001..=1000.0));
+                        });
+                    }
+                    ColliderShape::ConvexHull { points } => {
+                        ui.label(format!("{} 个点", points.len()));
+                    }
+                    ColliderShape::ConvexDecomposition { vertices, indices } | ColliderShape::TriMesh { vertices, indices } => {
+                        ui.label(format!("{} 个顶点, {} 个三角形", vertices.len(), indices.len()));
+                    }
+                    ColliderShape::HeightField { rows, cols, size, .. } => {
+                        ui.label(format!("{} x {} 采样点", rows, cols));
+                        vec3_row(ui, "尺寸", size);
+                    }
+                    ColliderShape::Compound => {
+                        ui.label("由不带刚体的子实体上的碰撞体组成");
+                    }
+                }
+                if matches!(col.shape, ColliderShape::TriMesh { .. }) && body_type == Some(RigidBodyType::Dynamic) {
+                    ui.colored_label(egui::Color32::YELLOW, "⚠ 动态刚体应使用凸包或凸分解");
+                }
+
+                vec3_row(ui, "偏移", &mut col.offset);
+                ui.checkbox(&mut col.is_sensor, "传感器 (触发区域)");
+                ui.horizontal(|ui| {
+                    ui.label("所属层");
+                    layer_mask_menu(ui, physics_layers, &mut col.layers.memberships);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("碰撞层");
+                    layer_mask_menu(ui, physics_layers, &mut col.layers.filter);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("密度");
+                    ui.add(egui::DragValue::new(&mut col.density).speed(0.01).clamp_range(0.0..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("摩擦力");
+                    ui.add(egui::DragValue::new(&mut col.friction).speed(0.01).clamp_range(0.0..=2.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("弹性");
+                    ui.add(egui::DragValue::new(&mut col.restitution).speed(0.01).clamp_range(0.0..=1.0));
+                });
+                ui.horizontal(|ui| {
+                    let mut report_forces = col.contact_force_threshold.is_some();
+                    if ui.checkbox(&mut report_forces, "接触力事件").changed() {
+                        col.contact_force_threshold = report_forces.then_some(10.0);
+                    }
+                    if let Some(threshold) = &mut col.contact_force_threshold {
+                        ui.label("阈值");
+                        ui.add(egui::DragValue::new(threshold).speed(0.1).clamp_range(0.0..=f32::MAX));
+                    }
+                });
+
+                if has_mesh {
+                    ui.menu_button("从网格自动适配碰撞体", |ui| fit_menu(ui, &mut fit_request));
+                }
+            });
+            if col != original {
+                scene.world.entity_mut(entity).insert(col);
+            }
+        }
+        None if has_mesh || body_type.is_some() => {
+            ui.menu_button("➕ 添加碰撞体", |ui| {
+                if ui.button("盒体").clicked() {
+                    scene.world.entity_mut(entity).insert(Collider::cuboid(0.5, 0.5, 0.5));
+                    ui.close_menu();
+                }
+                if has_mesh {
+                    ui.label("从网格自动适配:");
+                    fit_menu(ui, &mut fit_request);
+                }
+            });
+        }
+        None => {}
+    }
+
+    if let Some(fit) = fit_request {
+        let fitted = scene.mesh_data(entity).and_then(|mesh| fit_collider(&mesh, fit));
+        let Some((shape, offset)) = fitted else {
+            tracing::warn!("无法从实体 {:?} 的网格生成{}碰撞体", entity, fit.label());
+            return;
+        };
+        match scene.world.get_mut::<Collider>(entity) {
+            Some(mut col) => {
+                col.shape = shape;
+                col.offset = offset;
+            }
+            None => {
+                let mut col = Collider::new(shape);
+                col.offset = offset;
+                scene.world.entity_mut(entity).insert(col);
+            }
+        }
+    }
+}
+
+/// 按命名物理层编辑位掩码
+fn layer_mask_menu(ui: &mut egui::Ui, physics_layers: &PhysicsLayers, mask: &mut u32) {
+    ui.menu_button(physics_layers.describe(*mask), |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("全部").clicked() { *mask = u32::MAX; }
+            if ui.button("无").clicked() { *mask = 0; }
+        });
+        ui.separator();
+        for (index, name) in physics_layers.named() {
+            let bit = 1u32 << index;
+            let mut enabled = *mask & bit != 0;
+            if ui.checkbox(&mut enabled, name).changed() {
+                *mask ^= bit;
+            }
+        }
+    });
+}
+
+/// 项目物理层命名，修改后立即保存到项目配置文件
+fn show_physics_layers(ui: &mut egui::Ui, physics_layers: &mut PhysicsLayers) {
+    ui.collapsing("物理层 (Physics Layers)", |ui| {
+        let mut changed = false;
+        egui::Grid::new("physics_layers_grid").num_columns(2).show(ui, |ui| {
+            for (index, name) in physics_layers.names.iter_mut().enumerate() {
+                ui.label(format!("层 {}", index));
+                changed |= ui.text_edit_singleline(name).lost_focus();
+                ui.end_row();
+            }
+        });
+        if changed {
+            if let Err(e) = crate::physics_manager::save_physics_layers(crate::physics_manager::PHYSICS_LAYERS_FILE, physics_layers) {
+                tracing::error!("保存物理层配置失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 角色控制器编辑，运行时状态只读显示
+fn show_character_controller(ui: &mut egui::Ui, scene: &mut Scene, entity: Entity, physics_layers: &PhysicsLayers) {
+    let Some(original) = scene.world.get::<CharacterController>(entity).cloned() else {
+        if ui.button("➕ 添加角色控制器").clicked() {
+            scene.world.entity_mut(entity).insert(CharacterController::default());
+        }
+        return;
+    };
+    let mut cc = original.clone();
+
+    let mut remove = false;
+    ui.collapsing("角色控制器 (CharacterController)", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("半高");
+            ui.add(egui::DragValue::new(&mut cc.half_height).speed(0.01).clamp_range(0.0..=100.0));
+            ui.label("半径");
+            ui.add(egui::DragValue::new(&mut cc.radius).speed(0.01).clamp_range(0.001..=100.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("间隙");
+            ui.add(egui::DragValue::new(&mut cc.offset).speed(0.001).clamp_range(0.0..=1.0));
+        });
+        ui.horizontal(|ui| {
+            let mut autostep = cc.max_step_height.is_some();
+            if ui.checkbox(&mut autostep, "自动上台阶").changed() {
+                cc.max_step_height = autostep.then_some(0.3);
+            }
+            if let Some(height) = &mut cc.max_step_height {
+                ui.label("高度");
+                ui.add(egui::DragValue::new(height).speed(0.01).clamp_range(0.0..=10.0));
+            }
+        });
+        if cc.max_step_height.is_some() {
+            ui.horizontal(|ui| {
+                ui.label("台阶最小宽度");
+                ui.add(egui::DragValue::new(&mut cc.min_step_width).speed(0.01).clamp_range(0.0..=10.0));
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("最大爬坡角");
+            ui.add(egui::DragValue::new(&mut cc.max_slope_climb_angle).speed(0.5).clamp_range(0.0..=90.0).suffix("°"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("下滑坡度");
+            ui.add(egui::DragValue::new(&mut cc.min_slope_slide_angle).speed(0.5).clamp_range(0.0..=90.0).suffix("°"));
+        });
+        ui.horizontal(|ui| {
+            let mut snap = cc.snap_to_ground.is_some();
+            if ui.checkbox(&mut snap, "贴地").changed() {
+                cc.snap_to_ground = snap.then_some(0.2);
+            }
+            if let Some(distance) = &mut cc.snap_to_ground {
+                ui.label("距离");
+                ui.add(egui::DragValue::new(distance).speed(0.01).clamp_range(0.0..=10.0));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut cc.slide, "沿障碍物滑动");
+            ui.checkbox(&mut cc.apply_gravity, "重力");
+        });
+        ui.horizontal(|ui| {
+            let mut push = cc.push_mass.is_some();
+            if ui.checkbox(&mut push, "推动刚体").changed() {
+                cc.push_mass = push.then_some(70.0);
+            }
+            if let Some(mass) = &mut cc.push_mass {
+                ui.label("质量");
+                ui.add(egui::DragValue::new(mass).speed(0.5).clamp_range(0.001..=f32::MAX));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("碰撞层");
+            layer_mask_menu(ui, physics_layers, &mut cc.layers.filter);
+        });
+        ui.horizontal(|ui| {
+            let mut input = cc.input_speed.is_some();
+            if ui.checkbox(&mut input, "方向键控制").changed() {
+                cc.input_speed = input.then_some(3.0);
+            }
+            if let Some(speed) = &mut cc.input_speed {
+                ui.label("速度");
+                ui.add(egui::DragValue::new(speed).speed(0.1).clamp_range(0.0..=100.0));
+            }
+        });
+
+        ui.separator();
+        ui.label(if cc.grounded { "状态: 着地" } else { "状态: 空中" });
+        if !cc.collisions.is_empty() {
+            ui.label(format!("碰撞: {} 个实体", cc.collisions.len()));
+        }
+        if ui.button("🗑 移除角色控制器").clicked() {
+            remove = true;
+        }
+    });
+
+    if remove {
+        scene.world.entity_mut(entity).remove::<CharacterController>();
+    } else if cc != original {
+        scene.world.entity_mut(entity).insert(cc);
+    }
+}
+
+/// 力场编辑；爆炸力场引爆后自动禁用，可随时再次引爆
+fn show_force_field(ui: &mut egui::Ui, scene: &mut Scene, entity: Entity, physics_layers: &PhysicsLayers) {
+    let Some(original) = scene.world.get::<ForceField>(entity).cloned() else {
+        if ui.button("➕ 添加力场").clicked() {
+            scene.world.entity_mut(entity).insert(ForceField::default());
+        }
+        return;
+    };
+    let mut field = original.clone();
+
+    let mut remove = false;
+    ui.collapsing("力场 (ForceField)", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("类型");
+            egui::ComboBox::from_id_source("force_field_kind")
+                .selected_text(field.kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in ForceFieldKind::presets() {
+                        let selected = std::mem::discriminant(&field.kind) == std::mem::discriminant(&kind);
+                        if ui.selectable_label(selected, kind.label()).clicked() && !selected {
+                            field.kind = kind;
+                        }
+                    }
+                });
+        });
+
+        match &mut field.kind {
+            ForceFieldKind::Wind { direction, strength } => {
+                vec3_row(ui, "方向", direction);
+                ui.horizontal(|ui| {
+                    ui.label("强度");
+                    ui.add(egui::DragValue::new(strength).speed(0.1).suffix(" N"));
+                });
+            }
+            ForceFieldKind::Explosion { strength } => {
+                ui.horizontal(|ui| {
+                    ui.label("冲量");
+                    ui.add(egui::DragValue::new(strength).speed(0.1).suffix(" N·s"));
+                });
+            }
+            ForceFieldKind::Vortex { axis, strength, pull } => {
+                vec3_row(ui, "轴", axis);
+                ui.horizontal(|ui| {
+                    ui.label("强度");
+                    ui.add(egui::DragValue::new(strength).speed(0.1).suffix(" N"));
+                    ui.label("向心力");
+                    ui.add(egui::DragValue::new(pull).speed(0.1).suffix(" N"));
+                });
+            }
+            ForceFieldKind::Drag { linear, angular } => {
+                ui.horizontal(|ui| {
+                    ui.label("线性阻力");
+                    ui.add(egui::DragValue::new(linear).speed(0.01).clamp_range(0.0..=f32::MAX));
+                    ui.label("角阻力");
+                    ui.add(egui::DragValue::new(angular).speed(0.01).clamp_range(0.0..=f32::MAX));
+                });
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("范围");
+            let mut is_sphere = matches!(field.volume, ForceFieldVolume::Sphere { .. });
+            let extent = field.volume.extent();
+            if ui.radio_value(&mut is_sphere, true, "球体").clicked() {
+                field.volume = ForceFieldVolume::Sphere { radius: extent };
+            }
+            if ui.radio_value(&mut is_sphere, false, "盒体").clicked() {
+                field.volume = ForceFieldVolume::Box { half_extents: Vec3::splat(extent / 3.0f32.sqrt()) };
+            }
+        });
+        match &mut field.volume {
+            ForceFieldVolume::Sphere { radius } => {
+                ui.horizontal(|ui| {
+                    ui.label("半径");
+                    ui.add(egui::DragValue::new(radius).speed(0.05).clamp_range(0.0..=1000.0));
+                });
+            }
+            ForceFieldVolume::Box { half_extents } => vec3_row(ui, "半尺寸", half_extents),
+        }
+        ui.horizontal(|ui| {
+            ui.label("衰减");
+            egui::ComboBox::from_id_source("force_field_falloff")
+                .selected_text(field.falloff.label())
+                .show_ui(ui, |ui| {
+                    for falloff in ForceFieldFalloff::ALL {
+                        ui.selectable_value(&mut field.falloff, falloff, falloff.label());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("影响层");
+            layer_mask_menu(ui, physics_layers, &mut field.layers.filter);
+        });
+
+        ui.horizontal(|ui| {
+            if matches!(field.kind, ForceFieldKind::Explosion { .. }) {
+                ui.label(if field.enabled { "状态: 等待引爆" } else { "状态: 已引爆" });
+                if ui.button("💥 引爆").clicked() {
+                    field.enabled = true;
+                }
+            } else {
+                ui.checkbox(&mut field.enabled, "启用");
+            }
+        });
+        if ui.button("🗑 移除力场").clicked() {
+            remove = true;
+        }
+    });
+
+    if remove {
+        scene.world.entity_mut(entity).remove::<ForceField>();
+    } else if field != original {
+        scene.world.entity_mut(entity).insert(field);
+    }
+}
+
+/// 物理关节编辑：只能连接到其他带刚体的实体 (编辑副本，只有实际修改时才写回，以免每帧重建关节)
+fn show_physics_joint(ui: &mut egui::Ui, scene: &mut Scene, entity: Entity) {
+    let mut bodies: Vec<(Entity, String)> = scene.world.query::<(Entity, &RigidBody, Option<&Name>)>()
+        .iter(&scene.world)
+        .filter(|(e, _, _)| *e != entity)
+        .map(|(e, _, name)| (e, name.map(|n| n.0.clone()).unwrap_or_else(|| format!("{:?}", e))))
+        .collect();
+    bodies.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let Some(original) = scene.world.get::<PhysicsJoint>(entity).cloned() else {
+        if let Some((first, _)) = bodies.first() {
+            if ui.button("➕ 添加物理关节").clicked() {
+                scene.world.entity_mut(entity).insert(PhysicsJoint::new(PhysicsJointKind::Fixed, *first));
+            }
+        }
+        return;
+    };
+    let mut joint = original.clone();
+
+    let mut remove = false;
+    ui.collapsing("物理关节 (PhysicsJoint)", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("类型");
+            egui::ComboBox::from_id_source("joint_kind")
+                .selected_text(joint.kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in PhysicsJointKind::ALL {
+                        let selected = std::mem::discriminant(&joint.kind) == std::mem::discriminant(&kind);
+                        if ui.selectable_label(selected, kind.label()).clicked() && !selected {
+                            joint.kind = kind;
+                        }
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("连接刚体");
+            let current = bodies.iter().find(|(e, _)| *e == joint.connected).map(|(_, n)| n.clone()).unwrap_or_else(|| "(缺失)".to_string());
+            egui::ComboBox::from_id_source("joint_connected")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for (e, name) in &bodies {
+                        ui.selectable_value(&mut joint.connected, *e, name);
+                    }
+                });
+        });
+
+        match &mut joint.kind {
+            PhysicsJointKind::Rope { max_length } => {
+                ui.horizontal(|ui| {
+                    ui.label("最大长度");
+                    ui.add(egui::DragValue::new(max_length).speed(0.01).clamp_range(0.0..=1000.0));
+                });
+            }
+            PhysicsJointKind::Spring { rest_length, stiffness, damping } => {
+                ui.horizontal(|ui| {
+                    ui.label("静止长度");
+                    ui.add(egui::DragValue::new(rest_length).speed(0.01).clamp_range(0.0..=1000.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("刚度");
+                    ui.add(egui::DragValue::new(stiffness).speed(0.1).clamp_range(0.0..=f32::MAX));
+                    ui.label("阻尼");
+                    ui.add(egui::DragValue::new(damping).speed(0.01).clamp_range(0.0..=f32::MAX));
+                });
+            }
+            _ => {}
+        }
+
+        vec3_row(ui, "锚点 (连接刚体)", &mut joint.anchor1);
+        vec3_row(ui, "锚点 (本刚体)", &mut joint.anchor2);
+
+        if joint.kind.has_axis() {
+            vec3_row(ui, "轴", &mut joint.axis);
+
+            let mut has_limits = joint.limits.is_some();
+            if ui.checkbox(&mut has_limits, "限位").changed() {
+                joint.limits = has_limits.then_some([-1.0, 1.0]);
+            }
+            if let Some(limits) = &mut joint.limits {
+                ui.horizontal(|ui| {
+                    ui.label("最小");
+                    ui.add(egui::DragValue::new(&mut limits[0]).speed(0.01));
+                    ui.label("最大");
+                    ui.add(egui::DragValue::new(&mut limits[1]).speed(0.01));
+                });
+                limits[1] = limits[1].max(limits[0]);
+            }
+
+            let mut has_motor = joint.motor.is_some();
+            if ui.checkbox(&mut has_motor, "马达").changed() {
+                joint.motor = has_motor.then(JointMotor::default);
+            }
+            if let Some(motor) = &mut joint.motor {
+                ui.horizontal(|ui| {
+                    ui.label("目标位置");
+                    ui.add(egui::DragValue::new(&mut motor.target_position).speed(0.01));
+                    ui.label("目标速度");
+                    ui.add(egui::DragValue::new(&mut motor.target_velocity).speed(0.01));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("刚度");
+                    ui.add(egui::DragValue::new(&mut motor.stiffness).speed(0.1).clamp_range(0.0..=f32::MAX));
+                    ui.label("阻尼");
+                    ui.add(egui::DragValue::new(&mut motor.damping).speed(0.01).clamp_range(0.0..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("最大力");
+                    ui.add(egui::DragValue::new(&mut motor.max_force).speed(1.0).clamp_range(0.0..=f32::MAX));
+                });
+            }
+        }
+
+        ui.checkbox(&mut joint.contacts_enabled, "连接刚体间碰撞");
+        if ui.button("🗑 移除关节").clicked() {
+            remove = true;
+        }
+    });
+
+    if remove {
+        scene.world.entity_mut(entity).remove::<PhysicsJoint>();
+    } else if joint != original {
+        scene.world.entity_mut(entity).insert(joint);
+    }
+}
+
+/// 布娃娃：从蒙皮生成骨骼刚体，并在动画与模拟模式之间切换
+fn show_ragdoll(ui: &mut egui::Ui, scene: &mut Scene, entity: Entity, physics_layers: &PhysicsLayers) {
+    let bone_count = scene.world.query::<&RagdollBone>().iter(&scene.world).filter(|b| b.ragdoll == entity).count();
+    let original = scene.world.get::<Ragdoll>(entity).cloned();
+    let mut edited = original.clone();
+    let (mut build, mut remove) = (false, false);
+    ui.collapsing("布娃娃 (Ragdoll)", |ui| {
+        let Some(ragdoll) = edited.as_mut() else {
+            build = ui.button("🦴 从蒙皮生成布娃娃").clicked();
+            return;
+        };
+        ui.label(format!("骨骼数: {}", bone_count));
+        ui.horizontal(|ui| {
+            ui.label("模式");
+            let mut mode = ragdoll.mode;
+            egui::ComboBox::from_id_source("ragdoll_mode")
+                .selected_text(mode.label())
+                .show_ui(ui, |ui| {
+                    for m in RagdollMode::ALL {
+                        ui.selectable_value(&mut mode, m, m.label());
+                    }
+                });
+            if mode != ragdoll.mode {
+                ragdoll.set_mode(mode);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("过渡时长");
+            ui.add(egui::DragValue::new(&mut ragdoll.blend_duration).speed(0.01).clamp_range(0.0..=5.0).suffix(" s"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("所属层");
+            layer_mask_menu(ui, physics_layers, &mut ragdoll.layers.memberships);
+            ui.label("碰撞层");
+            layer_mask_menu(ui, physics_layers, &mut ragdoll.layers.filter);
+        });
+        ui.horizontal(|ui| {
+            build = ui.button("🔄 重新生成").clicked();
+            remove = ui.button("🗑 移除布娃娃").clicked();
+        });
+    });
+
+    if edited != original {
+        if let Some(ragdoll) = edited {
+            scene.world.entity_mut(entity).insert(ragdoll);
+        }
+    }
+    if build {
+        match insert_ragdoll(&mut scene.world, entity, &RagdollSettings::default()) {
+            Ok(count) => tracing::info!("已生成布娃娃: {} 根骨骼", count),
+            Err(e) => tracing::warn!("生成布娃娃失败: {}", e),
+        }
+    }
+    if remove {
+        remove_ragdoll(&mut scene.world, entity);
+    }
+}
+
+/// 布娃娃骨骼的胶囊体与关节限位 (编辑副本，只有实际修改时才写回，以免每帧重建关节)
+fn show_ragdoll_bone(ui: &mut egui::Ui, scene: &mut Scene, entity: Entity) {
+    let Some(original) = scene.world.get::<RagdollBone>(entity).cloned() else { return };
+    let mut bone = original.clone();
+    ui.collapsing("布娃娃骨骼 (RagdollBone)", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("半高");
+            ui.add(egui::DragValue::new(&mut bone.half_height).speed(0.01).clamp_range(0.0..=100.0));
+            ui.label("半径");
+            ui.add(egui::DragValue::new(&mut bone.radius).speed(0.01).clamp_range(0.001..=100.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("密度");
+            ui.add(egui::DragValue::new(&mut bone.density).speed(0.01).clamp_range(0.001..=f32::MAX));
+        });
+        ui.horizontal(|ui| {
+            ui.label("关节");
+            egui::ComboBox::from_id_source("ragdoll_joint_kind")
+                .selected_text(bone.joint.label())
+                .show_ui(ui, |ui| {
+                    let settings = RagdollSettings::default();
+                    let spherical = RagdollJointKind::Spherical { swing_limit: settings.swing_limit, twist_limit: settings.twist_limit };
+                    let revolute = RagdollJointKind::Revolute { limits: settings.hinge_limits };
+                    for kind in [spherical, revolute] {
+                        if ui.selectable_label(bone.joint.label() == kind.label(), kind.label()).clicked() && bone.joint.label() != kind.label() {
+                            bone.joint = kind;
+                        }
+                    }
+                });
+        });
+        match &mut bone.joint {
+            RagdollJointKind::Spherical { swing_limit, twist_limit } => {
+                ui.horizontal(|ui| {
+                    ui.label("摆动");
+                    ui.add(egui::DragValue::new(swing_limit).speed(0.5).clamp_range(0.0..=180.0).suffix("°"));
+                    ui.label("扭转");
+                    ui.add(egui::DragValue::new(twist_limit).speed(0.5).clamp_range(0.0..=180.0).suffix("°"));
+                });
+            }
+            RagdollJointKind::Revolute { limits } => {
+                ui.horizontal(|ui| {
+                    ui.label("限位");
+                    let [min, max] = limits;
+                    let upper = *max;
+                    ui.add(egui::DragValue::new(min).speed(0.5).clamp_range(-180.0..=upper).suffix("°"));
+                    let lower = *min;
+                    ui.add(egui::DragValue::new(max).speed(0.5).clamp_range(lower..=180.0).suffix("°"));
+                });
+            }
+        }
+        vec3_row(ui, "关节轴", &mut bone.joint_axis);
+        if bone.parent.is_none() {
+            ui.label("根骨骼");
+        }
+    });
+
+    if bone != original {
+        scene.world.entity_mut(entity).insert(bone);
+    }
+}
+
+/// 将代码编辑器的光标移动到指定的行列 (从 1 开始) 并获取焦点
+fn move_code_cursor(ui: &egui::Ui, id: egui::Id, code: &str, line: usize, column: usize) {
+    let index = code.split('\n')
+        .take(line.saturating_sub(1))
+        .map(|l| l.chars().count() + 1)
+        .sum::<usize>() + column.saturating_sub(1);
+    let mut state = egui::TextEdit::load_state(ui.ctx(), id).unwrap_or_default();
+    state.set_ccursor_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(index))));
+    state.store(ui.ctx(), id);
+    ui.memory_mut(|m| m.request_focus(id));
+}
+
+fn vec3_row(ui: &mut egui::Ui, label: &str, value: &mut Vec3) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(&mut value.x).speed(0.01));
+        ui.add(egui::DragValue::new(&mut value.y).speed(0.01));
+        ui.add(egui::DragValue::new(&mut value.z).speed(0.01));
+    });
+}
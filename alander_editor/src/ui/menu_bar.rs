@@ -15,7 +15,7 @@ pub enum MenuAction {
 }
 
 /// 渲染顶部菜单栏
-pub fn show_menu_bar(ui: &mut egui::Ui, command_manager: &CommandManager) -> MenuAction {
+pub fn show_menu_bar(ui: &mut egui::Ui, command_manager: &CommandManager, show_state_machine: &mut bool) -> MenuAction {
     let mut action = MenuAction::None;
     
     egui::menu::bar(ui, |ui| {
@@ -71,6 +71,7 @@ pub fn show_menu_bar(ui: &mut egui::Ui, command_manager: &CommandManager) -> Men
                 action = MenuAction::ResetCamera;
                 ui.close_menu();
             }
+            ui.checkbox(show_state_machine, "动画状态机");
         });
     });
     
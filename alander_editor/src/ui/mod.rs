@@ -1,8 +1,12 @@
 pub mod asset_browser;
+pub mod console;
+pub mod graph_editor;
 pub mod hierarchy;
 pub mod inspector;
 pub mod menu_bar;
+pub mod retarget;
 pub mod simulation_bar;
+pub mod state_machine_editor;
 pub mod timeline;
 
 pub use menu_bar::MenuAction;
@@ -12,14 +16,27 @@ use crate::scene_manager::SceneManager;
 use crate::physics_manager::PhysicsManager;
 use crate::gizmo_manager::GizmoManager;
 use crate::app::EditorState;
+use crate::script_console::ScriptConsole;
 
 pub struct EditorUI {
-    // 目前没有需要持久化的内部状态，但保留结构以便后续扩展（如 DockState）
+    /// 时间线/摄影表面板状态
+    pub timeline_state: timeline::TimelineState,
+    /// 动画重定向面板状态
+    pub retarget_state: retarget::RetargetState,
+    /// 动画状态机编辑器状态
+    pub state_machine_state: state_machine_editor::StateMachineEditorState,
+    /// 脚本控制台面板状态
+    pub console_state: console::ConsoleState,
 }
 
 impl EditorUI {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            timeline_state: timeline::TimelineState::default(),
+            retarget_state: retarget::RetargetState::default(),
+            state_machine_state: state_machine_editor::StateMachineEditorState::default(),
+            console_state: console::ConsoleState::default(),
+        }
     }
 
     /// 渲染整个编辑器 UI 并返回执行的菜单操作
@@ -32,21 +49,24 @@ impl EditorUI {
         renderer: &mut alander_render::renderer::Renderer,
         command_manager: &mut crate::editor_command::CommandManager,
         editor_state: &mut EditorState,
+        script_console: &mut ScriptConsole,
         frame_time: f32,
     ) -> MenuAction {
         let mut menu_action = MenuAction::None;
 
         // 1. 顶部菜单栏
         egui::TopBottomPanel::top("top_menu").show(ctx, |ui| {
-            menu_action = menu_bar::show_menu_bar(ui, command_manager);
+            menu_action = menu_bar::show_menu_bar(ui, command_manager, &mut self.state_machine_state.open);
         });
 
         // 2. 底部模拟控制栏
         egui::TopBottomPanel::bottom("simulation_bar").show(ctx, |ui| {
             simulation_bar::show_simulation_bar(
                 ui,
+                scene_manager.active_scene_mut(),
                 physics_manager,
                 gizmo_manager,
+                renderer,
                 editor_state,
                 frame_time,
             );
@@ -54,10 +74,17 @@ impl EditorUI {
         
         egui::TopBottomPanel::bottom("timeline_panel")
             .resizable(true)
-            .default_height(100.0)
+            .default_height(160.0)
             .show(ctx, |ui| {
                 if let Some(scene) = scene_manager.active_scene_mut() {
-                    timeline::show_timeline(ui, scene, editor_state.selected_entity);
+                    timeline::show_timeline(
+                        ui,
+                        scene,
+                        editor_state.selected_entity,
+                        &mut self.timeline_state,
+                        command_manager,
+                        renderer,
+                    );
                 }
             });
 
@@ -68,6 +95,13 @@ impl EditorUI {
                 asset_browser::show_asset_browser(ui, editor_state, std::path::Path::new("assets"));
             });
 
+        egui::TopBottomPanel::bottom("script_console")
+            .resizable(true)
+            .default_height(120.0)
+            .show(ctx, |ui| {
+                console::show_console(ui, script_console, scene_manager.active_scene_mut(), editor_state, &mut self.console_state);
+            });
+
         // 3. 左侧场景面板
         egui::SidePanel::left("scene_panel")
             .resizable(true)
@@ -85,9 +119,29 @@ impl EditorUI {
             .show(ctx, |ui| {
                 if let Some(scene) = scene_manager.active_scene_mut() {
                     inspector::show_inspector(ui, scene, editor_state);
+                    retarget::show_retarget_panel(
+                        ui,
+                        scene,
+                        editor_state.selected_entity,
+                        &mut self.retarget_state,
+                        command_manager,
+                        renderer,
+                    );
                 }
             });
 
+        // 浮动窗口
+        if let Some(scene) = scene_manager.active_scene_mut() {
+            state_machine_editor::show_state_machine_editor(
+                ctx,
+                scene,
+                editor_state.selected_entity,
+                &mut self.state_machine_state,
+                command_manager,
+                renderer,
+            );
+        }
+
         // 5. 中央面板（透明，显示 3D 视图）
         egui::CentralPanel::default()
             .frame(egui::Frame::none().fill(egui::Color32::TRANSPARENT))
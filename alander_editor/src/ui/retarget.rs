@@ -0,0 +1,203 @@
+use egui;
+use crate::scene_manager::Scene;
+use crate::editor_command::{AnimationEditCommand, CommandManager};
+use alander_core::retarget::{self, BoneMap, BoneMapping, SkeletonBindPose};
+use alander_core::scene::{AnimationPlayer, Name};
+use alander_render::renderer::Renderer;
+use bevy_ecs::prelude::*;
+
+/// 动画重定向面板的持久化状态
+pub struct RetargetState {
+    /// 提供动画的源实体
+    pub source_entity: Option<Entity>,
+    /// 源剪辑索引
+    pub clip_index: usize,
+    /// 当前编辑的骨骼映射
+    pub bone_map: BoneMap,
+    /// 骨骼映射文件路径
+    pub map_path: String,
+    /// 最近一次操作的提示
+    pub status: Option<String>,
+}
+
+impl Default for RetargetState {
+    fn default() -> Self {
+        Self {
+            source_entity: None,
+            clip_index: 0,
+            bone_map: BoneMap::new("New Bone Map".to_string()),
+            map_path: "assets/bone_map.json".to_string(),
+            status: None,
+        }
+    }
+}
+
+/// 渲染动画重定向面板，将源实体的剪辑烘焙到选中实体上
+pub fn show_retarget_panel(
+    ui: &mut egui::Ui,
+    scene: &mut Scene,
+    selected_entity: Option<Entity>,
+    state: &mut RetargetState,
+    command_manager: &mut CommandManager,
+    renderer: &mut Renderer,
+) {
+    let Some(target_entity) = selected_entity else { return };
+    if scene.world.get::<AnimationPlayer>(target_entity).is_none() {
+        return;
+    }
+
+    ui.collapsing("动画重定向 (Retarget)", |ui| {
+        let Some(target_pose) = retarget::find_skin(&scene.world, target_entity)
+            .and_then(|skin| SkeletonBindPose::from_skin(&scene.world, skin))
+        else {
+            ui.label("选中实体下没有蒙皮 (Skin)");
+            return;
+        };
+
+        // 可作为动画来源的实体: 带有 AnimationPlayer 与蒙皮
+        let sources: Vec<(Entity, String)> = {
+            let mut query = scene.world.query::<(Entity, &AnimationPlayer, Option<&Name>)>();
+            query
+                .iter(&scene.world)
+                .filter(|(e, p, _)| *e != target_entity && !p.clips.is_empty())
+                .map(|(e, _, n)| (e, n.map(|n| n.0.clone()).unwrap_or_else(|| format!("{:?}", e))))
+                .collect()
+        };
+        let sources: Vec<_> = sources.into_iter().filter(|(e, _)| retarget::find_skin(&scene.world, *e).is_some()).collect();
+        if sources.is_empty() {
+            ui.label("场景中没有其他带蒙皮动画的实体");
+            return;
+        }
+        if !state.source_entity.is_some_and(|e| sources.iter().any(|(s, _)| *s == e)) {
+            state.source_entity = Some(sources[0].0);
+        }
+        let source_entity = state.source_entity.unwrap();
+
+        ui.horizontal(|ui| {
+            ui.label("来源:");
+            let current = sources.iter().find(|(e, _)| *e == source_entity).map(|(_, n)| n.clone()).unwrap_or_default();
+            egui::ComboBox::from_id_source("retarget_source")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for (e, name) in &sources {
+                        ui.selectable_value(&mut state.source_entity, Some(*e), name);
+                    }
+                });
+        });
+
+        let Some(source_pose) = retarget::find_skin(&scene.world, source_entity)
+            .and_then(|skin| SkeletonBindPose::from_skin(&scene.world, skin))
+        else {
+            return;
+        };
+        let clip_names: Vec<String> = scene
+            .world
+            .get::<AnimationPlayer>(source_entity)
+            .map(|p| p.clips.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default();
+        state.clip_index = state.clip_index.min(clip_names.len().saturating_sub(1));
+
+        ui.horizontal(|ui| {
+            ui.label("剪辑:");
+            egui::ComboBox::from_id_source("retarget_clip")
+                .selected_text(clip_names.get(state.clip_index).cloned().unwrap_or_default())
+                .show_ui(ui, |ui| {
+                    for (i, name) in clip_names.iter().enumerate() {
+                        ui.selectable_value(&mut state.clip_index, i, name);
+                    }
+                });
+        });
+
+        // 骨骼映射编辑
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("映射文件:");
+            ui.text_edit_singleline(&mut state.map_path);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("加载").clicked() {
+                state.status = Some(match load_bone_map(&state.map_path) {
+                    Ok(map) => {
+                        state.bone_map = map;
+                        "映射已加载".to_string()
+                    }
+                    Err(e) => format!("加载失败: {}", e),
+                });
+            }
+            if ui.button("保存").clicked() {
+                state.status = Some(match save_bone_map(&state.map_path, &state.bone_map) {
+                    Ok(()) => "映射已保存".to_string(),
+                    Err(e) => format!("保存失败: {}", e),
+                });
+            }
+            if ui.button("按名称自动匹配").clicked() {
+                state.bone_map = BoneMap::auto_map(state.bone_map.name.clone(), &source_pose.bone_names(), &target_pose.bone_names());
+                state.status = Some(format!("匹配到 {} 根骨骼", state.bone_map.mappings.len()));
+            }
+        });
+
+        let target_bones = target_pose.bone_names();
+        let mut removed = None;
+        egui::ScrollArea::vertical().max_height(160.0).id_source("retarget_mappings").show(ui, |ui| {
+            for (i, mapping) in state.bone_map.mappings.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(&mapping.source);
+                    ui.label("→");
+                    egui::ComboBox::from_id_source(("retarget_target", i))
+                        .selected_text(mapping.target.clone())
+                        .show_ui(ui, |ui| {
+                            for bone in &target_bones {
+                                ui.selectable_value(&mut mapping.target, bone.clone(), bone);
+                            }
+                        });
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+        });
+        if let Some(i) = removed {
+            state.bone_map.mappings.remove(i);
+        }
+        ui.menu_button("添加映射", |ui| {
+            for bone in source_pose.bone_names() {
+                if state.bone_map.target_for(&bone).is_none() && ui.button(&bone).clicked() {
+                    let target = target_bones.first().cloned().unwrap_or_default();
+                    state.bone_map.mappings.push(BoneMapping { source: bone, target });
+                    ui.close_menu();
+                }
+            }
+        });
+
+        ui.separator();
+        if ui.add_enabled(!state.bone_map.mappings.is_empty(), egui::Button::new("烘焙到选中实体")).clicked() {
+            let source_clip = scene
+                .world
+                .get::<AnimationPlayer>(source_entity)
+                .and_then(|p| p.clips.get(state.clip_index).cloned());
+            let old = scene.world.get::<AnimationPlayer>(target_entity).map(|p| p.clips.clone());
+            if let (Some(clip), Some(old)) = (source_clip, old) {
+                let baked = retarget::retarget_clip(&clip, &source_pose, &target_pose, &state.bone_map);
+                state.status = Some(format!("已生成剪辑 \"{}\" ({} 个通道)", baked.name, baked.channels.len()));
+                let mut new = old.clone();
+                new.push(baked);
+                let cmd = AnimationEditCommand::new(target_entity, old, new, "烘焙重定向剪辑");
+                command_manager.execute(Box::new(cmd), scene, renderer);
+            }
+        }
+
+        if let Some(status) = &state.status {
+            ui.label(status);
+        }
+    });
+}
+
+fn load_bone_map(path: &str) -> Result<BoneMap, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+fn save_bone_map(path: &str, map: &BoneMap) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(map).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
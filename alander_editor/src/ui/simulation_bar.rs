@@ -1,12 +1,18 @@
 use egui;
 use crate::physics_manager::PhysicsManager;
 use crate::gizmo_manager::{GizmoManager, GizmoMode};
+use crate::scene_manager::Scene;
+
+/// 模拟录制的保存位置 (相对工作目录)
+const RECORDING_FILE: &str = "simulation_recording.json";
 
 /// 渲染模拟控制栏
 pub fn show_simulation_bar(
     ui: &mut egui::Ui,
+    scene: Option<&mut Scene>,
     physics_manager: &mut PhysicsManager,
     gizmo_manager: &mut GizmoManager,
+    renderer: &mut alander_render::renderer::Renderer,
     editor_state: &mut crate::app::EditorState,
     frame_time: f32,
 ) {
@@ -21,11 +27,55 @@ pub fn show_simulation_bar(
         ));
         ui.separator();
         
-        let play_text = if physics_manager.is_running { "⏸ 暂停物理模拟" } else { "▶ 开始物理模拟" };
-        if ui.button(play_text).clicked() {
-            physics_manager.is_running = !physics_manager.is_running;
+        if let Some(scene) = scene {
+            let play_text = if physics_manager.is_running { "⏸ 暂停物理模拟" } else { "▶ 开始物理模拟" };
+            if ui.button(play_text).clicked() {
+                if physics_manager.is_running {
+                    physics_manager.pause();
+                } else {
+                    physics_manager.play(&mut scene.world);
+                }
+            }
+            if ui.add_enabled(!physics_manager.is_running, egui::Button::new("⏭ 单步")).clicked() {
+                physics_manager.step_once(&mut scene.world);
+            }
+            if ui.add_enabled(physics_manager.has_snapshot(), egui::Button::new("⏹ 停止")).on_hover_text("恢复到开始模拟前的状态").clicked() {
+                let keep: Vec<_> = editor_state.selected_entity.filter(|_| editor_state.keep_simulated_selection).into_iter().collect();
+                for id in physics_manager.stop(scene, &keep) {
+                    renderer.remove_object(&id);
+                }
+            }
+            ui.checkbox(&mut editor_state.keep_simulated_selection, "保留选中实体的模拟结果");
         }
         
+        let recording = physics_manager.is_recording();
+        if ui.selectable_label(recording, "⏺ 录制").on_hover_text("录制模拟输入，停止时保存到 simulation_recording.json").clicked() {
+            if recording {
+                if let Some(recording) = physics_manager.stop_recording() {
+                    let result = serde_json::to_string_pretty(&recording).map_err(|e| e.to_string())
+                        .and_then(|json| std::fs::write(RECORDING_FILE, json).map_err(|e| e.to_string()));
+                    match result {
+                        Ok(()) => tracing::info!("模拟录制已保存: {} ({} 步)", RECORDING_FILE, recording.steps.len()),
+                        Err(e) => tracing::error!("保存模拟录制失败: {}", e),
+                    }
+                }
+            } else {
+                let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+                physics_manager.start_recording(seed);
+            }
+        }
+
+        ui.separator();
+        let timestep = &mut physics_manager.timestep;
+        let mut rate = (1.0 / timestep.fixed_dt).round();
+        ui.label("物理频率:");
+        if ui.add(egui::DragValue::new(&mut rate).speed(1.0).clamp_range(10.0..=1000.0).suffix("Hz")).changed() {
+            timestep.fixed_dt = 1.0 / rate;
+        }
+        ui.label("子步:");
+        ui.add(egui::DragValue::new(&mut timestep.substeps).clamp_range(1..=16));
+        ui.checkbox(&mut timestep.interpolate, "插值");
+
         ui.separator();
         ui.label("重力:");
         ui.add(egui::DragValue::new(&mut physics_manager.gravity.y).speed(0.1));
@@ -0,0 +1,924 @@
+use egui;
+use crate::scene_manager::Scene;
+use crate::editor_command::{CommandManager, StateMachineEditCommand};
+use alander_core::scene::{
+    AnimCondition, AnimParamValue, AnimationPlayer, AnimationState, AnimationStateMachine, AnimationTransition, InterruptionSource,
+};
+use alander_render::renderer::Renderer;
+use bevy_ecs::prelude::*;
+use glam::Vec2;
+
+/// 画布上的节点
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphNode {
+    /// "任意状态" 节点
+    AnyState,
+    /// 状态 (名称)
+    State(String),
+}
+
+/// 状态机编辑器中选中的对象
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateMachineSelection {
+    /// 状态 (名称)
+    State(String),
+    /// 转换 (源节点, 转换索引)
+    Transition(GraphNode, usize),
+}
+
+/// 画布上正在进行的拖动操作
+enum CanvasDrag {
+    /// 移动节点
+    Node { node: GraphNode, original: Box<AnimationStateMachine> },
+    /// 从节点拉出新的转换
+    Connect { from: GraphNode },
+    /// 平移画布
+    Pan,
+}
+
+/// 新建参数的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamKind {
+    Float,
+    Bool,
+    Trigger,
+    Int,
+    Enum,
+}
+
+impl ParamKind {
+    const ALL: [ParamKind; 5] = [ParamKind::Float, ParamKind::Bool, ParamKind::Trigger, ParamKind::Int, ParamKind::Enum];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ParamKind::Float => "浮点",
+            ParamKind::Bool => "布尔",
+            ParamKind::Trigger => "触发器",
+            ParamKind::Int => "整数",
+            ParamKind::Enum => "枚举",
+        }
+    }
+
+    fn default_value(&self) -> AnimParamValue {
+        match self {
+            ParamKind::Float => AnimParamValue::Float(0.0),
+            ParamKind::Bool => AnimParamValue::Bool(false),
+            ParamKind::Trigger => AnimParamValue::Trigger(false),
+            ParamKind::Int => AnimParamValue::Int(0),
+            ParamKind::Enum => AnimParamValue::Enum { value: 0, options: vec!["A".to_string(), "B".to_string()] },
+        }
+    }
+}
+
+/// 状态机编辑器窗口的持久化状态
+pub struct StateMachineEditorState {
+    /// 窗口是否打开
+    pub open: bool,
+    /// 当前选中的状态或转换
+    pub selection: Option<StateMachineSelection>,
+    /// 正在编辑的子状态机路径 (为空时编辑根状态机)
+    pub path: Vec<String>,
+    /// 画布平移量
+    pub pan: egui::Vec2,
+    /// 新建参数的名称
+    pub new_param_name: String,
+    new_param_kind: ParamKind,
+    /// 打开右键菜单时的画布坐标
+    menu_position: Vec2,
+    drag: Option<CanvasDrag>,
+}
+
+impl Default for StateMachineEditorState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            selection: None,
+            path: Vec::new(),
+            pan: egui::Vec2::ZERO,
+            new_param_name: "speed".to_string(),
+            new_param_kind: ParamKind::Float,
+            menu_position: Vec2::ZERO,
+            drag: None,
+        }
+    }
+}
+
+/// 本帧累积的编辑
+///
+/// 结构性修改通过命令提交 (可撤销)，字段微调则直接写回组件，与检视面板一致。
+struct PendingEdit {
+    /// 本帧开始时的状态机快照
+    original: AnimationStateMachine,
+    /// 结构性修改: (命令名称, 撤销快照)
+    structural: Option<(&'static str, AnimationStateMachine)>,
+    live_changed: bool,
+    /// 需要立即播放的剪辑
+    play_clip: Option<usize>,
+}
+
+impl PendingEdit {
+    fn commit(&mut self, name: &'static str) {
+        self.structural = Some((name, self.original.clone()));
+    }
+}
+
+const NODE_SIZE: egui::Vec2 = egui::vec2(140.0, 44.0);
+const EDGE_HIT_DISTANCE: f32 = 5.0;
+
+/// 渲染状态机编辑器窗口
+pub fn show_state_machine_editor(
+    ctx: &egui::Context,
+    scene: &mut Scene,
+    selected_entity: Option<Entity>,
+    state: &mut StateMachineEditorState,
+    command_manager: &mut CommandManager,
+    renderer: &mut Renderer,
+) {
+    if !state.open {
+        return;
+    }
+    let mut open = state.open;
+    egui::Window::new("动画状态机 (State Machine)")
+        .open(&mut open)
+        .default_size([720.0, 420.0])
+        .resizable(true)
+        .show(ctx, |ui| {
+            show_contents(ui, scene, selected_entity, state, command_manager, renderer);
+        });
+    state.open = open;
+}
+
+fn show_contents(
+    ui: &mut egui::Ui,
+    scene: &mut Scene,
+    selected_entity: Option<Entity>,
+    state: &mut StateMachineEditorState,
+    command_manager: &mut CommandManager,
+    renderer: &mut Renderer,
+) {
+    let Some(entity) = selected_entity else {
+        ui.label("请在层级面板中选择一个实体");
+        return;
+    };
+    let Some(player) = scene.world.get::<AnimationPlayer>(entity) else {
+        ui.label("选中实体不包含 AnimationPlayer 组件");
+        return;
+    };
+    let clip_names: Vec<String> = player.clips.iter().map(|c| c.name.clone()).collect();
+
+    let Some(original) = scene.world.get::<AnimationStateMachine>(entity).cloned() else {
+        if ui.button("➕ 添加动画状态机").clicked() {
+            let mut sm = AnimationStateMachine::new("Idle".to_string());
+            sm.states.insert("Idle".to_string(), AnimationState::new("Idle".to_string(), 0));
+            let cmd = StateMachineEditCommand::new(entity, None, Some(sm), "添加状态机");
+            command_manager.execute(Box::new(cmd), scene, renderer);
+        }
+        return;
+    };
+
+    let mut sm = original.clone();
+    let mut edit = PendingEdit { original, structural: None, live_changed: false, play_clip: None };
+
+    // 子状态机被删除或撤销后，回退到仍然存在的层级
+    while level_mut(&mut sm, &state.path).is_none() {
+        state.path.pop();
+        state.selection = None;
+    }
+    let path = state.path.clone();
+    let active_path = sm.active_path();
+    let level_active = active_path.starts_with(&path);
+
+    ui.horizontal(|ui| {
+        if ui.selectable_label(path.is_empty(), "根").clicked() {
+            state.path.clear();
+            state.selection = None;
+        }
+        for (i, name) in path.iter().enumerate() {
+            ui.label("›");
+            if ui.selectable_label(i + 1 == path.len(), name).clicked() {
+                state.path.truncate(i + 1);
+                state.selection = None;
+            }
+        }
+        ui.separator();
+        ui.label(format!("当前状态: {}", active_path.join(" › ")));
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("➕ 添加状态").clicked() {
+            if let Some(level) = level_mut(&mut sm, &path) {
+                let name = level.unique_state_name("State");
+                let mut new_state = AnimationState::new(name.clone(), 0);
+                new_state.position = Vec2::new(-state.pan.x + 20.0, -state.pan.y + 20.0);
+                level.states.insert(name.clone(), new_state);
+                state.selection = Some(StateMachineSelection::State(name));
+                edit.commit("添加状态");
+            }
+        }
+        if ui.button("🗑 删除状态机").clicked() {
+            let cmd = StateMachineEditCommand::new(entity, Some(edit.original.clone()), None, "删除状态机");
+            command_manager.execute(Box::new(cmd), scene, renderer);
+            state.selection = None;
+            state.path.clear();
+        }
+    });
+    if scene.world.get::<AnimationStateMachine>(entity).is_none() {
+        return;
+    }
+
+    egui::SidePanel::right("state_machine_properties")
+        .resizable(true)
+        .default_width(240.0)
+        .show_inside(ui, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // 参数属于根状态机，子状态机共享
+                show_parameters(ui, &mut sm, state, &mut edit);
+                ui.separator();
+                let parameters: Vec<(String, AnimParamValue)> = {
+                    let mut params: Vec<_> = sm.parameters.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                    params.sort_by(|a, b| a.0.cmp(&b.0));
+                    params
+                };
+                if let Some(level) = level_mut(&mut sm, &path) {
+                    show_selection(ui, level, state, &clip_names, &parameters, level_active, &mut edit);
+                }
+            });
+        });
+
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        if let Some(level) = level_mut(&mut sm, &path) {
+            show_canvas(ui, level, state, &clip_names, level_active, &mut edit);
+        }
+    });
+
+    if let Some((name, old)) = edit.structural {
+        let cmd = StateMachineEditCommand::new(entity, Some(old), Some(sm), name);
+        command_manager.execute(Box::new(cmd), scene, renderer);
+    } else if edit.live_changed {
+        if let Some(mut component) = scene.world.get_mut::<AnimationStateMachine>(entity) {
+            *component = sm;
+        }
+    }
+    if let Some(clip) = edit.play_clip {
+        if let Some(mut player) = scene.world.get_mut::<AnimationPlayer>(entity) {
+            player.play(clip);
+        }
+    }
+}
+
+/// 沿路径进入子状态机
+fn level_mut<'a>(sm: &'a mut AnimationStateMachine, path: &[String]) -> Option<&'a mut AnimationStateMachine> {
+    path.iter().try_fold(sm, |level, name| level.states.get_mut(name)?.sub_machine.as_deref_mut())
+}
+
+/// 节点在画布上的位置
+fn node_position(sm: &AnimationStateMachine, node: &GraphNode) -> Option<Vec2> {
+    match node {
+        GraphNode::AnyState => Some(sm.any_state_position),
+        GraphNode::State(name) => sm.states.get(name).map(|s| s.position),
+    }
+}
+
+/// 节点发出的转换列表
+fn transitions_mut<'a>(sm: &'a mut AnimationStateMachine, node: &GraphNode) -> Option<&'a mut Vec<AnimationTransition>> {
+    match node {
+        GraphNode::AnyState => Some(&mut sm.any_state_transitions),
+        GraphNode::State(name) => sm.states.get_mut(name).map(|s| &mut s.transitions),
+    }
+}
+
+/// 参数列表：运行时可直接修改以驱动转换
+fn show_parameters(
+    ui: &mut egui::Ui,
+    sm: &mut AnimationStateMachine,
+    state: &mut StateMachineEditorState,
+    edit: &mut PendingEdit,
+) {
+    ui.strong("参数");
+    let mut names: Vec<String> = sm.parameters.keys().cloned().collect();
+    names.sort();
+    let mut removed = None;
+    for name in &names {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            match sm.parameters.get_mut(name) {
+                Some(AnimParamValue::Float(v)) => {
+                    edit.live_changed |= ui.add(egui::DragValue::new(v).speed(0.05)).changed();
+                }
+                Some(AnimParamValue::Bool(v)) => {
+                    edit.live_changed |= ui.checkbox(v, "").changed();
+                }
+                Some(AnimParamValue::Trigger(v)) if ui.button(if *v { "已触发" } else { "触发" }).clicked() => {
+                    *v = true;
+                    edit.live_changed = true;
+                }
+                Some(AnimParamValue::Int(v)) => {
+                    edit.live_changed |= ui.add(egui::DragValue::new(v)).changed();
+                }
+                Some(AnimParamValue::Enum { value, options }) => {
+                    egui::ComboBox::from_id_source(("sm_enum_param", name.as_str()))
+                        .selected_text(options.get(*value).cloned().unwrap_or_default())
+                        .width(70.0)
+                        .show_ui(ui, |ui| {
+                            for (i, option) in options.iter().enumerate() {
+                                edit.live_changed |= ui.selectable_value(value, i, option).changed();
+                            }
+                        });
+                    ui.menu_button("选项", |ui| {
+                        let mut removed_option = None;
+                        for (i, option) in options.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                edit.live_changed |= ui.add(egui::TextEdit::singleline(option).desired_width(80.0)).changed();
+                                if ui.small_button("✖").clicked() {
+                                    removed_option = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = removed_option.filter(|_| options.len() > 1) {
+                            options.remove(i);
+                            *value = (*value).min(options.len() - 1);
+                            edit.commit("删除枚举选项");
+                        }
+                        if ui.button("添加选项").clicked() {
+                            options.push(format!("Option{}", options.len()));
+                            edit.commit("添加枚举选项");
+                        }
+                    });
+                }
+                _ => {}
+            }
+            if ui.small_button("✖").clicked() {
+                removed = Some(name.clone());
+            }
+        });
+    }
+    if let Some(name) = removed {
+        sm.parameters.remove(&name);
+        edit.commit("删除参数");
+    }
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.new_param_name).desired_width(80.0));
+        egui::ComboBox::from_id_source("sm_new_param_kind")
+            .selected_text(state.new_param_kind.label())
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                for kind in ParamKind::ALL {
+                    ui.selectable_value(&mut state.new_param_kind, kind, kind.label());
+                }
+            });
+        let valid = !state.new_param_name.is_empty() && !sm.parameters.contains_key(&state.new_param_name);
+        if ui.add_enabled(valid, egui::Button::new("添加")).clicked() {
+            sm.parameters.insert(state.new_param_name.clone(), state.new_param_kind.default_value());
+            edit.commit("添加参数");
+        }
+    });
+}
+
+/// 选中状态或转换的属性
+fn show_selection(
+    ui: &mut egui::Ui,
+    sm: &mut AnimationStateMachine,
+    state: &mut StateMachineEditorState,
+    clip_names: &[String],
+    parameters: &[(String, AnimParamValue)],
+    level_active: bool,
+    edit: &mut PendingEdit,
+) {
+    match state.selection.clone() {
+        Some(StateMachineSelection::State(name)) if sm.states.contains_key(&name) => {
+            ui.strong("状态");
+            let mut new_name = name.clone();
+            let response = ui.text_edit_singleline(&mut new_name);
+            if response.lost_focus() && new_name != name && !new_name.is_empty() && sm.rename_state(&name, &new_name) {
+                state.selection = Some(StateMachineSelection::State(new_name.clone()));
+                edit.commit("重命名状态");
+                return;
+            }
+
+            let st = sm.states.get_mut(&name).unwrap();
+            if st.sub_machine.is_some() {
+                ui.horizontal(|ui| {
+                    ui.label("子状态机");
+                    if ui.button("进入").clicked() {
+                        state.path.push(name.clone());
+                        state.selection = None;
+                    }
+                    if ui.button("移除子状态机").clicked() {
+                        st.sub_machine = None;
+                        edit.commit("移除子状态机");
+                    }
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label("剪辑:");
+                    egui::ComboBox::from_id_source("sm_state_clip")
+                        .selected_text(clip_names.get(st.clip_index).cloned().unwrap_or_else(|| "<无>".to_string()))
+                        .show_ui(ui, |ui| {
+                            for (i, clip) in clip_names.iter().enumerate() {
+                                edit.live_changed |= ui.selectable_value(&mut st.clip_index, i, clip).changed();
+                            }
+                        });
+                });
+                if ui.button("转换为子状态机").clicked() {
+                    let entry = "Entry".to_string();
+                    let mut sub = AnimationStateMachine::new(entry.clone());
+                    sub.entry_state = entry.clone();
+                    sub.states.insert(entry.clone(), AnimationState::new(entry, st.clip_index));
+                    st.sub_machine = Some(Box::new(sub));
+                    edit.commit("创建子状态机");
+                }
+            }
+
+            ui.horizontal(|ui| {
+                if ui.add_enabled(sm.entry_state != name, egui::Button::new("设为入口状态")).clicked() {
+                    sm.entry_state = name.clone();
+                    edit.commit("设置入口状态");
+                }
+                if ui.add_enabled(sm.current_state != name, egui::Button::new("设为当前状态")).clicked() {
+                    sm.current_state = name.clone();
+                    sm.previous_state = None;
+                    sm.state_time = 0.0;
+                    sm.active_transition = None;
+                    if let Some(sub) = sm.states.get_mut(&name).and_then(|s| s.sub_machine.as_deref_mut()) {
+                        sub.reset();
+                    }
+                    if level_active {
+                        edit.play_clip = sm.leaf_clip();
+                    }
+                    edit.live_changed = true;
+                }
+            });
+            if ui.button("删除状态").clicked() {
+                sm.remove_state(&name);
+                state.selection = None;
+                edit.commit("删除状态");
+            }
+        }
+        Some(StateMachineSelection::Transition(from, index)) if transitions_mut(sm, &from).is_some_and(|t| index < t.len()) => {
+            let state_names = {
+                let mut names: Vec<String> = sm.states.keys().cloned().collect();
+                names.sort();
+                names
+            };
+            let from_label = match &from {
+                GraphNode::AnyState => "任意状态".to_string(),
+                GraphNode::State(name) => name.clone(),
+            };
+            let transition = &mut transitions_mut(sm, &from).unwrap()[index];
+
+            ui.strong("转换");
+            ui.horizontal(|ui| {
+                ui.label(format!("{} →", from_label));
+                egui::ComboBox::from_id_source("sm_transition_target")
+                    .selected_text(transition.target_state.clone())
+                    .show_ui(ui, |ui| {
+                        for name in state_names.iter().filter(|n| **n != from_label) {
+                            edit.live_changed |= ui.selectable_value(&mut transition.target_state, name.clone(), name).changed();
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("过渡时长:");
+                edit.live_changed |= ui.add(egui::DragValue::new(&mut transition.duration).speed(0.01).clamp_range(0.0..=10.0).suffix("s")).changed();
+            });
+            ui.horizontal(|ui| {
+                edit.live_changed |= ui.checkbox(&mut transition.has_exit_time, "退出时间:").changed();
+                edit.live_changed |= ui
+                    .add_enabled(transition.has_exit_time, egui::DragValue::new(&mut transition.exit_time).speed(0.01).clamp_range(0.0..=10.0))
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("优先级:");
+                edit.live_changed |= ui.add(egui::DragValue::new(&mut transition.priority)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("打断来源:");
+                egui::ComboBox::from_id_source("sm_transition_interruption")
+                    .selected_text(transition.interruption_source.label())
+                    .show_ui(ui, |ui| {
+                        for source in InterruptionSource::ALL {
+                            edit.live_changed |= ui.selectable_value(&mut transition.interruption_source, source, source.label()).changed();
+                        }
+                    });
+            });
+
+            ui.label("条件 (全部满足时触发):");
+            let mut removed = None;
+            for (ci, condition) in transition.conditions.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    edit.live_changed |= condition_editor(ui, (index, ci), condition, parameters);
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(ci);
+                    }
+                });
+            }
+            if let Some(ci) = removed {
+                transition.conditions.remove(ci);
+                edit.commit("删除条件");
+            }
+            let mut delete_transition = false;
+            ui.horizontal(|ui| {
+                if ui.button("添加条件").clicked() {
+                    let condition = match parameters.first() {
+                        Some((name, value)) => make_condition(name.clone(), value, 0, None),
+                        None => AnimCondition::Trigger(String::new()),
+                    };
+                    transition.conditions.push(condition);
+                    edit.commit("添加条件");
+                }
+                delete_transition = ui.button("删除转换").clicked();
+            });
+            if delete_transition {
+                transitions_mut(sm, &from).unwrap().remove(index);
+                state.selection = None;
+                edit.commit("删除转换");
+            }
+        }
+        _ => {
+            ui.label("选择状态或转换以编辑属性");
+            ui.label("拖动节点移动，Shift+拖动 (或右键拖动) 连接转换");
+            ui.label("双击子状态机节点进入编辑");
+        }
+    }
+}
+
+/// 参数类型可用的条件运算符
+fn condition_operators(value: &AnimParamValue) -> &'static [&'static str] {
+    match value {
+        AnimParamValue::Float(_) => &[">", "<"],
+        AnimParamValue::Bool(_) => &["=="],
+        AnimParamValue::Trigger(_) => &["触发"],
+        AnimParamValue::Int(_) => &["==", "!=", ">", "<"],
+        AnimParamValue::Enum { .. } => &["是", "不是"],
+    }
+}
+
+/// 条件在其运算符列表中的索引
+fn condition_operator(condition: &AnimCondition) -> usize {
+    match condition {
+        AnimCondition::Greater(..)
+        | AnimCondition::Bool(..)
+        | AnimCondition::Trigger(_)
+        | AnimCondition::IntEquals(..)
+        | AnimCondition::EnumIs(..) => 0,
+        AnimCondition::Less(..) | AnimCondition::IntNotEquals(..) | AnimCondition::EnumIsNot(..) => 1,
+        AnimCondition::IntGreater(..) => 2,
+        AnimCondition::IntLess(..) => 3,
+    }
+}
+
+/// 按参数类型与运算符构建条件，尽量保留原条件的阈值
+fn make_condition(param: String, value: &AnimParamValue, operator: usize, previous: Option<&AnimCondition>) -> AnimCondition {
+    match value {
+        AnimParamValue::Float(_) => {
+            let threshold = match previous {
+                Some(AnimCondition::Greater(_, v) | AnimCondition::Less(_, v)) => *v,
+                _ => 0.0,
+            };
+            if operator == 1 { AnimCondition::Less(param, threshold) } else { AnimCondition::Greater(param, threshold) }
+        }
+        AnimParamValue::Bool(_) => {
+            let expected = match previous {
+                Some(AnimCondition::Bool(_, b)) => *b,
+                _ => true,
+            };
+            AnimCondition::Bool(param, expected)
+        }
+        AnimParamValue::Trigger(_) => AnimCondition::Trigger(param),
+        AnimParamValue::Int(_) => {
+            let threshold = match previous {
+                Some(
+                    AnimCondition::IntEquals(_, v)
+                    | AnimCondition::IntNotEquals(_, v)
+                    | AnimCondition::IntGreater(_, v)
+                    | AnimCondition::IntLess(_, v),
+                ) => *v,
+                _ => 0,
+            };
+            match operator {
+                1 => AnimCondition::IntNotEquals(param, threshold),
+                2 => AnimCondition::IntGreater(param, threshold),
+                3 => AnimCondition::IntLess(param, threshold),
+                _ => AnimCondition::IntEquals(param, threshold),
+            }
+        }
+        AnimParamValue::Enum { options, .. } => {
+            let option = match previous {
+                Some(AnimCondition::EnumIs(_, o) | AnimCondition::EnumIsNot(_, o)) if options.contains(o) => o.clone(),
+                _ => options.first().cloned().unwrap_or_default(),
+            };
+            if operator == 1 { AnimCondition::EnumIsNot(param, option) } else { AnimCondition::EnumIs(param, option) }
+        }
+    }
+}
+
+/// 单个条件的编辑控件，返回是否修改
+fn condition_editor(ui: &mut egui::Ui, id: (usize, usize), condition: &mut AnimCondition, parameters: &[(String, AnimParamValue)]) -> bool {
+    let mut changed = false;
+    let param = condition.param().to_string();
+    let mut new_param = param.clone();
+    egui::ComboBox::from_id_source(("sm_condition_param", id))
+        .selected_text(if param.is_empty() { "<参数>".to_string() } else { param.clone() })
+        .width(70.0)
+        .show_ui(ui, |ui| {
+            for (name, _) in parameters {
+                ui.selectable_value(&mut new_param, name.clone(), name);
+            }
+        });
+
+    let Some((_, value)) = parameters.iter().find(|(name, _)| *name == new_param) else {
+        return false;
+    };
+    if new_param != param {
+        *condition = make_condition(new_param.clone(), value, condition_operator(condition), Some(condition));
+        changed = true;
+    }
+
+    let operators = condition_operators(value);
+    let operator = condition_operator(condition).min(operators.len() - 1);
+    if operators.len() > 1 {
+        let mut new_operator = operator;
+        egui::ComboBox::from_id_source(("sm_condition_kind", id))
+            .selected_text(operators[operator])
+            .width(40.0)
+            .show_ui(ui, |ui| {
+                for (i, label) in operators.iter().enumerate() {
+                    ui.selectable_value(&mut new_operator, i, *label);
+                }
+            });
+        if new_operator != operator {
+            *condition = make_condition(new_param, value, new_operator, Some(condition));
+            changed = true;
+        }
+    } else {
+        ui.label(operators[0]);
+    }
+
+    match condition {
+        AnimCondition::Greater(_, v) | AnimCondition::Less(_, v) => {
+            changed |= ui.add(egui::DragValue::new(v).speed(0.05)).changed();
+        }
+        AnimCondition::Bool(_, v) => {
+            changed |= ui.checkbox(v, "").changed();
+        }
+        AnimCondition::IntEquals(_, v) | AnimCondition::IntNotEquals(_, v) | AnimCondition::IntGreater(_, v) | AnimCondition::IntLess(_, v) => {
+            changed |= ui.add(egui::DragValue::new(v)).changed();
+        }
+        AnimCondition::EnumIs(_, option) | AnimCondition::EnumIsNot(_, option) => {
+            if let AnimParamValue::Enum { options, .. } = value {
+                egui::ComboBox::from_id_source(("sm_condition_option", id))
+                    .selected_text(option.clone())
+                    .width(70.0)
+                    .show_ui(ui, |ui| {
+                        for o in options {
+                            changed |= ui.selectable_value(option, o.clone(), o).changed();
+                        }
+                    });
+            }
+        }
+        AnimCondition::Trigger(_) => {}
+    }
+    changed
+}
+
+/// 节点图画布
+fn show_canvas(
+    ui: &mut egui::Ui,
+    sm: &mut AnimationStateMachine,
+    state: &mut StateMachineEditorState,
+    clip_names: &[String],
+    level_active: bool,
+    edit: &mut PendingEdit,
+) {
+    let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+    let rect = response.rect;
+    let visuals = ui.visuals().clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let painter = painter.with_clip_rect(rect);
+
+    let origin = rect.min.to_vec2() + state.pan;
+    let node_rect = |p: Vec2| egui::Rect::from_min_size(egui::pos2(p.x, p.y) + origin, NODE_SIZE);
+    let to_canvas = |p: egui::Pos2| {
+        let v = p - origin;
+        Vec2::new(v.x, v.y)
+    };
+
+    // 背景网格
+    let grid = 40.0;
+    let grid_color = visuals.widgets.noninteractive.bg_stroke.color.linear_multiply(0.4);
+    let mut x = rect.left() + state.pan.x.rem_euclid(grid);
+    while x < rect.right() {
+        painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], (0.5, grid_color));
+        x += grid;
+    }
+    let mut y = rect.top() + state.pan.y.rem_euclid(grid);
+    while y < rect.bottom() {
+        painter.line_segment([egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)], (0.5, grid_color));
+        y += grid;
+    }
+
+    let mut names: Vec<String> = sm.states.keys().cloned().collect();
+    names.sort();
+    let mut nodes = vec![GraphNode::AnyState];
+    nodes.extend(names.iter().cloned().map(GraphNode::State));
+
+    // 正在进行的转换 (仅当编辑的层级处于活跃路径上)
+    let active = sm.active_transition.as_ref().filter(|_| level_active);
+    let progress = active.map(|a| if a.duration > 0.0 { (a.elapsed / a.duration).clamp(0.0, 1.0) } else { 1.0 });
+
+    // 转换连线
+    let mut edges = Vec::new();
+    for node in &nodes {
+        let from_pos = node_position(sm, node).unwrap();
+        let transitions = match node {
+            GraphNode::AnyState => &sm.any_state_transitions,
+            GraphNode::State(name) => &sm.states[name].transitions,
+        };
+        for (ti, transition) in transitions.iter().enumerate() {
+            let Some(target) = sm.states.get(&transition.target_state) else { continue };
+            if *node == GraphNode::State(transition.target_state.clone()) {
+                continue;
+            }
+            let (a, b) = edge_points(node_rect(from_pos), node_rect(target.position));
+            let selected = state.selection == Some(StateMachineSelection::Transition(node.clone(), ti));
+            let running = active.is_some_and(|a| {
+                a.to == transition.target_state
+                    && match node {
+                        GraphNode::State(name) => a.from == *name,
+                        // 源状态没有直接连向目标的转换时，视为由任意状态触发
+                        GraphNode::AnyState => !sm.states.get(&a.from).is_some_and(|s| s.transitions.iter().any(|t| t.target_state == a.to)),
+                    }
+            });
+            let color = if running {
+                egui::Color32::from_rgb(255, 160, 40)
+            } else if selected {
+                egui::Color32::YELLOW
+            } else {
+                visuals.text_color()
+            };
+            draw_arrow(&painter, a, b, egui::Stroke::new(if selected || running { 2.5 } else { 1.5 }, color));
+            if let (true, Some(progress)) = (running, progress) {
+                painter.circle_filled(a + (b - a) * progress, 4.0, color);
+            }
+            edges.push((StateMachineSelection::Transition(node.clone(), ti), a, b));
+        }
+    }
+
+    // "任意状态" 节点
+    let any_rect = node_rect(sm.any_state_position);
+    painter.rect(any_rect, 6.0, egui::Color32::from_rgb(40, 90, 120), egui::Stroke::new(1.0, visuals.widgets.inactive.fg_stroke.color));
+    painter.text(any_rect.center(), egui::Align2::CENTER_CENTER, "任意状态", egui::FontId::proportional(14.0), visuals.strong_text_color());
+
+    // 状态节点
+    for name in &names {
+        let st = &sm.states[name];
+        let r = node_rect(st.position);
+        let is_current = level_active && sm.current_state == *name;
+        let selected = state.selection == Some(StateMachineSelection::State(name.clone()));
+        let fill = if is_current { egui::Color32::from_rgb(40, 110, 60) } else { visuals.widgets.inactive.bg_fill };
+        let stroke = if selected {
+            egui::Stroke::new(2.0, egui::Color32::YELLOW)
+        } else {
+            egui::Stroke::new(1.0, visuals.widgets.inactive.fg_stroke.color)
+        };
+        painter.rect(r, 6.0, fill, stroke);
+        let title = if sm.entry_state == *name { format!("▶ {}", name) } else { name.clone() };
+        painter.text(r.center() - egui::vec2(0.0, 8.0), egui::Align2::CENTER_CENTER, title, egui::FontId::proportional(14.0), visuals.strong_text_color());
+        let subtitle = if st.sub_machine.is_some() {
+            "⊞ 子状态机"
+        } else {
+            clip_names.get(st.clip_index).map(String::as_str).unwrap_or("<无剪辑>")
+        };
+        painter.text(r.center() + egui::vec2(0.0, 10.0), egui::Align2::CENTER_CENTER, subtitle, egui::FontId::proportional(11.0), visuals.weak_text_color());
+    }
+
+    let pointer = response.interact_pointer_pos().or(response.hover_pos());
+    let hit_node = pointer.and_then(|p| nodes.iter().rev().find(|n| node_rect(node_position(sm, n).unwrap()).contains(p)).cloned());
+    let hit_edge = pointer.and_then(|p| {
+        edges
+            .iter()
+            .find(|(_, a, b)| distance_to_segment(p, *a, *b) <= EDGE_HIT_DISTANCE)
+            .map(|(sel, _, _)| sel.clone())
+    });
+
+    if response.drag_started() {
+        let connect = ui.input(|i| i.modifiers.shift) || response.dragged_by(egui::PointerButton::Secondary);
+        state.drag = Some(match &hit_node {
+            Some(node) if connect => CanvasDrag::Connect { from: node.clone() },
+            Some(node) => {
+                if let GraphNode::State(name) = node {
+                    state.selection = Some(StateMachineSelection::State(name.clone()));
+                }
+                CanvasDrag::Node { node: node.clone(), original: Box::new(edit.original.clone()) }
+            }
+            None => CanvasDrag::Pan,
+        });
+    }
+
+    match &state.drag {
+        Some(CanvasDrag::Node { node, .. }) => {
+            let d = response.drag_delta();
+            let position = match node {
+                GraphNode::AnyState => Some(&mut sm.any_state_position),
+                GraphNode::State(name) => sm.states.get_mut(name).map(|s| &mut s.position),
+            };
+            if let (Some(position), true) = (position, d != egui::Vec2::ZERO) {
+                *position += Vec2::new(d.x, d.y);
+                edit.live_changed = true;
+            }
+        }
+        Some(CanvasDrag::Pan) => state.pan += response.drag_delta(),
+        Some(CanvasDrag::Connect { from }) => {
+            if let (Some(from_pos), Some(p)) = (node_position(sm, from), pointer) {
+                draw_arrow(&painter, node_rect(from_pos).center(), p, egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE));
+            }
+        }
+        None => {}
+    }
+
+    if response.drag_released() {
+        match state.drag.take() {
+            Some(CanvasDrag::Node { original, .. }) => {
+                edit.structural = Some(("移动状态", *original));
+            }
+            Some(CanvasDrag::Connect { from }) => {
+                if let Some(GraphNode::State(target)) = hit_node.filter(|t| *t != from) {
+                    let transitions = transitions_mut(sm, &from).unwrap();
+                    transitions.push(AnimationTransition::new(target, 0.25));
+                    state.selection = Some(StateMachineSelection::Transition(from, transitions.len() - 1));
+                    edit.commit("添加转换");
+                }
+            }
+            _ => {}
+        }
+    } else if response.double_clicked() {
+        // 双击子状态机节点进入编辑
+        if let Some(GraphNode::State(name)) = &hit_node {
+            if sm.states[name].sub_machine.is_some() {
+                state.path.push(name.clone());
+                state.selection = None;
+            }
+        }
+    } else if response.clicked() {
+        state.selection = match (hit_node, hit_edge) {
+            (Some(GraphNode::State(name)), _) => Some(StateMachineSelection::State(name)),
+            (Some(GraphNode::AnyState), _) => None,
+            (None, Some(edge)) => Some(edge),
+            _ => None,
+        };
+    }
+
+    if response.secondary_clicked() {
+        if let Some(p) = pointer {
+            state.menu_position = to_canvas(p);
+        }
+    }
+    response.context_menu(|ui| {
+        if ui.button("在此添加状态").clicked() {
+            let name = sm.unique_state_name("State");
+            let mut new_state = AnimationState::new(name.clone(), 0);
+            new_state.position = state.menu_position;
+            sm.states.insert(name.clone(), new_state);
+            state.selection = Some(StateMachineSelection::State(name));
+            edit.commit("添加状态");
+            ui.close_menu();
+        }
+    });
+}
+
+/// 计算两个节点之间连线的端点 (裁剪到节点边框)，双向连线会各自偏移以免重叠
+fn edge_points(from: egui::Rect, to: egui::Rect) -> (egui::Pos2, egui::Pos2) {
+    let dir = (to.center() - from.center()).normalized();
+    let offset = egui::vec2(-dir.y, dir.x) * 6.0;
+    let a = clip_to_rect(from, dir) + offset;
+    let b = clip_to_rect(to, -dir) + offset;
+    (a, b)
+}
+
+/// 从矩形中心沿方向射出的射线与边框的交点
+fn clip_to_rect(rect: egui::Rect, dir: egui::Vec2) -> egui::Pos2 {
+    let half = rect.size() * 0.5;
+    let tx = if dir.x.abs() > f32::EPSILON { half.x / dir.x.abs() } else { f32::INFINITY };
+    let ty = if dir.y.abs() > f32::EPSILON { half.y / dir.y.abs() } else { f32::INFINITY };
+    rect.center() + dir * tx.min(ty)
+}
+
+fn draw_arrow(painter: &egui::Painter, from: egui::Pos2, to: egui::Pos2, stroke: egui::Stroke) {
+    painter.line_segment([from, to], stroke);
+    let dir = (to - from).normalized();
+    let normal = egui::vec2(-dir.y, dir.x);
+    let tip = to;
+    let base = to - dir * 10.0;
+    painter.add(egui::Shape::convex_polygon(vec![tip, base + normal * 5.0, base - normal * 5.0], stroke.color, egui::Stroke::NONE));
+}
+
+fn distance_to_segment(p: egui::Pos2, a: egui::Pos2, b: egui::Pos2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_sq();
+    if len_sq <= f32::EPSILON {
+        return p.distance(a);
+    }
+    let t = ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
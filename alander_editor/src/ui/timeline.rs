@@ -1,55 +1,535 @@
 use egui;
+use std::collections::{HashMap, HashSet};
 use crate::scene_manager::Scene;
-use alander_core::scene::AnimationPlayer;
+use crate::editor_command::{AnimationEditCommand, CommandManager};
+use super::graph_editor::{self, GraphEditorState};
+use alander_core::curve::TangentMode;
+use alander_core::scene::{AnimationClip, AnimationPlayer, Name, Parent, Transform, TransformTrack};
+use alander_render::renderer::Renderer;
+use bevy_ecs::prelude::*;
+
+/// 关键帧所在的轨道
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyTrack {
+    /// 变换通道的轨道，通道索引指向 `AnimationClip::channels`
+    Transform(TransformTrack),
+    /// 属性通道，通道索引指向 `AnimationClip::property_channels`
+    Property,
+}
+
+/// 摄影表中关键帧的引用 (通道索引, 轨道类型, 关键帧索引)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyRef {
+    pub channel: usize,
+    pub track: KeyTrack,
+    pub key: usize,
+}
+
+/// 正在拖动关键帧的状态
+struct KeyDrag {
+    /// 拖动开始前的剪辑快照 (用于撤销)
+    original_clips: Vec<AnimationClip>,
+    /// 拖动起点的指针 x 坐标
+    start_x: f32,
+}
+
+/// 时间线面板的视图模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineView {
+    /// 摄影表
+    DopeSheet,
+    /// 曲线编辑器
+    Curves,
+}
+
+/// 时间线面板的持久化状态
+pub struct TimelineState {
+    /// 当前视图
+    pub view: TimelineView,
+    /// 曲线编辑器状态
+    pub graph: GraphEditorState,
+    /// 选中的关键帧
+    pub selected_keys: HashSet<KeyRef>,
+    /// 每秒对应的像素宽度
+    pub pixels_per_second: f32,
+    /// 缩放选中关键帧的系数
+    pub scale_factor: f32,
+    /// 新建剪辑的名称
+    pub new_clip_name: String,
+    /// 记录属性关键帧时选中的属性索引
+    pub property_index: usize,
+    drag: Option<KeyDrag>,
+}
+
+impl Default for TimelineState {
+    fn default() -> Self {
+        Self {
+            view: TimelineView::DopeSheet,
+            graph: GraphEditorState::default(),
+            selected_keys: HashSet::new(),
+            pixels_per_second: 100.0,
+            scale_factor: 1.0,
+            new_clip_name: "New Clip".to_string(),
+            property_index: 0,
+            drag: None,
+        }
+    }
+}
+
+const LABEL_WIDTH: f32 = 140.0;
+const ROW_HEIGHT: f32 = 18.0;
+const RULER_HEIGHT: f32 = 20.0;
+const KEY_RADIUS: f32 = 5.0;
 
 /// 渲染时间线面板
-pub fn show_timeline(ui: &mut egui::Ui, scene: &mut Scene, selected_entity: Option<bevy_ecs::entity::Entity>) {
+pub fn show_timeline(
+    ui: &mut egui::Ui,
+    scene: &mut Scene,
+    selected_entity: Option<bevy_ecs::entity::Entity>,
+    state: &mut TimelineState,
+    command_manager: &mut CommandManager,
+    renderer: &mut Renderer,
+) {
     ui.heading("时间线 (Timeline)");
-    
-    if let Some(entity) = selected_entity {
-        if let Some(mut player) = scene.world.get_mut::<AnimationPlayer>(entity) {
-            ui.horizontal(|ui| {
-                if ui.button(if player.is_playing { "暂停" } else { "播放" }).clicked() {
-                    player.is_playing = !player.is_playing;
-                }
-                
-                ui.checkbox(&mut player.loop_enabled, "循环");
-                
-                ui.label(format!("当前时间: {:.2}s", player.current_time));
-            });
-
-            if let Some(clip_idx) = player.active_clip_index {
-                // 先获取进度条所需的元数据，避免在 UI 交互时持有对内部 clip 的借用
-                let (clip_name, clip_duration) = if let Some(clip) = player.clips.get(clip_idx) {
-                    (clip.name.clone(), clip.duration)
-                } else {
-                    ("Unknown".to_string(), 0.0)
-                };
-
-                let mut time = player.current_time;
-                ui.horizontal(|ui| {
-                    ui.label("时间:");
-                    ui.add(egui::DragValue::new(&mut time).speed(0.1).clamp_range(0.0..=1000.0));
-                    
-                    let slider_max = clip_duration.max(10.0);
-                    if ui.add(egui::Slider::new(&mut time, 0.0..=slider_max).text("进度")).changed() {
-                        // 联动更新
+
+    let Some(entity) = selected_entity else {
+        ui.label("请在层级面板中选择一个实体以控制其动画");
+        return;
+    };
+
+    let entity_name = scene.world.get::<Name>(entity).map(|n| n.0.clone());
+    let current_transform = scene.world.get::<Transform>(entity).cloned();
+    let properties: Vec<_> = alander_core::property::animatable_properties(&scene.world, entity)
+        .into_iter()
+        .filter_map(|(component, field, _)| {
+            let value = alander_core::property::get_property(&scene.world, entity, component, &field)?;
+            Some((component, field, value))
+        })
+        .collect();
+
+    // 骨骼等子实体通常不拥有播放器，关键帧记录到最近的拥有 AnimationPlayer 的祖先的剪辑中
+    let Some(player_entity) = animation_player_owner(&scene.world, entity) else {
+        ui.label("选中实体及其祖先都不包含 AnimationPlayer 组件");
+        return;
+    };
+    let Some(mut player) = scene.world.get_mut::<AnimationPlayer>(player_entity) else { return };
+
+    // 待提交的编辑: (编辑前快照, 编辑后剪辑, 命令名称)
+    let mut pending_edit: Option<(Vec<AnimationClip>, Vec<AnimationClip>, &'static str)> = None;
+    // 随编辑一同切换的当前剪辑 (编辑前, 编辑后)
+    let mut pending_active: Option<(Option<usize>, Option<usize>)> = None;
+
+    ui.horizontal(|ui| {
+        if ui.button(if player.is_playing { "暂停" } else { "播放" }).clicked() {
+            player.is_playing = !player.is_playing;
+        }
+
+        ui.checkbox(&mut player.loop_enabled, "循环");
+
+        ui.label(format!("当前时间: {:.2}s", player.current_time));
+
+        ui.separator();
+        if !player.clips.is_empty() {
+            let mut active = player.active_clip_index.filter(|&i| i < player.clips.len());
+            let selected_text = active.map_or("(无)".to_string(), |i| player.clips[i].name.clone());
+            egui::ComboBox::from_id_source("timeline_clip")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for (i, clip) in player.clips.iter().enumerate() {
+                        ui.selectable_value(&mut active, Some(i), &clip.name);
                     }
                 });
-                player.current_time = time;
-                
-                ui.label(format!("当前剪辑: {}", clip_name));
-            } else if !player.clips.is_empty() {
-                if ui.button("开始播放第一个剪辑").clicked() {
-                    player.play(0);
-                }
-            } else {
-                ui.label("该实体没有动画剪辑资源");
+            if let Some(index) = active.filter(|&i| Some(i) != player.active_clip_index) {
+                // 关键帧选择只对原剪辑有效
+                state.selected_keys.clear();
+                player.active_clip_index = Some(index);
+                player.current_time = 0.0;
+                player.transition_target_index = None;
             }
+        }
+
+        ui.text_edit_singleline(&mut state.new_clip_name);
+        if ui.button("➕ 新建剪辑").clicked() {
+            let old = player.clips.clone();
+            let mut new = old.clone();
+            new.push(AnimationClip::new(state.new_clip_name.clone()));
+            pending_active = Some((player.active_clip_index, Some(new.len() - 1)));
+            state.selected_keys.clear();
+            pending_edit = Some((old, new, "新建动画剪辑"));
+        }
+    });
+
+    // 新建剪辑会切换当前剪辑，本帧不再编辑旧剪辑
+    if pending_active.is_some() {
+        commit_edit(pending_edit, pending_active, player_entity, scene, command_manager, renderer);
+        return;
+    }
+
+    let Some(clip_idx) = player.active_clip_index.filter(|&i| i < player.clips.len()) else {
+        if player.clips.is_empty() {
+            ui.label("该实体没有动画剪辑资源");
         } else {
-            ui.label("选中实体不包含 AnimationPlayer 组件");
+            ui.label("请在上方选择要编辑的剪辑");
         }
-    } else {
-        ui.label("请在层级面板中选择一个实体以控制其动画");
+        commit_edit(pending_edit, None, player_entity, scene, command_manager, renderer);
+        return;
+    };
+
+    // 先获取进度条所需的元数据，避免在 UI 交互时持有对内部 clip 的借用
+    let (clip_name, clip_duration) = {
+        let clip = &player.clips[clip_idx];
+        (clip.name.clone(), clip.duration)
+    };
+
+    let mut time = player.current_time;
+    ui.horizontal(|ui| {
+        ui.label(format!("当前剪辑: {}", clip_name));
+        ui.label("时间:");
+        ui.add(egui::DragValue::new(&mut time).speed(0.1).clamp_range(0.0..=1000.0));
+
+        let slider_max = clip_duration.max(10.0);
+        ui.add(egui::Slider::new(&mut time, 0.0..=slider_max).text("进度"));
+    });
+    player.current_time = time;
+
+    // 关键帧设置工具
+    ui.horizontal(|ui| {
+        ui.label("设置关键帧:");
+        let mut key_tracks: Option<&[TransformTrack]> = None;
+        for track in TransformTrack::ALL.iter() {
+            if ui.button(track.label()).clicked() {
+                key_tracks = Some(std::slice::from_ref(track));
+            }
+        }
+        if ui.button("全部").clicked() {
+            key_tracks = Some(&TransformTrack::ALL);
+        }
+
+        if let (Some(tracks), Some(name), Some(transform)) = (key_tracks, entity_name.as_ref(), current_transform.as_ref()) {
+            let old = player.clips.clone();
+            let mut new = old.clone();
+            new[clip_idx].set_transform_key(name, time, transform, tracks);
+            pending_edit = Some((old, new, "设置关键帧"));
+        }
+
+        if !properties.is_empty() {
+            ui.separator();
+            state.property_index = state.property_index.min(properties.len() - 1);
+            let (component, field, value) = &properties[state.property_index];
+            egui::ComboBox::from_id_source("timeline_property")
+                .selected_text(format!("{}.{}", component, field))
+                .show_ui(ui, |ui| {
+                    for (i, (c, f, _)) in properties.iter().enumerate() {
+                        ui.selectable_value(&mut state.property_index, i, format!("{}.{}", c, f));
+                    }
+                });
+            if ui.button("属性").clicked() {
+                if let Some(name) = entity_name.as_ref() {
+                    let old = player.clips.clone();
+                    let mut new = old.clone();
+                    new[clip_idx].set_property_key(name, component, field, time, *value);
+                    pending_edit = Some((old, new, "设置属性关键帧"));
+                }
+            }
+        }
+
+        ui.separator();
+        let has_selection = !state.selected_keys.is_empty();
+        if ui.add_enabled(has_selection, egui::Button::new("删除所选")).clicked() {
+            let old = player.clips.clone();
+            let mut new = old.clone();
+            delete_keys(&mut new[clip_idx], &state.selected_keys);
+            state.selected_keys.clear();
+            pending_edit = Some((old, new, "删除关键帧"));
+        }
+        ui.add(egui::DragValue::new(&mut state.scale_factor).speed(0.01).clamp_range(0.01..=10.0).prefix("×"));
+        if ui.add_enabled(has_selection, egui::Button::new("缩放所选")).clicked() {
+            let old = player.clips.clone();
+            let mut new = old.clone();
+            scale_keys(&mut new[clip_idx], &state.selected_keys, state.scale_factor);
+            state.selected_keys.clear();
+            pending_edit = Some((old, new, "缩放关键帧"));
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut state.view, TimelineView::DopeSheet, "摄影表");
+        ui.selectable_value(&mut state.view, TimelineView::Curves, "曲线");
+
+        ui.separator();
+        ui.label("切线:");
+        let has_selection = !state.selected_keys.is_empty();
+        let has_rotation = state.selected_keys.iter().any(|k| k.track == KeyTrack::Transform(TransformTrack::Rotation));
+        for mode in TangentMode::ALL {
+            let enabled = has_selection && (!has_rotation || TangentMode::ROTATION.contains(&mode));
+            let button = ui.add_enabled(enabled, egui::Button::new(mode.label()));
+            let button = if has_rotation { button.on_disabled_hover_text("旋转关键帧只支持线性与常量") } else { button };
+            if button.clicked() {
+                let old = player.clips.clone();
+                let mut new = old.clone();
+                graph_editor::set_tangent_mode(&mut new[clip_idx], &state.selected_keys, mode);
+                pending_edit = Some((old, new, "设置切线模式"));
+            }
+        }
+    });
+
+    ui.separator();
+    let edit = match state.view {
+        TimelineView::DopeSheet => show_dope_sheet(ui, &mut player, clip_idx, state),
+        TimelineView::Curves => graph_editor::show_graph_editor(ui, &mut player, clip_idx, state),
+    };
+    if let Some(edit) = edit {
+        pending_edit = Some(edit);
+    }
+
+    commit_edit(pending_edit, None, player_entity, scene, command_manager, renderer);
+}
+
+/// 实体自身或最近的拥有 [`AnimationPlayer`] 的祖先
+fn animation_player_owner(world: &World, entity: Entity) -> Option<Entity> {
+    let mut current = Some(entity);
+    while let Some(e) = current {
+        if world.get::<AnimationPlayer>(e).is_some() {
+            return Some(e);
+        }
+        current = world.get::<Parent>(e).map(|p| p.0);
+    }
+    None
+}
+
+/// 绘制摄影表并处理关键帧的选择、拖动与时间轴拖拽
+fn show_dope_sheet(
+    ui: &mut egui::Ui,
+    player: &mut AnimationPlayer,
+    clip_idx: usize,
+    state: &mut TimelineState,
+) -> Option<(Vec<AnimationClip>, Vec<AnimationClip>, &'static str)> {
+    let rows = curve_rows(&player.clips[clip_idx]);
+    let duration = player.clips[clip_idx].duration.max(1.0);
+    let width = (LABEL_WIDTH + duration * state.pixels_per_second + 40.0).max(ui.available_width());
+    let height = RULER_HEIGHT + rows.len() as f32 * ROW_HEIGHT;
+
+    let mut edit = None;
+    egui::ScrollArea::both().show(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(egui::vec2(width, height), egui::Sense::click_and_drag());
+        let rect = response.rect;
+        let origin_x = rect.left() + LABEL_WIDTH;
+        let time_to_x = |t: f32| origin_x + t * state.pixels_per_second;
+        let x_to_time = |x: f32| ((x - origin_x) / state.pixels_per_second).max(0.0);
+        let visuals = ui.visuals().clone();
+
+        // 时间刻度
+        painter.rect_filled(egui::Rect::from_min_size(rect.min, egui::vec2(width, RULER_HEIGHT)), 0.0, visuals.faint_bg_color);
+        let step = if state.pixels_per_second >= 80.0 { 0.5 } else { 1.0 };
+        let mut t = 0.0;
+        while time_to_x(t) < rect.right() {
+            let x = time_to_x(t);
+            painter.line_segment([egui::pos2(x, rect.top() + RULER_HEIGHT * 0.5), egui::pos2(x, rect.bottom())], (1.0, visuals.widgets.noninteractive.bg_stroke.color));
+            painter.text(egui::pos2(x + 2.0, rect.top()), egui::Align2::LEFT_TOP, format!("{:.1}", t), egui::FontId::monospace(10.0), visuals.text_color());
+            t += step;
+        }
+
+        // 轨道行与关键帧
+        let mut key_positions = Vec::new();
+        for (row, (ci, track, label)) in rows.iter().enumerate() {
+            let y = rect.top() + RULER_HEIGHT + row as f32 * ROW_HEIGHT + ROW_HEIGHT * 0.5;
+            // 属性通道使用浅蓝色与变换轨道区分
+            let (text_color, key_color) = match track {
+                KeyTrack::Transform(_) => (visuals.text_color(), egui::Color32::LIGHT_GRAY),
+                KeyTrack::Property => (visuals.weak_text_color(), egui::Color32::LIGHT_BLUE),
+            };
+            painter.text(egui::pos2(rect.left() + 4.0, y), egui::Align2::LEFT_CENTER, label, egui::FontId::proportional(12.0), text_color);
+            let key = KeyRef { channel: *ci, track: *track, key: 0 };
+            for (ki, kt) in key_times(&player.clips[clip_idx], &key).into_iter().enumerate() {
+                let key = KeyRef { key: ki, ..key };
+                let center = egui::pos2(time_to_x(kt), y);
+                let color = if state.selected_keys.contains(&key) { egui::Color32::YELLOW } else { key_color };
+                painter.add(egui::Shape::convex_polygon(
+                    vec![
+                        center + egui::vec2(0.0, -KEY_RADIUS),
+                        center + egui::vec2(KEY_RADIUS, 0.0),
+                        center + egui::vec2(0.0, KEY_RADIUS),
+                        center + egui::vec2(-KEY_RADIUS, 0.0),
+                    ],
+                    color,
+                    egui::Stroke::new(1.0, egui::Color32::BLACK),
+                ));
+                key_positions.push((key, center));
+            }
+        }
+
+        // 播放头
+        let head_x = time_to_x(player.current_time);
+        painter.line_segment([egui::pos2(head_x, rect.top()), egui::pos2(head_x, rect.bottom())], (2.0, egui::Color32::RED));
+
+        let pointer = response.interact_pointer_pos().or(response.hover_pos());
+        let hit_key = pointer.and_then(|p| {
+            key_positions
+                .iter()
+                .find(|(_, c)| c.distance(p) <= KEY_RADIUS + 2.0)
+                .map(|(k, _)| *k)
+        });
+        let shift = ui.input(|i| i.modifiers.shift);
+
+        if response.drag_started() {
+            if let (Some(key), Some(p)) = (hit_key, pointer) {
+                if !state.selected_keys.contains(&key) {
+                    if !shift { state.selected_keys.clear(); }
+                    state.selected_keys.insert(key);
+                }
+                state.drag = Some(KeyDrag { original_clips: player.clips.clone(), start_x: p.x });
+            }
+        }
+
+        if let Some(drag) = &state.drag {
+            if let Some(p) = pointer {
+                // 基于拖动前快照计算，避免误差累积
+                let delta = (p.x - drag.start_x) / state.pixels_per_second;
+                let mut clip = drag.original_clips[clip_idx].clone();
+                retime_selected(&mut clip, &state.selected_keys, |x| x + delta);
+                player.clips[clip_idx] = clip;
+            }
+        }
+
+        if state.drag.is_some() {
+            if response.drag_released() {
+                let drag = state.drag.take().unwrap();
+                let mut new = player.clips.clone();
+                merge_moved_keys(&mut new[clip_idx], &state.selected_keys);
+                sort_keys(&mut new[clip_idx]);
+                new[clip_idx].update_duration();
+                state.selected_keys.clear();
+                edit = Some((drag.original_clips, new, "移动关键帧"));
+            }
+        } else if response.clicked() {
+            match hit_key {
+                Some(key) => {
+                    if !shift { state.selected_keys.clear(); }
+                    if !state.selected_keys.remove(&key) || !shift {
+                        state.selected_keys.insert(key);
+                    }
+                }
+                None => {
+                    state.selected_keys.clear();
+                    if let Some(p) = pointer {
+                        player.current_time = x_to_time(p.x);
+                    }
+                }
+            }
+        } else if response.dragged() && hit_key.is_none() {
+            // 在空白处拖动用于拖拽播放头
+            if let Some(p) = pointer {
+                player.current_time = x_to_time(p.x);
+            }
+        }
+    });
+    edit
+}
+
+/// 剪辑中的所有轨道: (通道索引, 轨道, 标签)，变换轨道在前，属性通道在后
+pub(super) fn curve_rows(clip: &AnimationClip) -> Vec<(usize, KeyTrack, String)> {
+    clip.channels
+        .iter()
+        .enumerate()
+        .flat_map(|(ci, c)| {
+            TransformTrack::ALL
+                .iter()
+                .filter(|t| c.has_track(**t))
+                .map(move |t| (ci, KeyTrack::Transform(*t), format!("{} / {}", c.target_name, t.label())))
+                .collect::<Vec<_>>()
+        })
+        .chain(clip.property_channels.iter().enumerate().map(|(ci, c)| (ci, KeyTrack::Property, c.label())))
+        .collect()
+}
+
+/// 关键帧所在轨道的全部关键帧时间 (忽略 `key.key`)
+fn key_times(clip: &AnimationClip, key: &KeyRef) -> Vec<f32> {
+    match key.track {
+        KeyTrack::Transform(track) => clip.channels.get(key.channel).map(|c| c.key_times(track)),
+        KeyTrack::Property => clip.property_channels.get(key.channel).map(|c| c.track.key_times()),
+    }
+    .unwrap_or_default()
+}
+
+/// 按 (通道, 轨道) 分组选中关键帧的索引
+fn group_keys(keys: &HashSet<KeyRef>) -> HashMap<(usize, KeyTrack), Vec<usize>> {
+    let mut groups: HashMap<(usize, KeyTrack), Vec<usize>> = HashMap::new();
+    for key in keys {
+        groups.entry((key.channel, key.track)).or_default().push(key.key);
+    }
+    groups
+}
+
+/// 按映射函数修改选中关键帧的时间 (不会重新排序)
+fn retime_selected(clip: &mut AnimationClip, keys: &HashSet<KeyRef>, f: impl Fn(f32) -> f32 + Copy) {
+    for ((channel, track), indices) in group_keys(keys) {
+        match track {
+            KeyTrack::Transform(track) => if let Some(c) = clip.channels.get_mut(channel) { c.retime_keys(track, &indices, f); },
+            KeyTrack::Property => if let Some(c) = clip.property_channels.get_mut(channel) { c.track.retime_keys(&indices, f); },
+        }
+    }
+}
+
+/// 移除与被移动关键帧时间重合的其他关键帧 (需在排序前调用，`keys` 为移动前的索引)
+pub(super) fn merge_moved_keys(clip: &mut AnimationClip, keys: &HashSet<KeyRef>) {
+    for ((channel, track), indices) in group_keys(keys) {
+        match track {
+            KeyTrack::Transform(track) => if let Some(c) = clip.channels.get_mut(channel) { c.merge_coincident_keys(track, &indices); },
+            KeyTrack::Property => if let Some(c) = clip.property_channels.get_mut(channel) { c.track.merge_coincident_keys(&indices); },
+        }
+    }
+}
+
+/// 重新按时间排序剪辑中所有轨道的关键帧
+pub(super) fn sort_keys(clip: &mut AnimationClip) {
+    for channel in &mut clip.channels {
+        channel.sort_keys();
+    }
+    for channel in &mut clip.property_channels {
+        channel.track.sort_keys();
+    }
+}
+
+/// 删除剪辑中选中的关键帧
+fn delete_keys(clip: &mut AnimationClip, keys: &HashSet<KeyRef>) {
+    for ((channel, track), indices) in group_keys(keys) {
+        match track {
+            KeyTrack::Transform(track) => if let Some(c) = clip.channels.get_mut(channel) { c.remove_keys(track, &indices); },
+            KeyTrack::Property => if let Some(c) = clip.property_channels.get_mut(channel) { c.track.remove_keys(&indices); },
+        }
+    }
+    clip.update_duration();
+}
+
+/// 以最早的选中关键帧为轴心缩放选中关键帧的时间
+fn scale_keys(clip: &mut AnimationClip, keys: &HashSet<KeyRef>, factor: f32) {
+    let pivot = keys
+        .iter()
+        .filter_map(|k| key_times(clip, k).get(k.key).copied())
+        .fold(f32::INFINITY, f32::min);
+    if !pivot.is_finite() {
+        return;
+    }
+    retime_selected(clip, keys, |t| pivot + (t - pivot) * factor);
+    merge_moved_keys(clip, keys);
+    sort_keys(clip);
+    clip.update_duration();
+}
+
+/// 通过命令管理器提交剪辑编辑，使其可撤销；`active` 为随编辑切换的当前剪辑
+fn commit_edit(
+    edit: Option<(Vec<AnimationClip>, Vec<AnimationClip>, &'static str)>,
+    active: Option<(Option<usize>, Option<usize>)>,
+    entity: Entity,
+    scene: &mut Scene,
+    command_manager: &mut CommandManager,
+    renderer: &mut Renderer,
+) {
+    if let Some((old, new, name)) = edit {
+        let mut cmd = AnimationEditCommand::new(entity, old, new, name);
+        if let Some((old_active, new_active)) = active {
+            cmd = cmd.with_active_clip(old_active, new_active);
+        }
+        command_manager.execute(Box::new(cmd), scene, renderer);
     }
 }
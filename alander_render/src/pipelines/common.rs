@@ -18,6 +18,9 @@ pub struct Vertex {
     pub tangent: [f32; 4],
     pub joint_indices: [u32; 4],
     pub joint_weights: [f32; 4],
+    /// 第二组骨骼影响 (每个顶点最多 8 根骨骼)
+    pub joint_indices_1: [u32; 4],
+    pub joint_weights_1: [f32; 4],
 }
 
 impl Vertex {
@@ -32,6 +35,8 @@ impl Vertex {
                 wgpu::VertexAttribute { offset: 32, shader_location: 3, format: wgpu::VertexFormat::Float32x4 },
                 wgpu::VertexAttribute { offset: 48, shader_location: 4, format: wgpu::VertexFormat::Uint32x4 },
                 wgpu::VertexAttribute { offset: 64, shader_location: 5, format: wgpu::VertexFormat::Float32x4 },
+                wgpu::VertexAttribute { offset: 80, shader_location: 6, format: wgpu::VertexFormat::Uint32x4 },
+                wgpu::VertexAttribute { offset: 96, shader_location: 7, format: wgpu::VertexFormat::Float32x4 },
             ],
         }
     }
@@ -242,15 +247,24 @@ impl ModelBuffer {
             _padding: [0; 3],
         }
     }
-}
 
-/// 骨骼矩阵 Buffer (最大支持 128 根骨骼)
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct BoneBuffer {
-    pub matrices: [[[f32; 4]; 4]; 128],
+    /// 按蒙皮方式设置标志：0 不蒙皮，1 线性混合，2 对偶四元数
+    pub fn with_skinning_mode(model: cgmath::Matrix4<f32>, mode: Option<alander_core::scene::SkinningMode>) -> Self {
+        Self {
+            model: model.into(),
+            has_skinning: match mode {
+                None => 0,
+                Some(alander_core::scene::SkinningMode::LinearBlend) => 1,
+                Some(alander_core::scene::SkinningMode::DualQuaternion) => 2,
+            },
+            _padding: [0; 3],
+        }
+    }
 }
 
+/// 关节调色板中的单个关节矩阵 (存储缓冲区元素)
+pub type JointMatrix = [[f32; 4]; 4];
+
 /// 光空间缓冲区 (用于阴影映射 - 支持 CSM)
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -1,5 +1,5 @@
 use wgpu::util::DeviceExt;
-use super::common::{Vertex, ModelBuffer, MaterialBuffer};
+use super::common::{Vertex, ModelBuffer, MaterialBuffer, JointMatrix};
 
 /// 基础网格渲染管线
 pub struct MeshPipeline {
@@ -142,7 +142,7 @@ impl MeshPipeline {
                         binding: 1,
                         visibility: wgpu::ShaderStages::VERTEX,
                         ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
                             has_dynamic_offset: false,
                             min_binding_size: None,
                         },
@@ -287,7 +287,10 @@ pub struct SceneObject {
     pub model_bind_group: wgpu::BindGroup,
     pub texture_bind_group: wgpu::BindGroup,
     pub material_bind_group: wgpu::BindGroup,
-    pub bone_buffer: Option<wgpu::Buffer>,
+    /// 网格是否带有骨骼权重
+    pub has_skinning: bool,
+    /// 当前绑定的蒙皮调色板 (标识, 缓冲区版本)
+    pub skin_binding: Option<(uuid::Uuid, u32)>,
 }
 
 impl SceneObject {
@@ -320,23 +323,8 @@ impl SceneObject {
 
         let cgmath_matrix = cgmath::Matrix4::from(matrix.to_cols_array_2d());
 
-        let bone_buffer = if has_skinning {
-            Some(device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("骨骼缓冲区"),
-                size: std::mem::size_of::<crate::pipelines::common::BoneBuffer>() as u64,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            }))
-        } else {
-            // 创建一个默认的骨骼缓冲区以满足绑定需求
-            Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("默认骨骼缓冲区"),
-                contents: bytemuck::bytes_of(&crate::pipelines::common::BoneBuffer {
-                    matrices: [[[0.0; 4]; 4]; 128],
-                }),
-                usage: wgpu::BufferUsages::UNIFORM,
-            }))
-        };
+        // 绑定蒙皮调色板之前使用的单位矩阵调色板，以满足绑定需求
+        let default_palette = SkinPalette::identity(device);
 
         let model_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("模型缓冲区"),
@@ -344,20 +332,7 @@ impl SceneObject {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        let model_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("模型绑定组"),
-            layout: model_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: model_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: bone_buffer.as_ref().unwrap().as_entire_binding(),
-                },
-            ],
-        });
+        let model_bind_group = create_model_bind_group(device, model_bind_group_layout, &model_buffer, &default_palette.buffer);
 
         let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("纹理绑定组"),
@@ -394,7 +369,8 @@ impl SceneObject {
             model_bind_group,
             texture_bind_group,
             material_bind_group,
-            bone_buffer: if has_skinning { bone_buffer } else { None },
+            has_skinning,
+            skin_binding: None,
         }
     }
 
@@ -403,10 +379,20 @@ impl SceneObject {
         queue.write_buffer(&self.model_buffer, 0, bytemuck::bytes_of(&model_buffer));
     }
 
-    pub fn update_bones(&self, queue: &wgpu::Queue, bones: &crate::pipelines::common::BoneBuffer) {
-        if let Some(ref buffer) = self.bone_buffer {
-            queue.write_buffer(buffer, 0, bytemuck::bytes_of(bones));
+    /// 更新模型矩阵与蒙皮方式 (None 表示按未蒙皮渲染)
+    pub fn update_model_skinned(&self, queue: &wgpu::Queue, model: cgmath::Matrix4<f32>, mode: Option<alander_core::scene::SkinningMode>) {
+        let model_buffer = ModelBuffer::with_skinning_mode(model, mode);
+        queue.write_buffer(&self.model_buffer, 0, bytemuck::bytes_of(&model_buffer));
+    }
+
+    /// 绑定蒙皮调色板，调色板缓冲区重建后会重新创建绑定组
+    pub fn bind_skin(&mut self, device: &wgpu::Device, model_bind_group_layout: &wgpu::BindGroupLayout, skin_id: uuid::Uuid, palette: &SkinPalette) {
+        let binding = Some((skin_id, palette.generation));
+        if self.skin_binding == binding {
+            return;
         }
+        self.model_bind_group = create_model_bind_group(device, model_bind_group_layout, &self.model_buffer, &palette.buffer);
+        self.skin_binding = binding;
     }
 
     pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
@@ -418,3 +404,65 @@ impl SceneObject {
         render_pass.draw_indexed(0..self.num_elements, 0, 0..1);
     }
 }
+
+fn create_model_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    model_buffer: &wgpu::Buffer,
+    palette_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("模型绑定组"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: model_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: palette_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
+/// 蒙皮关节调色板
+///
+/// 存储缓冲区按蒙皮的关节数分配，引用同一蒙皮的所有网格共享。
+pub struct SkinPalette {
+    pub buffer: wgpu::Buffer,
+    /// 缓冲区可容纳的关节数
+    pub capacity: usize,
+    /// 缓冲区版本，每次重建时递增，用于判断对象的绑定组是否过期
+    pub generation: u32,
+}
+
+impl SkinPalette {
+    pub fn new(device: &wgpu::Device, capacity: usize, generation: u32) -> Self {
+        let capacity = capacity.max(1);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("蒙皮调色板缓冲区"),
+            size: (capacity * std::mem::size_of::<JointMatrix>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self { buffer, capacity, generation }
+    }
+
+    /// 只包含一个单位矩阵的调色板
+    pub fn identity(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("默认蒙皮调色板"),
+            contents: bytemuck::cast_slice(&[glam::Mat4::IDENTITY.to_cols_array_2d()]),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        Self { buffer, capacity: 1, generation: 0 }
+    }
+
+    /// 写入关节矩阵，调用方需保证数量不超过容量
+    pub fn write(&self, queue: &wgpu::Queue, joints: &[JointMatrix]) {
+        debug_assert!(joints.len() <= self.capacity, "关节数 {} 超出调色板容量 {}", joints.len(), self.capacity);
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(joints));
+    }
+}
@@ -30,7 +30,7 @@ impl NormalPipeline {
                     binding: 1,
                     visibility: wgpu::ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
@@ -51,17 +51,8 @@ impl NormalPipeline {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<crate::pipelines::Vertex>() as wgpu::BufferAddress,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &wgpu::vertex_attr_array![
-                        0 => Float32x3, // position
-                        1 => Float32x3, // normal
-                        2 => Float32x2, // uv
-                        5 => Uint32x4,  // joint_indices
-                        6 => Float32x4, // joint_weights
-                    ],
-                }],
+                // 与网格管线使用相同的顶点布局，保证骨骼属性的偏移一致
+                buffers: &[crate::pipelines::Vertex::desc()],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
@@ -1459,6 +1459,8 @@ impl Renderer {
                     tangent: v.tangent.into(),
                     joint_indices: v.joint_indices,
                     joint_weights: v.joint_weights,
+                    joint_indices_1: v.joint_indices_1,
+                    joint_weights_1: v.joint_weights_1,
                 }).collect::<Vec<_>>(),
                 &gltf_mesh.data.indices,
                 self.pipelines.mesh.model_bind_group_layout(),
@@ -1507,6 +1509,31 @@ impl Renderer {
         self.resources.remove_object(id)
     }
 
+    /// 上传蒙皮的关节调色板，关节数超过容量时重建缓冲区
+    pub fn update_skin_palette(&mut self, skin_id: uuid::Uuid, joints: &[crate::pipelines::JointMatrix]) {
+        let resources = &mut self.resources;
+        if resources.skin_palettes.get(&skin_id).is_none_or(|palette| palette.capacity < joints.len()) {
+            // 版本全局递增：调色板被释放后以相同标识重建时，对象的旧绑定组同样会失效
+            resources.palette_generation += 1;
+            let palette = crate::pipelines::SkinPalette::new(self.ctx.device(), joints.len(), resources.palette_generation);
+            resources.skin_palettes.insert(skin_id, palette);
+        }
+        resources.skin_palettes[&skin_id].write(self.ctx.queue(), joints);
+    }
+
+    /// 让场景对象使用指定蒙皮的调色板 (需先调用 update_skin_palette)
+    pub fn bind_object_skin(&mut self, object_id: uuid::Uuid, skin_id: uuid::Uuid) {
+        let (Some(object), Some(palette)) = (self.resources.objects.get_mut(&object_id), self.resources.skin_palettes.get(&skin_id)) else {
+            return;
+        };
+        object.bind_skin(self.ctx.device(), self.pipelines.mesh.model_bind_group_layout(), skin_id, palette);
+    }
+
+    /// 释放蒙皮调色板 (绑定该调色板的对象在下次绑定前继续持有旧缓冲区)
+    pub fn remove_skin_palette(&mut self, skin_id: &uuid::Uuid) {
+        self.resources.skin_palettes.remove(skin_id);
+    }
+
     /// 获取表面
     pub fn surface(&self) -> &wgpu::Surface {
         self.ctx.surface()
@@ -1535,7 +1562,7 @@ impl Renderer {
         material: Option<crate::pipelines::MaterialBuffer>,
     ) {
         if let Some(object) = self.resources.objects.get_mut(object_id) {
-            object.update_model(self.ctx.queue(), model, object.bone_buffer.is_some());
+            object.update_model(self.ctx.queue(), model, object.has_skinning);
             if let Some(mat) = material {
                 self.ctx.queue().write_buffer(&object.material_buffer, 0, bytemuck::bytes_of(&mat));
             }
@@ -1754,6 +1781,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, -0.5, 0.5],
@@ -1762,6 +1791,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, 0.5, 0.5],
@@ -1770,6 +1801,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, 0.5, 0.5],
@@ -1778,6 +1811,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         // 后面
         Vertex {
@@ -1787,6 +1822,8 @@ pub fn create_cube(
             tangent: [-1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, -0.5, -0.5],
@@ -1795,6 +1832,8 @@ pub fn create_cube(
             tangent: [-1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, 0.5, -0.5],
@@ -1803,6 +1842,8 @@ pub fn create_cube(
             tangent: [-1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, 0.5, -0.5],
@@ -1811,6 +1852,8 @@ pub fn create_cube(
             tangent: [-1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         // 左面
         Vertex {
@@ -1820,6 +1863,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, 1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, -0.5, 0.5],
@@ -1828,6 +1873,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, 1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, 0.5, 0.5],
@@ -1836,6 +1883,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, 1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, 0.5, -0.5],
@@ -1844,6 +1893,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, 1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         // 右面
         Vertex {
@@ -1853,6 +1904,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, -1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, -0.5, -0.5],
@@ -1861,6 +1914,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, -1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, 0.5, -0.5],
@@ -1869,6 +1924,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, -1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, 0.5, 0.5],
@@ -1877,6 +1934,8 @@ pub fn create_cube(
             tangent: [0.0, 0.0, -1.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         // 上面
         Vertex {
@@ -1886,6 +1945,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, 0.5, 0.5],
@@ -1894,6 +1955,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, 0.5, -0.5],
@@ -1902,6 +1965,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, 0.5, -0.5],
@@ -1910,6 +1975,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         // 下面
         Vertex {
@@ -1919,6 +1986,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, -0.5, -0.5],
@@ -1927,6 +1996,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [0.5, -0.5, 0.5],
@@ -1935,6 +2006,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
         Vertex {
             position: [-0.5, -0.5, 0.5],
@@ -1943,6 +2016,8 @@ pub fn create_cube(
             tangent: [1.0, 0.0, 0.0, 1.0],
             joint_indices: [0; 4],
             joint_weights: [0.0; 4],
+            joint_indices_1: [0; 4],
+            joint_weights_1: [0.0; 4],
         },
     ];
 
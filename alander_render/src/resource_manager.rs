@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use uuid::Uuid;
 use crate::texture::Texture;
-use crate::pipelines::SceneObject;
+use crate::pipelines::{SceneObject, SkinPalette};
 
 /// 采样器缓存，解耦纹理与采样器
 pub struct SamplerCache {
@@ -50,6 +50,10 @@ pub struct ResourceManager {
     pub samplers: SamplerCache,
     /// 场景对象池
     pub objects: HashMap<Uuid, SceneObject>,
+    /// 蒙皮调色板池 (按 Skin 的调色板标识索引)
+    pub skin_palettes: HashMap<Uuid, SkinPalette>,
+    /// 最近创建的调色板版本
+    pub palette_generation: u32,
     /// 纹理池 (按 ID 索引)
     pub textures: HashMap<usize, Texture>,
     /// 默认白纹理
@@ -71,6 +75,8 @@ impl ResourceManager {
         Self {
             samplers,
             objects: HashMap::new(),
+            skin_palettes: HashMap::new(),
+            palette_generation: 0,
             textures: HashMap::new(),
             default_texture,
         }
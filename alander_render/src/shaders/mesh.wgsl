@@ -81,8 +81,9 @@ struct Model {
 @group(1) @binding(0)
 var<uniform> model: Model;
 
+// 蒙皮关节调色板 (按蒙皮共享的存储缓冲区)
 @group(1) @binding(1)
-var<uniform> bones: array<mat4x4<f32>, 128>;
+var<storage, read> joints: array<mat4x4<f32>>;
 
 @group(2) @binding(0)
 var t_diffuse: texture_2d<f32>;
@@ -107,6 +108,66 @@ var<uniform> material: Material;
 
 const PI: f32 = 3.14159265359;
 
+// 线性混合蒙皮：两组各 4 个骨骼影响，关节矩阵为世界空间
+fn skin_matrix(indices0: vec4<u32>, weights0: vec4<f32>, indices1: vec4<u32>, weights1: vec4<f32>) -> mat4x4<f32> {
+    return joints[indices0.x] * weights0.x +
+        joints[indices0.y] * weights0.y +
+        joints[indices0.z] * weights0.z +
+        joints[indices0.w] * weights0.w +
+        joints[indices1.x] * weights1.x +
+        joints[indices1.y] * weights1.y +
+        joints[indices1.z] * weights1.z +
+        joints[indices1.w] * weights1.w;
+}
+
+// 对偶四元数蒙皮：每个关节按列存放 (实部, 对偶部, 缩放)，混合后转换回矩阵
+fn skin_dual_quaternion(indices0: vec4<u32>, weights0: vec4<f32>, indices1: vec4<u32>, weights1: vec4<f32>) -> mat4x4<f32> {
+    var indices = array<u32, 8>(indices0.x, indices0.y, indices0.z, indices0.w, indices1.x, indices1.y, indices1.z, indices1.w);
+    var weights = array<f32, 8>(weights0.x, weights0.y, weights0.z, weights0.w, weights1.x, weights1.y, weights1.z, weights1.w);
+    let pivot = joints[indices[0]][0];
+    var real = vec4<f32>(0.0);
+    var dual = vec4<f32>(0.0);
+    var scale = vec3<f32>(0.0);
+    for (var i = 0u; i < 8u; i = i + 1u) {
+        let joint = joints[indices[i]];
+        // 与第一个关节保持在同一半球，避免沿远路插值
+        var w = weights[i];
+        if (dot(joint[0], pivot) < 0.0) {
+            w = -w;
+        }
+        real = real + joint[0] * w;
+        dual = dual + joint[1] * w;
+        scale = scale + joint[2].xyz * weights[i];
+    }
+
+    let len = length(real);
+    if (len < 1e-6) {
+        return mat4x4<f32>(vec4<f32>(0.0), vec4<f32>(0.0), vec4<f32>(0.0), vec4<f32>(0.0, 0.0, 0.0, 1.0));
+    }
+    real = real / len;
+    dual = dual / len;
+
+    let x = real.x;
+    let y = real.y;
+    let z = real.z;
+    let w = real.w;
+    let t = 2.0 * (w * dual.xyz - dual.w * real.xyz + cross(real.xyz, dual.xyz));
+    return mat4x4<f32>(
+        vec4<f32>(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + w * z), 2.0 * (x * z - w * y), 0.0) * scale.x,
+        vec4<f32>(2.0 * (x * y - w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + w * x), 0.0) * scale.y,
+        vec4<f32>(2.0 * (x * z + w * y), 2.0 * (y * z - w * x), 1.0 - 2.0 * (x * x + y * y), 0.0) * scale.z,
+        vec4<f32>(t, 1.0)
+    );
+}
+
+// 按模型的蒙皮方式计算世界矩阵：1 为线性混合，2 为对偶四元数
+fn skin_transform(mode: u32, indices0: vec4<u32>, weights0: vec4<f32>, indices1: vec4<u32>, weights1: vec4<f32>) -> mat4x4<f32> {
+    if (mode == 2u) {
+        return skin_dual_quaternion(indices0, weights0, indices1, weights1);
+    }
+    return skin_matrix(indices0, weights0, indices1, weights1);
+}
+
 @vertex
 fn vs_main(
     @location(0) position: vec3<f32>,
@@ -115,34 +176,27 @@ fn vs_main(
     @location(3) tangent: vec4<f32>,
     @location(4) joint_indices: vec4<u32>,
     @location(5) joint_weights: vec4<f32>,
+    @location(6) joint_indices_1: vec4<u32>,
+    @location(7) joint_weights_1: vec4<f32>,
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    var skin_matrix = mat4x4<f32>(
-        vec4<f32>(1.0, 0.0, 0.0, 0.0),
-        vec4<f32>(0.0, 1.0, 0.0, 0.0),
-        vec4<f32>(0.0, 0.0, 1.0, 0.0),
-        vec4<f32>(0.0, 0.0, 0.0, 1.0)
-    );
-
+    // 蒙皮网格的关节矩阵已包含世界变换，不再乘以模型矩阵
+    var world_matrix = model.matrix;
     if (model.has_skinning > 0u) {
-        skin_matrix = 
-            bones[joint_indices.x] * joint_weights.x +
-            bones[joint_indices.y] * joint_weights.y +
-            bones[joint_indices.z] * joint_weights.z +
-            bones[joint_indices.w] * joint_weights.w;
+        world_matrix = skin_transform(model.has_skinning, joint_indices, joint_weights, joint_indices_1, joint_weights_1);
     }
 
-    let world_pos = model.matrix * skin_matrix * vec4<f32>(position, 1.0);
+    let world_pos = world_matrix * vec4<f32>(position, 1.0);
     out.world_position = world_pos.xyz;
     out.clip_position = camera.view_proj * world_pos;
     out.uv = uv;
 
     // TBN
     let normal_matrix = mat3x3<f32>(
-        (model.matrix * skin_matrix)[0].xyz,
-        (model.matrix * skin_matrix)[1].xyz,
-        (model.matrix * skin_matrix)[2].xyz
+        world_matrix[0].xyz,
+        world_matrix[1].xyz,
+        world_matrix[2].xyz
     );
     let N_world = normalize(normal_matrix * normal);
     let T_world = normalize(normal_matrix * tangent.xyz);